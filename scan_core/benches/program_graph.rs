@@ -30,22 +30,11 @@ fn condition_pg() -> ProgramGraph {
         pre,
         action,
         post,
-        Some(Expression::Implies(Box::new((
-            Expression::LessEq(Box::new((
-                Expression::Sum(vec![
-                    Expression::Const(Val::Integer(1)),
-                    Expression::Const(Val::Integer(2)),
-                    Expression::Const(Val::Integer(3)),
-                    Expression::Const(Val::Integer(4)),
-                    Expression::Const(Val::Integer(5)),
-                ]),
-                Expression::Const(Val::Integer(100)),
-            ))),
-            Expression::Greater(Box::new((
-                Expression::Const(Val::Integer(5)),
-                Expression::Const(Val::Integer(6)),
-            ))),
-        )))),
+        Some(
+            Resolver::<Var>::new()
+                .parse("1 + 2 + 3 + 4 + 5 <= 100 => 5 > 6")
+                .unwrap(),
+        ),
     )
     .unwrap();
     pg.build()
@@ -13,8 +13,32 @@
 //! - Some PG actions are communication actions:
 //!     - `send` actions push the computed value of an expression to the rear of the channel queue;
 //!     - `receive` actions pop the value in front of the channel queue and write it onto a given PG variable;
+//!     - `selective_receive` actions are receive actions that only retrieve a value satisfying a given guard,
+//!       either in front of the channel queue or anywhere in it (see [`Selection`]);
+//!     - `peek` actions write the value in front of the channel queue onto a given PG variable, without popping it;
 //!     - `probe_empty_queue` actions can only be executed if the given channel has an empty queue;
 //!     - `probe_full_queue` actions can only be executed if the given channel has a full queue;
+//! - Channels with capacity zero use the handshake (rendezvous) protocol:
+//!   a `send` and a matching `receive` of two different PGs execute together, as a single step,
+//!   and the value is passed directly from the sender to the receiver.
+//! - Broadcast channels deliver each message to all of the PGs subscribed to them (i.e., receiving from them),
+//!   each of which has a queue of its own;
+//!   synchronous broadcast channels (with capacity zero) deliver the message at once
+//!   to all subscribers ready to receive it, if any.
+//! - Unreliable channels may lose, duplicate or reorder the messages sent through them,
+//!   each with a given probability (see [`Faults`]).
+//! - Bag channels let the messages in their queue be received in any order,
+//!   and priority channels in order of priority, as given by an expression on the message
+//!   (see [`ChannelSystemBuilder::set_priority`]).
+//! - Channels with latency hold the messages sent through them in flight for some time
+//!   (see [`Latency`]) before they can be received, though still in the order they were sent.
+//! - Locations can be urgent, so that time cannot elapse while any PG is in one,
+//!   or committed, so that furthermore only PGs in committed locations can transition (see [`Urgency`]).
+//!   Atomic locations make the transitions of a PG through them into a single atomic block,
+//!   which other PGs cannot interleave with, unless the PG gets stuck.
+//!
+//! - Global variables are shared by all PGs, whose guards and effects can read and write them:
+//!   the effects of a transition on global variables are seen by the other PGs only once it has been completed.
 //!
 //! Analogously to PGs, a CS is defined through a [`ChannelSystemBuilder`],
 //! by adding new PGs and channels.
@@ -102,6 +126,7 @@ pub use builder::*;
 use rand::seq::{IteratorRandom, SliceRandom};
 use rand::Rng;
 use std::collections::VecDeque;
+use std::ops::Range;
 use std::sync::Arc;
 use thiserror::Error;
 
@@ -110,8 +135,15 @@ use thiserror::Error;
 /// These cannot be directly created or manipulated,
 /// but have to be generated and/or provided by a [`ChannelSystemBuilder`] or [`ChannelSystem`].
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PgId(u16);
 
+impl std::fmt::Display for PgId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "pg{}", self.0)
+    }
+}
+
 impl From<PgId> for u16 {
     fn from(val: PgId) -> Self {
         val.0
@@ -122,14 +154,22 @@ impl From<PgId> for u16 {
 ///
 /// These cannot be directly created or manipulated,
 /// but have to be generated and/or provided by a [`ChannelSystemBuilder`] or [`ChannelSystem`].
-#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Channel(u16);
 
+impl std::fmt::Display for Channel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "c{}", self.0)
+    }
+}
+
 /// An indexing object for locations in a CS.
 ///
 /// These cannot be directly created or manipulated,
 /// but have to be generated and/or provided by a [`ChannelSystemBuilder`] or [`ChannelSystem`].
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Location(PgId, PgLocation);
 
 /// An indexing object for actions in a CS.
@@ -137,6 +177,7 @@ pub struct Location(PgId, PgLocation);
 /// These cannot be directly created or manipulated,
 /// but have to be generated and/or provided by a [`ChannelSystemBuilder`] or [`ChannelSystem`].
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Action(PgId, PgAction);
 
 /// An indexing object for typed variables in a CS.
@@ -144,26 +185,189 @@ pub struct Action(PgId, PgAction);
 /// These cannot be directly created or manipulated,
 /// but have to be generated and/or provided by a [`ChannelSystemBuilder`] or [`ChannelSystem`].
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Var(PgId, PgVar);
 
+impl std::fmt::Display for Var {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.0, self.1)
+    }
+}
+
+impl From<Var> for (PgId, PgVar) {
+    fn from(val: Var) -> Self {
+        (val.0, val.1)
+    }
+}
+
+/// An indexing object for global variables in a CS, shared by all of its PGs.
+///
+/// These cannot be directly created or manipulated,
+/// but have to be generated and/or provided by a [`ChannelSystemBuilder`].
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GlobalVar(u16);
+
+impl std::fmt::Display for GlobalVar {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "g{}", self.0)
+    }
+}
+
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Clock(PgId, PgClock);
 
+impl Clock {
+    /// The expression evaluating to the time elapsed since the clock was last reset.
+    ///
+    /// See also [`PgClock::elapsed`].
+    pub fn elapsed(self) -> CsExpression {
+        self.1.elapsed_with(|var| Var(self.0, var))
+    }
+}
+
 pub type TimeConstraint = (Clock, Option<Time>, Option<Time>);
 
+/// A transition of a PG in a CS, given by the PG, the action and the post-location.
+pub type Transition = (PgId, Action, Location);
+
 /// A message to be sent through a CS's channel.
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Message {
     /// Sending the computed value of an expression to a channel.
     Send,
     /// Retrieving a value out of a channel and associating it to a variable.
     Receive,
+    /// Retrieving a value satisfying a guard out of a channel and associating it to a variable.
+    SelectiveReceive(Selection),
+    /// Reading the value in front of a channel and associating it to a variable, without retrieving it.
+    Peek,
     /// Checking whether a channel is empty.
     ProbeEmptyQueue,
     /// Checking whether a channel is full.
     ProbeFullQueue,
 }
 
+/// Which message a selective receive can retrieve out of a channel.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Selection {
+    /// Only the message in front of the channel queue, if it satisfies the guard.
+    Front,
+    /// The first message in the channel queue satisfying the guard.
+    FirstMatching,
+}
+
+/// A fault affecting the delivery of a message sent through an unreliable channel.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Fault {
+    /// The message is lost.
+    Loss,
+    /// The message is delivered twice (if the channel has room for both copies).
+    Duplication,
+    /// The message overtakes the last message in the channel queue (if any).
+    Reordering,
+}
+
+/// The probabilities of the faults affecting each message sent through an unreliable channel.
+///
+/// Each probability has to be between `0` and `1`, and their sum cannot exceed `1`.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Faults {
+    /// The probability of a message being lost.
+    pub loss: f64,
+    /// The probability of a message being duplicated.
+    pub duplication: f64,
+    /// The probability of a message being reordered.
+    pub reordering: f64,
+}
+
+impl Faults {
+    /// The probability of the given fault.
+    pub fn probability(&self, fault: Fault) -> f64 {
+        match fault {
+            Fault::Loss => self.loss,
+            Fault::Duplication => self.duplication,
+            Fault::Reordering => self.reordering,
+        }
+    }
+
+    fn is_valid(&self) -> bool {
+        [self.loss, self.duplication, self.reordering]
+            .into_iter()
+            .all(|p| (0f64..=1f64).contains(&p))
+            && self.loss + self.duplication + self.reordering <= 1f64
+    }
+
+    fn sample<R: Rng>(&self, rng: &mut R) -> Option<Fault> {
+        let mut p = rng.gen::<f64>();
+        [Fault::Loss, Fault::Duplication, Fault::Reordering]
+            .into_iter()
+            .find(|fault| {
+                p -= self.probability(*fault);
+                p < 0f64
+            })
+    }
+}
+
+/// The delay, in model time, after which a message sent through a channel can be received.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Latency {
+    /// A fixed delay.
+    Fixed(Time),
+    /// Any delay within the given (inclusive) bounds, uniformly distributed in simulations.
+    Interval(Time, Time),
+    /// An exponentially distributed delay with the given mean.
+    Exponential(f64),
+}
+
+impl Default for Latency {
+    fn default() -> Self {
+        Self::Fixed(0)
+    }
+}
+
+impl Latency {
+    /// The minimum delay.
+    pub fn min(&self) -> Time {
+        match self {
+            Latency::Fixed(delay) | Latency::Interval(delay, _) => *delay,
+            Latency::Exponential(_) => 0,
+        }
+    }
+
+    /// Whether the latency admits the given delay.
+    pub fn admits(&self, delay: Time) -> bool {
+        match self {
+            Latency::Fixed(fixed) => delay == *fixed,
+            Latency::Interval(lower, upper) => (*lower..=*upper).contains(&delay),
+            Latency::Exponential(_) => true,
+        }
+    }
+
+    fn is_valid(&self) -> bool {
+        match self {
+            Latency::Fixed(_) => true,
+            Latency::Interval(lower, upper) => lower <= upper,
+            Latency::Exponential(mean) => mean.is_finite() && *mean > 0f64,
+        }
+    }
+
+    fn sample<R: Rng>(&self, rng: &mut R) -> Time {
+        match self {
+            Latency::Fixed(delay) => *delay,
+            Latency::Interval(lower, upper) => rng.gen_range(*lower..=*upper),
+            // Inverse transform sampling; the conversion saturates on (unlikely) huge delays.
+            Latency::Exponential(mean) => (-mean * (1f64 - rng.gen::<f64>()).ln()).round() as Time,
+        }
+    }
+}
+
 /// The error type for operations with [`ChannelSystemBuilder`]s and [`ChannelSystem`]s.
 #[derive(Debug, Clone, Error)]
 pub enum CsError {
@@ -185,6 +389,14 @@ pub enum CsError {
     /// The channel is not empty.
     #[error("channel {0:?} is not empty")]
     NotEmpty(Channel),
+    /// The PG cannot transition while another one is in a committed location.
+    #[error("program graph {0:?} cannot transition while another one is in a committed location")]
+    Committed(PgId),
+    /// The PG cannot transition while another one is executing an atomic block.
+    #[error(
+        "program graph {0:?} cannot transition while another one is executing an atomic block"
+    )]
+    Atomic(PgId),
     /// There is no such communication action in the CS.
     #[error("communication {0:?} has not been defined")]
     NoCommunication(Action),
@@ -214,6 +426,60 @@ pub enum CsError {
     /// Cannot probe for fullness an infinite capacity channel
     #[error("cannot probe for fullness the infinite capacity {0:?}")]
     ProbingInfiniteQueue(Channel),
+    /// Communications through an handshake channel can only happen together with a matching one.
+    #[error("communication through handshake {0:?} requires a matching partner")]
+    Handshake(Channel),
+    /// The actions are not a send and a receive of different PGs through the same handshake channel.
+    #[error("actions {0:?} and {1:?} do not form an handshake")]
+    NotHandshake(Action, Action),
+    /// Cannot selectively receive from or peek into an handshake channel.
+    #[error("cannot selectively receive from or peek into handshake {0:?}")]
+    SelectingHandshakeChannel(Channel),
+    /// No message in the channel satisfies the guard of the selective receive.
+    #[error("no message in channel {0:?} satisfies the guard")]
+    NoMatchingMessage(Channel),
+    /// A channel with infinite capacity reached the limit set by [`ChannelSystem::set_queue_limit`].
+    #[error("channel {0:?} reached the limit of {1} queued messages")]
+    QueueLimit(Channel, usize),
+    /// Only point-to-point channels with a queue can be unreliable.
+    #[error("channel {0:?} cannot be unreliable")]
+    UnreliableChannel(Channel),
+    /// The fault probabilities are not valid.
+    #[error("invalid fault probabilities {1:?} for channel {0:?}")]
+    InvalidFaults(Channel, Faults),
+    /// The fault cannot affect the channel.
+    #[error("fault {1:?} cannot affect channel {0:?}")]
+    ImpossibleFault(Channel, Fault),
+    /// Handshake channels cannot be bags.
+    #[error("handshake channels cannot be bags")]
+    HandshakeBag,
+    /// The channel is not a bag channel.
+    #[error("channel {0:?} is not a bag")]
+    NotBag(Channel),
+    /// There are not enough messages that can be received from the channel.
+    #[error("channel {0:?} has no message that can be received at position {1}")]
+    MissingMessage(Channel, usize),
+    /// Only point-to-point, reliable FIFO channels with a queue can be made priority channels.
+    #[error("channel {0:?} cannot be a priority channel")]
+    PriorityChannel(Channel),
+    /// The evaluation of the priority of a message failed.
+    #[error("failed to evaluate priority of message sent to channel {0:?}")]
+    Priority(Channel, #[source] EvalError),
+    /// Handshake channels cannot have latency.
+    #[error("handshake {0:?} cannot have latency")]
+    DelayedHandshake(Channel),
+    /// The latency is not valid.
+    #[error("invalid latency {1:?} for channel {0:?}")]
+    InvalidLatency(Channel, Latency),
+    /// The delay is not admitted by the channel's latency.
+    #[error("delay {1} is not admitted by channel {0:?}")]
+    ImpossibleDelay(Channel, Time),
+    /// There is no such global variable in the CS.
+    #[error("global variable {0:?} does not exists")]
+    MissingGlobalVar(GlobalVar),
+    /// The value is out of the range of the type.
+    #[error("value {0:?} is out of the range of type {1:?}")]
+    OutOfRange(Val, Type),
     /// A type error
     #[error("type error")]
     Type(#[source] TypeError),
@@ -221,6 +487,7 @@ pub enum CsError {
 
 /// A Channel System event related to a channel.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Event {
     /// The PG producing the event in the course of a transition.
     pub pg_id: PgId,
@@ -232,22 +499,117 @@ pub struct Event {
 
 /// A Channel System event type related to a channel.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum EventType {
     /// Sending a value to a channel.
     Send(Val),
     /// Retrieving a value out of a channel.
     Receive(Val),
+    /// Reading the value in front of a channel, without retrieving it.
+    Peek(Val),
+    /// Passing a value through a handshake channel to the given (receiving) PG.
+    Handshake(PgId, Val),
+    /// Sending a value through a broadcast channel to the given (recipient) PGs.
+    Broadcast(Vec<PgId>, Val),
+    /// Sending a value to an unreliable channel, with its delivery affected by the given fault.
+    FaultySend(Fault, Val),
     /// Checking whether a channel is empty.
     ProbeEmptyQueue,
     /// Checking whether a channel is full.
     ProbeFullQueue,
 }
 
+/// Statistics on the occupancy of a channel over the execution of a CS,
+/// or over multiple executions (see [`Self::merge`]).
+///
+/// The occupancy of a broadcast channel is that of its fullest queue.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChannelStats {
+    /// The maximum number of messages held by the channel at once.
+    pub max_occupancy: usize,
+    /// The number of times a PG got blocked because its only enabled actions were sends to the full channel.
+    pub blocked_sends: u64,
+    /// The (model) time the channel spent full.
+    pub time_full: u64,
+    /// The (model) time the execution lasted.
+    pub time: u64,
+    // The sum of the occupancy of the channel over the (model) time of the execution.
+    occupancy: u64,
+}
+
+impl ChannelStats {
+    /// The average number of messages held by the channel, over the (model) time of the execution.
+    pub fn avg_occupancy(&self) -> f64 {
+        if self.time == 0 {
+            0f64
+        } else {
+            self.occupancy as f64 / self.time as f64
+        }
+    }
+
+    /// The fraction of (model) time the channel spent full.
+    pub fn full_ratio(&self) -> f64 {
+        if self.time == 0 {
+            0f64
+        } else {
+            self.time_full as f64 / self.time as f64
+        }
+    }
+
+    /// Accumulates the statistics of another execution into `self`.
+    pub fn merge(&mut self, other: &Self) {
+        self.max_occupancy = self.max_occupancy.max(other.max_occupancy);
+        self.blocked_sends += other.blocked_sends;
+        self.time_full += other.time_full;
+        self.time += other.time;
+        self.occupancy += other.occupancy;
+    }
+}
+
+// The occupancy of a channel since it last changed, at the given time.
+#[derive(Debug, Clone, Copy, Default)]
+struct Occupancy {
+    len: usize,
+    full: bool,
+    time: Time,
+}
+
+// The PGs that take precedence over all others, so that only they can transition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Precedence {
+    // PGs in committed locations.
+    Committed,
+    // The PG executing an atomic block.
+    Block(PgId),
+    // PGs in atomic locations.
+    Atomic,
+}
+
+// How the messages in a channel's queue are ordered for reception.
+#[derive(Debug, Clone)]
+enum Discipline {
+    Fifo,
+    Bag,
+    // Messages are ordered by decreasing priority, and in the order they were sent among equal priorities.
+    Priority(Arc<FnExpression<Channel>>),
+}
+
 #[derive(Debug, Clone)]
 pub struct ChannelSystemDef {
     channels: Vec<(Type, Option<usize>)>,
+    // The PGs subscribed to each broadcast channel (sorted), or `None` for point-to-point channels.
+    subscribers: Vec<Option<Vec<PgId>>>,
+    // The queues of a channel are those in `queue_idxs[channel]..queue_idxs[channel + 1]`.
+    queue_idxs: Vec<usize>,
+    disciplines: Vec<Discipline>,
+    faults: Vec<Option<Faults>>,
+    latencies: Vec<Latency>,
     communications: Vec<(PgAction, Channel, Message)>,
     communications_pg_idxs: Vec<u16>,
+    // The variables through which each PG accesses global variables, with the global variable they stand for.
+    globals: Vec<Vec<(PgVar, usize)>>,
+    // The PGs accessing each global variable, with the variable they access it through.
+    global_vars: Vec<Vec<(PgId, PgVar)>>,
 }
 
 impl ChannelSystemDef {
@@ -265,6 +627,44 @@ impl ChannelSystemDef {
             })
             .ok()
     }
+
+    #[inline(always)]
+    fn queues(&self, channel: Channel) -> Range<usize> {
+        self.queue_idxs[channel.0 as usize]..self.queue_idxs[channel.0 as usize + 1]
+    }
+
+    // The queues of the channel accessed by the given PG:
+    // for broadcast channels, the PG's own queue if it is subscribed, or else all of the subscribers' queues.
+    #[inline(always)]
+    fn queues_of(&self, pg_id: PgId, channel: Channel) -> Range<usize> {
+        let queues = self.queues(channel);
+        match self.subscribers[channel.0 as usize]
+            .as_ref()
+            .map(|pgs| pgs.binary_search(&pg_id))
+        {
+            Some(Ok(idx)) => queues.start + idx..queues.start + idx + 1,
+            _ => queues,
+        }
+    }
+
+    // The priority of the message, if the channel is a priority channel.
+    fn priority(&self, channel: Channel, val: &Val) -> Result<Option<Integer>, EvalError> {
+        if let Discipline::Priority(priority) = &self.disciplines[channel.0 as usize] {
+            if let Val::Integer(priority) = priority.eval(&|_| val)? {
+                Ok(Some(priority))
+            } else {
+                panic!("priority is not an integer");
+            }
+        } else {
+            Ok(None)
+        }
+    }
+
+    #[inline(always)]
+    fn is_synchronous_broadcast(&self, channel: Channel) -> bool {
+        self.subscribers[channel.0 as usize].is_some()
+            && matches!(self.channels[channel.0 as usize].1, Some(0))
+    }
 }
 
 /// Representation of a CS that can be executed transition-by-transition.
@@ -281,7 +681,17 @@ impl ChannelSystemDef {
 pub struct ChannelSystem {
     time: Time,
     program_graphs: Vec<ProgramGraph>,
-    message_queue: Vec<VecDeque<Val>>,
+    // Messages are queued together with the time from which they can be received.
+    message_queue: Vec<VecDeque<(Time, Val)>>,
+    globals: Vec<Val>,
+    // Statistics are only brought up to date when the occupancy of the channel changes.
+    stats: Vec<ChannelStats>,
+    occupancy: Vec<Occupancy>,
+    // The full channels each PG has been found blocked on since it last transitioned.
+    blocked: Vec<Vec<Channel>>,
+    // The PG executing an atomic block, if any.
+    atomic: Option<PgId>,
+    queue_limit: Option<usize>,
     def: Arc<ChannelSystemDef>,
 }
 
@@ -291,17 +701,77 @@ impl ChannelSystem {
         self.time
     }
 
+    /// Sets a limit to the number of messages that channels with infinite capacity can hold,
+    /// or removes it if `None`.
+    ///
+    /// Sending a message to a channel that already holds as many messages fails with [`CsError::QueueLimit`],
+    /// so that the runaway growth of a queue is caught as an error.
+    pub fn set_queue_limit(&mut self, limit: Option<usize>) {
+        self.queue_limit = limit;
+    }
+
+    /// Returns the statistics on the occupancy of each channel over the execution so far.
+    pub fn channel_stats(&self) -> Vec<(Channel, ChannelStats)> {
+        self.stats
+            .iter()
+            .zip(self.occupancy.iter())
+            .enumerate()
+            .map(|(idx, (stats, occupancy))| {
+                let mut stats = *stats;
+                stats.occupancy += occupancy.len as u64 * (self.time - occupancy.time) as u64;
+                if occupancy.full {
+                    stats.time_full += (self.time - occupancy.time) as u64;
+                }
+                stats.time = self.time as u64;
+                (Channel(idx as u16), stats)
+            })
+            .collect()
+    }
+
+    // Brings the statistics of the channel up to date, after a transition that may have changed its occupancy.
+    fn update_stats(&mut self, channel: Channel) {
+        let (_, capacity) = self.def.channels[channel.0 as usize];
+        let len = self.message_queue[self.def.queues(channel)]
+            .iter()
+            .map(VecDeque::len)
+            .max()
+            .unwrap_or_default();
+        let occupancy = &mut self.occupancy[channel.0 as usize];
+        let stats = &mut self.stats[channel.0 as usize];
+        stats.occupancy += occupancy.len as u64 * (self.time - occupancy.time) as u64;
+        if occupancy.full {
+            stats.time_full += (self.time - occupancy.time) as u64;
+        }
+        stats.max_occupancy = stats.max_occupancy.max(len);
+        *occupancy = Occupancy {
+            len,
+            full: capacity.is_some_and(|cap| cap > 0 && len >= cap),
+            time: self.time,
+        };
+    }
+
     /// Iterates over all transitions that can be admitted in the current state.
     ///
     /// An admittable transition is characterized by the PG it executes on, the required action and the post-state
     /// (the pre-state being necessarily the current state of the machine).
     /// The (eventual) guard is guaranteed to be satisfied.
     ///
+    /// Communications through handshake channels cannot be executed on their own,
+    /// so they are not included: see [`Self::possible_handshakes`] instead.
+    /// The same goes for receiving from synchronous broadcast channels,
+    /// which only happens together with a send.
+    ///
+    /// While some PG is in a committed location, only PGs in committed locations can transition;
+    /// otherwise, while the PG executing an atomic block can transition, only it can,
+    /// and while some other PG in an atomic location can transition, only PGs in atomic locations can.
+    ///
     /// See also [`ProgramGraph::possible_transitions`].
-    pub fn possible_transitions(&self) -> impl Iterator<Item = (PgId, Action, Location)> + '_ {
+    pub fn possible_transitions(&self) -> impl Iterator<Item = Transition> + '_ {
+        let precedence = self.precedence();
         self.program_graphs
             .iter()
             .enumerate()
+            .filter(move |(id, _)| self.takes_precedence(precedence, PgId(*id as u16)))
             .flat_map(move |(id, pg)| {
                 let pg_id = PgId(id as u16);
                 pg.possible_transitions().filter_map(move |(action, post)| {
@@ -314,38 +784,279 @@ impl ChannelSystem {
             })
     }
 
+    /// Iterates over all handshakes that can be admitted in the current state,
+    /// as pairs of a send transition and a matching receive transition of another PG.
+    ///
+    /// ```
+    /// # use scan_core::*;
+    /// # use scan_core::channel_system::*;
+    /// let mut cs_builder = ChannelSystemBuilder::new();
+    /// // A channel with capacity zero uses the handshake protocol
+    /// let chn = cs_builder.new_channel(Type::Integer, Some(0));
+    ///
+    /// let sender = cs_builder.new_program_graph();
+    /// let initial_s = cs_builder.initial_location(sender).unwrap();
+    /// let send = cs_builder.new_send(sender, chn, CsExpression::from(1)).unwrap();
+    /// cs_builder.add_transition(sender, initial_s, send, initial_s, None).unwrap();
+    ///
+    /// let receiver = cs_builder.new_program_graph();
+    /// let initial_r = cs_builder.initial_location(receiver).unwrap();
+    /// let var = cs_builder.new_var(receiver, Expression::from(0)).unwrap();
+    /// let receive = cs_builder.new_receive(receiver, chn, var).unwrap();
+    /// cs_builder.add_transition(receiver, initial_r, receive, initial_r, None).unwrap();
+    ///
+    /// let mut cs = cs_builder.build();
+    /// // Neither the send nor the receive can be executed on its own...
+    /// assert_eq!(cs.possible_transitions().count(), 0);
+    /// // ...but they can be executed together.
+    /// let (send, receive) = cs.possible_handshakes().next().expect("handshake is possible");
+    /// let event = cs.handshake(send, receive).expect("handshake succeeds");
+    /// assert_eq!(event.event_type, EventType::Handshake(receiver, Val::Integer(1)));
+    /// ```
+    pub fn possible_handshakes(&self) -> impl Iterator<Item = (Transition, Transition)> + '_ {
+        let precedence = self.precedence();
+        self.program_graphs
+            .iter()
+            .enumerate()
+            .flat_map(move |(id, pg)| {
+                let pg_id = PgId(id as u16);
+                pg.possible_transitions().flat_map(move |(action, post)| {
+                    let send = (pg_id, Action(pg_id, action), Location(pg_id, post));
+                    let is_send =
+                        matches!(self.def.communication(send.1), Some((_, Message::Send)));
+                    is_send
+                        .then(|| self.partners(send.0, send.1))
+                        .into_iter()
+                        .flatten()
+                        // At least one of the PGs has to take precedence, if any does.
+                        .filter(move |receive| {
+                            self.takes_precedence(precedence, send.0)
+                                || self.takes_precedence(precedence, receive.0)
+                        })
+                        .map(move |receive| (send, receive))
+                })
+            })
+    }
+
+    // The PGs that take precedence over all others, if any:
+    // PGs in committed locations, or else the PG executing an atomic block,
+    // or else PGs in atomic locations, provided one of them can transition.
+    fn precedence(&self) -> Option<Precedence> {
+        if self
+            .program_graphs
+            .iter()
+            .any(|pg| pg.urgency() == Urgency::Committed)
+        {
+            Some(Precedence::Committed)
+        } else if let Some(pg_id) = self.atomic.filter(|pg_id| self.can_transition(*pg_id)) {
+            Some(Precedence::Block(pg_id))
+        } else if (0..self.program_graphs.len() as u16)
+            .map(PgId)
+            .filter(|pg_id| self.program_graphs[pg_id.0 as usize].urgency() == Urgency::Atomic)
+            .any(|pg_id| self.can_transition(pg_id))
+        {
+            Some(Precedence::Atomic)
+        } else {
+            None
+        }
+    }
+
+    // Whether the PG is allowed to transition, given the PGs that take precedence.
+    fn takes_precedence(&self, precedence: Option<Precedence>, pg_id: PgId) -> bool {
+        let urgency = self.program_graphs[pg_id.0 as usize].urgency();
+        match precedence {
+            None => true,
+            Some(Precedence::Committed) => urgency == Urgency::Committed,
+            Some(Precedence::Block(block)) => pg_id == block,
+            Some(Precedence::Atomic) => urgency == Urgency::Atomic,
+        }
+    }
+
+    // Whether the PG has some transition it can execute, either on its own or through a handshake.
+    fn can_transition(&self, pg_id: PgId) -> bool {
+        self.program_graphs[pg_id.0 as usize]
+            .possible_transitions()
+            .any(|(action, _)| {
+                let action = Action(pg_id, action);
+                self.check_communication(pg_id, action).is_ok()
+                    || self.partners(pg_id, action).next().is_some()
+            })
+    }
+
+    // Fails if some of the other PGs take precedence over all the given ones.
+    fn check_precedence(&self, pg_ids: &[PgId]) -> Result<(), CsError> {
+        let precedence = self.precedence();
+        if pg_ids
+            .iter()
+            .any(|pg_id| self.takes_precedence(precedence, *pg_id))
+        {
+            Ok(())
+        } else if precedence == Some(Precedence::Committed) {
+            Err(CsError::Committed(pg_ids[0]))
+        } else {
+            Err(CsError::Atomic(pg_ids[0]))
+        }
+    }
+
+    // Keeps track of the state of the PG after it transitions:
+    // a PG reaching an atomic location executes an atomic block, until it leaves atomic locations.
+    fn transitioned(&mut self, pg_id: PgId) {
+        self.blocked[pg_id.0 as usize].clear();
+        if self.program_graphs[pg_id.0 as usize].urgency() == Urgency::Atomic {
+            self.atomic = Some(pg_id);
+        } else if self.atomic == Some(pg_id) {
+            self.atomic = None;
+        }
+    }
+
+    // Iterates over the transitions of other PGs that can complete a handshake with the given action,
+    // if it is a send or receive through a handshake channel.
+    fn partners(&self, pg_id: PgId, action: Action) -> impl Iterator<Item = Transition> + '_ {
+        self.def
+            .communication(action)
+            .filter(|(channel, _)| {
+                matches!(self.def.channels[channel.0 as usize].1, Some(0))
+                    && self.def.subscribers[channel.0 as usize].is_none()
+            })
+            .and_then(|(channel, message)| match message {
+                Message::Send => Some((channel, Message::Receive)),
+                Message::Receive => Some((channel, Message::Send)),
+                Message::SelectiveReceive(_)
+                | Message::Peek
+                | Message::ProbeEmptyQueue
+                | Message::ProbeFullQueue => None,
+            })
+            .into_iter()
+            .flat_map(move |matching| {
+                (0..self.program_graphs.len() as u16)
+                    .map(PgId)
+                    .filter(move |other| *other != pg_id)
+                    .flat_map(move |other| self.ready(other, matching))
+            })
+    }
+
+    // Iterates over the transitions of the given PG performing the given communication
+    // that are admissible, but for the state of the channel.
+    fn ready(
+        &self,
+        pg_id: PgId,
+        communication: (Channel, Message),
+    ) -> impl Iterator<Item = Transition> + '_ {
+        self.program_graphs[pg_id.0 as usize]
+            .possible_transitions()
+            .map(move |(action, post)| (pg_id, Action(pg_id, action), Location(pg_id, post)))
+            .filter(move |(_, action, _)| self.def.communication(*action) == Some(communication))
+    }
+
     pub(crate) fn montecarlo_execution<R: Rng>(
         &mut self,
         rng: &mut R,
         duration: Time,
-    ) -> Option<Event> {
+    ) -> Result<Option<Event>, CsError> {
         let mut pg_vec = Vec::from_iter((0..self.program_graphs.len() as u16).map(PgId));
-        while self.time <= duration {
+        'time: while self.time <= duration {
+            let precedence = self.precedence();
             // Resets PG queue
             let mut pg_list = pg_vec.as_mut_slice();
             while !pg_list.is_empty() {
                 let (select, remainder) = pg_list.partial_shuffle(rng, 1);
                 pg_list = remainder;
                 let pg_id = select[0];
-                while let Some((action, post)) = self.program_graphs[pg_id.0 as usize]
-                    .possible_transitions()
-                    .filter(|(action, _)| {
-                        self.check_communication(pg_id, Action(pg_id, *action))
-                            .is_ok()
-                    })
-                    .choose(rng)
-                {
-                    let event = self
-                        .transition(pg_id, Action(pg_id, action), Location(pg_id, post))
-                        .expect("successful transition");
+                // PGs cannot transition while others take precedence over them.
+                while self.takes_precedence(precedence, pg_id) {
+                    let mut blocked = Vec::new();
+                    let transition = self.program_graphs[pg_id.0 as usize]
+                        .possible_transitions()
+                        .filter(|(action, _)| {
+                            let action = Action(pg_id, *action);
+                            match self.check_communication(pg_id, action) {
+                                Ok(()) => true,
+                                Err(CsError::OutOfCapacity(channel)) => {
+                                    blocked.push(channel);
+                                    false
+                                }
+                                Err(_) => self.partners(pg_id, action).next().is_some(),
+                            }
+                        })
+                        .choose(rng);
+                    let Some((action, post)) = transition else {
+                        // A PG stuck on full channels is counted as blocked once, until it transitions again.
+                        for channel in blocked {
+                            if !self.blocked[pg_id.0 as usize].contains(&channel) {
+                                self.stats[channel.0 as usize].blocked_sends += 1;
+                                self.blocked[pg_id.0 as usize].push(channel);
+                            }
+                        }
+                        break;
+                    };
+                    let own = (pg_id, Action(pg_id, action), Location(pg_id, post));
+                    let event =
+                        if let Some(partner) = self.partners(pg_id, own.1).choose(rng) {
+                            // The partner of a send is a receive, and vice versa.
+                            if let Some((_, Message::Send)) = self.def.communication(own.1) {
+                                Some(self.handshake(own, partner)?)
+                            } else {
+                                Some(self.handshake(partner, own)?)
+                            }
+                        } else if let Some((channel, Message::Send)) = self
+                            .def
+                            .communication(own.1)
+                            .filter(|(channel, _)| self.def.is_synchronous_broadcast(*channel))
+                        {
+                            Some(self.broadcast(own, channel, |receives| {
+                                receives.into_iter().choose(rng)
+                            })?)
+                        } else {
+                            // Messages in a bag can be received in any order.
+                            let position = self
+                                .def
+                                .communication(own.1)
+                                .filter(|(channel, message)| {
+                                    *message == Message::Receive
+                                        && matches!(
+                                            self.def.disciplines[channel.0 as usize],
+                                            Discipline::Bag
+                                        )
+                                })
+                                .map_or(0, |(channel, _)| {
+                                    let queue = self.def.queues_of(pg_id, channel).start;
+                                    let arrived =
+                                        self.arrived(channel, &self.message_queue[queue]).count();
+                                    rng.gen_range(0..arrived)
+                                });
+                            let send = self
+                                .def
+                                .communication(own.1)
+                                .filter(|(_, message)| *message == Message::Send);
+                            let fault = send
+                                .and_then(|(channel, _)| self.def.faults[channel.0 as usize])
+                                .and_then(|faults| faults.sample(rng));
+                            let delay = send.map_or(0, |(channel, _)| {
+                                self.def.latencies[channel.0 as usize].sample(rng)
+                            });
+                            self.execute(pg_id, own.1, own.2, fault, delay, position)?
+                        };
                     if event.is_some() {
-                        return event;
+                        return Ok(event);
+                    } else if self.precedence() != precedence
+                        || !self.def.globals[pg_id.0 as usize].is_empty()
+                    {
+                        // Start over, as PGs may have been passed over because of precedence,
+                        // or may race with this one to read and write global variables.
+                        continue 'time;
                     }
                 }
             }
-            self.wait(1).ok()?;
+            match self.wait(1) {
+                Ok(()) => {}
+                // Time cannot advance because of some time invariant.
+                Err(CsError::ProgramGraph(_, PgError::UnsatisfiedGuard | PgError::Urgent(_))) => {
+                    return Ok(None)
+                }
+                Err(err) => return Err(err),
+            }
         }
-        None
+        Ok(None)
     }
 
     fn check_communication(&self, pg_id: PgId, action: Action) -> Result<(), CsError> {
@@ -355,14 +1066,33 @@ impl ChannelSystem {
             Err(CsError::ActionNotInPg(action, pg_id))
         } else if let Some((channel, message)) = self.def.communication(action) {
             let (_, capacity) = self.def.channels[channel.0 as usize];
-            let queue = &self.message_queue[channel.0 as usize];
+            let broadcast = self.def.subscribers[channel.0 as usize].is_some();
+            // Sending a message pushes it to all of the channel's queues.
+            let queues = if let Message::Send = message {
+                &self.message_queue[self.def.queues(channel)]
+            } else {
+                &self.message_queue[self.def.queues_of(pg_id, channel)]
+            };
             // Channel capacity must never be exeeded!
-            assert!(capacity.is_none_or(|cap| queue.len() <= cap));
+            assert!(queues
+                .iter()
+                .all(|queue| capacity.is_none_or(|cap| queue.len() <= cap)));
+            let full = capacity.is_some_and(|cap| queues.iter().any(|queue| queue.len() >= cap));
+            let empty = !queues
+                .iter()
+                .any(|queue| self.arrived(channel, queue).next().is_some());
             match message {
-                Message::Send if capacity.is_some_and(|cap| queue.len() >= cap) => {
-                    Err(CsError::OutOfCapacity(channel))
+                Message::Send if broadcast && matches!(capacity, Some(0)) => Ok(()),
+                Message::Send | Message::Receive if matches!(capacity, Some(0)) => {
+                    Err(CsError::Handshake(channel))
                 }
-                Message::Receive if queue.is_empty() => Err(CsError::Empty(channel)),
+                Message::Send if full => Err(CsError::OutOfCapacity(channel)),
+                Message::Receive | Message::Peek if empty => Err(CsError::Empty(channel)),
+                Message::SelectiveReceive(_) if empty => Err(CsError::Empty(channel)),
+                Message::SelectiveReceive(selection) => self
+                    .select(pg_id, action, channel, &queues[0], selection)?
+                    .map(|_| ())
+                    .ok_or(CsError::NoMatchingMessage(channel)),
                 Message::ProbeEmptyQueue | Message::ProbeFullQueue
                     if matches!(capacity, Some(0)) =>
                 {
@@ -371,10 +1101,8 @@ impl ChannelSystem {
                 Message::ProbeFullQueue if capacity.is_none() => {
                     Err(CsError::ProbingInfiniteQueue(channel))
                 }
-                Message::ProbeEmptyQueue if !queue.is_empty() => Err(CsError::NotEmpty(channel)),
-                Message::ProbeFullQueue if capacity.is_some_and(|cap| queue.len() < cap) => {
-                    Err(CsError::NotFull(channel))
-                }
+                Message::ProbeEmptyQueue if !empty => Err(CsError::NotEmpty(channel)),
+                Message::ProbeFullQueue if !full => Err(CsError::NotFull(channel)),
                 _ => Ok(()),
             }
         } else {
@@ -383,16 +1111,289 @@ impl ChannelSystem {
         }
     }
 
+    // Iterates over the messages in the channel's queue that can be received, together with their index:
+    // messages still in flight cannot be received yet, nor, for FIFO channels, those queued behind them.
+    fn arrived<'a>(
+        &'a self,
+        channel: Channel,
+        queue: &'a VecDeque<(Time, Val)>,
+    ) -> impl Iterator<Item = (usize, &'a Val)> + 'a {
+        let fifo = matches!(self.def.disciplines[channel.0 as usize], Discipline::Fifo);
+        queue
+            .iter()
+            .enumerate()
+            .take_while(move |(_, (time, _))| !fifo || *time <= self.time)
+            .filter(|(_, (time, _))| *time <= self.time)
+            .map(|(idx, (_, val))| (idx, val))
+    }
+
+    // Finds the index of the message in the queue that the selective receive would retrieve, if any.
+    fn select(
+        &self,
+        pg_id: PgId,
+        action: Action,
+        channel: Channel,
+        queue: &VecDeque<(Time, Val)>,
+        selection: Selection,
+    ) -> Result<Option<usize>, CsError> {
+        let pg = &self.program_graphs[pg_id.0 as usize];
+        let mut arrived = self.arrived(channel, queue);
+        match selection {
+            Selection::Front => arrived.next().map_or(Ok(None), |(idx, val)| {
+                pg.accepts(action.1, val)
+                    .map(|pass| pass.then_some(idx))
+                    .map_err(|err| CsError::ProgramGraph(pg_id, err))
+            }),
+            Selection::FirstMatching => {
+                for (idx, val) in arrived {
+                    if pg
+                        .accepts(action.1, val)
+                        .map_err(|err| CsError::ProgramGraph(pg_id, err))?
+                    {
+                        return Ok(Some(idx));
+                    }
+                }
+                Ok(None)
+            }
+        }
+    }
+
     /// Executes a transition on the given PG characterized by the argument action and post-state.
     ///
     /// Fails if the requested transition is not admissible.
     ///
+    /// Sending through a synchronous broadcast channel also executes,
+    /// for each other subscriber ready to receive, the first of its admissible receive transitions.
+    /// Receiving from a bag channel retrieves the oldest message that can be received:
+    /// see [`Self::bag_transition`] to retrieve a different one.
+    ///
+    /// Messages sent through unreliable channels are delivered as if they were reliable:
+    /// see [`Self::faulty_transition`] to simulate faults.
+    /// Messages sent through channels with latency are delayed by the minimum admitted delay:
+    /// see [`Self::delayed_transition`] to choose a different one.
+    ///
     /// See also [`ProgramGraph::transition`].
     pub fn transition(
         &mut self,
         pg_id: PgId,
         action: Action,
         post: Location,
+    ) -> Result<Option<Event>, CsError> {
+        let delay = self.min_delay(action);
+        self.execute(pg_id, action, post, None, delay, 0)
+    }
+
+    /// Executes a transition sending a message through a channel with latency,
+    /// which can be received only after the given delay.
+    ///
+    /// Fails if the requested transition is not admissible,
+    /// or if the delay is not admitted by the channel's latency.
+    ///
+    /// ```
+    /// # use scan_core::*;
+    /// # use scan_core::channel_system::*;
+    /// let mut cs_builder = ChannelSystemBuilder::new();
+    /// let chn = cs_builder.new_channel(Type::Integer, None);
+    /// cs_builder.set_latency(chn, Latency::Interval(1, 3)).unwrap();
+    ///
+    /// let sender = cs_builder.new_program_graph();
+    /// let initial_s = cs_builder.initial_location(sender).unwrap();
+    /// let send = cs_builder.new_send(sender, chn, CsExpression::from(1)).unwrap();
+    /// cs_builder.add_transition(sender, initial_s, send, initial_s, None).unwrap();
+    ///
+    /// let receiver = cs_builder.new_program_graph();
+    /// let initial_r = cs_builder.initial_location(receiver).unwrap();
+    /// let var = cs_builder.new_var(receiver, Expression::from(0)).unwrap();
+    /// let receive = cs_builder.new_receive(receiver, chn, var).unwrap();
+    /// cs_builder.add_transition(receiver, initial_r, receive, initial_r, None).unwrap();
+    ///
+    /// let mut cs = cs_builder.build();
+    /// assert!(cs.delayed_transition(sender, send, initial_s, 5).is_err());
+    /// cs.delayed_transition(sender, send, initial_s, 2).unwrap();
+    /// // The message is still in flight.
+    /// assert!(cs.transition(receiver, receive, initial_r).is_err());
+    /// cs.wait(2).unwrap();
+    /// assert!(cs.transition(receiver, receive, initial_r).is_ok());
+    /// ```
+    pub fn delayed_transition(
+        &mut self,
+        pg_id: PgId,
+        action: Action,
+        post: Location,
+        delay: Time,
+    ) -> Result<Option<Event>, CsError> {
+        match self.def.communication(action) {
+            Some((channel, Message::Send)) => {
+                if self.def.latencies[channel.0 as usize].admits(delay) {
+                    self.execute(pg_id, action, post, None, delay, 0)
+                } else {
+                    Err(CsError::ImpossibleDelay(channel, delay))
+                }
+            }
+            _ => Err(CsError::NoCommunication(action)),
+        }
+    }
+
+    // The minimum delay of messages sent by the action, if it is a send.
+    fn min_delay(&self, action: Action) -> Time {
+        match self.def.communication(action) {
+            Some((channel, Message::Send)) => self.def.latencies[channel.0 as usize].min(),
+            _ => 0,
+        }
+    }
+
+    /// Executes a transition sending a message through an unreliable channel,
+    /// with its delivery affected by the given fault.
+    ///
+    /// Fails if the requested transition is not admissible,
+    /// or if the fault cannot affect the channel (i.e., it has probability zero).
+    /// A fault that has no effect
+    /// (duplicating a message with no room for the copy, or reordering a message into an empty channel)
+    /// results in a normal send.
+    ///
+    /// ```
+    /// # use scan_core::*;
+    /// # use scan_core::channel_system::*;
+    /// let mut cs_builder = ChannelSystemBuilder::new();
+    /// let chn = cs_builder.new_channel(Type::Integer, None);
+    /// let faults = Faults { loss: 0.1, ..Default::default() };
+    /// cs_builder.set_faults(chn, faults).unwrap();
+    ///
+    /// let pg_id = cs_builder.new_program_graph();
+    /// let initial = cs_builder.initial_location(pg_id).unwrap();
+    /// let send = cs_builder.new_send(pg_id, chn, CsExpression::from(1)).unwrap();
+    /// cs_builder.add_transition(pg_id, initial, send, initial, None).unwrap();
+    ///
+    /// let mut cs = cs_builder.build();
+    /// let event = cs.faulty_transition(pg_id, send, initial, Fault::Loss).unwrap();
+    /// assert_eq!(event.unwrap().event_type, EventType::FaultySend(Fault::Loss, Val::Integer(1)));
+    /// // This channel never duplicates messages.
+    /// assert!(cs.faulty_transition(pg_id, send, initial, Fault::Duplication).is_err());
+    /// ```
+    pub fn faulty_transition(
+        &mut self,
+        pg_id: PgId,
+        action: Action,
+        post: Location,
+        fault: Fault,
+    ) -> Result<Option<Event>, CsError> {
+        match self.def.communication(action) {
+            Some((channel, Message::Send)) => {
+                if self.def.faults[channel.0 as usize]
+                    .is_some_and(|faults| faults.probability(fault) > 0f64)
+                {
+                    let delay = self.def.latencies[channel.0 as usize].min();
+                    self.execute(pg_id, action, post, Some(fault), delay, 0)
+                } else {
+                    Err(CsError::ImpossibleFault(channel, fault))
+                }
+            }
+            _ => Err(CsError::NoCommunication(action)),
+        }
+    }
+
+    /// Executes a transition receiving from a bag channel
+    /// the message at the given position among those that can be received,
+    /// in the order they were sent.
+    ///
+    /// Fails if the requested transition is not admissible,
+    /// if the action does not receive from a bag channel,
+    /// or if there are not enough messages that can be received.
+    ///
+    /// ```
+    /// # use scan_core::*;
+    /// # use scan_core::channel_system::*;
+    /// let mut cs_builder = ChannelSystemBuilder::new();
+    /// let chn = cs_builder.new_bag_channel(Type::Integer, None).unwrap();
+    ///
+    /// let pg_id = cs_builder.new_program_graph();
+    /// let initial = cs_builder.initial_location(pg_id).unwrap();
+    /// let var = cs_builder.new_var(pg_id, Expression::from(0)).unwrap();
+    /// let send_one = cs_builder.new_send(pg_id, chn, CsExpression::from(1)).unwrap();
+    /// let send_two = cs_builder.new_send(pg_id, chn, CsExpression::from(2)).unwrap();
+    /// let receive = cs_builder.new_receive(pg_id, chn, var).unwrap();
+    /// let sent_one = cs_builder.new_location(pg_id).unwrap();
+    /// let sent_two = cs_builder.new_location(pg_id).unwrap();
+    /// cs_builder.add_transition(pg_id, initial, send_one, sent_one, None).unwrap();
+    /// cs_builder.add_transition(pg_id, sent_one, send_two, sent_two, None).unwrap();
+    /// cs_builder.add_transition(pg_id, sent_two, receive, sent_two, None).unwrap();
+    ///
+    /// let mut cs = cs_builder.build();
+    /// cs.transition(pg_id, send_one, sent_one).unwrap();
+    /// cs.transition(pg_id, send_two, sent_two).unwrap();
+    /// assert!(cs.bag_transition(pg_id, receive, sent_two, 2).is_err());
+    /// let event = cs.bag_transition(pg_id, receive, sent_two, 1).unwrap();
+    /// assert_eq!(event.unwrap().event_type, EventType::Receive(Val::Integer(2)));
+    /// ```
+    pub fn bag_transition(
+        &mut self,
+        pg_id: PgId,
+        action: Action,
+        post: Location,
+        position: usize,
+    ) -> Result<Option<Event>, CsError> {
+        match self.def.communication(action) {
+            Some((channel, Message::Receive)) => {
+                if matches!(self.def.disciplines[channel.0 as usize], Discipline::Bag) {
+                    self.execute(pg_id, action, post, None, 0, position)
+                } else {
+                    Err(CsError::NotBag(channel))
+                }
+            }
+            _ => Err(CsError::NoCommunication(action)),
+        }
+    }
+
+    // Executes a transition, where a send is affected by `fault` and `delay`,
+    // and a receive from a bag retrieves the message at `position` among those that can be received.
+    fn execute(
+        &mut self,
+        pg_id: PgId,
+        action: Action,
+        post: Location,
+        fault: Option<Fault>,
+        delay: Time,
+        position: usize,
+    ) -> Result<Option<Event>, CsError> {
+        let event = self.step(pg_id, action, post, fault, delay, position)?;
+        self.transitioned(pg_id);
+        if let Some((channel, _)) = self.def.communication(action) {
+            self.update_stats(channel);
+        }
+        self.share_globals(pg_id);
+        Ok(event)
+    }
+
+    // Propagates the values of the global variables written by the given PG to all other PGs accessing them.
+    fn share_globals(&mut self, pg_id: PgId) {
+        let def = self.def.clone();
+        for &(var, global) in def.globals[pg_id.0 as usize].iter() {
+            let val = self.program_graphs[pg_id.0 as usize].val(var);
+            if *val != self.globals[global] {
+                let val = val.clone();
+                for &(other, other_var) in def.global_vars[global].iter() {
+                    if other != pg_id {
+                        self.program_graphs[other.0 as usize].set_val(other_var, val.clone());
+                    }
+                }
+                self.globals[global] = val;
+            }
+        }
+    }
+
+    /// Returns the current value of the given global variable.
+    pub fn global_val(&self, global: GlobalVar) -> Option<&Val> {
+        self.globals.get(global.0 as usize)
+    }
+
+    fn step(
+        &mut self,
+        pg_id: PgId,
+        action: Action,
+        post: Location,
+        fault: Option<Fault>,
+        delay: Time,
+        position: usize,
     ) -> Result<Option<Event>, CsError> {
         // If action is a communication, check it is legal
         if pg_id.0 >= self.program_graphs.len() as u16 {
@@ -402,58 +1403,174 @@ impl ChannelSystem {
         } else if post.0 != pg_id {
             return Err(CsError::LocationNotInPg(post, pg_id));
         }
+        self.check_precedence(&[pg_id])?;
         // If the action is a communication, send/receive the message
         if let Some((channel, message)) = self.def.communication(action) {
             let (_, capacity) = self.def.channels[channel.0 as usize];
+            // Sending a message pushes it to all of the channel's queues.
+            let queues = if let Message::Send = message {
+                self.def.queues(channel)
+            } else {
+                self.def.queues_of(pg_id, channel)
+            };
+            let full = capacity.is_some_and(|cap| {
+                self.message_queue[queues.clone()]
+                    .iter()
+                    .any(|queue| queue.len() >= cap)
+            });
+            let empty = !self.message_queue[queues.clone()]
+                .iter()
+                .any(|queue| self.arrived(channel, queue).next().is_some());
             let event_type = match message {
+                Message::Send if self.def.is_synchronous_broadcast(channel) => {
+                    return self
+                        .broadcast((pg_id, action, post), channel, |receives| {
+                            receives.into_iter().next()
+                        })
+                        .map(Some);
+                }
+                Message::Send | Message::Receive if matches!(capacity, Some(0)) => {
+                    return Err(CsError::Handshake(channel));
+                }
+                Message::Send if full => {
+                    return Err(CsError::OutOfCapacity(channel));
+                }
                 Message::Send
-                    if capacity
-                        .is_some_and(|cap| self.message_queue[channel.0 as usize].len() >= cap) =>
+                    if capacity.is_none()
+                        && self.queue_limit.is_some_and(|limit| {
+                            self.message_queue[queues.clone()]
+                                .iter()
+                                .any(|queue| queue.len() >= limit)
+                        }) =>
                 {
-                    return Err(CsError::OutOfCapacity(channel));
+                    return Err(CsError::QueueLimit(
+                        channel,
+                        self.queue_limit.expect("queue limit"),
+                    ));
                 }
                 Message::Send => {
+                    // Evaluate the priority before executing the transition, as it could fail.
+                    let priority = self.program_graphs[pg_id.0 as usize]
+                        .message(action.1, post.1)
+                        .map_err(|err| CsError::ProgramGraph(pg_id, err))
+                        .and_then(|val| {
+                            self.def
+                                .priority(channel, &val)
+                                .map_err(|err| CsError::Priority(channel, err))
+                        })?;
                     let val = self.program_graphs[pg_id.0 as usize]
                         .send(action.1, post.1)
                         .map_err(|err| CsError::ProgramGraph(pg_id, err))?;
-                    self.message_queue[channel.0 as usize].push_back(val.clone());
-                    EventType::Send(val)
-                }
-                Message::Receive if self.message_queue[channel.0 as usize].is_empty() => {
-                    return Err(CsError::Empty(channel))
+                    let time = self.time.saturating_add(delay);
+                    // Unreliable channels are point-to-point, so they have a single queue.
+                    let queue = &mut self.message_queue[queues.start];
+                    match fault {
+                        Some(Fault::Loss) => EventType::FaultySend(Fault::Loss, val),
+                        Some(Fault::Duplication)
+                            if capacity.is_none_or(|cap| queue.len() + 2 <= cap) =>
+                        {
+                            queue.push_back((time, val.clone()));
+                            queue.push_back((time, val.clone()));
+                            EventType::FaultySend(Fault::Duplication, val)
+                        }
+                        Some(Fault::Reordering) if !queue.is_empty() => {
+                            queue.insert(queue.len() - 1, (time, val.clone()));
+                            EventType::FaultySend(Fault::Reordering, val)
+                        }
+                        // Priority channels are point-to-point, so they have a single queue.
+                        _ if priority.is_some() => {
+                            // Messages already queued have had their priority evaluated before.
+                            let idx = queue.partition_point(|(_, other)| {
+                                self.def
+                                    .priority(channel, other)
+                                    .expect("priority of queued message")
+                                    >= priority
+                            });
+                            queue.insert(idx, (time, val.clone()));
+                            EventType::Send(val)
+                        }
+                        _ => {
+                            for queue in &mut self.message_queue[queues] {
+                                queue.push_back((time, val.clone()));
+                            }
+                            if let Some(subscribers) = &self.def.subscribers[channel.0 as usize] {
+                                EventType::Broadcast(subscribers.clone(), val)
+                            } else {
+                                EventType::Send(val)
+                            }
+                        }
+                    }
                 }
+                Message::Receive if empty => return Err(CsError::Empty(channel)),
                 Message::Receive => {
-                    let val = self.message_queue[channel.0 as usize]
-                        .pop_front()
+                    // A PG only ever receives from a single queue.
+                    let (idx, _) = self
+                        .arrived(channel, &self.message_queue[queues.start])
+                        .nth(position)
+                        .ok_or(CsError::MissingMessage(channel, position))?;
+                    let (time, val) = self.message_queue[queues.start]
+                        .remove(idx)
                         .expect("communication has been verified before");
-                    self.program_graphs[pg_id.0 as usize]
-                        .receive(action.1, post.1, val.clone())
+                    if let Err(err) =
+                        self.program_graphs[pg_id.0 as usize].receive(action.1, post.1, val.clone())
+                    {
+                        // Put the message back, so that the channel is left untouched.
+                        self.message_queue[queues.start].insert(idx, (time, val));
+                        return Err(CsError::ProgramGraph(pg_id, err));
+                    }
+                    EventType::Receive(val)
+                }
+                Message::SelectiveReceive(_) if empty => return Err(CsError::Empty(channel)),
+                Message::SelectiveReceive(selection) => {
+                    let idx = self
+                        .select(
+                            pg_id,
+                            action,
+                            channel,
+                            &self.message_queue[queues.start],
+                            selection,
+                        )?
+                        .ok_or(CsError::NoMatchingMessage(channel))?;
+                    let (time, val) = self.message_queue[queues.start]
+                        .remove(idx)
                         .expect("communication has been verified before");
+                    if let Err(err) =
+                        self.program_graphs[pg_id.0 as usize].receive(action.1, post.1, val.clone())
+                    {
+                        // Put the message back, so that the channel is left untouched.
+                        self.message_queue[queues.start].insert(idx, (time, val));
+                        return Err(CsError::ProgramGraph(pg_id, err));
+                    }
                     EventType::Receive(val)
                 }
+                Message::Peek if empty => return Err(CsError::Empty(channel)),
+                Message::Peek => {
+                    let val = self
+                        .arrived(channel, &self.message_queue[queues.start])
+                        .next()
+                        .map(|(_, val)| val.clone())
+                        .expect("communication has been verified before");
+                    self.program_graphs[pg_id.0 as usize]
+                        .receive(action.1, post.1, val.clone())
+                        .map_err(|err| CsError::ProgramGraph(pg_id, err))?;
+                    EventType::Peek(val)
+                }
                 Message::ProbeEmptyQueue | Message::ProbeFullQueue
                     if matches!(capacity, Some(0)) =>
                 {
                     return Err(CsError::ProbingHandshakeChannel(channel));
                 }
-                Message::ProbeEmptyQueue if !self.message_queue[channel.0 as usize].is_empty() => {
-                    return Err(CsError::NotEmpty(channel))
-                }
+                Message::ProbeEmptyQueue if !empty => return Err(CsError::NotEmpty(channel)),
                 Message::ProbeEmptyQueue => {
                     self.program_graphs[pg_id.0 as usize]
                         .transition(action.1, post.1)
                         .map_err(|err| CsError::ProgramGraph(pg_id, err))?;
                     EventType::ProbeEmptyQueue
                 }
-                Message::ProbeFullQueue
-                    if capacity
-                        .is_some_and(|cap| self.message_queue[channel.0 as usize].len() < cap) =>
-                {
-                    return Err(CsError::NotFull(channel));
-                }
                 Message::ProbeFullQueue if capacity.is_none() => {
                     return Err(CsError::ProbingInfiniteQueue(channel));
                 }
+                Message::ProbeFullQueue if !full => return Err(CsError::NotFull(channel)),
                 Message::ProbeFullQueue => {
                     self.program_graphs[pg_id.0 as usize]
                         .transition(action.1, post.1)
@@ -475,6 +1592,110 @@ impl ChannelSystem {
         }
     }
 
+    /// Executes a send transition and a receive transition (of another PG) through the same handshake channel,
+    /// as a single step.
+    ///
+    /// Fails if the transitions are not admissible, or if they do not form an handshake.
+    /// If the receive fails, neither transition is executed.
+    ///
+    /// See also [`Self::possible_handshakes`].
+    pub fn handshake(&mut self, send: Transition, receive: Transition) -> Result<Event, CsError> {
+        for (pg_id, action, post) in [send, receive] {
+            if pg_id.0 >= self.program_graphs.len() as u16 {
+                return Err(CsError::MissingPg(pg_id));
+            } else if action.0 != pg_id {
+                return Err(CsError::ActionNotInPg(action, pg_id));
+            } else if post.0 != pg_id {
+                return Err(CsError::LocationNotInPg(post, pg_id));
+            }
+        }
+        let channel = match (
+            self.def.communication(send.1),
+            self.def.communication(receive.1),
+        ) {
+            (Some((channel, Message::Send)), Some((other, Message::Receive)))
+                if channel == other
+                    && send.0 != receive.0
+                    && matches!(self.def.channels[channel.0 as usize].1, Some(0))
+                    && self.def.subscribers[channel.0 as usize].is_none() =>
+            {
+                channel
+            }
+            _ => return Err(CsError::NotHandshake(send.1, receive.1)),
+        };
+        self.check_precedence(&[send.0, receive.0])?;
+        let val = self.program_graphs[send.0 .0 as usize]
+            .message(send.1 .1, send.2 .1)
+            .map_err(|err| CsError::ProgramGraph(send.0, err))?;
+        self.program_graphs[receive.0 .0 as usize]
+            .receive(receive.1 .1, receive.2 .1, val)
+            .map_err(|err| CsError::ProgramGraph(receive.0, err))?;
+        // Computing the message again cannot fail, as the sender's state has not changed.
+        let val = self.program_graphs[send.0 .0 as usize]
+            .send(send.1 .1, send.2 .1)
+            .map_err(|err| CsError::ProgramGraph(send.0, err))?;
+        self.transitioned(send.0);
+        self.transitioned(receive.0);
+        self.share_globals(receive.0);
+        Ok(Event {
+            pg_id: send.0,
+            channel,
+            event_type: EventType::Handshake(receive.0, val),
+        })
+    }
+
+    // Executes a send through a synchronous broadcast channel, as a single step together with
+    // a receive transition for each other subscriber ready to receive, as chosen by `select`.
+    // If any of the receives fails, no transition is executed.
+    fn broadcast(
+        &mut self,
+        send: Transition,
+        channel: Channel,
+        mut select: impl FnMut(Vec<Transition>) -> Option<Transition>,
+    ) -> Result<Event, CsError> {
+        let (pg_id, action, post) = send;
+        let val = self.program_graphs[pg_id.0 as usize]
+            .message(action.1, post.1)
+            .map_err(|err| CsError::ProgramGraph(pg_id, err))?;
+        let receives = self.def.subscribers[channel.0 as usize]
+            .iter()
+            .flatten()
+            .filter(|subscriber| **subscriber != pg_id)
+            .filter_map(|subscriber| {
+                select(Vec::from_iter(
+                    self.ready(*subscriber, (channel, Message::Receive)),
+                ))
+            })
+            .collect::<Vec<_>>();
+        for (pg_id, action, post) in receives.iter() {
+            self.program_graphs[pg_id.0 as usize]
+                .check_receive(action.1, post.1, &val)
+                .map_err(|err| CsError::ProgramGraph(*pg_id, err))?;
+        }
+        for (pg_id, action, post) in receives.iter() {
+            self.program_graphs[pg_id.0 as usize]
+                .receive(action.1, post.1, val.clone())
+                .map_err(|err| CsError::ProgramGraph(*pg_id, err))?;
+        }
+        // Computing the message again cannot fail, as the sender's state has not changed.
+        let val = self.program_graphs[pg_id.0 as usize]
+            .send(action.1, post.1)
+            .map_err(|err| CsError::ProgramGraph(pg_id, err))?;
+        self.transitioned(pg_id);
+        for (pg_id, _, _) in receives.iter() {
+            self.transitioned(*pg_id);
+            self.share_globals(*pg_id);
+        }
+        Ok(Event {
+            pg_id,
+            channel,
+            event_type: EventType::Broadcast(
+                receives.into_iter().map(|(pg_id, _, _)| pg_id).collect(),
+                val,
+            ),
+        })
+    }
+
     /// Tries waiting for the given delta of time.
     /// Returns error if any of the PG cannot wait due to some time invariant.
     pub fn wait(&mut self, delta: Time) -> Result<(), CsError> {
@@ -610,4 +1831,732 @@ mod tests {
         assert_eq!(cs.possible_transitions().count(), 0);
         Ok(())
     }
+
+    #[test]
+    fn handshake() -> Result<(), CsError> {
+        use rand::rngs::SmallRng;
+        use rand::SeedableRng;
+
+        let mut cs = ChannelSystemBuilder::new();
+        let ch = cs.new_channel(Type::Integer, Some(0));
+
+        let pg1 = cs.new_program_graph();
+        let initial1 = cs.initial_location(pg1)?;
+        let post1 = cs.new_location(pg1)?;
+        let send = cs.new_send(pg1, ch, CsExpression::Const(Val::Integer(1)))?;
+        cs.add_transition(pg1, initial1, send, post1, None)?;
+
+        let pg2 = cs.new_program_graph();
+        let initial2 = cs.initial_location(pg2)?;
+        let post2 = cs.new_location(pg2)?;
+        let var = cs.new_var(pg2, Expression::Const(Val::Integer(0)))?;
+        let receive = cs.new_receive(pg2, ch, var)?;
+        cs.add_transition(pg2, initial2, receive, post2, None)?;
+
+        let mut cs = cs.build();
+        assert_eq!(cs.possible_transitions().count(), 0);
+        assert_eq!(cs.possible_handshakes().count(), 1);
+        assert!(matches!(
+            cs.transition(pg1, send, post1),
+            Err(CsError::Handshake(_))
+        ));
+        assert!(matches!(
+            cs.handshake((pg2, receive, post2), (pg1, send, post1)),
+            Err(CsError::NotHandshake(..))
+        ));
+
+        let rng = &mut SmallRng::seed_from_u64(0);
+        let event = cs.montecarlo_execution(rng, 0)?.expect("handshake event");
+        assert_eq!(event.pg_id, pg1);
+        assert_eq!(event.event_type, EventType::Handshake(pg2, Val::Integer(1)));
+        assert_eq!(cs.possible_handshakes().count(), 0);
+        assert!(cs.montecarlo_execution(rng, 0)?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn broadcast() -> Result<(), CsError> {
+        let mut cs = ChannelSystemBuilder::new();
+        let ch = cs.new_broadcast_channel(Type::Integer, Some(1));
+
+        let pg0 = cs.new_program_graph();
+        let initial0 = cs.initial_location(pg0)?;
+        let send = cs.new_send(pg0, ch, CsExpression::Const(Val::Integer(1)))?;
+        cs.add_transition(pg0, initial0, send, initial0, None)?;
+
+        let mut receivers = Vec::new();
+        for _ in 0..2 {
+            let pg = cs.new_program_graph();
+            let initial = cs.initial_location(pg)?;
+            let post = cs.new_location(pg)?;
+            let var = cs.new_var(pg, Expression::Const(Val::Integer(0)))?;
+            let receive = cs.new_receive(pg, ch, var)?;
+            cs.add_transition(pg, initial, receive, post, None)?;
+            receivers.push((pg, receive, post));
+        }
+
+        let mut cs = cs.build();
+        let event = cs
+            .transition(pg0, send, initial0)?
+            .expect("broadcast event");
+        assert_eq!(
+            event.event_type,
+            EventType::Broadcast(vec![receivers[0].0, receivers[1].0], Val::Integer(1))
+        );
+        // Every subscriber's queue is full.
+        assert!(matches!(
+            cs.transition(pg0, send, initial0),
+            Err(CsError::OutOfCapacity(_))
+        ));
+        let (pg, receive, post) = receivers[0];
+        cs.transition(pg, receive, post)?;
+        // One subscriber's queue is still full.
+        assert!(matches!(
+            cs.transition(pg0, send, initial0),
+            Err(CsError::OutOfCapacity(_))
+        ));
+        let (pg, receive, post) = receivers[1];
+        cs.transition(pg, receive, post)?;
+        assert_eq!(cs.possible_transitions().count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn synchronous_broadcast() -> Result<(), CsError> {
+        let mut cs = ChannelSystemBuilder::new();
+        let ch = cs.new_broadcast_channel(Type::Integer, Some(0));
+
+        let pg0 = cs.new_program_graph();
+        let initial0 = cs.initial_location(pg0)?;
+        let send = cs.new_send(pg0, ch, CsExpression::Const(Val::Integer(1)))?;
+        cs.add_transition(pg0, initial0, send, initial0, None)?;
+
+        // Only the first subscriber is ready to receive.
+        let pg1 = cs.new_program_graph();
+        let initial1 = cs.initial_location(pg1)?;
+        let var1 = cs.new_var(pg1, Expression::Const(Val::Integer(0)))?;
+        let receive1 = cs.new_receive(pg1, ch, var1)?;
+        cs.add_transition(pg1, initial1, receive1, initial1, None)?;
+        let pg2 = cs.new_program_graph();
+        let var2 = cs.new_var(pg2, Expression::Const(Val::Integer(0)))?;
+        let receive2 = cs.new_receive(pg2, ch, var2)?;
+        let post2 = cs.new_location(pg2)?;
+        cs.add_transition(pg2, post2, receive2, post2, None)?;
+
+        let mut cs = cs.build();
+        // Only the send can be executed on its own.
+        assert_eq!(
+            Vec::from_iter(cs.possible_transitions()),
+            vec![(pg0, send, initial0)]
+        );
+        assert!(matches!(
+            cs.transition(pg1, receive1, initial1),
+            Err(CsError::Handshake(_))
+        ));
+        let event = cs
+            .transition(pg0, send, initial0)?
+            .expect("broadcast event");
+        assert_eq!(
+            event.event_type,
+            EventType::Broadcast(vec![pg1], Val::Integer(1))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn faults() -> Result<(), CsError> {
+        let mut cs = ChannelSystemBuilder::new();
+        let ch = cs.new_channel(Type::Integer, Some(2));
+        let invalid = Faults {
+            loss: 0.6,
+            duplication: 0.6,
+            reordering: 0.0,
+        };
+        assert!(matches!(
+            cs.set_faults(ch, invalid),
+            Err(CsError::InvalidFaults(..))
+        ));
+        let handshake = cs.new_channel(Type::Integer, Some(0));
+        assert!(matches!(
+            cs.set_faults(handshake, Faults::default()),
+            Err(CsError::UnreliableChannel(_))
+        ));
+        let faults = Faults {
+            loss: 0.2,
+            duplication: 0.2,
+            reordering: 0.2,
+        };
+        cs.set_faults(ch, faults)?;
+
+        let pg0 = cs.new_program_graph();
+        let initial0 = cs.initial_location(pg0)?;
+        let var = cs.new_var(pg0, Expression::Const(Val::Integer(0)))?;
+        let send = cs.new_send(pg0, ch, CsExpression::Var(var, Type::Integer))?;
+        cs.add_transition(pg0, initial0, send, initial0, None)?;
+        let increment = cs.new_action(pg0)?;
+        cs.add_effect(
+            pg0,
+            increment,
+            var,
+            CsExpression::Sum(vec![
+                CsExpression::Var(var, Type::Integer),
+                CsExpression::Const(Val::Integer(1)),
+            ]),
+        )?;
+        cs.add_transition(pg0, initial0, increment, initial0, None)?;
+
+        let pg1 = cs.new_program_graph();
+        let initial1 = cs.initial_location(pg1)?;
+        let var1 = cs.new_var(pg1, Expression::Const(Val::Integer(0)))?;
+        let receive = cs.new_receive(pg1, ch, var1)?;
+        cs.add_transition(pg1, initial1, receive, initial1, None)?;
+
+        let mut cs = cs.build();
+        let event = cs.faulty_transition(pg0, send, initial0, Fault::Loss)?;
+        assert_eq!(
+            event.expect("event").event_type,
+            EventType::FaultySend(Fault::Loss, Val::Integer(0))
+        );
+        let event = cs.faulty_transition(pg0, send, initial0, Fault::Duplication)?;
+        assert_eq!(
+            event.expect("event").event_type,
+            EventType::FaultySend(Fault::Duplication, Val::Integer(0))
+        );
+        // Receive both copies.
+        for _ in 0..2 {
+            let event = cs.transition(pg1, receive, initial1)?;
+            assert_eq!(
+                event.expect("event").event_type,
+                EventType::Receive(Val::Integer(0))
+            );
+        }
+        // Send 0, then 1 reordered ahead of it.
+        cs.transition(pg0, send, initial0)?;
+        cs.transition(pg0, increment, initial0)?;
+        let event = cs.faulty_transition(pg0, send, initial0, Fault::Reordering)?;
+        assert_eq!(
+            event.expect("event").event_type,
+            EventType::FaultySend(Fault::Reordering, Val::Integer(1))
+        );
+        for val in [1, 0] {
+            let event = cs.transition(pg1, receive, initial1)?;
+            assert_eq!(
+                event.expect("event").event_type,
+                EventType::Receive(Val::Integer(val))
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn latency() -> Result<(), CsError> {
+        let mut cs = ChannelSystemBuilder::new();
+        let ch = cs.new_channel(Type::Integer, None);
+        assert!(matches!(
+            cs.set_latency(ch, Latency::Interval(3, 1)),
+            Err(CsError::InvalidLatency(..))
+        ));
+        let handshake = cs.new_channel(Type::Integer, Some(0));
+        assert!(matches!(
+            cs.set_latency(handshake, Latency::Fixed(1)),
+            Err(CsError::DelayedHandshake(_))
+        ));
+        cs.set_latency(ch, Latency::Interval(1, 3))?;
+
+        let pg0 = cs.new_program_graph();
+        let initial0 = cs.initial_location(pg0)?;
+        let send_0 = cs.new_send(pg0, ch, CsExpression::Const(Val::Integer(0)))?;
+        cs.add_transition(pg0, initial0, send_0, initial0, None)?;
+        let send_1 = cs.new_send(pg0, ch, CsExpression::Const(Val::Integer(1)))?;
+        cs.add_transition(pg0, initial0, send_1, initial0, None)?;
+
+        let pg1 = cs.new_program_graph();
+        let initial1 = cs.initial_location(pg1)?;
+        let var = cs.new_var(pg1, Expression::Const(Val::Integer(0)))?;
+        let receive = cs.new_receive(pg1, ch, var)?;
+        cs.add_transition(pg1, initial1, receive, initial1, None)?;
+
+        let mut cs = cs.build();
+        assert!(matches!(
+            cs.delayed_transition(pg0, send_0, initial0, 0),
+            Err(CsError::ImpossibleDelay(_, 0))
+        ));
+        cs.delayed_transition(pg0, send_0, initial0, 3)?;
+        // Sent with the minimum delay.
+        cs.transition(pg0, send_1, initial0)?;
+        cs.wait(1)?;
+        // The second message cannot overtake the first one.
+        assert!(matches!(
+            cs.transition(pg1, receive, initial1),
+            Err(CsError::Empty(_))
+        ));
+        cs.wait(2)?;
+        for val in [0, 1] {
+            let event = cs.transition(pg1, receive, initial1)?;
+            assert_eq!(
+                event.expect("event").event_type,
+                EventType::Receive(Val::Integer(val))
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn selective_receive() -> Result<(), CsError> {
+        let mut cs = ChannelSystemBuilder::new();
+        let ch = cs.new_channel(Type::Integer, None);
+        let handshake = cs.new_channel(Type::Integer, Some(0));
+
+        let pg0 = cs.new_program_graph();
+        let initial0 = cs.initial_location(pg0)?;
+        let send_0 = cs.new_send(pg0, ch, CsExpression::Const(Val::Integer(0)))?;
+        cs.add_transition(pg0, initial0, send_0, initial0, None)?;
+        let send_1 = cs.new_send(pg0, ch, CsExpression::Const(Val::Integer(1)))?;
+        cs.add_transition(pg0, initial0, send_1, initial0, None)?;
+
+        let pg1 = cs.new_program_graph();
+        let initial1 = cs.initial_location(pg1)?;
+        let var = cs.new_var(pg1, Expression::Const(Val::Integer(0)))?;
+        let positive = CsExpression::Greater(Box::new((
+            CsExpression::Var(var, Type::Integer),
+            CsExpression::Const(Val::Integer(0)),
+        )));
+        assert!(matches!(
+            cs.new_selective_receive(pg1, handshake, var, positive.clone(), Selection::Front),
+            Err(CsError::SelectingHandshakeChannel(_))
+        ));
+        assert!(matches!(
+            cs.new_peek(pg1, handshake, var),
+            Err(CsError::SelectingHandshakeChannel(_))
+        ));
+        let front = cs.new_selective_receive(pg1, ch, var, positive.clone(), Selection::Front)?;
+        cs.add_transition(pg1, initial1, front, initial1, None)?;
+        let first = cs.new_selective_receive(pg1, ch, var, positive, Selection::FirstMatching)?;
+        cs.add_transition(pg1, initial1, first, initial1, None)?;
+        let peek = cs.new_peek(pg1, ch, var)?;
+        cs.add_transition(pg1, initial1, peek, initial1, None)?;
+
+        let mut cs = cs.build();
+        assert!(matches!(
+            cs.transition(pg1, peek, initial1),
+            Err(CsError::Empty(_))
+        ));
+        cs.transition(pg0, send_0, initial0)?;
+        cs.transition(pg0, send_1, initial0)?;
+        // The message in front does not satisfy the guard.
+        assert!(matches!(
+            cs.transition(pg1, front, initial1),
+            Err(CsError::NoMatchingMessage(_))
+        ));
+        assert_eq!(
+            Vec::from_iter(
+                cs.possible_transitions()
+                    .filter(|(pg_id, ..)| *pg_id == pg1)
+            ),
+            vec![(pg1, first, initial1), (pg1, peek, initial1)]
+        );
+        // Peeking leaves the message in the channel.
+        for _ in 0..2 {
+            let event = cs.transition(pg1, peek, initial1)?;
+            assert_eq!(
+                event.expect("event").event_type,
+                EventType::Peek(Val::Integer(0))
+            );
+        }
+        let event = cs.transition(pg1, first, initial1)?;
+        assert_eq!(
+            event.expect("event").event_type,
+            EventType::Receive(Val::Integer(1))
+        );
+        // Only the message that did not satisfy the guard is left.
+        let event = cs.transition(pg1, peek, initial1)?;
+        assert_eq!(
+            event.expect("event").event_type,
+            EventType::Peek(Val::Integer(0))
+        );
+        assert!(matches!(
+            cs.transition(pg1, first, initial1),
+            Err(CsError::NoMatchingMessage(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn bag_and_priority() -> Result<(), CsError> {
+        let mut cs = ChannelSystemBuilder::new();
+        assert!(matches!(
+            cs.new_bag_channel(Type::Integer, Some(0)),
+            Err(CsError::HandshakeBag)
+        ));
+        let bag = cs.new_bag_channel(Type::Integer, None)?;
+        let prio = cs.new_channel(Type::Integer, None);
+        assert!(matches!(
+            cs.set_priority(bag, Expression::Var(bag, Type::Integer)),
+            Err(CsError::PriorityChannel(_))
+        ));
+        assert!(matches!(
+            cs.set_priority(prio, Expression::Var(bag, Type::Integer)),
+            Err(CsError::Type(_))
+        ));
+        assert!(matches!(
+            cs.set_faults(bag, Faults::default()),
+            Err(CsError::UnreliableChannel(_))
+        ));
+        // Lower values have higher priority.
+        cs.set_priority(prio, -Expression::Var(prio, Type::Integer))?;
+
+        let pg0 = cs.new_program_graph();
+        let initial0 = cs.initial_location(pg0)?;
+        let mut sends = Vec::new();
+        for val in [2, 0, 1, 0] {
+            for chn in [bag, prio] {
+                let send = cs.new_send(pg0, chn, CsExpression::Const(Val::Integer(val)))?;
+                sends.push(send);
+            }
+        }
+
+        let pg1 = cs.new_program_graph();
+        let initial1 = cs.initial_location(pg1)?;
+        let var = cs.new_var(pg1, Expression::Const(Val::Integer(0)))?;
+        let receive_bag = cs.new_receive(pg1, bag, var)?;
+        cs.add_transition(pg1, initial1, receive_bag, initial1, None)?;
+        let receive_prio = cs.new_receive(pg1, prio, var)?;
+        cs.add_transition(pg1, initial1, receive_prio, initial1, None)?;
+        for &send in sends.iter() {
+            cs.add_transition(pg0, initial0, send, initial0, None)?;
+        }
+
+        let mut cs = cs.build();
+        for send in sends {
+            cs.transition(pg0, send, initial0)?;
+        }
+        for val in [0, 0, 1, 2] {
+            let event = cs.transition(pg1, receive_prio, initial1)?;
+            assert_eq!(
+                event.expect("event").event_type,
+                EventType::Receive(Val::Integer(val))
+            );
+        }
+        // Receiving from a bag deterministically retrieves the oldest message,
+        // unless another one is chosen.
+        assert!(matches!(
+            cs.bag_transition(pg1, receive_prio, initial1, 0),
+            Err(CsError::NotBag(_))
+        ));
+        assert!(matches!(
+            cs.bag_transition(pg1, receive_bag, initial1, 4),
+            Err(CsError::MissingMessage(_, 4))
+        ));
+        let event = cs.bag_transition(pg1, receive_bag, initial1, 2)?;
+        assert_eq!(
+            event.expect("event").event_type,
+            EventType::Receive(Val::Integer(1))
+        );
+        for val in [2, 0, 0] {
+            let event = cs.transition(pg1, receive_bag, initial1)?;
+            assert_eq!(
+                event.expect("event").event_type,
+                EventType::Receive(Val::Integer(val))
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn bag_montecarlo() -> Result<(), CsError> {
+        use rand::rngs::SmallRng;
+        use rand::SeedableRng;
+
+        let mut cs = ChannelSystemBuilder::new();
+        let bag = cs.new_bag_channel(Type::Integer, None)?;
+        let pg0 = cs.new_program_graph();
+        let mut loc0 = cs.initial_location(pg0)?;
+        let mut sends = Vec::new();
+        for val in 0..3 {
+            let send = cs.new_send(pg0, bag, CsExpression::from(val))?;
+            let next = cs.new_location(pg0)?;
+            cs.add_transition(pg0, loc0, send, next, None)?;
+            sends.push((send, next));
+            loc0 = next;
+        }
+        let pg1 = cs.new_program_graph();
+        let initial1 = cs.initial_location(pg1)?;
+        let var = cs.new_var(pg1, Expression::from(0))?;
+        let receive = cs.new_receive(pg1, bag, var)?;
+        cs.add_transition(pg1, initial1, receive, initial1, None)?;
+        let mut cs = cs.build();
+        for (send, next) in sends {
+            cs.transition(pg0, send, next)?;
+        }
+
+        // Only the receiver can still transition, retrieving the messages in random order.
+        let rng = &mut SmallRng::seed_from_u64(0);
+        let mut orders = std::collections::HashSet::new();
+        for _ in 0..100 {
+            let mut cs = cs.clone();
+            let mut order = Vec::new();
+            while let Some(event) = cs.montecarlo_execution(rng, 0)? {
+                order.push(event.event_type);
+            }
+            assert_eq!(order.len(), 3);
+            orders.insert(order);
+        }
+        assert!(orders.len() > 1);
+        Ok(())
+    }
+
+    #[test]
+    fn global_vars() -> Result<(), CsError> {
+        let mut cs = ChannelSystemBuilder::new();
+        assert!(matches!(
+            cs.new_global_var(Val::Integer(-1), Type::Bounded(0, 3)),
+            Err(CsError::OutOfRange(..))
+        ));
+        let global = cs.new_global_var(Val::Integer(0), Type::Bounded(0, 3))?;
+        let ch = cs.new_channel(Type::Integer, None);
+
+        let pg0 = cs.new_program_graph();
+        let initial0 = cs.initial_location(pg0)?;
+        let var0 = cs.global_var(pg0, global)?;
+        assert_eq!(cs.global_var(pg0, global)?, var0);
+        // Effects on global variables are executed atomically.
+        let increment = cs.new_action(pg0)?;
+        for _ in 0..2 {
+            cs.add_effect(
+                pg0,
+                increment,
+                var0,
+                CsExpression::Var(var0, Type::Integer) + CsExpression::from(1),
+            )?;
+        }
+        cs.add_transition(pg0, initial0, increment, initial0, None)?;
+        let send = cs.new_send(pg0, ch, CsExpression::Var(var0, Type::Integer))?;
+        cs.add_transition(pg0, initial0, send, initial0, None)?;
+
+        let pg1 = cs.new_program_graph();
+        let initial1 = cs.initial_location(pg1)?;
+        let var1 = cs.global_var(pg1, global)?;
+        let receive = cs.new_receive(pg1, ch, var1)?;
+        cs.add_transition(pg1, initial1, receive, initial1, None)?;
+
+        let mut cs = cs.build();
+        cs.transition(pg0, send, initial0)?;
+        cs.transition(pg0, increment, initial0)?;
+        assert_eq!(cs.global_val(global), Some(&Val::Integer(2)));
+        // Receiving writes the global variable as well.
+        cs.transition(pg1, receive, initial1)?;
+        assert_eq!(cs.global_val(global), Some(&Val::Integer(0)));
+        cs.transition(pg0, increment, initial0)?;
+        assert_eq!(cs.global_val(global), Some(&Val::Integer(2)));
+        // The global variable cannot exceed its bounds.
+        assert!(cs.transition(pg0, increment, initial0).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn global_race() -> Result<(), CsError> {
+        use rand::rngs::SmallRng;
+        use rand::SeedableRng;
+
+        let mut cs = ChannelSystemBuilder::new();
+        let global = cs.new_global_var(Val::Integer(0), Type::Integer)?;
+        // Each PG increments the global variable non-atomically.
+        for _ in 0..2 {
+            let pg = cs.new_program_graph();
+            let initial = cs.initial_location(pg)?;
+            let var = cs.global_var(pg, global)?;
+            let tmp = cs.new_var(pg, Expression::from(0))?;
+            let read = cs.new_action(pg)?;
+            cs.add_effect(pg, read, tmp, CsExpression::Var(var, Type::Integer))?;
+            let loaded = cs.new_location(pg)?;
+            cs.add_transition(pg, initial, read, loaded, None)?;
+            let write = cs.new_action(pg)?;
+            cs.add_effect(
+                pg,
+                write,
+                var,
+                CsExpression::Var(tmp, Type::Integer) + CsExpression::from(1),
+            )?;
+            let stored = cs.new_location(pg)?;
+            cs.add_transition(pg, loaded, write, stored, None)?;
+        }
+        let cs = cs.build();
+
+        // The increments are lost if both PGs read the global variable before writing it.
+        let rng = &mut SmallRng::seed_from_u64(0);
+        let mut outcomes = std::collections::HashSet::new();
+        for _ in 0..100 {
+            let mut cs = cs.clone();
+            assert!(cs.montecarlo_execution(rng, 0)?.is_none());
+            outcomes.insert(cs.global_val(global).cloned().expect("global"));
+        }
+        assert_eq!(
+            outcomes,
+            std::collections::HashSet::from([Val::Integer(1), Val::Integer(2)])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn channel_stats() -> Result<(), CsError> {
+        use rand::rngs::SmallRng;
+        use rand::SeedableRng;
+
+        let mut cs = ChannelSystemBuilder::new();
+        let chn = cs.new_channel(Type::Integer, Some(2));
+        let pg = cs.new_program_graph();
+        let initial = cs.initial_location(pg)?;
+        let send = cs.new_send(pg, chn, CsExpression::Const(Val::Integer(0)))?;
+        cs.add_transition(pg, initial, send, initial, None)?;
+        let mut cs = cs.build();
+        cs.transition(pg, send, initial)?;
+        cs.transition(pg, send, initial)?;
+        // The send stays blocked until the duration is exceeded, being counted only once.
+        let rng = &mut SmallRng::seed_from_u64(0);
+        assert!(cs.montecarlo_execution(rng, 3)?.is_none());
+        let (channel, stats) = cs.channel_stats()[0];
+        assert_eq!(channel, chn);
+        assert_eq!(stats.max_occupancy, 2);
+        assert_eq!(stats.blocked_sends, 1);
+        assert_eq!(stats.time_full, 4);
+        assert_eq!(stats.full_ratio(), 1f64);
+        assert_eq!(stats.avg_occupancy(), 2f64);
+
+        let mut cs = ChannelSystemBuilder::new();
+        let chn = cs.new_channel(Type::Integer, None);
+        let pg = cs.new_program_graph();
+        let initial = cs.initial_location(pg)?;
+        let send = cs.new_send(pg, chn, CsExpression::Const(Val::Integer(0)))?;
+        cs.add_transition(pg, initial, send, initial, None)?;
+        let mut cs = cs.build();
+        cs.set_queue_limit(Some(2));
+        cs.transition(pg, send, initial)?;
+        cs.transition(pg, send, initial)?;
+        assert!(matches!(
+            cs.transition(pg, send, initial),
+            Err(CsError::QueueLimit(_, 2))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn urgency() -> Result<(), CsError> {
+        let mut cs = ChannelSystemBuilder::new();
+        let pg0 = cs.new_program_graph();
+        let initial0 = cs.initial_location(pg0)?;
+        let committed = cs.new_location_with_urgency(pg0, Urgency::Committed)?;
+        let enter = cs.new_action(pg0)?;
+        cs.add_transition(pg0, initial0, enter, committed, None)?;
+        let exit = cs.new_action(pg0)?;
+        cs.add_transition(pg0, committed, exit, initial0, None)?;
+
+        let pg1 = cs.new_program_graph();
+        let initial1 = cs.initial_location(pg1)?;
+        let urgent = cs.new_location(pg1)?;
+        cs.set_urgency(urgent, Urgency::Urgent)?;
+        let hurry = cs.new_action(pg1)?;
+        cs.add_transition(pg1, initial1, hurry, urgent, None)?;
+
+        let mut cs = cs.build();
+        assert_eq!(cs.possible_transitions().count(), 2);
+        cs.wait(1)?;
+        cs.transition(pg0, enter, committed)?;
+        // Only the PG in the committed location can transition, and time cannot elapse.
+        assert_eq!(
+            Vec::from_iter(cs.possible_transitions()),
+            vec![(pg0, exit, initial0)]
+        );
+        assert!(matches!(
+            cs.transition(pg1, hurry, urgent),
+            Err(CsError::Committed(_))
+        ));
+        assert!(matches!(
+            cs.wait(1),
+            Err(CsError::ProgramGraph(_, PgError::Urgent(_)))
+        ));
+        cs.transition(pg0, exit, initial0)?;
+        cs.transition(pg1, hurry, urgent)?;
+        // Other PGs can still transition while one is in an urgent location, but time cannot elapse.
+        assert_eq!(
+            Vec::from_iter(cs.possible_transitions()),
+            vec![(pg0, enter, committed)]
+        );
+        assert!(matches!(
+            cs.wait(1),
+            Err(CsError::ProgramGraph(_, PgError::Urgent(_)))
+        ));
+        assert_eq!(cs.time(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn atomic() -> Result<(), CsError> {
+        let mut cs = ChannelSystemBuilder::new();
+        let chn = cs.new_channel(Type::Integer, Some(1));
+        let pg0 = cs.new_program_graph();
+        let initial0 = cs.initial_location(pg0)?;
+        let atomic = cs.new_location_with_urgency(pg0, Urgency::Atomic)?;
+        let enter = cs.new_action(pg0)?;
+        cs.add_transition(pg0, initial0, enter, atomic, None)?;
+        let var = cs.new_var(pg0, Expression::Const(Val::Integer(0)))?;
+        let receive = cs.new_receive(pg0, chn, var)?;
+        cs.add_transition(pg0, atomic, receive, initial0, None)?;
+
+        let pg1 = cs.new_program_graph();
+        let initial1 = cs.initial_location(pg1)?;
+        let send = cs.new_send(pg1, chn, CsExpression::Const(Val::Integer(1)))?;
+        cs.add_transition(pg1, initial1, send, initial1, None)?;
+
+        let mut cs = cs.build();
+        cs.transition(pg0, enter, atomic)?;
+        // The atomic block is stuck, so other PGs can transition.
+        assert_eq!(
+            Vec::from_iter(cs.possible_transitions()),
+            vec![(pg1, send, initial1)]
+        );
+        cs.transition(pg1, send, initial1)?;
+        // Now the atomic block can proceed, and takes precedence.
+        assert_eq!(
+            Vec::from_iter(cs.possible_transitions()),
+            vec![(pg0, receive, initial0)]
+        );
+        assert!(matches!(
+            cs.wait(1),
+            Err(CsError::ProgramGraph(_, PgError::Urgent(_)))
+        ));
+        cs.transition(pg0, receive, initial0)?;
+        cs.wait(1)?;
+        Ok(())
+    }
+
+    #[test]
+    fn atomic_block() -> Result<(), CsError> {
+        let mut cs = ChannelSystemBuilder::new();
+        let mut steps = Vec::new();
+        for _ in 0..2 {
+            let pg = cs.new_program_graph();
+            let initial = cs.initial_location(pg)?;
+            cs.set_urgency(initial, Urgency::Atomic)?;
+            let step = cs.new_action(pg)?;
+            cs.add_transition(pg, initial, step, initial, None)?;
+            steps.push((pg, step, initial));
+        }
+
+        let mut cs = cs.build();
+        // PGs in atomic locations share precedence until one of them transitions...
+        assert_eq!(Vec::from_iter(cs.possible_transitions()), steps);
+        let (pg0, step0, initial0) = steps[0];
+        cs.transition(pg0, step0, initial0)?;
+        // ...so that it executes an atomic block that the other PGs cannot interleave with.
+        assert_eq!(
+            Vec::from_iter(cs.possible_transitions()),
+            vec![(pg0, step0, initial0)]
+        );
+        let (pg1, step1, initial1) = steps[1];
+        assert!(matches!(
+            cs.transition(pg1, step1, initial1),
+            Err(CsError::Atomic(_))
+        ));
+        Ok(())
+    }
 }
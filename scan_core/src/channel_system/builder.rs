@@ -1,9 +1,9 @@
 use super::{
-    Action, Channel, ChannelSystem, Clock, CsError, Location, Message, PgError, PgExpression, PgId,
-    ProgramGraph, ProgramGraphBuilder, TimeConstraint, Var,
+    Action, Channel, ChannelSystem, Clock, CsError, Faults, GlobalVar, Latency, Location, Message,
+    PgExpression, PgId, ProgramGraph, ProgramGraphBuilder, Selection, TimeConstraint, Urgency, Var,
 };
-use crate::channel_system::ChannelSystemDef;
-use crate::grammar::Type;
+use crate::channel_system::{ChannelStats, ChannelSystemDef, Discipline, Occupancy};
+use crate::grammar::{Expected, FnExpression, Overflow, Type, TypeError, TypeErrorKind, Val};
 use crate::Expression;
 // use ahash::AHashMap as HashMap;
 use hashbrown::HashMap;
@@ -33,6 +33,11 @@ impl TryFrom<(PgId, CsExpression)> for PgExpression {
             Expression::Component(index, expr) => (pg_id, *expr)
                 .try_into()
                 .map(|expr| Expression::Component(index, Box::new(expr))),
+            Expression::Ite(comps) => Ok(Expression::Ite(Box::new((
+                (pg_id, comps.0).try_into()?,
+                (pg_id, comps.1).try_into()?,
+                (pg_id, comps.2).try_into()?,
+            )))),
             Expression::And(comps) => Ok(Expression::And(
                 comps
                     .into_iter()
@@ -86,6 +91,12 @@ impl TryFrom<(PgId, CsExpression)> for PgExpression {
                 (pg_id, comps.0).try_into()?,
                 (pg_id, comps.1).try_into()?,
             )))),
+            Expression::Concat(comps) => Ok(Expression::Concat(
+                comps
+                    .into_iter()
+                    .map(|comp| (pg_id, comp).try_into())
+                    .collect::<Result<Vec<PgExpression>, CsError>>()?,
+            )),
             Expression::Append(comps) => Ok(Expression::Append(Box::new((
                 (pg_id, comps.0).try_into()?,
                 (pg_id, comps.1).try_into()?,
@@ -94,20 +105,80 @@ impl TryFrom<(PgId, CsExpression)> for PgExpression {
                 Ok(Expression::Truncate(Box::new((pg_id, *comp).try_into()?)))
             }
             Expression::Len(comp) => Ok(Expression::Len(Box::new((pg_id, *comp).try_into()?))),
+            Expression::Array(comps) => Ok(Expression::Array(
+                comps
+                    .into_iter()
+                    .map(|comp| (pg_id, comp).try_into())
+                    .collect::<Result<Vec<PgExpression>, CsError>>()?,
+            )),
+            Expression::Index(comps) => Ok(Expression::Index(Box::new((
+                (pg_id, comps.0).try_into()?,
+                (pg_id, comps.1).try_into()?,
+            )))),
+            Expression::Update(comps) => Ok(Expression::Update(Box::new((
+                (pg_id, comps.0).try_into()?,
+                (pg_id, comps.1).try_into()?,
+                (pg_id, comps.2).try_into()?,
+            )))),
             Expression::Mod(comps) => Ok(Expression::Mod(Box::new((
                 (pg_id, comps.0).try_into()?,
                 (pg_id, comps.1).try_into()?,
             )))),
+            Expression::Div(comps) => Ok(Expression::Div(Box::new((
+                (pg_id, comps.0).try_into()?,
+                (pg_id, comps.1).try_into()?,
+            )))),
+            Expression::Pow(comps) => Ok(Expression::Pow(Box::new((
+                (pg_id, comps.0).try_into()?,
+                (pg_id, comps.1).try_into()?,
+            )))),
+            Expression::Min(comps) => Ok(Expression::Min(
+                comps
+                    .into_iter()
+                    .map(|comp| (pg_id, comp).try_into())
+                    .collect::<Result<Vec<PgExpression>, CsError>>()?,
+            )),
+            Expression::Max(comps) => Ok(Expression::Max(
+                comps
+                    .into_iter()
+                    .map(|comp| (pg_id, comp).try_into())
+                    .collect::<Result<Vec<PgExpression>, CsError>>()?,
+            )),
+            Expression::Abs(comp) => Ok(Expression::Abs(Box::new((pg_id, *comp).try_into()?))),
+            Expression::Floor(comp) => Ok(Expression::Floor(Box::new((pg_id, *comp).try_into()?))),
+            Expression::Ceil(comp) => Ok(Expression::Ceil(Box::new((pg_id, *comp).try_into()?))),
+            Expression::Round(comp) => Ok(Expression::Round(Box::new((pg_id, *comp).try_into()?))),
+            Expression::ToFloat(comp) => {
+                Ok(Expression::ToFloat(Box::new((pg_id, *comp).try_into()?)))
+            }
+            Expression::ToInt(comp) => Ok(Expression::ToInt(Box::new((pg_id, *comp).try_into()?))),
         }
     }
 }
 
 /// The object used to define and build a CS.
 #[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ChannelSystemBuilder {
     program_graphs: Vec<ProgramGraphBuilder>,
     channels: Vec<(Type, Option<usize>)>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    broadcasts: Vec<Channel>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    bags: Vec<Channel>,
+    #[cfg_attr(feature = "serde", serde(default, with = "crate::serde_seq"))]
+    priorities: HashMap<Channel, Expression<Channel>>,
+    #[cfg_attr(feature = "serde", serde(default, with = "crate::serde_seq"))]
+    faults: HashMap<Channel, Faults>,
+    #[cfg_attr(feature = "serde", serde(default, with = "crate::serde_seq"))]
+    latencies: HashMap<Channel, Latency>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_seq"))]
     communications: HashMap<Action, (Channel, Message)>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    globals: Vec<(Val, Type)>,
+    #[cfg_attr(feature = "serde", serde(default, with = "crate::serde_seq"))]
+    global_vars: HashMap<(PgId, GlobalVar), Var>,
+    overflow: Overflow,
 }
 
 impl ChannelSystemBuilder {
@@ -117,10 +188,22 @@ impl ChannelSystemBuilder {
         Self::default()
     }
 
+    /// Sets how integer overflows behave in the expressions of all PGs in the CS,
+    /// including those already added.
+    ///
+    /// See also [`ProgramGraphBuilder::set_overflow`].
+    pub fn set_overflow(&mut self, overflow: Overflow) {
+        self.overflow = overflow;
+        for pg in self.program_graphs.iter_mut() {
+            pg.set_overflow(overflow);
+        }
+    }
+
     /// Add a new PG to the CS.
     pub fn new_program_graph(&mut self) -> PgId {
         let pg_id = PgId(self.program_graphs.len() as u16);
-        let pg = ProgramGraphBuilder::new();
+        let mut pg = ProgramGraphBuilder::new();
+        pg.set_overflow(self.overflow);
         self.program_graphs.push(pg);
         pg_id
     }
@@ -154,6 +237,106 @@ impl ChannelSystemBuilder {
         Ok(Var(pg_id, var))
     }
 
+    /// Add a new variable of the given type and initial value to the given PG.
+    ///
+    /// It fails if the CS contains no such PG, if the expression is badly-typed,
+    /// or if its value does not fit the declared type.
+    ///
+    /// See [`ProgramGraphBuilder::new_var_with_type`] for more info.
+    pub fn new_var_with_type(
+        &mut self,
+        pg_id: PgId,
+        init: CsExpression,
+        r#type: Type,
+    ) -> Result<Var, CsError> {
+        let pg = self
+            .program_graphs
+            .get_mut(pg_id.0 as usize)
+            .ok_or(CsError::MissingPg(pg_id))?;
+        let init = PgExpression::try_from((pg_id, init))?;
+        let var = pg
+            .new_var_with_type(init, r#type)
+            .map_err(|err| CsError::ProgramGraph(pg_id, err))?;
+        Ok(Var(pg_id, var))
+    }
+
+    /// Adds a new global variable of the given type and initial value to the CS,
+    /// which the guards and effects of any PG can read and write
+    /// through the variable given by [`Self::global_var`].
+    ///
+    /// It fails if the initial value does not match the type, or if it is out of range.
+    ///
+    /// ```
+    /// # use scan_core::*;
+    /// # use scan_core::channel_system::*;
+    /// let mut cs_builder = ChannelSystemBuilder::new();
+    /// let global = cs_builder.new_global_var(Val::Integer(0), Type::Integer).unwrap();
+    ///
+    /// // Both PGs access the global variable, through variables of their own.
+    /// let pg_1 = cs_builder.new_program_graph();
+    /// let var_1 = cs_builder.global_var(pg_1, global).unwrap();
+    /// let pg_2 = cs_builder.new_program_graph();
+    /// let var_2 = cs_builder.global_var(pg_2, global).unwrap();
+    ///
+    /// // pg_1 writes the global variable.
+    /// let initial_1 = cs_builder.initial_location(pg_1).unwrap();
+    /// let write = cs_builder.new_action(pg_1).unwrap();
+    /// cs_builder.add_effect(pg_1, write, var_1, CsExpression::from(1)).unwrap();
+    /// cs_builder.add_transition(pg_1, initial_1, write, initial_1, None).unwrap();
+    ///
+    /// // pg_2 can only transition once the global variable has been written.
+    /// let initial_2 = cs_builder.initial_location(pg_2).unwrap();
+    /// let read = cs_builder.new_action(pg_2).unwrap();
+    /// let guard = CsExpression::Equal(Box::new((
+    ///     CsExpression::Var(var_2, Type::Integer),
+    ///     CsExpression::from(1),
+    /// )));
+    /// cs_builder.add_transition(pg_2, initial_2, read, initial_2, Some(guard)).unwrap();
+    ///
+    /// let mut cs = cs_builder.build();
+    /// assert_eq!(Vec::from_iter(cs.possible_transitions()), vec![(pg_1, write, initial_1)]);
+    /// cs.transition(pg_1, write, initial_1).unwrap();
+    /// assert_eq!(cs.global_val(global), Some(&Val::Integer(1)));
+    /// assert!(cs.transition(pg_2, read, initial_2).is_ok());
+    /// ```
+    pub fn new_global_var(&mut self, init: Val, r#type: Type) -> Result<GlobalVar, CsError> {
+        let found = init.r#type();
+        if found != r#type.unbounded() {
+            let kind = TypeErrorKind::Mismatch {
+                expected: Expected::Type(r#type.unbounded()),
+                found,
+            };
+            Err(CsError::Type(kind.into()))
+        } else if r#type.check_bounds(&init).is_err() {
+            Err(CsError::OutOfRange(init, r#type))
+        } else {
+            let global = GlobalVar(self.globals.len() as u16);
+            self.globals.push((init, r#type));
+            Ok(global)
+        }
+    }
+
+    /// Returns the variable through which the given PG accesses the given global variable,
+    /// adding it to the PG if it does not have it already.
+    ///
+    /// Guards and effects of the PG can use the variable to read and write the global variable,
+    /// and so can its receive communications.
+    ///
+    /// It fails if the CS contains no such PG or global variable.
+    pub fn global_var(&mut self, pg_id: PgId, global: GlobalVar) -> Result<Var, CsError> {
+        if let Some(var) = self.global_vars.get(&(pg_id, global)) {
+            return Ok(*var);
+        }
+        let (init, r#type) = self
+            .globals
+            .get(global.0 as usize)
+            .ok_or(CsError::MissingGlobalVar(global))?
+            .to_owned();
+        let var = self.new_var_with_type(pg_id, CsExpression::Const(init), r#type)?;
+        self.global_vars.insert((pg_id, global), var);
+        Ok(var)
+    }
+
     pub fn new_clock(&mut self, pg_id: PgId) -> Result<Clock, CsError> {
         self.program_graphs
             .get_mut(pg_id.0 as usize)
@@ -268,6 +451,36 @@ impl ChannelSystemBuilder {
             .map(|pg| Location(pg_id, pg.new_location()))
     }
 
+    /// Adds a new location with the given [`Urgency`] to the given PG.
+    ///
+    /// It fails if the CS contains no such PG.
+    ///
+    /// See also [`ProgramGraphBuilder::new_location_with_urgency`].
+    pub fn new_location_with_urgency(
+        &mut self,
+        pg_id: PgId,
+        urgency: Urgency,
+    ) -> Result<Location, CsError> {
+        self.program_graphs
+            .get_mut(pg_id.0 as usize)
+            .ok_or(CsError::MissingPg(pg_id))
+            .map(|pg| Location(pg_id, pg.new_location_with_urgency(urgency)))
+    }
+
+    /// Sets the [`Urgency`] of a location of the given PG.
+    ///
+    /// It fails if the CS contains no such PG, or if the location does not belong to it.
+    ///
+    /// See also [`ProgramGraphBuilder::set_urgency`].
+    pub fn set_urgency(&mut self, location: Location, urgency: Urgency) -> Result<(), CsError> {
+        let pg_id = location.0;
+        self.program_graphs
+            .get_mut(pg_id.0 as usize)
+            .ok_or(CsError::MissingPg(pg_id))?
+            .set_urgency(location.1, urgency)
+            .map_err(|err| CsError::ProgramGraph(pg_id, err))
+    }
+
     /// TODO
     pub fn new_timed_location(
         &mut self,
@@ -290,6 +503,28 @@ impl ChannelSystemBuilder {
             .map(|pg| Location(pg_id, pg.new_timed_location(&invariants)))
     }
 
+    /// Adds a new location to the given PG, with an invariant given by an arbitrary boolean expression.
+    ///
+    /// It fails if the CS contains no such PG, if the invariant uses variables of other PGs,
+    /// or if it is not a boolean expression.
+    ///
+    /// See also [`ProgramGraphBuilder::new_location_with_invariant`].
+    pub fn new_location_with_invariant(
+        &mut self,
+        pg_id: PgId,
+        invariant: CsExpression,
+    ) -> Result<Location, CsError> {
+        let invariant = PgExpression::try_from((pg_id, invariant))?;
+        self.program_graphs
+            .get_mut(pg_id.0 as usize)
+            .ok_or(CsError::MissingPg(pg_id))
+            .and_then(|pg| {
+                pg.new_location_with_invariant(invariant)
+                    .map(|loc| Location(pg_id, loc))
+                    .map_err(|err| CsError::ProgramGraph(pg_id, err))
+            })
+    }
+
     /// Adds a transition to the PG.
     ///
     /// Fails if the CS contains no such PG, or if the given action, variable or locations do not belong to it.
@@ -435,13 +670,151 @@ impl ChannelSystemBuilder {
     /// Adds a new channel of the given type and capacity to the CS.
     ///
     /// - [`None`] capacity means that the channel's capacity is unlimited.
-    /// - [`Some(0)`] capacity means the channel uses the handshake protocol (NOT YET IMPLEMENTED!)
+    /// - [`Some(0)`] capacity means the channel uses the handshake protocol
+    ///   (see [`ChannelSystem::handshake`]).
+    ///
+    /// Bounds on integer types are dropped:
+    /// range checks are only performed by the receiving variables.
     pub fn new_channel(&mut self, var_type: Type, capacity: Option<usize>) -> Channel {
         let channel = Channel(self.channels.len() as u16);
-        self.channels.push((var_type, capacity));
+        self.channels.push((var_type.unbounded(), capacity));
         channel
     }
 
+    /// Adds a new broadcast channel of the given type and capacity to the CS.
+    ///
+    /// Every PG with a Receive communication action on a broadcast channel is subscribed to it,
+    /// and has a queue of its own with the given capacity:
+    /// sending a message pushes it to the queue of every subscriber,
+    /// and fails if any of them is full.
+    ///
+    /// - [`None`] capacity means that the subscribers' queues are unlimited.
+    /// - [`Some(0)`] capacity means the channel is synchronous:
+    ///   sending a message is always possible, and it is received at once
+    ///   by all the subscribers that are ready to receive it at that time.
+    pub fn new_broadcast_channel(&mut self, var_type: Type, capacity: Option<usize>) -> Channel {
+        let channel = self.new_channel(var_type, capacity);
+        self.broadcasts.push(channel);
+        channel
+    }
+
+    /// Adds a new bag channel of the given type and capacity to the CS,
+    /// where the messages in the queue can be received in any order,
+    /// as chosen nondeterministically.
+    ///
+    /// Fails if the capacity is zero, as handshake channels have no queue.
+    ///
+    /// See also [`Self::new_channel`].
+    pub fn new_bag_channel(
+        &mut self,
+        var_type: Type,
+        capacity: Option<usize>,
+    ) -> Result<Channel, CsError> {
+        if matches!(capacity, Some(0)) {
+            return Err(CsError::HandshakeBag);
+        }
+        let channel = self.new_channel(var_type, capacity);
+        self.bags.push(channel);
+        Ok(channel)
+    }
+
+    /// Makes the given channel a priority channel,
+    /// where the messages in the queue are received in order of decreasing priority,
+    /// and in the order they were sent among messages of equal priority.
+    ///
+    /// The priority of a message is given by an integer expression,
+    /// where the channel itself stands for the message.
+    ///
+    /// Fails if the channel uses the handshake protocol, if it is a broadcast, bag or unreliable channel,
+    /// or if the priority is not an integer expression on the message.
+    ///
+    /// ```
+    /// # use scan_core::*;
+    /// # use scan_core::channel_system::*;
+    /// let mut cs_builder = ChannelSystemBuilder::new();
+    /// // Messages are pairs, prioritized by their second component.
+    /// let msg_type = Type::Product(vec![Type::Integer, Type::Integer]);
+    /// let chn = cs_builder.new_channel(msg_type.clone(), None);
+    /// let priority = Expression::Component(1, Box::new(Expression::Var(chn, msg_type)));
+    /// cs_builder.set_priority(chn, priority).unwrap();
+    /// ```
+    pub fn set_priority(
+        &mut self,
+        channel: Channel,
+        priority: Expression<Channel>,
+    ) -> Result<(), CsError> {
+        let (msg_type, cap) = self
+            .channels
+            .get(channel.0 as usize)
+            .ok_or(CsError::MissingChannel(channel))?;
+        if matches!(cap, Some(0))
+            || self.broadcasts.contains(&channel)
+            || self.bags.contains(&channel)
+            || self.faults.contains_key(&channel)
+        {
+            return Err(CsError::PriorityChannel(channel));
+        }
+        priority
+            .context(&|chn| (chn == channel).then(|| msg_type.clone()))
+            .map_err(CsError::Type)?;
+        match priority.r#type().map_err(CsError::Type)? {
+            Type::Integer => {
+                self.priorities.insert(channel, priority);
+                Ok(())
+            }
+            found => {
+                let kind = TypeErrorKind::Mismatch {
+                    expected: Expected::Type(Type::Integer),
+                    found,
+                };
+                Err(CsError::Type(kind.into()))
+            }
+        }
+    }
+
+    /// Makes the given channel unreliable,
+    /// with the messages sent through it affected by faults with the given probabilities.
+    ///
+    /// Fails if the channel uses the handshake protocol or it is a broadcast, bag or priority channel,
+    /// or if the probabilities are not valid.
+    pub fn set_faults(&mut self, channel: Channel, faults: Faults) -> Result<(), CsError> {
+        let (_, cap) = self
+            .channels
+            .get(channel.0 as usize)
+            .ok_or(CsError::MissingChannel(channel))?;
+        if matches!(cap, Some(0))
+            || self.broadcasts.contains(&channel)
+            || self.bags.contains(&channel)
+            || self.priorities.contains_key(&channel)
+        {
+            Err(CsError::UnreliableChannel(channel))
+        } else if !faults.is_valid() {
+            Err(CsError::InvalidFaults(channel, faults))
+        } else {
+            self.faults.insert(channel, faults);
+            Ok(())
+        }
+    }
+
+    /// Sets the latency of the given channel,
+    /// so that the messages sent through it can only be received after some delay.
+    ///
+    /// Fails if the channel uses the handshake protocol, or if the latency is not valid.
+    pub fn set_latency(&mut self, channel: Channel, latency: Latency) -> Result<(), CsError> {
+        let (_, cap) = self
+            .channels
+            .get(channel.0 as usize)
+            .ok_or(CsError::MissingChannel(channel))?;
+        if matches!(cap, Some(0)) {
+            Err(CsError::DelayedHandshake(channel))
+        } else if !latency.is_valid() {
+            Err(CsError::InvalidLatency(channel, latency))
+        } else {
+            self.latencies.insert(channel, latency);
+            Ok(())
+        }
+    }
+
     /// Adds a new Send communication action to the given PG.
     ///
     /// Fails if the channel and message types do not match.
@@ -460,7 +833,11 @@ impl ChannelSystemBuilder {
         let msg = PgExpression::try_from((pg_id, msg))?;
         let message_type = msg.r#type().map_err(CsError::Type)?;
         if channel_type != message_type {
-            Err(CsError::ProgramGraph(pg_id, PgError::TypeMismatch))
+            let kind = TypeErrorKind::Mismatch {
+                expected: Expected::Type(channel_type),
+                found: message_type,
+            };
+            Err(CsError::Type(kind.into()))
         } else {
             let action = self.program_graphs[pg_id.0 as usize]
                 .new_send(msg)
@@ -480,6 +857,84 @@ impl ChannelSystemBuilder {
         channel: Channel,
         var: Var,
     ) -> Result<Action, CsError> {
+        self.check_receive(pg_id, channel, var)?;
+        let action = self.program_graphs[pg_id.0 as usize]
+            .new_receive(var.1)
+            .map_err(|err| CsError::ProgramGraph(pg_id, err))?;
+        let action = Action(pg_id, action);
+        self.communications
+            .insert(action, (channel, Message::Receive));
+        Ok(action)
+    }
+
+    /// Adds a new SelectiveReceive communication action to the given PG,
+    /// which can only retrieve a message satisfying the guard,
+    /// as selected by the [`Selection`] mode.
+    /// The guard is evaluated as if the message had already been written onto the variable.
+    ///
+    /// Fails if the channel and message types do not match, if the guard is not a boolean expression,
+    /// or if the queue uses the handshake protocol.
+    ///
+    /// ```
+    /// # use scan_core::*;
+    /// # use scan_core::channel_system::*;
+    /// let mut cs_builder = ChannelSystemBuilder::new();
+    /// let chn = cs_builder.new_channel(Type::Integer, None);
+    /// let pg_id = cs_builder.new_program_graph();
+    /// let initial = cs_builder.initial_location(pg_id).unwrap();
+    /// let var = cs_builder.new_var(pg_id, Expression::from(0)).unwrap();
+    /// // Only receive positive messages
+    /// let guard = CsExpression::Greater(Box::new((
+    ///     CsExpression::Var(var, Type::Integer),
+    ///     CsExpression::from(0),
+    /// )));
+    /// let receive = cs_builder
+    ///     .new_selective_receive(pg_id, chn, var, guard, Selection::FirstMatching)
+    ///     .unwrap();
+    /// cs_builder.add_transition(pg_id, initial, receive, initial, None).unwrap();
+    /// ```
+    pub fn new_selective_receive(
+        &mut self,
+        pg_id: PgId,
+        channel: Channel,
+        var: Var,
+        guard: CsExpression,
+        selection: Selection,
+    ) -> Result<Action, CsError> {
+        self.check_receive(pg_id, channel, var)?;
+        if matches!(self.channels[channel.0 as usize].1, Some(0)) {
+            return Err(CsError::SelectingHandshakeChannel(channel));
+        }
+        let guard = PgExpression::try_from((pg_id, guard))?;
+        let action = self.program_graphs[pg_id.0 as usize]
+            .new_selective_receive(var.1, guard)
+            .map_err(|err| CsError::ProgramGraph(pg_id, err))?;
+        let action = Action(pg_id, action);
+        self.communications
+            .insert(action, (channel, Message::SelectiveReceive(selection)));
+        Ok(action)
+    }
+
+    /// Adds a new Peek communication action to the given PG,
+    /// which reads the message in front of the channel queue without retrieving it.
+    ///
+    /// Fails if the channel and message types do not match,
+    /// or if the queue uses the handshake protocol.
+    pub fn new_peek(&mut self, pg_id: PgId, channel: Channel, var: Var) -> Result<Action, CsError> {
+        self.check_receive(pg_id, channel, var)?;
+        if matches!(self.channels[channel.0 as usize].1, Some(0)) {
+            return Err(CsError::SelectingHandshakeChannel(channel));
+        }
+        let action = self.program_graphs[pg_id.0 as usize]
+            .new_receive(var.1)
+            .map_err(|err| CsError::ProgramGraph(pg_id, err))?;
+        let action = Action(pg_id, action);
+        self.communications.insert(action, (channel, Message::Peek));
+        Ok(action)
+    }
+
+    // Checks that messages from the channel can be written onto the variable of the given PG.
+    fn check_receive(&self, pg_id: PgId, channel: Channel, var: Var) -> Result<(), CsError> {
         if pg_id != var.0 {
             Err(CsError::VarNotInPg(var, pg_id))
         } else {
@@ -497,15 +952,16 @@ impl ChannelSystemBuilder {
                 .map_err(|err| CsError::ProgramGraph(pg_id, err))?
                 .to_owned();
             if channel_type != message_type {
-                Err(CsError::ProgramGraph(pg_id, PgError::TypeMismatch))
+                Err(CsError::Type(TypeError {
+                    kind: TypeErrorKind::Mismatch {
+                        expected: Expected::Type(channel_type),
+                        found: message_type,
+                    },
+                    path: Vec::new(),
+                    var: Some(var.to_string()),
+                }))
             } else {
-                let action = self.program_graphs[pg_id.0 as usize]
-                    .new_receive(var.1)
-                    .map_err(|err| CsError::ProgramGraph(pg_id, err))?;
-                let action = Action(pg_id, action);
-                self.communications
-                    .insert(action, (channel, Message::Receive));
-                Ok(action)
+                Ok(())
             }
         }
     }
@@ -584,6 +1040,23 @@ impl ChannelSystemBuilder {
 
         program_graphs.shrink_to_fit();
         self.channels.shrink_to_fit();
+        let mut subscribers = vec![None; self.channels.len()];
+        for channel in self.broadcasts {
+            subscribers[channel.0 as usize] = Some(Vec::new());
+        }
+        for (action, (channel, message)) in self.communications.iter() {
+            if let (Message::Receive | Message::SelectiveReceive(_) | Message::Peek, Some(pgs)) =
+                (message, &mut subscribers[channel.0 as usize])
+            {
+                pgs.push(action.0);
+            }
+        }
+        for pgs in subscribers.iter_mut().flatten() {
+            pgs.sort_unstable();
+            pgs.dedup();
+            pgs.shrink_to_fit();
+        }
+
         let mut communications_map = Vec::from_iter(self.communications);
         communications_map.sort_unstable_by_key(|(a, _)| *a);
         let mut communications = Vec::with_capacity(communications_map.len());
@@ -609,28 +1082,72 @@ impl ChannelSystemBuilder {
         );
         assert_eq!(communications_pg_idxs.len(), program_graphs.len() + 1);
 
-        let message_queue = self
-            .channels
-            .iter()
-            .map(|(_, cap)| {
+        // Point-to-point channels have a single queue, broadcast channels one for each subscriber.
+        let mut queue_idxs = Vec::with_capacity(self.channels.len() + 1);
+        queue_idxs.push(0);
+        let mut message_queue = Vec::new();
+        for ((_, cap), pgs) in self.channels.iter().zip(subscribers.iter()) {
+            let queues = pgs.as_ref().map_or(1, Vec::len);
+            message_queue.extend((0..queues).map(|_| {
                 if let Some(cap) = cap {
                     VecDeque::with_capacity(*cap)
                 } else {
                     VecDeque::default()
                 }
-            })
-            .collect();
+            }));
+            queue_idxs.push(message_queue.len());
+        }
+
+        let mut disciplines = Vec::from_iter((0..self.channels.len()).map(|_| Discipline::Fifo));
+        for channel in self.bags {
+            disciplines[channel.0 as usize] = Discipline::Bag;
+        }
+        for (channel, priority) in self.priorities {
+            disciplines[channel.0 as usize] = Discipline::Priority(Arc::new(FnExpression::new(
+                priority.simplify_with_overflow(self.overflow),
+                self.overflow,
+            )));
+        }
+
+        let mut faults = vec![None; self.channels.len()];
+        for (channel, channel_faults) in self.faults {
+            faults[channel.0 as usize] = Some(channel_faults);
+        }
+        let mut latencies = vec![Latency::default(); self.channels.len()];
+        for (channel, latency) in self.latencies {
+            latencies[channel.0 as usize] = latency;
+        }
+
+        let mut globals = vec![Vec::new(); program_graphs.len()];
+        let mut global_vars = vec![Vec::new(); self.globals.len()];
+        for ((pg_id, global), var) in self.global_vars {
+            globals[pg_id.0 as usize].push((var.1, global.0 as usize));
+            global_vars[global.0 as usize].push((pg_id, var.1));
+        }
 
         let def = ChannelSystemDef {
             channels: self.channels,
+            subscribers,
+            queue_idxs,
+            disciplines,
+            faults,
+            latencies,
             communications,
             communications_pg_idxs,
+            globals,
+            global_vars,
         };
 
         ChannelSystem {
             time: 0,
+            blocked: vec![Vec::new(); program_graphs.len()],
             program_graphs,
             message_queue,
+            globals: self.globals.into_iter().map(|(init, _)| init).collect(),
+            stats: vec![ChannelStats::default(); def.channels.len()],
+            occupancy: vec![Occupancy::default(); def.channels.len()],
+            atomic: None,
+            queue_limit: None,
             def: Arc::new(def),
         }
     }
@@ -2,44 +2,212 @@
 //!
 //! The type [`Expression<V>`] encodes the used language,
 //! where `V` is the type parameter of variables.
-//! The language features base types and product types,
+//! The language features base types (including strings) and product types,
 //! Boolean logic and basic arithmetic expressions.
+//!
+//! Expressions are evaluated after having been type-checked,
+//! so evaluation can only fail on runtime conditions,
+//! such as indexing a list out of its bounds (see [`EvalError`]).
+//! How integer arithmetic behaves on overflow is set when compiling expressions (see [`Overflow`]).
+
+#[cfg(any(feature = "bytecode", test))]
+mod bytecode;
+mod parser;
+mod printer;
+mod simplify;
+mod string;
+
+pub use parser::{parse_expression, parse_type, ParseError, Resolver, Span};
+pub use printer::DisplayExpression;
+pub use string::Str;
 
 use ordered_float::OrderedFloat;
 use std::hash::Hash;
 use thiserror::Error;
 
-/// The error type for operations with [`Type`].
-#[derive(Debug, Clone, Copy, Error)]
-pub enum TypeError {
-    /// Types that should be matching are not,
-    /// or are not compatible with each other.
-    #[error("type mismatch")]
-    TypeMismatch,
+/// The type (or class of types) that an expression was expected to have (see [`TypeErrorKind::Mismatch`]).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expected {
+    /// The given type.
+    Type(Type),
+    /// Any of the given types.
+    OneOf(Vec<Type>),
+    /// Any product type.
+    Tuple,
+    /// Any list type.
+    List,
+    /// Any list or array type.
+    Sequence,
+}
+
+impl Expected {
+    const NUMERIC: [Type; 2] = [Type::Integer, Type::Float];
+}
+
+/// What went wrong when type-checking an expression (see [`TypeError`]).
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum TypeErrorKind {
+    /// The sub-expression does not have the expected type.
+    #[error("expected {expected}, found {found}")]
+    Mismatch {
+        /// The type the sub-expression should have had.
+        expected: Expected,
+        /// The type the sub-expression actually has.
+        found: Type,
+    },
     /// The tuple has no component for such index.
-    #[error("the tuple does not have the component")]
-    MissingComponent,
+    #[error("type {found} has no component {index}")]
+    MissingComponent {
+        /// The index of the missing component.
+        index: usize,
+        /// The type of the tuple.
+        found: Type,
+    },
     /// The variable's type is unknown.
     #[error("the type of variable is unknown")]
     UnknownVar,
-    /// The index is out of bounds.
-    #[error("the index is out of bounds")]
-    IndexOutOfBounds,
+    /// The variable is used with a type different from the one it was declared with.
+    #[error("variable of type {declared} is used as {found}")]
+    VarMismatch {
+        /// The type the variable was declared with.
+        declared: Type,
+        /// The type the variable is used with.
+        found: Type,
+    },
+    /// The (constant) index is out of bounds.
+    #[error("index {index} is out of bounds for length {len}")]
+    IndexOutOfBounds {
+        /// The index.
+        index: Integer,
+        /// The length of the array.
+        len: usize,
+    },
+    /// The operator requires at least one argument.
+    #[error("expected at least one argument")]
+    NoArguments,
+}
+
+/// The error type for the type-checking of [`Expression`]s.
+///
+/// Besides what went wrong, it locates the offending sub-expression
+/// (see [`Expression::subexpression`]) and, if any, the variable involved.
+///
+/// ```
+/// # use scan_core::*;
+/// let types = |name: &str| (name == "x").then_some(Type::Integer);
+/// let expr = parse_expression("x > 0 && (x || true)", &types).unwrap();
+/// let err = expr.r#type().unwrap_err();
+/// assert_eq!(
+///     err.kind,
+///     TypeErrorKind::Mismatch { expected: Expected::Type(Type::Boolean), found: Type::Integer },
+/// );
+/// assert_eq!(err.path, vec![1, 0]);
+/// assert_eq!(expr.subexpression(&err.path).unwrap().to_string(), "x");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeError {
+    /// What went wrong.
+    pub kind: TypeErrorKind,
+    /// The position of the offending sub-expression within the type-checked one,
+    /// as the indices of the arguments leading to it from the root.
+    pub path: Vec<usize>,
+    /// The name of the variable involved, if any.
+    pub var: Option<String>,
+}
+
+impl TypeError {
+    // Locates the error within the `idx`-th argument of an expression.
+    fn at(mut self, idx: usize) -> Self {
+        self.path.insert(0, idx);
+        self
+    }
+}
+
+impl From<TypeErrorKind> for TypeError {
+    fn from(kind: TypeErrorKind) -> Self {
+        TypeError {
+            kind,
+            path: Vec::new(),
+            var: None,
+        }
+    }
+}
+
+impl std::fmt::Display for TypeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.kind)?;
+        if let Some(ref var) = self.var {
+            write!(f, " (variable {var})")?;
+        }
+        if !self.path.is_empty() {
+            write!(f, " at sub-expression {:?}", self.path)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for TypeError {}
+
+// The error for an argument not having the expected type.
+fn mismatch(idx: usize, expected: Expected, found: Type) -> TypeError {
+    TypeError::from(TypeErrorKind::Mismatch { expected, found }).at(idx)
+}
+
+/// The error type for the evaluation of (well-typed) expressions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum EvalError {
+    /// The index is out of the bounds of the list or array.
+    #[error("index {0} is out of bounds for length {1}")]
+    IndexOutOfBounds(Integer, usize),
+    /// Cannot truncate an empty list.
+    #[error("cannot truncate an empty list")]
+    EmptyList,
+    /// The result of an integer operation does not fit into an [`Integer`].
+    #[error("integer overflow")]
+    Overflow,
+    /// The value does not fit into the bounds of a [`Type::Bounded`] integer.
+    #[error("value {0} is out of range [{1}, {2}]")]
+    OutOfRange(Integer, Integer, Integer),
+}
+
+/// How integer operations behave when their result does not fit into an [`Integer`].
+///
+/// Float-to-integer conversions saturate, unless overflows are checked.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Overflow {
+    /// Overflowing operations fail with [`EvalError::Overflow`].
+    #[default]
+    Checked,
+    /// Results are clamped to the bounds of [`Integer`].
+    Saturating,
+    /// Results wrap around the bounds of [`Integer`] (two's complement).
+    Wrapping,
 }
 
 /// The types supported by the language internally used by PGs and CSs.
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Type {
     /// Boolean type.
     Boolean,
-    /// Integer numerical type.
+    /// Integer numerical type (64-bit signed).
     Integer,
+    /// Integer numerical type restricted to the (inclusive) range between the given bounds.
+    ///
+    /// Within expressions, bounded integers behave as [`Type::Integer`]:
+    /// the range is only checked when a value is assigned to a variable of this type.
+    Bounded(Integer, Integer),
     /// Floating-point numerical type.
     Float,
+    /// String type.
+    String,
     /// Product of a list of types (including other products).
     Product(Vec<Type>),
     /// List type
     List(Box<Type>),
+    /// Array type, i.e., a list with fixed length.
+    Array(Box<Type>, usize),
 }
 
 impl Type {
@@ -49,23 +217,87 @@ impl Type {
         match self {
             Type::Boolean => Val::Boolean(false),
             Type::Integer => Val::Integer(0),
+            // The value within the range that is closest to zero.
+            Type::Bounded(min, max) => Val::Integer((*min).max(0).min((*max).max(*min))),
             Type::Float => Val::Float(OrderedFloat(0.0)),
+            Type::String => Val::String(Str::default()),
             Type::Product(tuple) => {
                 Val::Tuple(Vec::from_iter(tuple.iter().map(Self::default_value)))
             }
-            Type::List(t) => Val::List((**t).clone(), Vec::new()),
+            Type::List(t) => Val::List(t.unbounded(), Vec::new()),
+            Type::Array(t, len) => Val::Array(t.unbounded(), vec![t.default_value(); *len]),
+        }
+    }
+
+    /// The type obtained by replacing every [`Type::Bounded`] integer with [`Type::Integer`].
+    ///
+    /// This is the type that values and expressions of the given type actually have.
+    ///
+    /// ```
+    /// # use scan_core::Type;
+    /// let t = Type::List(Box::new(Type::Bounded(0, 255)));
+    /// assert_eq!(t.unbounded(), Type::List(Box::new(Type::Integer)));
+    /// ```
+    pub fn unbounded(&self) -> Type {
+        match self {
+            Type::Bounded(_, _) => Type::Integer,
+            Type::Product(types) => Type::Product(types.iter().map(Self::unbounded).collect()),
+            Type::List(t) => Type::List(Box::new(t.unbounded())),
+            Type::Array(t, len) => Type::Array(Box::new(t.unbounded()), *len),
+            t => t.clone(),
+        }
+    }
+
+    /// Whether the type contains any [`Type::Bounded`] integer.
+    pub fn is_bounded(&self) -> bool {
+        match self {
+            Type::Bounded(_, _) => true,
+            Type::Product(types) => types.iter().any(Self::is_bounded),
+            Type::List(t) | Type::Array(t, _) => t.is_bounded(),
+            _ => false,
+        }
+    }
+
+    /// Checks that the (well-typed) value is within the bounds prescribed by the type.
+    ///
+    /// ```
+    /// # use scan_core::*;
+    /// assert!(Type::Bounded(0, 9).check_bounds(&Val::Integer(9)).is_ok());
+    /// assert_eq!(
+    ///     Type::Bounded(0, 9).check_bounds(&Val::Integer(10)),
+    ///     Err(EvalError::OutOfRange(10, 0, 9)),
+    /// );
+    /// ```
+    pub fn check_bounds(&self, val: &Val) -> Result<(), EvalError> {
+        match (self, val) {
+            (Type::Bounded(min, max), Val::Integer(i)) => {
+                if (min..=max).contains(&i) {
+                    Ok(())
+                } else {
+                    Err(EvalError::OutOfRange(*i, *min, *max))
+                }
+            }
+            (Type::Product(types), Val::Tuple(vals)) => types
+                .iter()
+                .zip(vals)
+                .try_for_each(|(t, val)| t.check_bounds(val)),
+            (Type::List(t), Val::List(_, vals)) | (Type::Array(t, _), Val::Array(_, vals)) => {
+                vals.iter().try_for_each(|val| t.check_bounds(val))
+            }
+            _ => Ok(()),
         }
     }
 }
 
 /// Integer values.
-pub type Integer = i32;
+pub type Integer = i64;
 
 /// Floating-point values.
 pub type Float = f64;
 
 /// Possible values for each [`Type`].
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Val {
     /// Boolean values.
     Boolean(bool),
@@ -73,10 +305,14 @@ pub enum Val {
     Integer(Integer),
     /// Floating-point values.
     Float(OrderedFloat<Float>),
+    /// String values (interned).
+    String(Str),
     /// Values for product types, i.e., tuples of suitable values.
     Tuple(Vec<Val>),
     /// Values for list types
     List(Type, Vec<Val>),
+    /// Values for array types
+    Array(Type, Vec<Val>),
 }
 
 impl Val {
@@ -86,7 +322,9 @@ impl Val {
             Val::Integer(_) => Type::Integer,
             Val::Tuple(comps) => Type::Product(comps.iter().map(Val::r#type).collect()),
             Val::List(t, _) => Type::List(Box::new(t.clone())),
+            Val::Array(t, vals) => Type::Array(Box::new(t.clone()), vals.len()),
             Val::Float(_) => Type::Float,
+            Val::String(_) => Type::String,
         }
     }
 }
@@ -97,12 +335,19 @@ impl From<Float> for Val {
     }
 }
 
+impl From<&str> for Val {
+    fn from(value: &str) -> Self {
+        Val::String(Str::new(value))
+    }
+}
+
 /// Expressions for the language internally used by PGs and CSs.
 ///
 /// [`Expression<V>`] encodes the language in which `V` is the type of variables.
 ///
 /// Note that not all expressions that can be formed are well-typed.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Expression<V>
 where
     V: Clone,
@@ -118,6 +363,10 @@ where
     Tuple(Vec<Expression<V>>),
     /// The component of a tuple.
     Component(usize, Box<Expression<V>>),
+    /// Conditional expression: if the condition holds, the first branch, otherwise the second one.
+    ///
+    /// Only the selected branch is evaluated.
+    Ite(Box<(Expression<V>, Expression<V>, Expression<V>)>),
     // -----------------
     // Logical operators
     // -----------------
@@ -139,11 +388,38 @@ where
     /// Arithmetic n-ary multiplication.
     Mult(Vec<Expression<V>>),
     /// Mod operation
+    ///
+    /// The remainder has the sign of the dividend, and `x % 0` evaluates to `x`.
     Mod(Box<(Expression<V>, Expression<V>)>),
+    /// Division.
+    ///
+    /// Division between integers truncates towards zero, and dividing an integer by zero evaluates to `0`.
+    /// Otherwise, it is floating-point division, following IEEE 754.
+    Div(Box<(Expression<V>, Expression<V>)>),
+    /// Exponentiation: LHS raised to the power of RHS.
+    ///
+    /// Between integers, a negative exponent truncates the result towards zero.
+    Pow(Box<(Expression<V>, Expression<V>)>),
+    /// Minimum of a non-empty list of numerical expressions.
+    Min(Vec<Expression<V>>),
+    /// Maximum of a non-empty list of numerical expressions.
+    Max(Vec<Expression<V>>),
+    /// Absolute value of a numerical expression.
+    Abs(Box<Expression<V>>),
+    /// Largest integer less than or equal to a numerical expression.
+    Floor(Box<Expression<V>>),
+    /// Smallest integer greater than or equal to a numerical expression.
+    Ceil(Box<Expression<V>>),
+    /// Integer closest to a numerical expression, with halfway cases rounded up (as in ECMAScript).
+    Round(Box<Expression<V>>),
+    /// Conversion of a numerical expression to floating-point.
+    ToFloat(Box<Expression<V>>),
+    /// Conversion of a numerical expression to integer, truncating towards zero.
+    ToInt(Box<Expression<V>>),
     // ------------
     // (In)Equality
     // ------------
-    /// Equality of numerical expressions.
+    /// Equality of integer, Boolean or string expressions.
     Equal(Box<(Expression<V>, Expression<V>)>),
     /// Disequality of numerical expressions: LHS greater than RHS.
     Greater(Box<(Expression<V>, Expression<V>)>),
@@ -153,6 +429,11 @@ where
     Less(Box<(Expression<V>, Expression<V>)>),
     /// Disequality of numerical expressions: LHS less than, or equal to, RHS.
     LessEq(Box<(Expression<V>, Expression<V>)>),
+    // -------
+    // Strings
+    // -------
+    /// n-ary string concatenation.
+    Concat(Vec<Expression<V>>),
     // -----
     // Lists
     // -----
@@ -160,10 +441,19 @@ where
     Append(Box<(Expression<V>, Expression<V>)>),
     /// Truncate last element from a list.
     Truncate(Box<Expression<V>>),
-    /// Take length of a list.
+    /// Take length of a list or array.
     Len(Box<Expression<V>>),
-    // /// The component of a tuple.
-    // Entry(Box<(Expression<V>, Expression<V>)>),
+    /// A non-empty array of expressions of the same type.
+    Array(Vec<Expression<V>>),
+    /// Element of a list or array (LHS) at the given index (RHS).
+    ///
+    /// Evaluation fails if the index is out of bounds.
+    Index(Box<(Expression<V>, Expression<V>)>),
+    /// List or array with the element at the given index replaced by a new value:
+    /// `Update((list, index, value))`.
+    ///
+    /// Evaluation fails if the index is out of bounds.
+    Update(Box<(Expression<V>, Expression<V>, Expression<V>)>),
 }
 
 impl<V> Expression<V>
@@ -174,158 +464,292 @@ where
     // Fails if the expression is badly typed,
     // e.g., if variables in it have type incompatible with the expression.
     pub fn r#type(&self) -> Result<Type, TypeError> {
-        match self {
-            Expression::Const(val) => Ok(val.r#type()),
-            Expression::Tuple(tuple) => tuple
+        // Types of the arguments, locating errors within them.
+        let arg = |idx: usize, expr: &Expression<V>| expr.r#type().map_err(|err| err.at(idx));
+        let args = |exprs: &[Expression<V>]| {
+            exprs
                 .iter()
-                .map(|e| e.r#type())
+                .enumerate()
+                .map(|(idx, expr)| arg(idx, expr))
                 .collect::<Result<Vec<Type>, TypeError>>()
-                .map(Type::Product),
-            Expression::Var(_var, t) => Ok(t.clone()),
+        };
+        let numeric = || Expected::OneOf(Expected::NUMERIC.to_vec());
+        match self {
+            Expression::Const(val) => Ok(val.r#type()),
+            Expression::Tuple(tuple) => args(tuple).map(Type::Product),
+            Expression::Var(_var, t) => Ok(t.unbounded()),
             Expression::And(props) | Expression::Or(props) => {
-                if props
-                    .iter()
-                    .map(|prop| prop.r#type())
-                    .collect::<Result<Vec<Type>, TypeError>>()?
-                    .iter()
-                    .all(|prop| matches!(prop, Type::Boolean))
-                {
-                    Ok(Type::Boolean)
-                } else {
-                    Err(TypeError::TypeMismatch)
+                for (idx, t) in args(props)?.into_iter().enumerate() {
+                    if t != Type::Boolean {
+                        return Err(mismatch(idx, Expected::Type(Type::Boolean), t));
+                    }
                 }
+                Ok(Type::Boolean)
             }
             Expression::Implies(props) => {
-                if matches!(props.0.r#type()?, Type::Boolean)
-                    && matches!(props.1.r#type()?, Type::Boolean)
+                for (idx, t) in [arg(0, &props.0)?, arg(1, &props.1)?]
+                    .into_iter()
+                    .enumerate()
                 {
-                    Ok(Type::Boolean)
-                } else {
-                    Err(TypeError::TypeMismatch)
+                    if t != Type::Boolean {
+                        return Err(mismatch(idx, Expected::Type(Type::Boolean), t));
+                    }
                 }
+                Ok(Type::Boolean)
             }
-            Expression::Not(prop) => {
-                if matches!(prop.r#type()?, Type::Boolean) {
-                    Ok(Type::Boolean)
-                } else {
-                    Err(TypeError::TypeMismatch)
+            Expression::Not(prop) => match arg(0, prop)? {
+                Type::Boolean => Ok(Type::Boolean),
+                t => Err(mismatch(0, Expected::Type(Type::Boolean), t)),
+            },
+            Expression::Opposite(expr) | Expression::Abs(expr) => match arg(0, expr)? {
+                t @ (Type::Integer | Type::Float) => Ok(t),
+                t => Err(mismatch(0, numeric(), t)),
+            },
+            Expression::Sum(exprs)
+            | Expression::Mult(exprs)
+            | Expression::Min(exprs)
+            | Expression::Max(exprs) => {
+                if exprs.is_empty() && matches!(self, Expression::Min(_) | Expression::Max(_)) {
+                    return Err(TypeErrorKind::NoArguments.into());
+                }
+                let mut result = Type::Integer;
+                for (idx, t) in args(exprs)?.into_iter().enumerate() {
+                    match t {
+                        Type::Integer => {}
+                        Type::Float => result = Type::Float,
+                        t => return Err(mismatch(idx, numeric(), t)),
+                    }
                 }
+                Ok(result)
             }
-            Expression::Opposite(expr) => match expr.r#type()? {
-                Type::Integer => Ok(Type::Integer),
-                Type::Float => Ok(Type::Float),
-                _ => Err(TypeError::TypeMismatch),
-            },
-            Expression::Sum(exprs) | Expression::Mult(exprs) => {
-                let types = exprs
-                    .iter()
-                    .map(|expr| expr.r#type())
-                    .collect::<Result<Vec<Type>, TypeError>>()?;
-
-                if types.iter().all(|expr| matches!(expr, Type::Integer)) {
-                    Ok(Type::Integer)
-                } else if types
-                    .iter()
-                    .all(|expr| matches!(expr, Type::Integer | Type::Float))
-                {
-                    Ok(Type::Float)
+            Expression::Equal(exprs) => {
+                let type_0 = arg(0, &exprs.0)?;
+                let type_1 = arg(1, &exprs.1)?;
+                if !matches!(type_0, Type::Integer | Type::Boolean | Type::String) {
+                    Err(mismatch(
+                        0,
+                        Expected::OneOf(vec![Type::Integer, Type::Boolean, Type::String]),
+                        type_0,
+                    ))
+                } else if type_0 != type_1 {
+                    Err(mismatch(1, Expected::Type(type_0), type_1))
                 } else {
-                    Err(TypeError::TypeMismatch)
+                    Ok(Type::Boolean)
                 }
             }
-            Expression::Equal(exprs) | Expression::GreaterEq(exprs) | Expression::LessEq(exprs) => {
-                let type_0 = exprs.0.r#type()?;
-                let type_1 = exprs.1.r#type()?;
-                if matches!(type_0, Type::Integer | Type::Boolean) && type_0 == type_1 {
-                    Ok(Type::Boolean)
+            Expression::GreaterEq(exprs) | Expression::LessEq(exprs) => {
+                let type_0 = arg(0, &exprs.0)?;
+                let type_1 = arg(1, &exprs.1)?;
+                if !matches!(type_0, Type::Integer | Type::Boolean) {
+                    Err(mismatch(
+                        0,
+                        Expected::OneOf(vec![Type::Integer, Type::Boolean]),
+                        type_0,
+                    ))
+                } else if type_0 != type_1 {
+                    Err(mismatch(1, Expected::Type(type_0), type_1))
                 } else {
-                    Err(TypeError::TypeMismatch)
+                    Ok(Type::Boolean)
                 }
             }
             Expression::Greater(exprs) | Expression::Less(exprs) => {
-                if matches!(exprs.0.r#type()?, Type::Integer | Type::Float)
-                    && matches!(exprs.1.r#type()?, Type::Integer | Type::Float)
+                for (idx, t) in [arg(0, &exprs.0)?, arg(1, &exprs.1)?]
+                    .into_iter()
+                    .enumerate()
                 {
-                    Ok(Type::Boolean)
-                } else {
-                    Err(TypeError::TypeMismatch)
+                    if !matches!(t, Type::Integer | Type::Float) {
+                        return Err(mismatch(idx, numeric(), t));
+                    }
                 }
+                Ok(Type::Boolean)
             }
-            Expression::Component(index, expr) => {
-                if let Type::Product(components) = expr.r#type()? {
-                    components
-                        .get(*index)
-                        .cloned()
-                        .ok_or(TypeError::MissingComponent)
+            Expression::Concat(exprs) => {
+                for (idx, t) in args(exprs)?.into_iter().enumerate() {
+                    if t != Type::String {
+                        return Err(mismatch(idx, Expected::Type(Type::String), t));
+                    }
+                }
+                Ok(Type::String)
+            }
+            Expression::Component(index, expr) => match arg(0, expr)? {
+                Type::Product(components) if *index < components.len() => {
+                    Ok(components[*index].clone())
+                }
+                found @ Type::Product(_) => Err(TypeErrorKind::MissingComponent {
+                    index: *index,
+                    found,
+                }
+                .into()),
+                t => Err(mismatch(0, Expected::Tuple, t)),
+            },
+            Expression::Ite(exprs) => {
+                let cond_type = arg(0, &exprs.0)?;
+                let then_type = arg(1, &exprs.1)?;
+                let else_type = arg(2, &exprs.2)?;
+                if cond_type != Type::Boolean {
+                    Err(mismatch(0, Expected::Type(Type::Boolean), cond_type))
+                } else if then_type != else_type {
+                    Err(mismatch(2, Expected::Type(then_type), else_type))
                 } else {
-                    Err(TypeError::TypeMismatch)
+                    Ok(then_type)
                 }
             }
             Expression::Append(exprs) => {
-                let list_type = exprs.0.r#type()?;
-                let element_type = exprs.1.r#type()?;
+                let list_type = arg(0, &exprs.0)?;
+                let element_type = arg(1, &exprs.1)?;
                 if let Type::List(ref elements_type) = list_type {
                     if &element_type == elements_type.as_ref() {
                         Ok(list_type)
                     } else {
-                        Err(TypeError::TypeMismatch)
+                        Err(mismatch(
+                            1,
+                            Expected::Type(elements_type.as_ref().clone()),
+                            element_type,
+                        ))
                     }
                 } else {
-                    Err(TypeError::TypeMismatch)
+                    Err(mismatch(0, Expected::List, list_type))
                 }
             }
-            Expression::Truncate(list) => {
-                let list_type = list.r#type()?;
-                if let Type::List(_) = list_type {
-                    Ok(list_type)
+            Expression::Truncate(list) => match arg(0, list)? {
+                list_type @ Type::List(_) => Ok(list_type),
+                t => Err(mismatch(0, Expected::List, t)),
+            },
+            Expression::Len(list) => match arg(0, list)? {
+                Type::List(_) | Type::Array(_, _) => Ok(Type::Integer),
+                t => Err(mismatch(0, Expected::Sequence, t)),
+            },
+            Expression::Array(exprs) => {
+                let types = args(exprs)?;
+                let t = types.first().ok_or(TypeErrorKind::NoArguments)?;
+                if let Some((idx, other)) = types.iter().enumerate().find(|(_, other)| *other != t)
+                {
+                    Err(mismatch(idx, Expected::Type(t.clone()), other.clone()))
                 } else {
-                    Err(TypeError::TypeMismatch)
+                    Ok(Type::Array(Box::new(t.clone()), types.len()))
                 }
             }
-            Expression::Len(list) => {
-                let list_type = list.r#type()?;
-                if let Type::List(_) = list_type {
-                    Ok(Type::Integer)
+            Expression::Index(exprs) => {
+                let (elem_type, len) = match arg(0, &exprs.0)? {
+                    Type::List(t) => (*t, None),
+                    Type::Array(t, len) => (*t, Some(len)),
+                    t => return Err(mismatch(0, Expected::Sequence, t)),
+                };
+                check_index(&exprs.1, len)?;
+                Ok(elem_type)
+            }
+            Expression::Update(exprs) => {
+                let list_type = arg(0, &exprs.0)?;
+                let (elem_type, len) = match &list_type {
+                    Type::List(t) => (t.as_ref(), None),
+                    Type::Array(t, len) => (t.as_ref(), Some(*len)),
+                    t => return Err(mismatch(0, Expected::Sequence, t.clone())),
+                };
+                check_index(&exprs.1, len)?;
+                let element_type = arg(2, &exprs.2)?;
+                if &element_type == elem_type {
+                    Ok(list_type)
                 } else {
-                    Err(TypeError::TypeMismatch)
+                    Err(mismatch(2, Expected::Type(elem_type.clone()), element_type))
                 }
             }
             Expression::Mod(exprs) => {
-                if matches!(exprs.0.r#type()?, Type::Integer)
-                    && matches!(exprs.1.r#type()?, Type::Integer)
+                for (idx, t) in [arg(0, &exprs.0)?, arg(1, &exprs.1)?]
+                    .into_iter()
+                    .enumerate()
                 {
-                    Ok(Type::Integer)
-                } else {
-                    Err(TypeError::TypeMismatch)
+                    if t != Type::Integer {
+                        return Err(mismatch(idx, Expected::Type(Type::Integer), t));
+                    }
                 }
+                Ok(Type::Integer)
             }
+            Expression::Div(exprs) | Expression::Pow(exprs) => {
+                match (arg(0, &exprs.0)?, arg(1, &exprs.1)?) {
+                    (Type::Integer, Type::Integer) => Ok(Type::Integer),
+                    (Type::Integer | Type::Float, Type::Integer | Type::Float) => Ok(Type::Float),
+                    (Type::Integer | Type::Float, t) => Err(mismatch(1, numeric(), t)),
+                    (t, _) => Err(mismatch(0, numeric(), t)),
+                }
+            }
+            Expression::Floor(expr)
+            | Expression::Ceil(expr)
+            | Expression::Round(expr)
+            | Expression::ToInt(expr) => match arg(0, expr)? {
+                Type::Integer | Type::Float => Ok(Type::Integer),
+                t => Err(mismatch(0, numeric(), t)),
+            },
+            Expression::ToFloat(expr) => match arg(0, expr)? {
+                Type::Integer | Type::Float => Ok(Type::Float),
+                t => Err(mismatch(0, numeric(), t)),
+            },
+        }
+    }
+
+    /// Checks that the variables in the expression are used with their actual types,
+    /// as given by `vars`.
+    ///
+    /// ```
+    /// # use scan_core::*;
+    /// let expr = Expression::Sum(vec![Expression::from(1), Expression::Var("x", Type::Integer)]);
+    /// let err = expr.context(&|_| Some(Type::Float)).unwrap_err();
+    /// assert_eq!(
+    ///     err.kind,
+    ///     TypeErrorKind::VarMismatch { declared: Type::Float, found: Type::Integer },
+    /// );
+    /// assert_eq!(err.path, vec![1]);
+    /// assert_eq!(err.var.as_deref(), Some("x"));
+    /// ```
+    pub fn context(&self, vars: &dyn Fn(V) -> Option<Type>) -> Result<(), TypeError>
+    where
+        V: std::fmt::Display,
+    {
+        if let Expression::Var(var, t) = self {
+            let kind = match vars(var.clone()) {
+                Some(var_t) if var_t.unbounded() == t.unbounded() => return Ok(()),
+                Some(declared) => TypeErrorKind::VarMismatch {
+                    declared,
+                    found: t.clone(),
+                },
+                None => TypeErrorKind::UnknownVar,
+            };
+            Err(TypeError {
+                kind,
+                path: Vec::new(),
+                var: Some(var.to_string()),
+            })
+        } else {
+            self.args()
+                .into_iter()
+                .enumerate()
+                .try_for_each(|(idx, expr)| expr.context(vars).map_err(|err| err.at(idx)))
         }
     }
 
-    pub fn context(&self, vars: &dyn Fn(V) -> Option<Type>) -> Result<(), TypeError> {
+    // The arguments of the expression, in the order used by paths (see [`TypeError::path`]).
+    fn args(&self) -> Vec<&Expression<V>> {
         match self {
-            Expression::Var(var, t) => {
-                if let Some(var_t) = vars(var.clone()) {
-                    if &var_t == t {
-                        Ok(())
-                    } else {
-                        Err(TypeError::TypeMismatch)
-                    }
-                } else {
-                    Err(TypeError::UnknownVar)
-                }
-            }
-            Expression::Const(_) => Ok(()),
-            Expression::Tuple(tuple)
-            | Expression::And(tuple)
-            | Expression::Or(tuple)
-            | Expression::Sum(tuple)
-            | Expression::Mult(tuple) => tuple.iter().try_for_each(|expr| expr.context(vars)),
+            Expression::Const(_) | Expression::Var(_, _) => Vec::new(),
+            Expression::Tuple(exprs)
+            | Expression::And(exprs)
+            | Expression::Or(exprs)
+            | Expression::Sum(exprs)
+            | Expression::Mult(exprs)
+            | Expression::Min(exprs)
+            | Expression::Max(exprs)
+            | Expression::Concat(exprs)
+            | Expression::Array(exprs) => exprs.iter().collect(),
             Expression::Component(_, expr)
             | Expression::Not(expr)
             | Expression::Opposite(expr)
             | Expression::Truncate(expr)
-            | Expression::Len(expr) => expr.context(vars),
+            | Expression::Len(expr)
+            | Expression::Abs(expr)
+            | Expression::Floor(expr)
+            | Expression::Ceil(expr)
+            | Expression::Round(expr)
+            | Expression::ToFloat(expr)
+            | Expression::ToInt(expr) => vec![expr.as_ref()],
             Expression::Implies(exprs)
             | Expression::Equal(exprs)
             | Expression::Greater(exprs)
@@ -333,12 +757,23 @@ where
             | Expression::Less(exprs)
             | Expression::LessEq(exprs)
             | Expression::Mod(exprs)
-            | Expression::Append(exprs) => {
-                exprs.0.context(vars).and_then(|_| exprs.1.context(vars))
+            | Expression::Div(exprs)
+            | Expression::Pow(exprs)
+            | Expression::Append(exprs)
+            | Expression::Index(exprs) => vec![&exprs.0, &exprs.1],
+            Expression::Ite(exprs) | Expression::Update(exprs) => {
+                vec![&exprs.0, &exprs.1, &exprs.2]
             }
         }
     }
 
+    /// The sub-expression at the given path,
+    /// i.e., following the sequence of argument indices from the root (see [`TypeError::path`]).
+    pub fn subexpression(&self, path: &[usize]) -> Option<&Expression<V>> {
+        path.iter()
+            .try_fold(self, |expr, idx| expr.args().get(*idx).copied())
+    }
+
     pub fn and(args: Vec<Self>) -> Self {
         match args.len() {
             0 => Expression::Const(Val::Boolean(true)),
@@ -382,6 +817,33 @@ where
             Expression::Component(index, Box::new(self))
         }
     }
+
+    // Whether the expression contains no variables.
+    fn is_constant(&self) -> bool {
+        !matches!(self, Expression::Var(_, _)) && self.args().iter().all(|arg| arg.is_constant())
+    }
+}
+
+impl<V> Expression<V>
+where
+    V: Clone + Copy + Send + Sync + 'static,
+{
+    /// Evaluates an expression that contains no variables,
+    /// with integer overflows behaving as specified.
+    ///
+    /// Returns `None` if the expression contains variables.
+    ///
+    /// ```
+    /// # use scan_core::{Expression, Integer, Overflow, Val};
+    /// let expr = Expression::<usize>::from(Integer::from(2)) * Expression::from(Integer::from(3));
+    /// assert_eq!(expr.eval_constant(Overflow::Checked), Some(Ok(Val::Integer(6))));
+    /// ```
+    pub fn eval_constant(&self, overflow: Overflow) -> Option<Result<Val, EvalError>> {
+        self.is_constant().then(|| {
+            FnExpression::new(self.clone(), overflow)
+                .eval(&|_| unreachable!("constant expressions have no variables"))
+        })
+    }
 }
 
 impl<V> std::ops::Not for Expression<V>
@@ -503,29 +965,197 @@ where
     }
 }
 
-type DynFnExpr<V> = dyn Fn(&dyn Fn(V) -> Val) -> Val + Send + Sync;
+impl<V> From<&str> for Expression<V>
+where
+    V: Clone,
+{
+    fn from(value: &str) -> Self {
+        Expression::Const(Val::from(value))
+    }
+}
 
+// Arithmetic operations with no natural result on some inputs
+// (division by zero, overflows, non-finite floats) are made total,
+// so that all backends and constant folding share the same semantics.
+
+impl Overflow {
+    /// Integer addition.
+    pub(crate) fn add(self, lhs: Integer, rhs: Integer) -> Result<Integer, EvalError> {
+        match self {
+            Overflow::Checked => lhs.checked_add(rhs).ok_or(EvalError::Overflow),
+            Overflow::Saturating => Ok(lhs.saturating_add(rhs)),
+            Overflow::Wrapping => Ok(lhs.wrapping_add(rhs)),
+        }
+    }
+
+    /// Integer multiplication.
+    pub(crate) fn mul(self, lhs: Integer, rhs: Integer) -> Result<Integer, EvalError> {
+        match self {
+            Overflow::Checked => lhs.checked_mul(rhs).ok_or(EvalError::Overflow),
+            Overflow::Saturating => Ok(lhs.saturating_mul(rhs)),
+            Overflow::Wrapping => Ok(lhs.wrapping_mul(rhs)),
+        }
+    }
+
+    /// Integer opposite.
+    pub(crate) fn neg(self, i: Integer) -> Result<Integer, EvalError> {
+        match self {
+            Overflow::Checked => i.checked_neg().ok_or(EvalError::Overflow),
+            Overflow::Saturating => Ok(i.saturating_neg()),
+            Overflow::Wrapping => Ok(i.wrapping_neg()),
+        }
+    }
+
+    /// Integer absolute value.
+    pub(crate) fn abs(self, i: Integer) -> Result<Integer, EvalError> {
+        match self {
+            Overflow::Checked => i.checked_abs().ok_or(EvalError::Overflow),
+            Overflow::Saturating => Ok(i.saturating_abs()),
+            Overflow::Wrapping => Ok(i.wrapping_abs()),
+        }
+    }
+
+    /// Integer division, truncating towards zero, with `x / 0 == 0`.
+    pub(crate) fn div(self, lhs: Integer, rhs: Integer) -> Result<Integer, EvalError> {
+        match self {
+            _ if rhs == 0 => Ok(0),
+            Overflow::Checked => lhs.checked_div(rhs).ok_or(EvalError::Overflow),
+            Overflow::Saturating => Ok(lhs.saturating_div(rhs)),
+            Overflow::Wrapping => Ok(lhs.wrapping_div(rhs)),
+        }
+    }
+
+    /// Integer exponentiation, truncating the result towards zero for negative exponents.
+    pub(crate) fn pow(self, base: Integer, exp: Integer) -> Result<Integer, EvalError> {
+        match self {
+            Overflow::Wrapping if exp > u32::MAX as Integer => {
+                // Exponentiation by squaring, as `wrapping_pow` only takes `u32` exponents.
+                let (mut acc, mut base, mut exp) = (1 as Integer, base, exp);
+                while exp > 0 {
+                    if exp % 2 == 1 {
+                        acc = acc.wrapping_mul(base);
+                    }
+                    base = base.wrapping_mul(base);
+                    exp /= 2;
+                }
+                Ok(acc)
+            }
+            _ if exp >= 0 => {
+                // Huge exponents overflow unless `base` is -1, 0 or 1,
+                // in which case only their parity matters.
+                let exp = u32::try_from(exp).unwrap_or(u32::MAX - (exp % 2 == 0) as u32);
+                match self {
+                    Overflow::Checked => base.checked_pow(exp).ok_or(EvalError::Overflow),
+                    Overflow::Saturating => Ok(base.saturating_pow(exp)),
+                    Overflow::Wrapping => Ok(base.wrapping_pow(exp)),
+                }
+            }
+            _ => Ok(match base {
+                1 => 1,
+                -1 if exp % 2 == 0 => 1,
+                -1 => -1,
+                _ => 0,
+            }),
+        }
+    }
+
+    /// Conversion to integer, mapping NaN to `0`.
+    pub(crate) fn float_to_int(self, f: Float) -> Result<Integer, EvalError> {
+        // `Integer::MAX as Float` rounds up to a power of two, which is out of range.
+        if self == Overflow::Checked
+            && !(Integer::MIN as Float..Integer::MAX as Float).contains(&f.trunc())
+            && !f.is_nan()
+        {
+            Err(EvalError::Overflow)
+        } else {
+            Ok(f as Integer)
+        }
+    }
+}
+
+/// Integer remainder, with the sign of `lhs` and `x % 0 == x`.
+pub(crate) fn int_rem(lhs: Integer, rhs: Integer) -> Integer {
+    if rhs == 0 {
+        lhs
+    } else {
+        lhs.wrapping_rem(rhs)
+    }
+}
+
+/// Position in a list or array of length `len`, if `idx` is within bounds.
+pub(crate) fn index(idx: Integer, len: usize) -> Result<usize, EvalError> {
+    usize::try_from(idx)
+        .ok()
+        .filter(|idx| *idx < len)
+        .ok_or(EvalError::IndexOutOfBounds(idx, len))
+}
+
+// Constant indexes of arrays (the second argument of an expression) can be checked statically.
+fn check_index<V: Clone>(idx: &Expression<V>, len: Option<usize>) -> Result<(), TypeError> {
+    match idx.r#type().map_err(|err| err.at(1))? {
+        Type::Integer => {}
+        t => return Err(mismatch(1, Expected::Type(Type::Integer), t)),
+    }
+    if let (Some(len), Expression::Const(Val::Integer(idx))) = (len, idx) {
+        index(*idx, len).map_err(|_| {
+            TypeError::from(TypeErrorKind::IndexOutOfBounds { index: *idx, len }).at(1)
+        })?;
+    }
+    Ok(())
+}
+
+/// Rounding to the closest integer, with halfway cases rounded up.
+pub(crate) fn round(f: Float) -> Float {
+    let floor = f.floor();
+    if f - floor >= 0.5 {
+        floor + 1.0
+    } else {
+        floor
+    }
+}
+
+// Expressions are compiled into closures by default,
+// or into bytecode if the `bytecode` feature is enabled.
+#[cfg(feature = "bytecode")]
+pub(crate) use bytecode::FnExpression;
+
+#[cfg(not(feature = "bytecode"))]
+type DynFnExpr<V> = dyn for<'a> Fn(&dyn Fn(V) -> &'a Val) -> Result<Val, EvalError> + Send + Sync;
+
+#[cfg(not(feature = "bytecode"))]
 pub(crate) struct FnExpression<V>(Box<DynFnExpr<V>>);
 
+#[cfg(not(feature = "bytecode"))]
 impl<C> std::fmt::Debug for FnExpression<C> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "Expression")
     }
 }
 
+#[cfg(not(feature = "bytecode"))]
 impl<V> FnExpression<V> {
     #[inline(always)]
-    pub fn eval(&self, vars: &dyn Fn(V) -> Val) -> Val {
+    pub fn eval<'a>(&self, vars: &dyn Fn(V) -> &'a Val) -> Result<Val, EvalError> {
         self.0(vars)
     }
 }
 
+#[cfg(not(feature = "bytecode"))]
 impl<V: Clone + Copy + Send + Sync + 'static> From<Expression<V>> for FnExpression<V> {
     fn from(value: Expression<V>) -> Self {
+        Self::new(value, Overflow::default())
+    }
+}
+
+#[cfg(not(feature = "bytecode"))]
+impl<V: Clone + Copy + Send + Sync + 'static> FnExpression<V> {
+    /// Compiles the expression, with integer overflows behaving as specified.
+    pub fn new(value: Expression<V>, overflow: Overflow) -> Self {
+        let compile = move |expr: Expression<V>| Self::new(expr, overflow);
         FnExpression(match value {
-            Expression::Const(val) => Box::new(move |_| val.clone()),
+            Expression::Const(val) => Box::new(move |_| Ok(val.clone())),
             Expression::Var(var, _t) => Box::new(move |vars| {
-                vars(var)
+                Ok(vars(var).clone())
                 // let val = vars(var);
                 // if t == val.r#type() {
                 //     val
@@ -534,46 +1164,66 @@ impl<V: Clone + Copy + Send + Sync + 'static> From<Expression<V>> for FnExpressi
                 // }
             }),
             Expression::Tuple(exprs) => {
-                let exprs: Vec<FnExpression<_>> =
-                    exprs.into_iter().map(FnExpression::from).collect();
+                let exprs: Vec<FnExpression<_>> = exprs.into_iter().map(compile).collect();
                 Box::new(move |vars| {
-                    Val::Tuple(exprs.iter().map(|expr| expr.eval(vars)).collect::<Vec<_>>())
+                    exprs
+                        .iter()
+                        .map(|expr| expr.eval(vars))
+                        .collect::<Result<Vec<_>, _>>()
+                        .map(Val::Tuple)
                 })
             }
             Expression::Component(index, expr) => {
-                let expr = Self::from(*expr);
+                let expr = compile(*expr);
                 Box::new(move |vars| {
-                    if let Val::Tuple(vals) = expr.eval(vars) {
-                        vals[index].clone()
+                    if let Val::Tuple(vals) = expr.eval(vars)? {
+                        Ok(vals[index].clone())
                     } else {
                         panic!("index out of bounds");
                     }
                 })
             }
+            Expression::Ite(exprs) => {
+                let (cond, then, r#else) = *exprs;
+                let cond = compile(cond);
+                let then = compile(then);
+                let r#else = compile(r#else);
+                Box::new(move |vars| {
+                    if let Val::Boolean(b) = cond.eval(vars)? {
+                        if b {
+                            then.eval(vars)
+                        } else {
+                            r#else.eval(vars)
+                        }
+                    } else {
+                        panic!("type mismatch");
+                    }
+                })
+            }
             Expression::And(exprs) => {
-                let exprs: Vec<FnExpression<_>> = exprs.into_iter().map(Self::from).collect();
+                let exprs: Vec<FnExpression<_>> = exprs.into_iter().map(compile).collect();
                 Box::new(move |vars| {
                     for expr in exprs.iter() {
-                        if let Val::Boolean(b) = expr.eval(vars) {
+                        if let Val::Boolean(b) = expr.eval(vars)? {
                             if b {
                                 continue;
                             } else {
-                                return Val::Boolean(false);
+                                return Ok(Val::Boolean(false));
                             }
                         } else {
                             panic!("type mismatch");
                         }
                     }
-                    Val::Boolean(true)
+                    Ok(Val::Boolean(true))
                 })
             }
             Expression::Or(exprs) => {
-                let exprs: Vec<FnExpression<_>> = exprs.into_iter().map(Self::from).collect();
+                let exprs: Vec<FnExpression<_>> = exprs.into_iter().map(compile).collect();
                 Box::new(move |vars| {
                     for expr in exprs.iter() {
-                        if let Val::Boolean(b) = expr.eval(vars) {
+                        if let Val::Boolean(b) = expr.eval(vars)? {
                             if b {
-                                return Val::Boolean(true);
+                                return Ok(Val::Boolean(true));
                             } else {
                                 continue;
                             }
@@ -581,99 +1231,122 @@ impl<V: Clone + Copy + Send + Sync + 'static> From<Expression<V>> for FnExpressi
                             panic!("type mismatch");
                         }
                     }
-                    Val::Boolean(false)
+                    Ok(Val::Boolean(false))
                 })
             }
             Expression::Implies(exprs) => {
                 let (lhs, rhs) = *exprs;
-                let lhs = FnExpression::from(lhs);
-                let rhs = FnExpression::from(rhs);
+                let lhs = compile(lhs);
+                let rhs = compile(rhs);
                 Box::new(move |vars| {
-                    if let (Val::Boolean(lhs), Val::Boolean(rhs)) = (lhs.eval(vars), rhs.eval(vars))
-                    {
-                        Val::Boolean(rhs || !lhs)
+                    if let Val::Boolean(lhs) = lhs.eval(vars)? {
+                        if !lhs {
+                            return Ok(Val::Boolean(true));
+                        }
+                    } else {
+                        panic!("type mismatch");
+                    }
+                    if let Val::Boolean(rhs) = rhs.eval(vars)? {
+                        Ok(Val::Boolean(rhs))
                     } else {
                         panic!("type mismatch");
                     }
                 })
             }
             Expression::Not(expr) => {
-                let expr = FnExpression::from(*expr);
+                let expr = compile(*expr);
                 Box::new(move |vars| {
-                    if let Val::Boolean(b) = expr.eval(vars) {
-                        Val::Boolean(!b)
+                    if let Val::Boolean(b) = expr.eval(vars)? {
+                        Ok(Val::Boolean(!b))
                     } else {
                         panic!("type mismatch");
                     }
                 })
             }
             Expression::Opposite(expr) => {
-                let expr = FnExpression::from(*expr);
-                Box::new(move |vars| match expr.eval(vars) {
-                    Val::Integer(i) => Val::Integer(-i),
-                    Val::Float(f) => Val::Float(-f),
+                let expr = compile(*expr);
+                Box::new(move |vars| match expr.eval(vars)? {
+                    Val::Integer(i) => Ok(Val::Integer(overflow.neg(i)?)),
+                    Val::Float(f) => Ok(Val::Float(-f)),
                     _ => panic!("type mismatch"),
                 })
             }
             Expression::Sum(exprs) => {
-                let exprs: Vec<FnExpression<_>> = exprs.into_iter().map(Self::from).collect();
+                let exprs: Vec<FnExpression<_>> = exprs.into_iter().map(compile).collect();
                 Box::new(move |vars| {
-                    exprs.iter().fold(Val::Integer(0), |val, expr| match val {
-                        Val::Integer(acc) => match expr.eval(vars) {
-                            Val::Integer(i) => Val::Integer(acc + i),
-                            Val::Float(f) => Val::Float(OrderedFloat::from(acc) + f),
+                    exprs.iter().try_fold(Val::Integer(0), |val, expr| {
+                        Ok(match val {
+                            Val::Integer(acc) => match expr.eval(vars)? {
+                                Val::Integer(i) => Val::Integer(overflow.add(acc, i)?),
+                                Val::Float(f) => Val::Float(OrderedFloat(acc as Float) + f),
+                                _ => panic!("type mismatch"),
+                            },
+                            Val::Float(acc) => match expr.eval(vars)? {
+                                Val::Integer(i) => Val::Float(acc + OrderedFloat(i as Float)),
+                                Val::Float(f) => Val::Float(acc + f),
+                                _ => panic!("type mismatch"),
+                            },
                             _ => panic!("type mismatch"),
-                        },
-                        Val::Float(acc) => match expr.eval(vars) {
-                            Val::Integer(i) => Val::Float(acc + OrderedFloat::from(i)),
-                            Val::Float(f) => Val::Float(acc + f),
-                            _ => panic!("type mismatch"),
-                        },
-                        _ => panic!("type mismatch"),
+                        })
                     })
                 })
             }
             Expression::Mult(exprs) => {
-                let exprs: Vec<FnExpression<_>> = exprs.into_iter().map(Self::from).collect();
+                let exprs: Vec<FnExpression<_>> = exprs.into_iter().map(compile).collect();
                 Box::new(move |vars| {
-                    exprs.iter().fold(Val::Integer(0), |val, expr| match val {
-                        Val::Integer(acc) => match expr.eval(vars) {
-                            Val::Integer(i) => Val::Integer(acc * i),
-                            Val::Float(f) => Val::Float(OrderedFloat::from(acc) * f),
+                    exprs.iter().try_fold(Val::Integer(1), |val, expr| {
+                        Ok(match val {
+                            Val::Integer(acc) => match expr.eval(vars)? {
+                                Val::Integer(i) => Val::Integer(overflow.mul(acc, i)?),
+                                Val::Float(f) => Val::Float(OrderedFloat(acc as Float) * f),
+                                _ => panic!("type mismatch"),
+                            },
+                            Val::Float(acc) => match expr.eval(vars)? {
+                                Val::Integer(i) => Val::Float(acc * OrderedFloat(i as Float)),
+                                Val::Float(f) => Val::Float(acc * f),
+                                _ => panic!("type mismatch"),
+                            },
                             _ => panic!("type mismatch"),
-                        },
-                        Val::Float(acc) => match expr.eval(vars) {
-                            Val::Integer(i) => Val::Float(acc * OrderedFloat::from(i)),
-                            Val::Float(f) => Val::Float(acc * f),
-                            _ => panic!("type mismatch"),
-                        },
-                        _ => panic!("type mismatch"),
+                        })
                     })
                 })
             }
             Expression::Equal(exprs) => {
                 let (lhs, rhs) = *exprs;
-                let lhs = FnExpression::from(lhs);
-                let rhs = FnExpression::from(rhs);
-                Box::new(move |vars| match (lhs.eval(vars), rhs.eval(vars)) {
-                    (Val::Integer(lhs), Val::Integer(rhs)) => Val::Boolean(lhs == rhs),
-                    (Val::Boolean(lhs), Val::Boolean(rhs)) => Val::Boolean(lhs == rhs),
+                let lhs = compile(lhs);
+                let rhs = compile(rhs);
+                Box::new(move |vars| match (lhs.eval(vars)?, rhs.eval(vars)?) {
+                    (Val::Integer(lhs), Val::Integer(rhs)) => Ok(Val::Boolean(lhs == rhs)),
+                    (Val::Boolean(lhs), Val::Boolean(rhs)) => Ok(Val::Boolean(lhs == rhs)),
+                    (Val::String(lhs), Val::String(rhs)) => Ok(Val::Boolean(lhs == rhs)),
                     _ => panic!("type mismatch"),
                 })
             }
+            Expression::Concat(exprs) => {
+                let exprs: Vec<FnExpression<_>> = exprs.into_iter().map(compile).collect();
+                Box::new(move |vars| {
+                    exprs
+                        .iter()
+                        .try_fold(Str::default(), |acc, expr| match expr.eval(vars)? {
+                            Val::String(s) => Ok(acc.concat(&s)),
+                            _ => panic!("type mismatch"),
+                        })
+                        .map(Val::String)
+                })
+            }
             Expression::Greater(exprs) => {
                 let (lhs, rhs) = *exprs;
-                let lhs = FnExpression::from(lhs);
-                let rhs = FnExpression::from(rhs);
-                Box::new(move |vars| match lhs.eval(vars) {
-                    Val::Integer(lhs) => match rhs.eval(vars) {
-                        Val::Integer(rhs) => Val::Boolean(lhs > rhs),
-                        Val::Float(rhs) => Val::Boolean(OrderedFloat::from(lhs) > rhs),
+                let lhs = compile(lhs);
+                let rhs = compile(rhs);
+                Box::new(move |vars| match lhs.eval(vars)? {
+                    Val::Integer(lhs) => match rhs.eval(vars)? {
+                        Val::Integer(rhs) => Ok(Val::Boolean(lhs > rhs)),
+                        Val::Float(rhs) => Ok(Val::Boolean(OrderedFloat(lhs as Float) > rhs)),
                         _ => panic!("type mismatch"),
                     },
-                    Val::Float(lhs) => match rhs.eval(vars) {
-                        Val::Integer(rhs) => Val::Boolean(lhs > OrderedFloat::from(rhs)),
-                        Val::Float(rhs) => Val::Boolean(lhs > rhs),
+                    Val::Float(lhs) => match rhs.eval(vars)? {
+                        Val::Integer(rhs) => Ok(Val::Boolean(lhs > OrderedFloat(rhs as Float))),
+                        Val::Float(rhs) => Ok(Val::Boolean(lhs > rhs)),
                         _ => panic!("type mismatch"),
                     },
                     _ => panic!("type mismatch"),
@@ -681,12 +1354,13 @@ impl<V: Clone + Copy + Send + Sync + 'static> From<Expression<V>> for FnExpressi
             }
             Expression::GreaterEq(exprs) => {
                 let (lhs, rhs) = *exprs;
-                let lhs = FnExpression::from(lhs);
-                let rhs = FnExpression::from(rhs);
+                let lhs = compile(lhs);
+                let rhs = compile(rhs);
                 Box::new(move |vars| {
-                    if let (Val::Integer(lhs), Val::Integer(rhs)) = (lhs.eval(vars), rhs.eval(vars))
+                    if let (Val::Integer(lhs), Val::Integer(rhs)) =
+                        (lhs.eval(vars)?, rhs.eval(vars)?)
                     {
-                        Val::Boolean(lhs >= rhs)
+                        Ok(Val::Boolean(lhs >= rhs))
                     } else {
                         panic!("type mismatch");
                     }
@@ -694,17 +1368,17 @@ impl<V: Clone + Copy + Send + Sync + 'static> From<Expression<V>> for FnExpressi
             }
             Expression::Less(exprs) => {
                 let (lhs, rhs) = *exprs;
-                let lhs = FnExpression::from(lhs);
-                let rhs = FnExpression::from(rhs);
-                Box::new(move |vars| match lhs.eval(vars) {
-                    Val::Integer(lhs) => match rhs.eval(vars) {
-                        Val::Integer(rhs) => Val::Boolean(lhs < rhs),
-                        Val::Float(rhs) => Val::Boolean(OrderedFloat::from(lhs) < rhs),
+                let lhs = compile(lhs);
+                let rhs = compile(rhs);
+                Box::new(move |vars| match lhs.eval(vars)? {
+                    Val::Integer(lhs) => match rhs.eval(vars)? {
+                        Val::Integer(rhs) => Ok(Val::Boolean(lhs < rhs)),
+                        Val::Float(rhs) => Ok(Val::Boolean(OrderedFloat(lhs as Float) < rhs)),
                         _ => panic!("type mismatch"),
                     },
-                    Val::Float(lhs) => match rhs.eval(vars) {
-                        Val::Integer(rhs) => Val::Boolean(lhs < OrderedFloat::from(rhs)),
-                        Val::Float(rhs) => Val::Boolean(lhs < rhs),
+                    Val::Float(lhs) => match rhs.eval(vars)? {
+                        Val::Integer(rhs) => Ok(Val::Boolean(lhs < OrderedFloat(rhs as Float))),
+                        Val::Float(rhs) => Ok(Val::Boolean(lhs < rhs)),
                         _ => panic!("type mismatch"),
                     },
                     _ => panic!("type mismatch"),
@@ -712,12 +1386,13 @@ impl<V: Clone + Copy + Send + Sync + 'static> From<Expression<V>> for FnExpressi
             }
             Expression::LessEq(exprs) => {
                 let (source_lhs, source_rhs) = *exprs;
-                let lhs = FnExpression::from(source_lhs);
-                let rhs = FnExpression::from(source_rhs);
+                let lhs = compile(source_lhs);
+                let rhs = compile(source_rhs);
                 Box::new(move |vars| {
-                    if let (Val::Integer(lhs), Val::Integer(rhs)) = (lhs.eval(vars), rhs.eval(vars))
+                    if let (Val::Integer(lhs), Val::Integer(rhs)) =
+                        (lhs.eval(vars)?, rhs.eval(vars)?)
                     {
-                        Val::Boolean(lhs <= rhs)
+                        Ok(Val::Boolean(lhs <= rhs))
                     } else {
                         panic!("type mismatch");
                     }
@@ -725,14 +1400,14 @@ impl<V: Clone + Copy + Send + Sync + 'static> From<Expression<V>> for FnExpressi
             }
             Expression::Append(exprs) => {
                 let (list, element) = *exprs;
-                let list = FnExpression::from(list);
-                let element = FnExpression::from(element);
+                let list = compile(list);
+                let element = compile(element);
                 Box::new(move |vars| {
-                    if let Val::List(t, mut l) = list.eval(vars) {
-                        let element = element.eval(vars);
+                    if let Val::List(t, mut l) = list.eval(vars)? {
+                        let element = element.eval(vars)?;
                         if element.r#type() == t {
                             l.push(element);
-                            Val::List(t, l)
+                            Ok(Val::List(t, l))
                         } else {
                             panic!("type mismatch");
                         }
@@ -742,14 +1417,13 @@ impl<V: Clone + Copy + Send + Sync + 'static> From<Expression<V>> for FnExpressi
                 })
             }
             Expression::Truncate(list) => {
-                let list = FnExpression::from(*list);
+                let list = compile(*list);
                 Box::new(move |vars| {
-                    if let Val::List(t, mut l) = list.eval(vars) {
-                        if !l.is_empty() {
-                            let _ = l.pop();
-                            Val::List(t, l)
+                    if let Val::List(t, mut l) = list.eval(vars)? {
+                        if l.pop().is_some() {
+                            Ok(Val::List(t, l))
                         } else {
-                            panic!("type mismatch");
+                            Err(EvalError::EmptyList)
                         }
                     } else {
                         panic!("type mismatch");
@@ -757,28 +1431,192 @@ impl<V: Clone + Copy + Send + Sync + 'static> From<Expression<V>> for FnExpressi
                 })
             }
             Expression::Len(list) => {
-                let list = FnExpression::from(*list);
+                let list = compile(*list);
                 Box::new(move |vars| {
-                    if let Val::List(_t, l) = list.eval(vars) {
-                        Val::Integer(l.len() as Integer)
+                    if let Val::List(_t, l) | Val::Array(_t, l) = list.eval(vars)? {
+                        Ok(Val::Integer(l.len() as Integer))
                     } else {
                         panic!("type mismatch");
                     }
                 })
             }
+            Expression::Array(exprs) => {
+                let t = exprs
+                    .first()
+                    .and_then(|expr| expr.r#type().ok())
+                    .expect("non-empty well-typed array");
+                let exprs: Vec<FnExpression<_>> = exprs.into_iter().map(compile).collect();
+                Box::new(move |vars| {
+                    exprs
+                        .iter()
+                        .map(|expr| expr.eval(vars))
+                        .collect::<Result<Vec<_>, _>>()
+                        .map(|vals| Val::Array(t.clone(), vals))
+                })
+            }
+            Expression::Index(exprs) => {
+                let (list, idx) = *exprs;
+                let list = compile(list);
+                let idx = compile(idx);
+                Box::new(move |vars| {
+                    if let (Val::List(_, mut l) | Val::Array(_, mut l), Val::Integer(idx)) =
+                        (list.eval(vars)?, idx.eval(vars)?)
+                    {
+                        let idx = index(idx, l.len())?;
+                        Ok(l.swap_remove(idx))
+                    } else {
+                        panic!("type mismatch");
+                    }
+                })
+            }
+            Expression::Update(exprs) => {
+                let (list, idx, element) = *exprs;
+                let list = compile(list);
+                let idx = compile(idx);
+                let element = compile(element);
+                Box::new(move |vars| {
+                    match (list.eval(vars)?, idx.eval(vars)?, element.eval(vars)?) {
+                        (Val::List(t, mut l), Val::Integer(idx), element) => {
+                            let idx = index(idx, l.len())?;
+                            l[idx] = element;
+                            Ok(Val::List(t, l))
+                        }
+                        (Val::Array(t, mut l), Val::Integer(idx), element) => {
+                            let idx = index(idx, l.len())?;
+                            l[idx] = element;
+                            Ok(Val::Array(t, l))
+                        }
+                        _ => panic!("type mismatch"),
+                    }
+                })
+            }
             Expression::Mod(exprs) => {
                 let (lhs, rhs) = *exprs;
-                let lhs = FnExpression::from(lhs);
-                let rhs = FnExpression::from(rhs);
+                let lhs = compile(lhs);
+                let rhs = compile(rhs);
                 Box::new(move |vars| {
-                    if let (Val::Integer(lhs), Val::Integer(rhs)) = (lhs.eval(vars), rhs.eval(vars))
+                    if let (Val::Integer(lhs), Val::Integer(rhs)) =
+                        (lhs.eval(vars)?, rhs.eval(vars)?)
                     {
-                        Val::Integer(lhs % rhs)
+                        Ok(Val::Integer(int_rem(lhs, rhs)))
                     } else {
                         panic!("type mismatch");
                     }
                 })
             }
+            Expression::Div(exprs) => {
+                let (lhs, rhs) = *exprs;
+                let lhs = compile(lhs);
+                let rhs = compile(rhs);
+                Box::new(move |vars| match (lhs.eval(vars)?, rhs.eval(vars)?) {
+                    (Val::Integer(lhs), Val::Integer(rhs)) => {
+                        Ok(Val::Integer(overflow.div(lhs, rhs)?))
+                    }
+                    (lhs, rhs) => Ok(Val::from(as_float(lhs) / as_float(rhs))),
+                })
+            }
+            Expression::Pow(exprs) => {
+                let (lhs, rhs) = *exprs;
+                let lhs = compile(lhs);
+                let rhs = compile(rhs);
+                Box::new(move |vars| match (lhs.eval(vars)?, rhs.eval(vars)?) {
+                    (Val::Integer(lhs), Val::Integer(rhs)) => {
+                        Ok(Val::Integer(overflow.pow(lhs, rhs)?))
+                    }
+                    (lhs, rhs) => Ok(Val::from(as_float(lhs).powf(as_float(rhs)))),
+                })
+            }
+            Expression::Min(exprs) => {
+                let exprs: Vec<FnExpression<_>> = exprs.into_iter().map(compile).collect();
+                Box::new(move |vars| {
+                    let vals = exprs
+                        .iter()
+                        .map(|expr| expr.eval(vars))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    Ok(vals
+                        .into_iter()
+                        .reduce(|acc, val| match (acc, val) {
+                            (Val::Integer(acc), Val::Integer(i)) => Val::Integer(acc.min(i)),
+                            (acc, val) => Val::from(as_float(acc).min(as_float(val))),
+                        })
+                        .expect("non-empty arguments"))
+                })
+            }
+            Expression::Max(exprs) => {
+                let exprs: Vec<FnExpression<_>> = exprs.into_iter().map(compile).collect();
+                Box::new(move |vars| {
+                    let vals = exprs
+                        .iter()
+                        .map(|expr| expr.eval(vars))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    Ok(vals
+                        .into_iter()
+                        .reduce(|acc, val| match (acc, val) {
+                            (Val::Integer(acc), Val::Integer(i)) => Val::Integer(acc.max(i)),
+                            (acc, val) => Val::from(as_float(acc).max(as_float(val))),
+                        })
+                        .expect("non-empty arguments"))
+                })
+            }
+            Expression::Abs(expr) => {
+                let expr = compile(*expr);
+                Box::new(move |vars| match expr.eval(vars)? {
+                    Val::Integer(i) => Ok(Val::Integer(overflow.abs(i)?)),
+                    Val::Float(f) => Ok(Val::from(f.into_inner().abs())),
+                    _ => panic!("type mismatch"),
+                })
+            }
+            Expression::Floor(expr) => {
+                let expr = compile(*expr);
+                Box::new(move |vars| match expr.eval(vars)? {
+                    Val::Integer(i) => Ok(Val::Integer(i)),
+                    Val::Float(f) => {
+                        Ok(Val::Integer(overflow.float_to_int(f.into_inner().floor())?))
+                    }
+                    _ => panic!("type mismatch"),
+                })
+            }
+            Expression::Ceil(expr) => {
+                let expr = compile(*expr);
+                Box::new(move |vars| match expr.eval(vars)? {
+                    Val::Integer(i) => Ok(Val::Integer(i)),
+                    Val::Float(f) => {
+                        Ok(Val::Integer(overflow.float_to_int(f.into_inner().ceil())?))
+                    }
+                    _ => panic!("type mismatch"),
+                })
+            }
+            Expression::Round(expr) => {
+                let expr = compile(*expr);
+                Box::new(move |vars| match expr.eval(vars)? {
+                    Val::Integer(i) => Ok(Val::Integer(i)),
+                    Val::Float(f) => {
+                        Ok(Val::Integer(overflow.float_to_int(round(f.into_inner()))?))
+                    }
+                    _ => panic!("type mismatch"),
+                })
+            }
+            Expression::ToInt(expr) => {
+                let expr = compile(*expr);
+                Box::new(move |vars| match expr.eval(vars)? {
+                    Val::Integer(i) => Ok(Val::Integer(i)),
+                    Val::Float(f) => Ok(Val::Integer(overflow.float_to_int(f.into_inner())?)),
+                    _ => panic!("type mismatch"),
+                })
+            }
+            Expression::ToFloat(expr) => {
+                let expr = compile(*expr);
+                Box::new(move |vars| Ok(Val::from(as_float(expr.eval(vars)?))))
+            }
         })
     }
 }
+
+#[cfg(not(feature = "bytecode"))]
+fn as_float(val: Val) -> Float {
+    match val {
+        Val::Integer(i) => i as Float,
+        Val::Float(f) => f.into_inner(),
+        _ => panic!("type mismatch"),
+    }
+}
@@ -0,0 +1,856 @@
+//! Alternative backend compiling [`Expression`]s into bytecode for a stack-based VM.
+//!
+//! Operations are specialized on the (statically known) type of their arguments,
+//! so that evaluation does not need to dispatch on [`Val`]s' types.
+//! Variables are accessed by reference,
+//! and tuple components, list lengths and list elements are read in place without cloning the whole value.
+//!
+//! Enabled by the `bytecode` feature.
+
+use super::{
+    index, int_rem, round, EvalError, Expression, Float, Integer, Overflow, Str, Type, Val,
+};
+use ordered_float::OrderedFloat;
+use smallvec::SmallVec;
+
+#[derive(Debug, Clone)]
+enum Op<V> {
+    Const(Val),
+    Load(V),
+    // Loads a nested component of a tuple variable.
+    LoadComponent(V, Box<[usize]>),
+    // Loads the length of a list variable.
+    LoadLen(V),
+    // Pops an index and loads the element of a list variable at that index.
+    LoadIndex(V),
+    Tuple(usize),
+    Array(Type, usize),
+    Component(usize),
+    Jump(usize),
+    // Pops the top of the stack and jumps if it is `false`.
+    JumpIfFalse(usize),
+    // Short-circuiting: jump if the top of the stack is `false`, or pop it otherwise.
+    JumpIfFalseOrPop(usize),
+    // Short-circuiting: jump if the top of the stack is `true`, or pop it otherwise.
+    JumpIfTrueOrPop(usize),
+    Not,
+    IntToFloat,
+    NegInt,
+    NegFloat,
+    AddInt,
+    AddFloat,
+    MulInt,
+    MulFloat,
+    ModInt,
+    DivInt,
+    DivFloat,
+    PowInt,
+    PowFloat,
+    MinInt,
+    MinFloat,
+    MaxInt,
+    MaxFloat,
+    AbsInt,
+    AbsFloat,
+    Floor,
+    Ceil,
+    Round,
+    FloatToInt,
+    EqInt,
+    EqBool,
+    Eq,
+    LessInt,
+    LessFloat,
+    LessEqInt,
+    LessEqFloat,
+    GreaterInt,
+    GreaterFloat,
+    GreaterEqInt,
+    GreaterEqFloat,
+    // Pops the given number of strings and pushes their concatenation.
+    Concat(usize),
+    Append,
+    Truncate,
+    Len,
+    Index,
+    Update,
+}
+
+/// Expressions compiled into bytecode, together with the behaviour of integer overflows.
+pub(crate) struct FnExpression<V>(Box<[Op<V>]>, Overflow);
+
+impl<V> std::fmt::Debug for FnExpression<V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Expression")
+    }
+}
+
+type Stack = SmallVec<[Val; 8]>;
+
+#[inline(always)]
+fn pop(stack: &mut Stack) -> Val {
+    stack.pop().expect("stack underflow")
+}
+
+#[inline(always)]
+fn pop_int(stack: &mut Stack) -> Integer {
+    if let Val::Integer(i) = pop(stack) {
+        i
+    } else {
+        panic!("type mismatch");
+    }
+}
+
+#[inline(always)]
+fn pop_float(stack: &mut Stack) -> OrderedFloat<Float> {
+    if let Val::Float(f) = pop(stack) {
+        f
+    } else {
+        panic!("type mismatch");
+    }
+}
+
+#[inline(always)]
+fn pop_bool(stack: &mut Stack) -> bool {
+    if let Val::Boolean(b) = pop(stack) {
+        b
+    } else {
+        panic!("type mismatch");
+    }
+}
+
+#[inline(always)]
+fn top_bool(stack: &Stack) -> bool {
+    if let Some(Val::Boolean(b)) = stack.last() {
+        *b
+    } else {
+        panic!("type mismatch");
+    }
+}
+
+impl<V: Copy> FnExpression<V> {
+    pub fn eval<'a>(&self, vars: &dyn Fn(V) -> &'a Val) -> Result<Val, EvalError> {
+        let overflow = self.1;
+        let mut stack = Stack::new();
+        let mut pc = 0;
+        while let Some(op) = self.0.get(pc) {
+            pc += 1;
+            match op {
+                Op::Const(val) => stack.push(val.clone()),
+                Op::Load(var) => stack.push(vars(*var).clone()),
+                Op::LoadComponent(var, path) => {
+                    let val = path.iter().fold(vars(*var), |val, idx| {
+                        if let Val::Tuple(vals) = val {
+                            &vals[*idx]
+                        } else {
+                            panic!("type mismatch");
+                        }
+                    });
+                    stack.push(val.clone());
+                }
+                Op::LoadLen(var) => {
+                    if let Val::List(_, vals) | Val::Array(_, vals) = vars(*var) {
+                        stack.push(Val::Integer(vals.len() as Integer));
+                    } else {
+                        panic!("type mismatch");
+                    }
+                }
+                Op::LoadIndex(var) => {
+                    let idx = pop_int(&mut stack);
+                    if let Val::List(_, vals) | Val::Array(_, vals) = vars(*var) {
+                        stack.push(vals[index(idx, vals.len())?].clone());
+                    } else {
+                        panic!("type mismatch");
+                    }
+                }
+                Op::Tuple(len) => {
+                    let vals = stack.drain(stack.len() - len..).collect();
+                    stack.push(Val::Tuple(vals));
+                }
+                Op::Array(t, len) => {
+                    let vals = stack.drain(stack.len() - len..).collect();
+                    stack.push(Val::Array(t.clone(), vals));
+                }
+                Op::Component(idx) => {
+                    if let Val::Tuple(mut vals) = pop(&mut stack) {
+                        stack.push(vals.swap_remove(*idx));
+                    } else {
+                        panic!("type mismatch");
+                    }
+                }
+                Op::Jump(target) => pc = *target,
+                Op::JumpIfFalse(target) => {
+                    if !pop_bool(&mut stack) {
+                        pc = *target;
+                    }
+                }
+                Op::JumpIfFalseOrPop(target) => {
+                    if top_bool(&stack) {
+                        stack.pop();
+                    } else {
+                        pc = *target;
+                    }
+                }
+                Op::JumpIfTrueOrPop(target) => {
+                    if top_bool(&stack) {
+                        pc = *target;
+                    } else {
+                        stack.pop();
+                    }
+                }
+                Op::Not => {
+                    let b = pop_bool(&mut stack);
+                    stack.push(Val::Boolean(!b));
+                }
+                Op::IntToFloat => {
+                    let i = pop_int(&mut stack);
+                    stack.push(Val::Float(OrderedFloat(i as Float)));
+                }
+                Op::NegInt => {
+                    let i = pop_int(&mut stack);
+                    stack.push(Val::Integer(overflow.neg(i)?));
+                }
+                Op::NegFloat => {
+                    let f = pop_float(&mut stack);
+                    stack.push(Val::Float(-f));
+                }
+                Op::AddInt => {
+                    let (rhs, lhs) = (pop_int(&mut stack), pop_int(&mut stack));
+                    stack.push(Val::Integer(overflow.add(lhs, rhs)?));
+                }
+                Op::AddFloat => {
+                    let (rhs, lhs) = (pop_float(&mut stack), pop_float(&mut stack));
+                    stack.push(Val::Float(lhs + rhs));
+                }
+                Op::MulInt => {
+                    let (rhs, lhs) = (pop_int(&mut stack), pop_int(&mut stack));
+                    stack.push(Val::Integer(overflow.mul(lhs, rhs)?));
+                }
+                Op::MulFloat => {
+                    let (rhs, lhs) = (pop_float(&mut stack), pop_float(&mut stack));
+                    stack.push(Val::Float(lhs * rhs));
+                }
+                Op::ModInt => {
+                    let (rhs, lhs) = (pop_int(&mut stack), pop_int(&mut stack));
+                    stack.push(Val::Integer(int_rem(lhs, rhs)));
+                }
+                Op::DivInt => {
+                    let (rhs, lhs) = (pop_int(&mut stack), pop_int(&mut stack));
+                    stack.push(Val::Integer(overflow.div(lhs, rhs)?));
+                }
+                Op::DivFloat => {
+                    let (rhs, lhs) = (pop_float(&mut stack), pop_float(&mut stack));
+                    stack.push(Val::Float(lhs / rhs));
+                }
+                Op::PowInt => {
+                    let (rhs, lhs) = (pop_int(&mut stack), pop_int(&mut stack));
+                    stack.push(Val::Integer(overflow.pow(lhs, rhs)?));
+                }
+                Op::PowFloat => {
+                    let (rhs, lhs) = (pop_float(&mut stack), pop_float(&mut stack));
+                    stack.push(Val::from(lhs.into_inner().powf(rhs.into_inner())));
+                }
+                Op::MinInt => {
+                    let (rhs, lhs) = (pop_int(&mut stack), pop_int(&mut stack));
+                    stack.push(Val::Integer(lhs.min(rhs)));
+                }
+                Op::MinFloat => {
+                    let (rhs, lhs) = (pop_float(&mut stack), pop_float(&mut stack));
+                    stack.push(Val::from(lhs.into_inner().min(rhs.into_inner())));
+                }
+                Op::MaxInt => {
+                    let (rhs, lhs) = (pop_int(&mut stack), pop_int(&mut stack));
+                    stack.push(Val::Integer(lhs.max(rhs)));
+                }
+                Op::MaxFloat => {
+                    let (rhs, lhs) = (pop_float(&mut stack), pop_float(&mut stack));
+                    stack.push(Val::from(lhs.into_inner().max(rhs.into_inner())));
+                }
+                Op::AbsInt => {
+                    let i = pop_int(&mut stack);
+                    stack.push(Val::Integer(overflow.abs(i)?));
+                }
+                Op::AbsFloat => {
+                    let f = pop_float(&mut stack);
+                    stack.push(Val::from(f.into_inner().abs()));
+                }
+                Op::Floor => {
+                    let f = pop_float(&mut stack);
+                    stack.push(Val::Integer(overflow.float_to_int(f.into_inner().floor())?));
+                }
+                Op::Ceil => {
+                    let f = pop_float(&mut stack);
+                    stack.push(Val::Integer(overflow.float_to_int(f.into_inner().ceil())?));
+                }
+                Op::Round => {
+                    let f = pop_float(&mut stack);
+                    stack.push(Val::Integer(overflow.float_to_int(round(f.into_inner()))?));
+                }
+                Op::FloatToInt => {
+                    let f = pop_float(&mut stack);
+                    stack.push(Val::Integer(overflow.float_to_int(f.into_inner())?));
+                }
+                Op::EqInt => {
+                    let (rhs, lhs) = (pop_int(&mut stack), pop_int(&mut stack));
+                    stack.push(Val::Boolean(lhs == rhs));
+                }
+                Op::EqBool => {
+                    let (rhs, lhs) = (pop_bool(&mut stack), pop_bool(&mut stack));
+                    stack.push(Val::Boolean(lhs == rhs));
+                }
+                Op::Eq => {
+                    let (rhs, lhs) = (pop(&mut stack), pop(&mut stack));
+                    stack.push(Val::Boolean(lhs == rhs));
+                }
+                Op::Concat(len) => {
+                    let start = stack.len() - len;
+                    let string = stack.drain(start..).fold(Str::default(), |acc, val| {
+                        if let Val::String(s) = val {
+                            acc.concat(&s)
+                        } else {
+                            panic!("type mismatch");
+                        }
+                    });
+                    stack.push(Val::String(string));
+                }
+                Op::LessInt => {
+                    let (rhs, lhs) = (pop_int(&mut stack), pop_int(&mut stack));
+                    stack.push(Val::Boolean(lhs < rhs));
+                }
+                Op::LessFloat => {
+                    let (rhs, lhs) = (pop_float(&mut stack), pop_float(&mut stack));
+                    stack.push(Val::Boolean(lhs < rhs));
+                }
+                Op::LessEqInt => {
+                    let (rhs, lhs) = (pop_int(&mut stack), pop_int(&mut stack));
+                    stack.push(Val::Boolean(lhs <= rhs));
+                }
+                Op::LessEqFloat => {
+                    let (rhs, lhs) = (pop_float(&mut stack), pop_float(&mut stack));
+                    stack.push(Val::Boolean(lhs <= rhs));
+                }
+                Op::GreaterInt => {
+                    let (rhs, lhs) = (pop_int(&mut stack), pop_int(&mut stack));
+                    stack.push(Val::Boolean(lhs > rhs));
+                }
+                Op::GreaterFloat => {
+                    let (rhs, lhs) = (pop_float(&mut stack), pop_float(&mut stack));
+                    stack.push(Val::Boolean(lhs > rhs));
+                }
+                Op::GreaterEqInt => {
+                    let (rhs, lhs) = (pop_int(&mut stack), pop_int(&mut stack));
+                    stack.push(Val::Boolean(lhs >= rhs));
+                }
+                Op::GreaterEqFloat => {
+                    let (rhs, lhs) = (pop_float(&mut stack), pop_float(&mut stack));
+                    stack.push(Val::Boolean(lhs >= rhs));
+                }
+                Op::Append => {
+                    let element = pop(&mut stack);
+                    if let Val::List(t, mut l) = pop(&mut stack) {
+                        if element.r#type() == t {
+                            l.push(element);
+                            stack.push(Val::List(t, l));
+                        } else {
+                            panic!("type mismatch");
+                        }
+                    } else {
+                        panic!("type mismatch");
+                    }
+                }
+                Op::Truncate => {
+                    if let Val::List(t, mut l) = pop(&mut stack) {
+                        if l.pop().is_some() {
+                            stack.push(Val::List(t, l));
+                        } else {
+                            return Err(EvalError::EmptyList);
+                        }
+                    } else {
+                        panic!("type mismatch");
+                    }
+                }
+                Op::Len => {
+                    if let Val::List(_, l) | Val::Array(_, l) = pop(&mut stack) {
+                        stack.push(Val::Integer(l.len() as Integer));
+                    } else {
+                        panic!("type mismatch");
+                    }
+                }
+                Op::Index => {
+                    let idx = pop_int(&mut stack);
+                    if let Val::List(_, mut l) | Val::Array(_, mut l) = pop(&mut stack) {
+                        let idx = index(idx, l.len())?;
+                        stack.push(l.swap_remove(idx));
+                    } else {
+                        panic!("type mismatch");
+                    }
+                }
+                Op::Update => {
+                    let element = pop(&mut stack);
+                    let idx = pop_int(&mut stack);
+                    match stack.last_mut() {
+                        Some(Val::List(_, l) | Val::Array(_, l)) => {
+                            let idx = index(idx, l.len())?;
+                            l[idx] = element;
+                        }
+                        _ => panic!("type mismatch"),
+                    }
+                }
+            }
+        }
+        Ok(pop(&mut stack))
+    }
+}
+
+impl<V: Clone + Copy + Send + Sync + 'static> FnExpression<V> {
+    /// Compiles the expression, with integer overflows behaving as specified.
+    pub fn new(value: Expression<V>, overflow: Overflow) -> Self {
+        let mut code = Vec::new();
+        compile(value, &mut code);
+        FnExpression(code.into_boxed_slice(), overflow)
+    }
+}
+
+impl<V: Clone + Copy + Send + Sync + 'static> From<Expression<V>> for FnExpression<V> {
+    fn from(value: Expression<V>) -> Self {
+        Self::new(value, Overflow::default())
+    }
+}
+
+fn is_float<V: Clone>(expr: &Expression<V>) -> bool {
+    matches!(expr.r#type(), Ok(Type::Float))
+}
+
+// Compiles a numerical expression, converting it to float if required.
+fn compile_num<V: Clone>(expr: Expression<V>, float: bool, code: &mut Vec<Op<V>>) {
+    let convert = float && !is_float(&expr);
+    compile(expr, code);
+    if convert {
+        code.push(Op::IntToFloat);
+    }
+}
+
+// Compiles the arguments of a binary numerical operator,
+// and returns whether the float version of the operator is needed.
+fn compile_num_args<V: Clone>(args: (Expression<V>, Expression<V>), code: &mut Vec<Op<V>>) -> bool {
+    let float = is_float(&args.0) || is_float(&args.1);
+    compile_num(args.0, float, code);
+    compile_num(args.1, float, code);
+    float
+}
+
+fn compile_short_circuit<V: Clone>(
+    args: Vec<Expression<V>>,
+    jump: fn(usize) -> Op<V>,
+    code: &mut Vec<Op<V>>,
+) {
+    let mut jumps = Vec::with_capacity(args.len());
+    let len = args.len();
+    for (idx, arg) in args.into_iter().enumerate() {
+        compile(arg, code);
+        if idx + 1 < len {
+            jumps.push(code.len());
+            // Placeholder, target is set once known.
+            code.push(jump(0));
+        }
+    }
+    let end = code.len();
+    for idx in jumps {
+        code[idx] = jump(end);
+    }
+}
+
+// Compiles a non-empty n-ary numerical operator as a chain of binary operations.
+fn compile_arith<V: Clone>(
+    args: Vec<Expression<V>>,
+    int_op: Op<V>,
+    float_op: Op<V>,
+    code: &mut Vec<Op<V>>,
+) {
+    let float = args.iter().any(is_float);
+    let op = if float { float_op } else { int_op };
+    for (idx, arg) in args.into_iter().enumerate() {
+        compile_num(arg, float, code);
+        if idx > 0 {
+            code.push(op.clone());
+        }
+    }
+}
+
+fn compile<V: Clone>(expr: Expression<V>, code: &mut Vec<Op<V>>) {
+    match expr {
+        Expression::Const(val) => code.push(Op::Const(val)),
+        Expression::Var(var, _) => code.push(Op::Load(var)),
+        Expression::Tuple(args) => {
+            let len = args.len();
+            args.into_iter().for_each(|arg| compile(arg, code));
+            code.push(Op::Tuple(len));
+        }
+        Expression::Component(idx, expr) => {
+            // Collect the path of nested components to access variables in place.
+            let mut path = vec![idx];
+            let mut expr = *expr;
+            while let Expression::Component(idx, inner) = expr {
+                path.push(idx);
+                expr = *inner;
+            }
+            if let Expression::Var(var, _) = expr {
+                path.reverse();
+                code.push(Op::LoadComponent(var, path.into_boxed_slice()));
+            } else {
+                compile(expr, code);
+                code.extend(path.into_iter().rev().map(Op::Component));
+            }
+        }
+        Expression::Ite(args) => {
+            let (cond, then, r#else) = *args;
+            compile(cond, code);
+            let jump_else = code.len();
+            // Placeholders, targets are set once known.
+            code.push(Op::JumpIfFalse(0));
+            compile(then, code);
+            let jump_end = code.len();
+            code.push(Op::Jump(0));
+            code[jump_else] = Op::JumpIfFalse(code.len());
+            compile(r#else, code);
+            code[jump_end] = Op::Jump(code.len());
+        }
+        Expression::And(args) if args.is_empty() => code.push(Op::Const(Val::Boolean(true))),
+        Expression::And(args) => compile_short_circuit(args, Op::JumpIfFalseOrPop, code),
+        Expression::Or(args) if args.is_empty() => code.push(Op::Const(Val::Boolean(false))),
+        Expression::Or(args) => compile_short_circuit(args, Op::JumpIfTrueOrPop, code),
+        Expression::Implies(args) => {
+            let (lhs, rhs) = *args;
+            compile_short_circuit(vec![!lhs, rhs], Op::JumpIfTrueOrPop, code);
+        }
+        Expression::Not(expr) => {
+            compile(*expr, code);
+            code.push(Op::Not);
+        }
+        Expression::Opposite(expr) => {
+            let float = is_float(&expr);
+            compile(*expr, code);
+            code.push(if float { Op::NegFloat } else { Op::NegInt });
+        }
+        Expression::Sum(args) if args.is_empty() => code.push(Op::Const(Val::Integer(0))),
+        Expression::Sum(args) => compile_arith(args, Op::AddInt, Op::AddFloat, code),
+        Expression::Mult(args) if args.is_empty() => code.push(Op::Const(Val::Integer(1))),
+        Expression::Mult(args) => compile_arith(args, Op::MulInt, Op::MulFloat, code),
+        Expression::Min(args) => compile_arith(args, Op::MinInt, Op::MinFloat, code),
+        Expression::Max(args) => compile_arith(args, Op::MaxInt, Op::MaxFloat, code),
+        Expression::Mod(args) => {
+            compile(args.0, code);
+            compile(args.1, code);
+            code.push(Op::ModInt);
+        }
+        Expression::Div(args) => {
+            let float = compile_num_args(*args, code);
+            code.push(if float { Op::DivFloat } else { Op::DivInt });
+        }
+        Expression::Pow(args) => {
+            let float = compile_num_args(*args, code);
+            code.push(if float { Op::PowFloat } else { Op::PowInt });
+        }
+        Expression::Abs(expr) => {
+            let float = is_float(&expr);
+            compile(*expr, code);
+            code.push(if float { Op::AbsFloat } else { Op::AbsInt });
+        }
+        // Rounding and conversions are the identity on integers.
+        Expression::Floor(expr)
+        | Expression::Ceil(expr)
+        | Expression::Round(expr)
+        | Expression::ToInt(expr)
+            if !is_float(&expr) =>
+        {
+            compile(*expr, code)
+        }
+        Expression::Floor(expr) => {
+            compile(*expr, code);
+            code.push(Op::Floor);
+        }
+        Expression::Ceil(expr) => {
+            compile(*expr, code);
+            code.push(Op::Ceil);
+        }
+        Expression::Round(expr) => {
+            compile(*expr, code);
+            code.push(Op::Round);
+        }
+        Expression::ToInt(expr) => {
+            compile(*expr, code);
+            code.push(Op::FloatToInt);
+        }
+        Expression::ToFloat(expr) => compile_num(*expr, true, code),
+        Expression::Equal(args) => {
+            let op = match (args.0.r#type(), args.1.r#type()) {
+                (Ok(Type::Integer), Ok(Type::Integer)) => Op::EqInt,
+                (Ok(Type::Boolean), Ok(Type::Boolean)) => Op::EqBool,
+                _ => Op::Eq,
+            };
+            compile(args.0, code);
+            compile(args.1, code);
+            code.push(op);
+        }
+        Expression::Concat(args) => {
+            let len = args.len();
+            for arg in args {
+                compile(arg, code);
+            }
+            code.push(Op::Concat(len));
+        }
+        Expression::Greater(args) => {
+            let float = compile_num_args(*args, code);
+            code.push(if float {
+                Op::GreaterFloat
+            } else {
+                Op::GreaterInt
+            });
+        }
+        Expression::GreaterEq(args) => {
+            let float = compile_num_args(*args, code);
+            code.push(if float {
+                Op::GreaterEqFloat
+            } else {
+                Op::GreaterEqInt
+            });
+        }
+        Expression::Less(args) => {
+            let float = compile_num_args(*args, code);
+            code.push(if float { Op::LessFloat } else { Op::LessInt });
+        }
+        Expression::LessEq(args) => {
+            let float = compile_num_args(*args, code);
+            code.push(if float {
+                Op::LessEqFloat
+            } else {
+                Op::LessEqInt
+            });
+        }
+        Expression::Append(args) => {
+            compile(args.0, code);
+            compile(args.1, code);
+            code.push(Op::Append);
+        }
+        Expression::Truncate(expr) => {
+            compile(*expr, code);
+            code.push(Op::Truncate);
+        }
+        Expression::Len(expr) => {
+            if let Expression::Var(var, _) = *expr {
+                code.push(Op::LoadLen(var));
+            } else {
+                compile(*expr, code);
+                code.push(Op::Len);
+            }
+        }
+        Expression::Array(args) => {
+            let t = args
+                .first()
+                .and_then(|arg| arg.r#type().ok())
+                .expect("non-empty well-typed array");
+            let len = args.len();
+            args.into_iter().for_each(|arg| compile(arg, code));
+            code.push(Op::Array(t, len));
+        }
+        Expression::Index(args) => {
+            let (list, idx) = *args;
+            if let Expression::Var(var, _) = list {
+                compile(idx, code);
+                code.push(Op::LoadIndex(var));
+            } else {
+                compile(list, code);
+                compile(idx, code);
+                code.push(Op::Index);
+            }
+        }
+        Expression::Update(args) => {
+            let (list, idx, element) = *args;
+            compile(list, code);
+            compile(idx, code);
+            compile(element, code);
+            code.push(Op::Update);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grammar::Resolver;
+
+    fn vals() -> Vec<(&'static str, Val)> {
+        vec![
+            ("x", Val::Integer(3)),
+            ("y", Val::Integer(-7)),
+            ("f", Val::from(0.5)),
+            ("a", Val::Boolean(true)),
+            ("b", Val::Boolean(false)),
+            (
+                "t",
+                Val::Tuple(vec![
+                    Val::Integer(1),
+                    Val::Tuple(vec![Val::Boolean(true), Val::from(2.5)]),
+                ]),
+            ),
+            (
+                "l",
+                Val::List(Type::Integer, vec![Val::Integer(4), Val::Integer(5)]),
+            ),
+            ("v", Val::Array(Type::Boolean, vec![Val::Boolean(false); 3])),
+            ("m", Val::Integer(Integer::MAX)),
+            ("s", Val::from("ok")),
+        ]
+    }
+
+    fn try_eval(input: &str) -> Result<Val, EvalError> {
+        try_eval_with(input, Overflow::Checked)
+    }
+
+    fn try_eval_with(input: &str, overflow: Overflow) -> Result<Val, EvalError> {
+        let vals = vals();
+        let mut resolver = Resolver::new();
+        for (idx, (name, val)) in vals.iter().enumerate() {
+            resolver.insert(*name, idx, val.r#type());
+        }
+        let expr = resolver.parse(input).expect("parsing");
+        let vm = FnExpression::new(expr.clone(), overflow);
+        let result = vm.eval(&|var| &vals[var].1);
+        #[cfg(not(feature = "bytecode"))]
+        {
+            // Compare with the default backend.
+            let closure = crate::grammar::FnExpression::new(expr, overflow);
+            assert_eq!(closure.eval(&|var| &vals[var].1), result);
+        }
+        result
+    }
+
+    fn eval(input: &str) -> Val {
+        try_eval(input).expect("evaluation")
+    }
+
+    #[test]
+    fn strings() {
+        assert_eq!(eval(r#"concat(s, "!", s)"#), Val::from("ok!ok"));
+        assert_eq!(eval(r#"concat("o", "k") == s"#), Val::Boolean(true));
+        assert_eq!(eval(r#"a ? s : "no""#), Val::from("ok"));
+    }
+
+    #[test]
+    fn arithmetic() {
+        assert_eq!(eval("x + y * 2"), Val::Integer(-11));
+        assert_eq!(eval("x * y % 4"), Val::Integer(-1));
+        assert_eq!(eval("-x + f"), Val::from(-2.5));
+        assert_eq!(eval("x * f * 2"), Val::from(3.0));
+        assert_eq!(eval("f * x > y"), Val::Boolean(true));
+        assert_eq!(eval("x <= 3"), Val::Boolean(true));
+        assert_eq!(eval("x > 3"), Val::Boolean(false));
+    }
+
+    #[test]
+    fn division() {
+        assert_eq!(eval("y / 2"), Val::Integer(-3));
+        assert_eq!(eval("y % 2"), Val::Integer(-1));
+        assert_eq!(eval("x / 0 + x % 0"), Val::Integer(3));
+        assert_eq!(eval("y / f"), Val::from(-14.0));
+        assert_eq!(eval("x / (f - f)"), Val::from(Float::INFINITY));
+        assert_eq!(eval("pow(x, 2) + pow(2, -1)"), Val::Integer(9));
+        assert_eq!(eval("pow(4, f)"), Val::from(2.0));
+    }
+
+    #[test]
+    fn functions() {
+        assert_eq!(eval("min(x, y, 0) + max(x, y)"), Val::Integer(-4));
+        assert_eq!(eval("max(x, f)"), Val::from(3.0));
+        assert_eq!(eval("abs(y) + abs(-f)"), Val::from(7.5));
+        assert_eq!(
+            eval("floor(-f) + ceil(f) + round(f) + round(-f)"),
+            Val::Integer(1)
+        );
+        assert_eq!(eval("int(y * f) + int(x)"), Val::Integer(0));
+        assert_eq!(eval("float(x) / 2"), Val::from(1.5));
+    }
+
+    #[test]
+    fn logic() {
+        assert_eq!(eval("a && !b"), Val::Boolean(true));
+        assert_eq!(eval("b || b || a"), Val::Boolean(true));
+        assert_eq!(eval("a && b && a"), Val::Boolean(false));
+        assert_eq!(eval("b => x == 1"), Val::Boolean(true));
+        assert_eq!(eval("a => b"), Val::Boolean(false));
+        assert_eq!(eval("(x == 3) == a"), Val::Boolean(true));
+        assert_eq!(eval("a ? x : y"), Val::Integer(3));
+        assert_eq!(eval("b ? x : a ? y : 0"), Val::Integer(-7));
+        assert_eq!(eval("(b ? f : 1.5) + 1"), Val::from(2.5));
+    }
+
+    #[test]
+    fn structures() {
+        assert_eq!(eval("t.1.1"), Val::from(2.5));
+        assert_eq!(eval("(x, t).1.0 + 1"), Val::Integer(2));
+        assert_eq!(eval("len(l) + len(truncate(l))"), Val::Integer(3));
+        assert_eq!(
+            eval("append(l, x)"),
+            Val::List(
+                Type::Integer,
+                vec![Val::Integer(4), Val::Integer(5), Val::Integer(3)]
+            )
+        );
+    }
+
+    #[test]
+    fn indexing() {
+        assert_eq!(eval("l[1] + l[x - 3]"), Val::Integer(9));
+        assert_eq!(eval("[x, y][1] + len(v)"), Val::Integer(-4));
+        assert_eq!(eval("update(v, 2, a)[2] && !v[2]"), Val::Boolean(true));
+        assert_eq!(
+            eval("update(l, 0, x)"),
+            Val::List(Type::Integer, vec![Val::Integer(3), Val::Integer(5)])
+        );
+        assert_eq!(
+            eval("array[x, y]"),
+            Val::Array(Type::Integer, vec![Val::Integer(3), Val::Integer(-7)])
+        );
+        assert_eq!(try_eval("l[x]"), Err(EvalError::IndexOutOfBounds(3, 2)));
+        assert_eq!(try_eval("v[y]"), Err(EvalError::IndexOutOfBounds(-7, 3)));
+        assert_eq!(
+            try_eval("update(l, 2, 0)"),
+            Err(EvalError::IndexOutOfBounds(2, 2))
+        );
+        assert_eq!(
+            try_eval("truncate(truncate(truncate(l)))"),
+            Err(EvalError::EmptyList)
+        );
+        assert_eq!(try_eval("a || l[x] == 0"), Ok(Val::Boolean(true)));
+        assert_eq!(try_eval("b => l[x] == 0"), Ok(Val::Boolean(true)));
+    }
+
+    #[test]
+    fn overflow() {
+        let checked = |input| try_eval_with(input, Overflow::Checked);
+        let saturating = |input| try_eval_with(input, Overflow::Saturating);
+        let wrapping = |input| try_eval_with(input, Overflow::Wrapping);
+        assert_eq!(checked("m + 1"), Err(EvalError::Overflow));
+        assert_eq!(saturating("m + 1"), Ok(Val::Integer(Integer::MAX)));
+        assert_eq!(wrapping("m + 1"), Ok(Val::Integer(Integer::MIN)));
+        assert_eq!(checked("m * y"), Err(EvalError::Overflow));
+        assert_eq!(saturating("m * y"), Ok(Val::Integer(Integer::MIN)));
+        assert_eq!(wrapping("m * 2"), Ok(Val::Integer(-2)));
+        assert_eq!(checked("(-m - 1) / -1"), Err(EvalError::Overflow));
+        assert_eq!(saturating("abs(-m - 1)"), Ok(Val::Integer(Integer::MAX)));
+        assert_eq!(wrapping("-(-m - 1)"), Ok(Val::Integer(Integer::MIN)));
+        assert_eq!(checked("pow(y, 23)"), Err(EvalError::Overflow));
+        assert_eq!(saturating("pow(y, 23)"), Ok(Val::Integer(Integer::MIN)));
+        assert_eq!(wrapping("pow(2, 64)"), Ok(Val::Integer(0)));
+        assert_eq!(wrapping("pow(-1, m)"), Ok(Val::Integer(-1)));
+        assert_eq!(checked("pow(1, m)"), Ok(Val::Integer(1)));
+        assert_eq!(checked("int(1e19)"), Err(EvalError::Overflow));
+        assert_eq!(saturating("int(1e19)"), Ok(Val::Integer(Integer::MAX)));
+        assert_eq!(
+            checked("round(-f) + (m - 1)"),
+            Ok(Val::Integer(Integer::MAX - 1))
+        );
+        // Values within bounds are unaffected.
+        assert_eq!(wrapping("x * y - 1"), Ok(Val::Integer(-22)));
+    }
+}
@@ -0,0 +1,1106 @@
+//! Parser for the concrete syntax of [`Expression`]s.
+//!
+//! The syntax is the same used by the printer (see [`Expression::display`]),
+//! so that printing and parsing an expression give back an equivalent expression.
+//!
+//! Literals are `true`, `false`, integers (e.g., `42`), floats (e.g., `4.2` or `1e3`),
+//! strings (e.g., `"ok"`, with `\"` and `\\` as escapes), tuples (e.g., `(x, 1)` or `(x,)`), lists (e.g., `[1, 2]` or `list<int>[]`)
+//! and arrays (e.g., `array[1, 2]` or `array<int>[]`).
+//! Available operators are, from lowest to highest precedence:
+//! the conditional `c ? a : b`, `=>`, `||`, `&&`, comparisons (`==`, `!=`, `<`, `<=`, `>`, `>=`), `+` and `-`, `*`, `/` and `%`,
+//! unary `!` and `-`, tuple components (e.g., `t.0`), indexing (e.g., `l[0]`),
+//! the list operators `len(l)`, `append(l, e)`, `truncate(l)` and `update(l, i, e)`,
+//! and the numerical functions `pow(x, y)`, `min(x, ...)`, `max(x, ...)`, `abs(x)`,
+//! `floor(x)`, `ceil(x)`, `round(x)`, `float(x)` and `int(x)`,
+//! and the string concatenation `concat(s, ...)`.
+
+use super::{Expected, Expression, Integer, Str, Type, TypeError, TypeErrorKind, Val};
+use std::{collections::HashMap, ops::Range};
+use thiserror::Error;
+
+/// A range of byte offsets into the parsed text.
+pub type Span = Range<usize>;
+
+/// The error type for parsing [`Expression`]s.
+///
+/// Every error carries the [`Span`] of the text that caused it.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ParseError {
+    /// The character does not belong to any token.
+    #[error("unexpected character '{1}' at {0:?}")]
+    UnexpectedChar(Span, char),
+    /// The token is not allowed in this position.
+    #[error("unexpected '{1}' at {0:?}, expected {2}")]
+    UnexpectedToken(Span, String, &'static str),
+    /// The text ended before the expression was complete.
+    #[error("unexpected end of input at {0:?}, expected {1}")]
+    UnexpectedEnd(Span, &'static str),
+    /// The numeric literal cannot be represented.
+    #[error("invalid numeric literal '{1}' at {0:?}")]
+    InvalidNumber(Span, String),
+    /// The name does not correspond to any known variable.
+    #[error("unknown variable '{1}' at {0:?}")]
+    UnknownVar(Span, String),
+    /// Comparisons cannot be chained without parentheses.
+    #[error("comparison operators cannot be chained at {0:?}")]
+    ChainedComparison(Span),
+    /// The list literal is badly typed.
+    #[error("type error in list literal at {0:?}")]
+    Type(Span, #[source] TypeError),
+}
+
+impl ParseError {
+    /// The position in the parsed text where the error occurred.
+    pub fn span(&self) -> Span {
+        match self {
+            ParseError::UnexpectedChar(span, _)
+            | ParseError::UnexpectedToken(span, _, _)
+            | ParseError::UnexpectedEnd(span, _)
+            | ParseError::InvalidNumber(span, _)
+            | ParseError::UnknownVar(span, _)
+            | ParseError::ChainedComparison(span)
+            | ParseError::Type(span, _) => span.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token<'a> {
+    Ident(&'a str),
+    Integer(&'a str),
+    Float(&'a str),
+    // The content of a string literal, with escapes not yet resolved.
+    String(&'a str),
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Comma,
+    Dot,
+    Question,
+    Colon,
+    Implies,
+    Or,
+    And,
+    Not,
+    Equal,
+    NotEqual,
+    Less,
+    LessEq,
+    Greater,
+    GreaterEq,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Percent,
+}
+
+impl Token<'_> {
+    fn as_str(&self) -> &str {
+        match self {
+            Token::Ident(s) | Token::Integer(s) | Token::Float(s) | Token::String(s) => s,
+            Token::LParen => "(",
+            Token::RParen => ")",
+            Token::LBracket => "[",
+            Token::RBracket => "]",
+            Token::Comma => ",",
+            Token::Dot => ".",
+            Token::Question => "?",
+            Token::Colon => ":",
+            Token::Implies => "=>",
+            Token::Or => "||",
+            Token::And => "&&",
+            Token::Not => "!",
+            Token::Equal => "==",
+            Token::NotEqual => "!=",
+            Token::Less => "<",
+            Token::LessEq => "<=",
+            Token::Greater => ">",
+            Token::GreaterEq => ">=",
+            Token::Plus => "+",
+            Token::Minus => "-",
+            Token::Star => "*",
+            Token::Slash => "/",
+            Token::Percent => "%",
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<(Token<'_>, Span)>, ParseError> {
+    let bytes = input.as_bytes();
+    let mut tokens: Vec<(Token<'_>, Span)> = Vec::new();
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let start = pos;
+        let c = bytes[pos];
+        let token = match c {
+            b' ' | b'\t' | b'\n' | b'\r' => {
+                pos += 1;
+                continue;
+            }
+            b'a'..=b'z' | b'A'..=b'Z' | b'_' => {
+                while pos < bytes.len()
+                    && (bytes[pos].is_ascii_alphanumeric() || bytes[pos] == b'_')
+                {
+                    pos += 1;
+                }
+                Token::Ident(&input[start..pos])
+            }
+            b'0'..=b'9' => {
+                while pos < bytes.len() && bytes[pos].is_ascii_digit() {
+                    pos += 1;
+                }
+                // A number following a dot is a tuple index (as in `t.0.1`),
+                // so it cannot be a float.
+                if matches!(tokens.last(), Some((Token::Dot, _))) {
+                    Token::Integer(&input[start..pos])
+                } else {
+                    let mut float = false;
+                    if pos + 1 < bytes.len()
+                        && bytes[pos] == b'.'
+                        && bytes[pos + 1].is_ascii_digit()
+                    {
+                        float = true;
+                        pos += 1;
+                        while pos < bytes.len() && bytes[pos].is_ascii_digit() {
+                            pos += 1;
+                        }
+                    }
+                    if pos < bytes.len() && matches!(bytes[pos], b'e' | b'E') {
+                        let mut exp = pos + 1;
+                        if exp < bytes.len() && matches!(bytes[exp], b'+' | b'-') {
+                            exp += 1;
+                        }
+                        if exp < bytes.len() && bytes[exp].is_ascii_digit() {
+                            float = true;
+                            pos = exp;
+                            while pos < bytes.len() && bytes[pos].is_ascii_digit() {
+                                pos += 1;
+                            }
+                        }
+                    }
+                    if float {
+                        Token::Float(&input[start..pos])
+                    } else {
+                        Token::Integer(&input[start..pos])
+                    }
+                }
+            }
+            b'"' => {
+                pos += 1;
+                while pos < bytes.len() && bytes[pos] != b'"' {
+                    // Skip escaped characters.
+                    pos += if bytes[pos] == b'\\' { 2 } else { 1 };
+                }
+                if pos >= bytes.len() {
+                    return Err(ParseError::UnexpectedEnd(bytes.len()..bytes.len(), "'\"'"));
+                }
+                pos += 1;
+                Token::String(&input[start + 1..pos - 1])
+            }
+            _ => {
+                let next = bytes.get(pos + 1).copied();
+                let (token, len) = match (c, next) {
+                    (b'=', Some(b'>')) => (Token::Implies, 2),
+                    (b'=', Some(b'=')) => (Token::Equal, 2),
+                    (b'|', Some(b'|')) => (Token::Or, 2),
+                    (b'&', Some(b'&')) => (Token::And, 2),
+                    (b'!', Some(b'=')) => (Token::NotEqual, 2),
+                    (b'<', Some(b'=')) => (Token::LessEq, 2),
+                    (b'>', Some(b'=')) => (Token::GreaterEq, 2),
+                    (b'!', _) => (Token::Not, 1),
+                    (b'<', _) => (Token::Less, 1),
+                    (b'>', _) => (Token::Greater, 1),
+                    (b'(', _) => (Token::LParen, 1),
+                    (b')', _) => (Token::RParen, 1),
+                    (b'[', _) => (Token::LBracket, 1),
+                    (b']', _) => (Token::RBracket, 1),
+                    (b',', _) => (Token::Comma, 1),
+                    (b'.', _) => (Token::Dot, 1),
+                    (b'?', _) => (Token::Question, 1),
+                    (b':', _) => (Token::Colon, 1),
+                    (b'+', _) => (Token::Plus, 1),
+                    (b'-', _) => (Token::Minus, 1),
+                    (b'*', _) => (Token::Star, 1),
+                    (b'/', _) => (Token::Slash, 1),
+                    (b'%', _) => (Token::Percent, 1),
+                    _ => {
+                        let c = input[start..].chars().next().expect("non-empty input");
+                        return Err(ParseError::UnexpectedChar(start..start + c.len_utf8(), c));
+                    }
+                };
+                pos += len;
+                token
+            }
+        };
+        tokens.push((token, start..pos));
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a, 'r, V> {
+    tokens: Vec<(Token<'a>, Span)>,
+    pos: usize,
+    end: usize,
+    resolve: &'r dyn Fn(&str) -> Option<(V, Type)>,
+}
+
+impl<'a, V: Clone> Parser<'a, '_, V> {
+    fn peek(&self) -> Option<&Token<'a>> {
+        self.tokens.get(self.pos).map(|(token, _)| token)
+    }
+
+    fn next(&mut self, expected: &'static str) -> Result<(Token<'a>, Span), ParseError> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or(ParseError::UnexpectedEnd(self.end..self.end, expected))?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    // Start of the span of the next token.
+    fn start(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .map_or(self.end, |(_, span)| span.start)
+    }
+
+    // End of the span of the last consumed token.
+    fn last_end(&self) -> usize {
+        self.pos
+            .checked_sub(1)
+            .and_then(|pos| self.tokens.get(pos))
+            .map_or(0, |(_, span)| span.end)
+    }
+
+    fn eat(&mut self, token: Token<'_>) -> bool {
+        if self.peek() == Some(&token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: Token<'_>, expected: &'static str) -> Result<Span, ParseError> {
+        let (next, span) = self.next(expected)?;
+        if next == token {
+            Ok(span)
+        } else {
+            Err(ParseError::UnexpectedToken(
+                span,
+                next.as_str().to_string(),
+                expected,
+            ))
+        }
+    }
+
+    fn expression(&mut self) -> Result<Expression<V>, ParseError> {
+        let cond = self.implies()?;
+        if self.eat(Token::Question) {
+            let then = self.expression()?;
+            self.expect(Token::Colon, "':'")?;
+            let r#else = self.expression()?;
+            Ok(Expression::Ite(Box::new((cond, then, r#else))))
+        } else {
+            Ok(cond)
+        }
+    }
+
+    fn implies(&mut self) -> Result<Expression<V>, ParseError> {
+        let lhs = self.or()?;
+        if self.eat(Token::Implies) {
+            let rhs = self.implies()?;
+            Ok(Expression::Implies(Box::new((lhs, rhs))))
+        } else {
+            Ok(lhs)
+        }
+    }
+
+    fn or(&mut self) -> Result<Expression<V>, ParseError> {
+        let mut args = vec![self.and()?];
+        while self.eat(Token::Or) {
+            args.push(self.and()?);
+        }
+        Ok(if args.len() == 1 {
+            args.pop().expect("one argument")
+        } else {
+            Expression::Or(args)
+        })
+    }
+
+    fn and(&mut self) -> Result<Expression<V>, ParseError> {
+        let mut args = vec![self.comparison()?];
+        while self.eat(Token::And) {
+            args.push(self.comparison()?);
+        }
+        Ok(if args.len() == 1 {
+            args.pop().expect("one argument")
+        } else {
+            Expression::And(args)
+        })
+    }
+
+    fn comparison(&mut self) -> Result<Expression<V>, ParseError> {
+        let start = self.start();
+        let lhs = self.sum()?;
+        let op = match self.peek() {
+            Some(
+                op @ (Token::Equal
+                | Token::NotEqual
+                | Token::Less
+                | Token::LessEq
+                | Token::Greater
+                | Token::GreaterEq),
+            ) => op.clone(),
+            _ => return Ok(lhs),
+        };
+        self.pos += 1;
+        let rhs = self.sum()?;
+        if matches!(
+            self.peek(),
+            Some(
+                Token::Equal
+                    | Token::NotEqual
+                    | Token::Less
+                    | Token::LessEq
+                    | Token::Greater
+                    | Token::GreaterEq
+            )
+        ) {
+            let end = self.tokens[self.pos].1.end;
+            return Err(ParseError::ChainedComparison(start..end));
+        }
+        let args = Box::new((lhs, rhs));
+        Ok(match op {
+            Token::Equal => Expression::Equal(args),
+            Token::NotEqual => Expression::Not(Box::new(Expression::Equal(args))),
+            Token::Less => Expression::Less(args),
+            Token::LessEq => Expression::LessEq(args),
+            Token::Greater => Expression::Greater(args),
+            Token::GreaterEq => Expression::GreaterEq(args),
+            _ => unreachable!("comparison operator"),
+        })
+    }
+
+    fn sum(&mut self) -> Result<Expression<V>, ParseError> {
+        let mut args = vec![self.mult()?];
+        loop {
+            if self.eat(Token::Plus) {
+                args.push(self.mult()?);
+            } else if self.eat(Token::Minus) {
+                let arg = self.mult()?;
+                args.push(negate(arg));
+            } else {
+                break;
+            }
+        }
+        Ok(if args.len() == 1 {
+            args.pop().expect("one argument")
+        } else {
+            Expression::Sum(args)
+        })
+    }
+
+    fn mult(&mut self) -> Result<Expression<V>, ParseError> {
+        let mut acc = self.unary()?;
+        // Whether `acc` is a product built by this very chain of operators,
+        // so that further factors can be added to it.
+        let mut chain = false;
+        loop {
+            if self.eat(Token::Star) {
+                let arg = self.unary()?;
+                acc = match acc {
+                    Expression::Mult(mut args) if chain => {
+                        args.push(arg);
+                        Expression::Mult(args)
+                    }
+                    acc => Expression::Mult(vec![acc, arg]),
+                };
+                chain = true;
+            } else if self.eat(Token::Percent) {
+                let arg = self.unary()?;
+                acc = Expression::Mod(Box::new((acc, arg)));
+                chain = false;
+            } else if self.eat(Token::Slash) {
+                let arg = self.unary()?;
+                acc = Expression::Div(Box::new((acc, arg)));
+                chain = false;
+            } else {
+                return Ok(acc);
+            }
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expression<V>, ParseError> {
+        if self.eat(Token::Not) {
+            Ok(Expression::Not(Box::new(self.unary()?)))
+        } else if self.eat(Token::Minus) {
+            // Parse negative literals directly, as `-9223372036854775808` would overflow otherwise.
+            if let Some((Token::Integer(lit) | Token::Float(lit), span)) =
+                self.tokens.get(self.pos).cloned()
+            {
+                if !matches!(self.tokens.get(self.pos + 1), Some((Token::Dot, _))) {
+                    self.pos += 1;
+                    let lit = format!("-{lit}");
+                    return if let Ok(i) = lit.parse::<Integer>() {
+                        Ok(Expression::from(i))
+                    } else if let Ok(f) = lit.parse::<f64>() {
+                        Ok(Expression::from(f))
+                    } else {
+                        Err(ParseError::InvalidNumber(span, lit))
+                    };
+                }
+            }
+            let arg = self.unary()?;
+            Ok(negate(arg))
+        } else {
+            self.postfix()
+        }
+    }
+
+    fn postfix(&mut self) -> Result<Expression<V>, ParseError> {
+        let mut expr = self.primary()?;
+        loop {
+            if self.eat(Token::Dot) {
+                let (token, span) = self.next("tuple index")?;
+                if let Token::Integer(idx) = token {
+                    let idx = idx
+                        .parse::<usize>()
+                        .map_err(|_| ParseError::InvalidNumber(span, idx.to_string()))?;
+                    expr = Expression::Component(idx, Box::new(expr));
+                } else {
+                    return Err(ParseError::UnexpectedToken(
+                        span,
+                        token.as_str().to_string(),
+                        "tuple index",
+                    ));
+                }
+            } else if self.eat(Token::LBracket) {
+                let idx = self.expression()?;
+                self.expect(Token::RBracket, "']'")?;
+                expr = Expression::Index(Box::new((expr, idx)));
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expression<V>, ParseError> {
+        let (token, span) = self.next("expression")?;
+        match token {
+            Token::Integer(lit) => lit
+                .parse::<Integer>()
+                .map(|i| Expression::Const(Val::Integer(i)))
+                .map_err(|_| ParseError::InvalidNumber(span, lit.to_string())),
+            Token::Float(lit) => lit
+                .parse::<f64>()
+                .map(Expression::from)
+                .map_err(|_| ParseError::InvalidNumber(span, lit.to_string())),
+            Token::String(lit) => {
+                let mut string = String::with_capacity(lit.len());
+                let mut chars = lit.chars();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => match chars.next() {
+                            Some(c @ ('"' | '\\')) => string.push(c),
+                            _ => {
+                                return Err(ParseError::UnexpectedToken(
+                                    span,
+                                    format!("\"{lit}\""),
+                                    "valid escape sequence",
+                                ))
+                            }
+                        },
+                        c => string.push(c),
+                    }
+                }
+                Ok(Expression::Const(Val::String(Str::from(string))))
+            }
+            Token::Ident("true") => Ok(Expression::from(true)),
+            Token::Ident("false") => Ok(Expression::from(false)),
+            Token::Ident("len") => {
+                let [arg] = self.call_args::<1>()?;
+                Ok(Expression::Len(Box::new(arg)))
+            }
+            Token::Ident("truncate") => {
+                let [arg] = self.call_args::<1>()?;
+                Ok(Expression::Truncate(Box::new(arg)))
+            }
+            Token::Ident("append") => {
+                let [list, elem] = self.call_args::<2>()?;
+                Ok(Expression::Append(Box::new((list, elem))))
+            }
+            Token::Ident("update") => {
+                let [list, idx, elem] = self.call_args::<3>()?;
+                Ok(Expression::Update(Box::new((list, idx, elem))))
+            }
+            Token::Ident("pow") => {
+                let [base, exp] = self.call_args::<2>()?;
+                Ok(Expression::Pow(Box::new((base, exp))))
+            }
+            Token::Ident("min") => Ok(Expression::Min(self.variadic_args()?)),
+            Token::Ident("max") => Ok(Expression::Max(self.variadic_args()?)),
+            Token::Ident("concat") => Ok(Expression::Concat(self.variadic_args()?)),
+            Token::Ident("abs") => {
+                let [arg] = self.call_args::<1>()?;
+                Ok(Expression::Abs(Box::new(arg)))
+            }
+            Token::Ident("floor") => {
+                let [arg] = self.call_args::<1>()?;
+                Ok(Expression::Floor(Box::new(arg)))
+            }
+            Token::Ident("ceil") => {
+                let [arg] = self.call_args::<1>()?;
+                Ok(Expression::Ceil(Box::new(arg)))
+            }
+            Token::Ident("round") => {
+                let [arg] = self.call_args::<1>()?;
+                Ok(Expression::Round(Box::new(arg)))
+            }
+            Token::Ident("float") => {
+                let [arg] = self.call_args::<1>()?;
+                Ok(Expression::ToFloat(Box::new(arg)))
+            }
+            Token::Ident("int") => {
+                let [arg] = self.call_args::<1>()?;
+                Ok(Expression::ToInt(Box::new(arg)))
+            }
+            Token::Ident("list") => {
+                let t = self.list_type()?;
+                self.expect(Token::LBracket, "'['")?;
+                self.expect(Token::RBracket, "']'")?;
+                Ok(Expression::Const(Val::List(t.unbounded(), Vec::new())))
+            }
+            Token::Ident("array") if self.peek() == Some(&Token::Less) => {
+                let t = self.list_type()?;
+                self.expect(Token::LBracket, "'['")?;
+                self.expect(Token::RBracket, "']'")?;
+                Ok(Expression::Const(Val::Array(t.unbounded(), Vec::new())))
+            }
+            Token::Ident("array") => {
+                self.expect(Token::LBracket, "'['")?;
+                let elems = self.elements()?;
+                array_literal(elems, span.start..self.last_end())
+            }
+            Token::Ident(name) => (self.resolve)(name)
+                .map(|(var, t)| Expression::Var(var, t))
+                .ok_or(ParseError::UnknownVar(span, name.to_string())),
+            Token::LParen => {
+                let mut args = Vec::new();
+                let mut trailing_comma = false;
+                while !self.eat(Token::RParen) {
+                    args.push(self.expression()?);
+                    trailing_comma = self.eat(Token::Comma);
+                    if !trailing_comma {
+                        self.expect(Token::RParen, "',' or ')'")?;
+                        break;
+                    }
+                }
+                if args.len() == 1 && !trailing_comma {
+                    Ok(args.pop().expect("one argument"))
+                } else {
+                    Ok(Expression::Tuple(args))
+                }
+            }
+            Token::LBracket => {
+                let elems = self.elements()?;
+                list_literal(elems, span.start..self.last_end())
+            }
+            token => Err(ParseError::UnexpectedToken(
+                span,
+                token.as_str().to_string(),
+                "expression",
+            )),
+        }
+    }
+
+    // Parses the elements of a list or array literal, after the opening bracket.
+    fn elements(&mut self) -> Result<Vec<(Expression<V>, Span)>, ParseError> {
+        let mut elems = Vec::new();
+        while !self.eat(Token::RBracket) {
+            let start = self.start();
+            let elem = self.expression()?;
+            elems.push((elem, start..self.last_end()));
+            if !self.eat(Token::Comma) {
+                self.expect(Token::RBracket, "',' or ']'")?;
+                break;
+            }
+        }
+        Ok(elems)
+    }
+
+    fn call_args<const N: usize>(&mut self) -> Result<[Expression<V>; N], ParseError> {
+        self.expect(Token::LParen, "'('")?;
+        let mut args = Vec::with_capacity(N);
+        for idx in 0..N {
+            if idx > 0 {
+                self.expect(Token::Comma, "','")?;
+            }
+            args.push(self.expression()?);
+        }
+        self.expect(Token::RParen, "')'")?;
+        Ok(args
+            .try_into()
+            .unwrap_or_else(|_| unreachable!("exactly N arguments")))
+    }
+
+    // Parses a non-empty list of arguments.
+    fn variadic_args(&mut self) -> Result<Vec<Expression<V>>, ParseError> {
+        self.expect(Token::LParen, "'('")?;
+        let mut args = vec![self.expression()?];
+        while self.eat(Token::Comma) {
+            args.push(self.expression()?);
+        }
+        self.expect(Token::RParen, "',' or ')'")?;
+        Ok(args)
+    }
+
+    fn list_type(&mut self) -> Result<Type, ParseError> {
+        self.expect(Token::Less, "'<'")?;
+        let t = self.r#type()?;
+        self.expect(Token::Greater, "'>'")?;
+        Ok(t)
+    }
+
+    // Parses a (possibly negative) integer literal bounding an integer type.
+    fn bound(&mut self) -> Result<Integer, ParseError> {
+        let neg = self.eat(Token::Minus);
+        let (token, span) = self.next("integer bound")?;
+        let Token::Integer(lit) = token else {
+            return Err(ParseError::UnexpectedToken(
+                span,
+                token.as_str().to_string(),
+                "integer bound",
+            ));
+        };
+        let lit = if neg {
+            format!("-{lit}")
+        } else {
+            lit.to_string()
+        };
+        lit.parse::<Integer>()
+            .map_err(|_| ParseError::InvalidNumber(span, lit))
+    }
+
+    fn r#type(&mut self) -> Result<Type, ParseError> {
+        let (token, span) = self.next("type")?;
+        match token {
+            Token::Ident("bool") => Ok(Type::Boolean),
+            Token::Ident("int") if self.eat(Token::Less) => {
+                let min = self.bound()?;
+                self.expect(Token::Comma, "','")?;
+                let max = self.bound()?;
+                self.expect(Token::Greater, "'>'")?;
+                Ok(Type::Bounded(min, max))
+            }
+            Token::Ident("int") => Ok(Type::Integer),
+            Token::Ident("float") => Ok(Type::Float),
+            Token::Ident("string") => Ok(Type::String),
+            Token::Ident("list") => Ok(Type::List(Box::new(self.list_type()?))),
+            Token::Ident("array") => {
+                self.expect(Token::Less, "'<'")?;
+                let t = self.r#type()?;
+                self.expect(Token::Comma, "','")?;
+                let (token, span) = self.next("array length")?;
+                let Token::Integer(len) = token else {
+                    return Err(ParseError::UnexpectedToken(
+                        span,
+                        token.as_str().to_string(),
+                        "array length",
+                    ));
+                };
+                let len = len
+                    .parse::<usize>()
+                    .map_err(|_| ParseError::InvalidNumber(span, len.to_string()))?;
+                self.expect(Token::Greater, "'>'")?;
+                Ok(Type::Array(Box::new(t), len))
+            }
+            Token::LParen => {
+                let mut types = Vec::new();
+                while !self.eat(Token::RParen) {
+                    types.push(self.r#type()?);
+                    if !self.eat(Token::Comma) {
+                        self.expect(Token::RParen, "',' or ')'")?;
+                        break;
+                    }
+                }
+                Ok(Type::Product(types))
+            }
+            token => Err(ParseError::UnexpectedToken(
+                span,
+                token.as_str().to_string(),
+                "type",
+            )),
+        }
+    }
+}
+
+// Negative numeric literals are parsed as constants rather than as opposites.
+fn negate<V: Clone>(expr: Expression<V>) -> Expression<V> {
+    match expr {
+        // The opposite of the minimum integer overflows, so it is left to evaluation.
+        Expression::Const(Val::Integer(i)) => i.checked_neg().map_or_else(
+            || -Expression::Const(Val::Integer(i)),
+            |i| Expression::Const(Val::Integer(i)),
+        ),
+        Expression::Const(Val::Float(f)) => Expression::Const(Val::Float(-f)),
+        expr => -expr,
+    }
+}
+
+// Evaluates expressions made only of constants, such as tuples of constants.
+fn constant<V: Clone>(expr: &Expression<V>) -> Option<Val> {
+    match expr {
+        Expression::Const(val) => Some(val.clone()),
+        Expression::Tuple(args) => args
+            .iter()
+            .map(constant)
+            .collect::<Option<Vec<_>>>()
+            .map(Val::Tuple),
+        _ => None,
+    }
+}
+
+// The common type of the elements of a non-empty list or array literal.
+fn literal_type<V: Clone>(elems: &[(Expression<V>, Span)], span: Span) -> Result<Type, ParseError> {
+    let Some((first, first_span)) = elems.first() else {
+        return Err(ParseError::Type(span, TypeErrorKind::NoArguments.into()));
+    };
+    let t = first
+        .r#type()
+        .map_err(|err| ParseError::Type(first_span.clone(), err))?;
+    for (elem, span) in elems.iter() {
+        let found = elem
+            .r#type()
+            .map_err(|err| ParseError::Type(span.clone(), err))?;
+        if found != t {
+            let kind = TypeErrorKind::Mismatch {
+                expected: Expected::Type(t),
+                found,
+            };
+            return Err(ParseError::Type(span.clone(), kind.into()));
+        }
+    }
+    Ok(t)
+}
+
+// A list literal is a constant if all its elements are constant,
+// otherwise it is built by appending its elements to an empty list.
+fn list_literal<V: Clone>(
+    elems: Vec<(Expression<V>, Span)>,
+    span: Span,
+) -> Result<Expression<V>, ParseError> {
+    let t = literal_type(&elems, span)?;
+    if let Some(vals) = elems
+        .iter()
+        .map(|(elem, _)| constant(elem))
+        .collect::<Option<Vec<_>>>()
+    {
+        Ok(Expression::Const(Val::List(t, vals)))
+    } else {
+        Ok(elems.into_iter().fold(
+            Expression::Const(Val::List(t, Vec::new())),
+            |list, (elem, _)| Expression::Append(Box::new((list, elem))),
+        ))
+    }
+}
+
+// An array literal is a constant if all its elements are constant.
+fn array_literal<V: Clone>(
+    elems: Vec<(Expression<V>, Span)>,
+    span: Span,
+) -> Result<Expression<V>, ParseError> {
+    let t = literal_type(&elems, span)?;
+    if let Some(vals) = elems
+        .iter()
+        .map(|(elem, _)| constant(elem))
+        .collect::<Option<Vec<_>>>()
+    {
+        Ok(Expression::Const(Val::Array(t, vals)))
+    } else {
+        Ok(Expression::Array(
+            elems.into_iter().map(|(elem, _)| elem).collect(),
+        ))
+    }
+}
+
+fn parse<V: Clone>(
+    input: &str,
+    resolve: &dyn Fn(&str) -> Option<(V, Type)>,
+) -> Result<Expression<V>, ParseError> {
+    let tokens = tokenize(input)?;
+    let mut parser = Parser {
+        tokens,
+        pos: 0,
+        end: input.len(),
+        resolve,
+    };
+    let expr = parser.expression()?;
+    if let Some((token, span)) = parser.tokens.get(parser.pos) {
+        Err(ParseError::UnexpectedToken(
+            span.clone(),
+            token.as_str().to_string(),
+            "end of input",
+        ))
+    } else {
+        Ok(expr)
+    }
+}
+
+/// Parses an expression from text,
+/// using `types` to know the type of the variables appearing in it.
+///
+/// ```
+/// # use scan_core::*;
+/// let types = |name: &str| (name == "x").then_some(Type::Integer);
+/// let expr = parse_expression("(x + 1) * 2 > 0", &types).unwrap();
+/// assert_eq!(expr.to_string(), "(x + 1) * 2 > 0");
+/// assert!(parse_expression("y + 1", &types).is_err());
+/// ```
+pub fn parse_expression(
+    input: &str,
+    types: &dyn Fn(&str) -> Option<Type>,
+) -> Result<Expression<String>, ParseError> {
+    parse(input, &|name| types(name).map(|t| (name.to_string(), t)))
+}
+
+/// Parses a type from text, in the syntax used by list and array literals.
+///
+/// ```
+/// # use scan_core::*;
+/// assert_eq!(parse_type("list<int<0, 9>>"), Ok(Type::List(Box::new(Type::Bounded(0, 9)))));
+/// assert_eq!(parse_type("(bool, string)").unwrap().to_string(), "(bool, string)");
+/// assert!(parse_type("int<9>").is_err());
+/// ```
+pub fn parse_type(input: &str) -> Result<Type, ParseError> {
+    let tokens = tokenize(input)?;
+    let mut parser = Parser::<()> {
+        tokens,
+        pos: 0,
+        end: input.len(),
+        resolve: &|_| None,
+    };
+    let t = parser.r#type()?;
+    if let Some((token, span)) = parser.tokens.get(parser.pos) {
+        Err(ParseError::UnexpectedToken(
+            span.clone(),
+            token.as_str().to_string(),
+            "end of input",
+        ))
+    } else {
+        Ok(t)
+    }
+}
+
+/// Maps names to variables of type `V` (e.g., PG's [`Var`](crate::program_graph::Var)s, or [`Channel`](crate::channel_system::Channel)s),
+/// so that expressions can be parsed directly in terms of such variables.
+///
+/// ```
+/// # use scan_core::{program_graph::*, *};
+/// let mut pg = ProgramGraphBuilder::new();
+/// let var = pg.new_var(Expression::from(0)).unwrap();
+/// let mut resolver = Resolver::new();
+/// resolver.insert("counter", var, Type::Integer);
+/// let expr: PgExpression = resolver.parse("counter + 1").unwrap();
+/// let action = pg.new_action();
+/// pg.add_effect(action, var, expr).unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct Resolver<V> {
+    vars: HashMap<String, (V, Type)>,
+}
+
+impl<V> Default for Resolver<V> {
+    fn default() -> Self {
+        Self {
+            vars: HashMap::new(),
+        }
+    }
+}
+
+impl<V: Clone> Resolver<V> {
+    /// Creates a new empty [`Resolver`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Associates the name to a variable of the given type.
+    ///
+    /// Returns the variable previously associated to the name, if any.
+    pub fn insert(&mut self, name: impl Into<String>, var: V, t: Type) -> Option<(V, Type)> {
+        self.vars.insert(name.into(), (var, t))
+    }
+
+    /// Parses an expression from text, resolving names into the associated variables.
+    pub fn parse(&self, input: &str) -> Result<Expression<V>, ParseError> {
+        parse(input, &|name| self.vars.get(name).cloned())
+    }
+
+    /// The variable associated to the name, with its type, if any.
+    pub fn get(&self, name: &str) -> Option<&(V, Type)> {
+        self.vars.get(name)
+    }
+
+    /// The name associated to a variable, if any.
+    ///
+    /// Useful to print expressions through [`Expression::display`].
+    pub fn name(&self, var: &V) -> Option<&str>
+    where
+        V: PartialEq,
+    {
+        self.vars
+            .iter()
+            .find_map(|(name, (v, _))| (v == var).then_some(name.as_str()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn types(name: &str) -> Option<Type> {
+        match name {
+            "x" | "y" | "z" => Some(Type::Integer),
+            "a" | "b" | "c" => Some(Type::Boolean),
+            "f" => Some(Type::Float),
+            "l" => Some(Type::List(Box::new(Type::Integer))),
+            "v" => Some(Type::Array(Box::new(Type::Float), 3)),
+            "t" => Some(Type::Product(vec![
+                Type::Integer,
+                Type::Product(vec![Type::Boolean, Type::Float]),
+            ])),
+            _ => None,
+        }
+    }
+
+    fn roundtrip(input: &str) {
+        let expr = parse_expression(input, &types).expect("parsing");
+        assert_eq!(expr.to_string(), input);
+        assert_eq!(parse_expression(&expr.to_string(), &types), Ok(expr));
+    }
+
+    #[test]
+    fn roundtrips() {
+        roundtrip("x + 1");
+        roundtrip("(x + 1) * y % z");
+        roundtrip("x * (y % z)");
+        roundtrip("x + -y * -3 > 2.5");
+        roundtrip("a && (b || c) => !a => b");
+        roundtrip("(a => b) => c");
+        roundtrip("(x < y) == a");
+        roundtrip("t.1.0 || t.0 <= 1");
+        roundtrip("-(x + y)");
+        roundtrip("(x, (a,), ())");
+        roundtrip("len(append(l, x + 1)) >= 2");
+        roundtrip("truncate(list<int>[])");
+        roundtrip("[(1, true), (2, false)]");
+        roundtrip("f * 1e-7 < 0.1");
+        roundtrip("x / y * z / (x % 2)");
+        roundtrip("min(x, abs(y), 3) <= pow(float(z), 0.5)");
+        roundtrip("max(floor(f), ceil(f), round(f / 2)) == int(f)");
+        roundtrip("a ? x : b ? y : (c ? x : y) + 1");
+        roundtrip("(a ? b : c) ? (x, f) : (y, f * 2)");
+        roundtrip("l[x + 1] * l[l[0]] + len(v)");
+        roundtrip("update(v, 2, v[0] + f)[1] > 0.0");
+        roundtrip("array[(1, true)][0].1 && t.1.0");
+        roundtrip("len(array[x, -1]) == len(array<int>[])");
+        roundtrip(r#"concat("a \"quoted\" \\", "") == "a \"quoted\" \\""#);
+        roundtrip(r#"len(list<string>[]) == 0 && "" == concat("x", "y", "z")"#);
+    }
+
+    #[test]
+    fn structure() {
+        let expr = parse_expression("x - y * z * 2", &types).unwrap();
+        assert_eq!(
+            expr,
+            Expression::Sum(vec![
+                Expression::Var("x".to_string(), Type::Integer),
+                Expression::Opposite(Box::new(Expression::Mult(vec![
+                    Expression::Var("y".to_string(), Type::Integer),
+                    Expression::Var("z".to_string(), Type::Integer),
+                    Expression::from(2),
+                ]))),
+            ])
+        );
+        let expr = parse_expression(&format!("1 - {}", Integer::MIN), &types).unwrap();
+        assert_eq!(
+            expr,
+            Expression::Sum(vec![
+                Expression::from(1),
+                Expression::Opposite(Box::new(Expression::from(Integer::MIN))),
+            ])
+        );
+        let expr = parse_expression("[x, 1]", &types).unwrap();
+        assert_eq!(expr.to_string(), "append(append(list<int>[], x), 1)");
+        let expr = parse_expression("x != -9223372036854775808", &types).unwrap();
+        assert_eq!(expr.to_string(), "!(x == -9223372036854775808)");
+        let expr = parse_expression("len(list<int<-1, 255>>[])", &types).unwrap();
+        assert_eq!(expr.to_string(), "len(list<int>[])");
+    }
+
+    #[test]
+    fn errors() {
+        let err = |input| parse_expression(input, &types).unwrap_err();
+        assert_eq!(err("x + w"), ParseError::UnknownVar(4..5, "w".to_string()));
+        assert_eq!(err("x + $"), ParseError::UnexpectedChar(4..5, '$'));
+        assert_eq!(err("x +"), ParseError::UnexpectedEnd(3..3, "expression"));
+        assert_eq!(err("(x, y"), ParseError::UnexpectedEnd(5..5, "',' or ')'"));
+        assert_eq!(
+            err("x y"),
+            ParseError::UnexpectedToken(2..3, "y".to_string(), "end of input")
+        );
+        assert_eq!(err("x < y < z"), ParseError::ChainedComparison(0..7));
+        assert_eq!(
+            err("[1, true]"),
+            ParseError::Type(
+                4..8,
+                TypeErrorKind::Mismatch {
+                    expected: Expected::Type(Type::Integer),
+                    found: Type::Boolean
+                }
+                .into()
+            )
+        );
+        assert_eq!(
+            err("x + 99999999999999999999"),
+            ParseError::InvalidNumber(4..24, "99999999999999999999".to_string())
+        );
+        assert_eq!(
+            err("a ? x y"),
+            ParseError::UnexpectedToken(6..7, "y".to_string(), "':'")
+        );
+        assert_eq!(err("l[x"), ParseError::UnexpectedEnd(3..3, "']'"));
+        assert_eq!(
+            err("array[]"),
+            ParseError::Type(0..7, TypeErrorKind::NoArguments.into())
+        );
+        assert_eq!(
+            err("min()"),
+            ParseError::UnexpectedToken(4..5, ")".to_string(), "expression")
+        );
+        assert_eq!(err("\"abc"), ParseError::UnexpectedEnd(4..4, "'\"'"));
+        assert_eq!(err("x + w").span(), 4..5);
+    }
+
+    #[test]
+    fn resolver() {
+        let mut resolver = Resolver::new();
+        resolver.insert("x", 0u16, Type::Integer);
+        resolver.insert("y", 1u16, Type::Integer);
+        let expr = resolver.parse("x * y").unwrap();
+        assert_eq!(
+            expr,
+            Expression::Mult(vec![
+                Expression::Var(0, Type::Integer),
+                Expression::Var(1, Type::Integer),
+            ])
+        );
+        let printed = expr.display(|v| resolver.name(v).unwrap().to_string());
+        assert_eq!(printed.to_string(), "x * y");
+        assert!(resolver.parse("x * z").is_err());
+    }
+}
@@ -0,0 +1,493 @@
+//! Human-readable rendering of [`Expression`]s, [`Val`]s and [`Type`]s.
+//!
+//! Expressions are printed in a C-like concrete syntax,
+//! using operator precedence to omit unnecessary parentheses.
+//! From lowest to highest precedence:
+//!
+//! - conditional `c ? a : b` (right-associative);
+//! - implication `=>` (right-associative);
+//! - disjunction `||`;
+//! - conjunction `&&`;
+//! - comparisons `==`, `<`, `<=`, `>`, `>=` (non-associative);
+//! - sum `+`;
+//! - multiplication `*`, division `/` and modulo `%`;
+//! - negation `!` and opposite `-`;
+//! - tuple components `e.0`, indexing `e[i]`, function-like operators such as `len(e)` or `max(e, f)`, and atoms.
+//!
+//! String literals are double-quoted, escaping `"` and `\` with a backslash.
+
+use super::{Expected, Expression, Type, Val};
+use std::fmt;
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Boolean => write!(f, "bool"),
+            Type::Integer => write!(f, "int"),
+            Type::Bounded(min, max) => write!(f, "int<{min}, {max}>"),
+            Type::Float => write!(f, "float"),
+            Type::String => write!(f, "string"),
+            Type::Product(types) => {
+                write!(f, "(")?;
+                write_list(f, types.iter(), |f, t| write!(f, "{t}"))?;
+                if types.len() == 1 {
+                    write!(f, ",")?;
+                }
+                write!(f, ")")
+            }
+            Type::List(t) => write!(f, "list<{t}>"),
+            Type::Array(t, len) => write!(f, "array<{t}, {len}>"),
+        }
+    }
+}
+
+impl fmt::Display for Expected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expected::Type(t) => write!(f, "{t}"),
+            Expected::OneOf(types) => match types.split_last() {
+                Some((last, [])) => write!(f, "{last}"),
+                Some((last, init)) => {
+                    write_list(f, init.iter(), |f, t| write!(f, "{t}"))?;
+                    write!(f, " or {last}")
+                }
+                None => write!(f, "nothing"),
+            },
+            Expected::Tuple => write!(f, "tuple"),
+            Expected::List => write!(f, "list"),
+            Expected::Sequence => write!(f, "list or array"),
+        }
+    }
+}
+
+impl fmt::Display for Val {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Val::Boolean(b) => write!(f, "{b}"),
+            Val::Integer(i) => write!(f, "{i}"),
+            // Debug formatting always marks the value as floating-point, e.g., `1.0` rather than `1`.
+            Val::Float(x) => write!(f, "{:?}", x.into_inner()),
+            Val::String(s) => {
+                write!(f, "\"")?;
+                for c in s.chars() {
+                    match c {
+                        '"' | '\\' => write!(f, "\\{c}")?,
+                        c => write!(f, "{c}")?,
+                    }
+                }
+                write!(f, "\"")
+            }
+            Val::Tuple(vals) => {
+                write!(f, "(")?;
+                write_list(f, vals.iter(), |f, v| write!(f, "{v}"))?;
+                if vals.len() == 1 {
+                    write!(f, ",")?;
+                }
+                write!(f, ")")
+            }
+            // The type of an empty list cannot be inferred from its elements, so it is made explicit.
+            Val::List(t, vals) if vals.is_empty() => write!(f, "list<{t}>[]"),
+            Val::List(_, vals) => {
+                write!(f, "[")?;
+                write_list(f, vals.iter(), |f, v| write!(f, "{v}"))?;
+                write!(f, "]")
+            }
+            Val::Array(t, vals) if vals.is_empty() => write!(f, "array<{t}>[]"),
+            Val::Array(_, vals) => {
+                write!(f, "array[")?;
+                write_list(f, vals.iter(), |f, v| write!(f, "{v}"))?;
+                write!(f, "]")
+            }
+        }
+    }
+}
+
+fn write_list<'a, T: 'a>(
+    f: &mut fmt::Formatter<'_>,
+    items: impl Iterator<Item = &'a T>,
+    write_item: impl Fn(&mut fmt::Formatter<'_>, &T) -> fmt::Result,
+) -> fmt::Result {
+    for (idx, item) in items.enumerate() {
+        if idx > 0 {
+            write!(f, ", ")?;
+        }
+        write_item(f, item)?;
+    }
+    Ok(())
+}
+
+/// Precedence levels of the concrete syntax, from the loosest to the tightest binding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Prec {
+    Ite,
+    Implies,
+    Or,
+    And,
+    Compare,
+    Sum,
+    Mult,
+    Unary,
+    Atom,
+}
+
+impl<V: Clone> Expression<V> {
+    fn precedence(&self) -> Prec {
+        match self {
+            Expression::And(args)
+            | Expression::Or(args)
+            | Expression::Sum(args)
+            | Expression::Mult(args)
+                if args.len() == 1 =>
+            {
+                args[0].precedence()
+            }
+            Expression::Ite(_) => Prec::Ite,
+            Expression::Implies(_) => Prec::Implies,
+            Expression::Or(args) if args.len() > 1 => Prec::Or,
+            Expression::And(args) if args.len() > 1 => Prec::And,
+            Expression::Equal(_)
+            | Expression::Greater(_)
+            | Expression::GreaterEq(_)
+            | Expression::Less(_)
+            | Expression::LessEq(_) => Prec::Compare,
+            Expression::Sum(args) if args.len() > 1 => Prec::Sum,
+            Expression::Mult(args) if args.len() > 1 => Prec::Mult,
+            Expression::Mod(_) | Expression::Div(_) => Prec::Mult,
+            Expression::Not(_) | Expression::Opposite(_) => Prec::Unary,
+            Expression::Const(Val::Integer(i)) if *i < 0 => Prec::Unary,
+            Expression::Const(Val::Float(x)) if x.is_sign_negative() => Prec::Unary,
+            _ => Prec::Atom,
+        }
+    }
+
+    /// Renders the expression in human-readable form,
+    /// using `names` to print variables.
+    ///
+    /// ```
+    /// # use scan_core::*;
+    /// let expr = Expression::Mult(vec![
+    ///     Expression::Sum(vec![Expression::Var("x", Type::Integer), Expression::from(1)]),
+    ///     Expression::Var("y", Type::Integer),
+    /// ]);
+    /// assert_eq!(expr.display(|v| v.to_string()).to_string(), "(x + 1) * y");
+    /// ```
+    pub fn display<F>(&self, names: F) -> DisplayExpression<'_, V, F>
+    where
+        F: Fn(&V) -> String,
+    {
+        DisplayExpression { expr: self, names }
+    }
+}
+
+/// Helper struct to print an [`Expression`] through [`fmt::Display`].
+///
+/// Created by [`Expression::display`].
+pub struct DisplayExpression<'a, V, F>
+where
+    V: Clone,
+{
+    expr: &'a Expression<V>,
+    names: F,
+}
+
+impl<V, F> fmt::Display for DisplayExpression<'_, V, F>
+where
+    V: Clone,
+    F: Fn(&V) -> String,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, self.expr, Prec::Ite)
+    }
+}
+
+impl<V, F> DisplayExpression<'_, V, F>
+where
+    V: Clone,
+    F: Fn(&V) -> String,
+{
+    // Writes `expr`, wrapping it in parentheses if it binds more loosely than `min`.
+    fn write(&self, f: &mut fmt::Formatter<'_>, expr: &Expression<V>, min: Prec) -> fmt::Result {
+        if expr.precedence() < min {
+            write!(f, "(")?;
+            self.write_expr(f, expr)?;
+            write!(f, ")")
+        } else {
+            self.write_expr(f, expr)
+        }
+    }
+
+    // Writes a chain of operands separated by `op`.
+    // The first operand may bind as the operator itself (left-associativity),
+    // while the following ones must bind more tightly.
+    fn write_nary(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        args: &[Expression<V>],
+        op: &str,
+        prec: Prec,
+        tighter: Prec,
+    ) -> fmt::Result {
+        for (idx, arg) in args.iter().enumerate() {
+            if idx == 0 {
+                self.write(f, arg, prec)?;
+            } else {
+                write!(f, " {op} ")?;
+                self.write(f, arg, tighter)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_expr(&self, f: &mut fmt::Formatter<'_>, expr: &Expression<V>) -> fmt::Result {
+        match expr {
+            Expression::Const(val) => write!(f, "{val}"),
+            Expression::Var(var, _) => write!(f, "{}", (self.names)(var)),
+            Expression::Tuple(args) => {
+                write!(f, "(")?;
+                write_list(f, args.iter(), |f, arg| self.write(f, arg, Prec::Ite))?;
+                if args.len() == 1 {
+                    write!(f, ",")?;
+                }
+                write!(f, ")")
+            }
+            Expression::Component(idx, expr) => {
+                self.write(f, expr, Prec::Atom)?;
+                write!(f, ".{idx}")
+            }
+            Expression::Array(args) => {
+                write!(f, "array[")?;
+                write_list(f, args.iter(), |f, arg| self.write(f, arg, Prec::Ite))?;
+                write!(f, "]")
+            }
+            Expression::Index(args) => {
+                self.write(f, &args.0, Prec::Atom)?;
+                write!(f, "[")?;
+                self.write(f, &args.1, Prec::Ite)?;
+                write!(f, "]")
+            }
+            // Empty conjunctions, disjunctions, sums and products are printed as their neutral element.
+            Expression::And(args) if args.is_empty() => write!(f, "true"),
+            Expression::Or(args) if args.is_empty() => write!(f, "false"),
+            Expression::Sum(args) if args.is_empty() => write!(f, "0"),
+            Expression::Mult(args) if args.is_empty() => write!(f, "1"),
+            // Singletons are printed as their only argument.
+            Expression::And(args)
+            | Expression::Or(args)
+            | Expression::Sum(args)
+            | Expression::Mult(args)
+                if args.len() == 1 =>
+            {
+                self.write_expr(f, &args[0])
+            }
+            Expression::And(args) => self.write_nary(f, args, "&&", Prec::And, Prec::Compare),
+            Expression::Or(args) => self.write_nary(f, args, "||", Prec::Or, Prec::And),
+            Expression::Sum(args) => self.write_nary(f, args, "+", Prec::Sum, Prec::Mult),
+            Expression::Mult(args) => self.write_nary(f, args, "*", Prec::Mult, Prec::Unary),
+            Expression::Ite(args) => {
+                self.write(f, &args.0, Prec::Implies)?;
+                write!(f, " ? ")?;
+                self.write(f, &args.1, Prec::Ite)?;
+                write!(f, " : ")?;
+                self.write(f, &args.2, Prec::Ite)
+            }
+            Expression::Implies(args) => {
+                self.write(f, &args.0, Prec::Or)?;
+                write!(f, " => ")?;
+                self.write(f, &args.1, Prec::Implies)
+            }
+            Expression::Not(expr) => {
+                write!(f, "!")?;
+                self.write(f, expr, Prec::Unary)
+            }
+            Expression::Opposite(expr) => {
+                write!(f, "-")?;
+                self.write(f, expr, Prec::Unary)
+            }
+            Expression::Mod(args) => {
+                self.write(f, &args.0, Prec::Mult)?;
+                write!(f, " % ")?;
+                self.write(f, &args.1, Prec::Unary)
+            }
+            Expression::Div(args) => {
+                self.write(f, &args.0, Prec::Mult)?;
+                write!(f, " / ")?;
+                self.write(f, &args.1, Prec::Unary)
+            }
+            Expression::Equal(args) => self.write_compare(f, args, "=="),
+            Expression::Greater(args) => self.write_compare(f, args, ">"),
+            Expression::GreaterEq(args) => self.write_compare(f, args, ">="),
+            Expression::Less(args) => self.write_compare(f, args, "<"),
+            Expression::LessEq(args) => self.write_compare(f, args, "<="),
+            Expression::Append(args) => self.write_call(f, "append", [&args.0, &args.1]),
+            Expression::Truncate(expr) => self.write_call(f, "truncate", [expr.as_ref()]),
+            Expression::Len(expr) => self.write_call(f, "len", [expr.as_ref()]),
+            Expression::Update(args) => self.write_call(f, "update", [&args.0, &args.1, &args.2]),
+            Expression::Pow(args) => self.write_call(f, "pow", [&args.0, &args.1]),
+            Expression::Min(args) => self.write_call(f, "min", args),
+            Expression::Max(args) => self.write_call(f, "max", args),
+            // An empty concatenation is the empty string.
+            Expression::Concat(args) if args.is_empty() => write!(f, "\"\""),
+            Expression::Concat(args) => self.write_call(f, "concat", args),
+            Expression::Abs(expr) => self.write_call(f, "abs", [expr.as_ref()]),
+            Expression::Floor(expr) => self.write_call(f, "floor", [expr.as_ref()]),
+            Expression::Ceil(expr) => self.write_call(f, "ceil", [expr.as_ref()]),
+            Expression::Round(expr) => self.write_call(f, "round", [expr.as_ref()]),
+            Expression::ToFloat(expr) => self.write_call(f, "float", [expr.as_ref()]),
+            Expression::ToInt(expr) => self.write_call(f, "int", [expr.as_ref()]),
+        }
+    }
+
+    // Writes a function-like operator applied to its arguments.
+    fn write_call<'e>(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        name: &str,
+        args: impl IntoIterator<Item = &'e Expression<V>>,
+    ) -> fmt::Result
+    where
+        V: 'e,
+    {
+        write!(f, "{name}(")?;
+        for (idx, arg) in args.into_iter().enumerate() {
+            if idx > 0 {
+                write!(f, ", ")?;
+            }
+            self.write(f, arg, Prec::Ite)?;
+        }
+        write!(f, ")")
+    }
+
+    fn write_compare(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        args: &(Expression<V>, Expression<V>),
+        op: &str,
+    ) -> fmt::Result {
+        // Comparisons are non-associative.
+        self.write(f, &args.0, Prec::Sum)?;
+        write!(f, " {op} ")?;
+        self.write(f, &args.1, Prec::Sum)
+    }
+}
+
+impl<V> fmt::Display for Expression<V>
+where
+    V: Clone + fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.display(|var| var.to_string()).fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn var(name: &'static str) -> Expression<&'static str> {
+        Expression::Var(name, Type::Integer)
+    }
+
+    fn bvar(name: &'static str) -> Expression<&'static str> {
+        Expression::Var(name, Type::Boolean)
+    }
+
+    #[test]
+    fn arithmetic() {
+        let expr = (var("x") + Expression::from(1)) * var("y");
+        assert_eq!(expr.to_string(), "(x + 1) * y");
+        let expr = var("x") + Expression::from(1) * var("y");
+        assert_eq!(expr.to_string(), "x + 1 * y");
+        let expr = var("x") + -(var("y") + var("z"));
+        assert_eq!(expr.to_string(), "x + -(y + z)");
+        let expr = Expression::Mod(Box::new((var("x"), var("y") * var("z"))));
+        assert_eq!(expr.to_string(), "x % (y * z)");
+        let expr = Expression::Mod(Box::new((var("x") * var("y"), var("z"))));
+        assert_eq!(expr.to_string(), "x * y % z");
+        let expr = var("x") * Expression::from(-2) + Expression::from(0.5);
+        assert_eq!(expr.to_string(), "x * -2 + 0.5");
+        let expr = Expression::Div(Box::new((var("x") * var("y"), var("z") * var("x"))));
+        assert_eq!(expr.to_string(), "x * y / (z * x)");
+        let expr = Expression::Max(vec![
+            Expression::Abs(Box::new(var("x"))),
+            Expression::Pow(Box::new((var("y"), Expression::from(2)))),
+        ]);
+        assert_eq!(expr.to_string(), "max(abs(x), pow(y, 2))");
+    }
+
+    #[test]
+    fn logic() {
+        let expr = Expression::Implies(Box::new((
+            Expression::and(vec![bvar("a"), Expression::or(vec![bvar("b"), bvar("c")])]),
+            Expression::Implies(Box::new((bvar("d"), !bvar("e")))),
+        )));
+        assert_eq!(expr.to_string(), "a && (b || c) => d => !e");
+        let expr = Expression::Implies(Box::new((
+            Expression::Implies(Box::new((bvar("a"), bvar("b")))),
+            bvar("c"),
+        )));
+        assert_eq!(expr.to_string(), "(a => b) => c");
+        let expr = !Expression::Equal(Box::new((var("x"), var("y") + Expression::from(1))));
+        assert_eq!(expr.to_string(), "!(x == y + 1)");
+        let expr = Expression::Equal(Box::new((
+            Expression::Less(Box::new((var("x"), var("y")))),
+            bvar("a"),
+        )));
+        assert_eq!(expr.to_string(), "(x < y) == a");
+        let expr = Expression::Ite(Box::new((
+            Expression::Ite(Box::new((bvar("a"), bvar("b"), bvar("c")))),
+            var("x") + Expression::from(1),
+            Expression::Ite(Box::new((bvar("b"), var("y"), var("z")))),
+        )));
+        assert_eq!(expr.to_string(), "(a ? b : c) ? x + 1 : b ? y : z");
+        let expr = Expression::Sum(vec![
+            var("x"),
+            Expression::Ite(Box::new((bvar("a"), var("y"), var("z")))),
+        ]);
+        assert_eq!(expr.to_string(), "x + (a ? y : z)");
+    }
+
+    #[test]
+    fn structures() {
+        let expr = Expression::Component(
+            1,
+            Box::new(Expression::Tuple(vec![
+                var("x"),
+                Expression::or(vec![bvar("a"), bvar("b")]),
+            ])),
+        );
+        assert_eq!(expr.to_string(), "(x, a || b).1");
+        let list = Expression::Const(Val::List(Type::Integer, Vec::new()));
+        assert_eq!(list.to_string(), "list<int>[]");
+        let expr = Expression::Len(Box::new(Expression::Append(Box::new((list, var("x"))))));
+        assert_eq!(expr.to_string(), "len(append(list<int>[], x))");
+        let val = Val::Tuple(vec![Val::List(Type::Float, vec![Val::from(1.0)])]);
+        assert_eq!(val.to_string(), "([1.0],)");
+        let t = Type::Product(vec![Type::Boolean, Type::List(Box::new(Type::Integer))]);
+        assert_eq!(t.to_string(), "(bool, list<int>)");
+        let expr = Expression::Update(Box::new((
+            Expression::Array(vec![var("x"), Expression::from(0)]),
+            Expression::Index(Box::new((
+                Expression::Const(Val::Array(Type::Integer, vec![Val::Integer(1)])),
+                var("y") + Expression::from(1),
+            ))),
+            -var("z"),
+        )));
+        assert_eq!(expr.to_string(), "update(array[x, 0], array[1][y + 1], -z)");
+        let val = Val::Array(Type::Boolean, Vec::new());
+        assert_eq!(val.to_string(), "array<bool>[]");
+        let t = Type::Array(Box::new(Type::Float), 3);
+        assert_eq!(t.to_string(), "array<float, 3>");
+    }
+
+    #[test]
+    fn custom_names() {
+        let expr = Expression::Sum(vec![
+            Expression::Var(0usize, Type::Integer),
+            Expression::from(1),
+        ]);
+        assert_eq!(
+            expr.display(|v| format!("var_{v}")).to_string(),
+            "var_0 + 1"
+        );
+    }
+}
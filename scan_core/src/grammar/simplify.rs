@@ -0,0 +1,1015 @@
+//! Algebraic simplification of [`Expression`]s.
+//!
+//! Simplification folds constants, flattens nested n-ary operators,
+//! removes neutral elements and duplicates,
+//! and applies absorption and complementation laws of Boolean logic.
+//!
+//! Expressions are assumed to be well-typed.
+//! Then, the simplified expression has the same type as the original one,
+//! and evaluates to the same result for every assignment of its variables,
+//! including failures.
+//! To this end, operations that would fail at runtime (e.g., an integer overflow or an out-of-bounds index)
+//! are never folded into constants,
+//! and sub-expressions that do not affect the result (e.g., other elements of an indexed array)
+//! are only discarded if their evaluation cannot fail.
+
+use super::{index, int_rem, round, Expression, Float, Integer, Overflow, Str, Type, Val};
+use ordered_float::OrderedFloat;
+
+impl<V> Expression<V>
+where
+    V: Clone + PartialEq,
+{
+    /// Simplifies a (well-typed) expression, preserving its type and semantics
+    /// when integer overflows are checked.
+    ///
+    /// ```
+    /// # use scan_core::*;
+    /// let types = |name: &str| (name == "x").then_some(Type::Integer);
+    /// let expr = parse_expression("true && (1 + 2 + x > 0 || false)", &types).unwrap();
+    /// assert_eq!(expr.simplify().to_string(), "3 + x > 0");
+    /// ```
+    pub fn simplify(self) -> Self {
+        self.simplify_with_overflow(Overflow::default())
+    }
+
+    /// Simplifies a (well-typed) expression, preserving its type and semantics
+    /// when integer overflows behave according to `overflow`.
+    ///
+    /// Only wrapping integer arithmetic is associative,
+    /// so sums and products are otherwise simplified in evaluation order.
+    ///
+    /// ```
+    /// # use scan_core::*;
+    /// let types = |name: &str| (name == "x").then_some(Type::Integer);
+    /// let expr = parse_expression("x + 1 + -1", &types).unwrap();
+    /// assert_eq!(expr.clone().simplify_with_overflow(Overflow::Wrapping).to_string(), "x");
+    /// assert_eq!(expr.simplify_with_overflow(Overflow::Checked).to_string(), "x + 1 + -1");
+    /// ```
+    pub fn simplify_with_overflow(self, overflow: Overflow) -> Self {
+        match self {
+            Expression::Const(_) | Expression::Var(_, _) => self,
+            Expression::Tuple(args) => {
+                let args: Vec<_> = args
+                    .into_iter()
+                    .map(|arg| arg.simplify_with_overflow(overflow))
+                    .collect();
+                if let Some(vals) = args
+                    .iter()
+                    .map(|arg| match arg {
+                        Expression::Const(val) => Some(val.clone()),
+                        _ => None,
+                    })
+                    .collect::<Option<Vec<_>>>()
+                {
+                    Expression::Const(Val::Tuple(vals))
+                } else {
+                    Expression::Tuple(args)
+                }
+            }
+            Expression::Component(idx, expr) => match expr.simplify_with_overflow(overflow) {
+                // Other components can be discarded, if they cannot fail.
+                Expression::Tuple(mut args)
+                    if idx < args.len() && args.iter().all(|arg| arg.is_infallible(overflow)) =>
+                {
+                    args.swap_remove(idx)
+                }
+                Expression::Const(Val::Tuple(mut vals)) if idx < vals.len() => {
+                    Expression::Const(vals.swap_remove(idx))
+                }
+                expr => Expression::Component(idx, Box::new(expr)),
+            },
+            Expression::Ite(args) => {
+                let (cond, then, r#else) = *args;
+                match (
+                    cond.simplify_with_overflow(overflow),
+                    then.simplify_with_overflow(overflow),
+                    r#else.simplify_with_overflow(overflow),
+                ) {
+                    (Expression::Const(Val::Boolean(b)), then, r#else) => {
+                        if b {
+                            then
+                        } else {
+                            r#else
+                        }
+                    }
+                    (cond, then, r#else) if then == r#else && cond.is_infallible(overflow) => then,
+                    (
+                        cond,
+                        Expression::Const(Val::Boolean(then)),
+                        Expression::Const(Val::Boolean(r#else)),
+                    ) => {
+                        // Branches are different Boolean constants.
+                        debug_assert_ne!(then, r#else);
+                        if then {
+                            cond
+                        } else {
+                            (!cond).simplify_with_overflow(overflow)
+                        }
+                    }
+                    // Negated conditions are replaced by swapping the branches.
+                    (Expression::Not(cond), then, r#else) => {
+                        Expression::Ite(Box::new((*cond, r#else, then)))
+                    }
+                    (cond, then, r#else) => Expression::Ite(Box::new((cond, then, r#else))),
+                }
+            }
+            Expression::And(args) => simplify_junction(args, true, overflow),
+            Expression::Or(args) => simplify_junction(args, false, overflow),
+            Expression::Implies(args) => {
+                let (lhs, rhs) = *args;
+                match (
+                    lhs.simplify_with_overflow(overflow),
+                    rhs.simplify_with_overflow(overflow),
+                ) {
+                    (Expression::Const(Val::Boolean(true)), rhs) => rhs,
+                    // The consequent is only evaluated if the premise holds.
+                    (Expression::Const(Val::Boolean(false)), _) => Expression::from(true),
+                    (lhs, Expression::Const(Val::Boolean(true))) if lhs.is_infallible(overflow) => {
+                        Expression::from(true)
+                    }
+                    (lhs, Expression::Const(Val::Boolean(false))) => {
+                        (!lhs).simplify_with_overflow(overflow)
+                    }
+                    (lhs, rhs) if lhs == rhs && lhs.is_infallible(overflow) => {
+                        Expression::from(true)
+                    }
+                    (lhs, rhs) => Expression::Implies(Box::new((lhs, rhs))),
+                }
+            }
+            Expression::Not(expr) => match expr.simplify_with_overflow(overflow) {
+                Expression::Const(Val::Boolean(b)) => Expression::from(!b),
+                Expression::Not(expr) => *expr,
+                expr => Expression::Not(Box::new(expr)),
+            },
+            Expression::Opposite(expr) => match expr.simplify_with_overflow(overflow) {
+                Expression::Const(Val::Integer(i)) if i != Integer::MIN => Expression::from(-i),
+                Expression::Const(Val::Float(f)) => Expression::Const(Val::Float(-f)),
+                // Negating the minimum integer twice does not give it back, unless wrapping.
+                Expression::Opposite(expr)
+                    if overflow == Overflow::Wrapping
+                        || matches!(expr.r#type(), Ok(Type::Float)) =>
+                {
+                    *expr
+                }
+                expr => Expression::Opposite(Box::new(expr)),
+            },
+            Expression::Sum(args) => simplify_arithmetic(args, false, overflow),
+            Expression::Mult(args) => simplify_arithmetic(args, true, overflow),
+            Expression::Mod(args) => {
+                let (lhs, rhs) = *args;
+                match (
+                    lhs.simplify_with_overflow(overflow),
+                    rhs.simplify_with_overflow(overflow),
+                ) {
+                    (
+                        Expression::Const(Val::Integer(lhs)),
+                        Expression::Const(Val::Integer(rhs)),
+                    ) if rhs == 0 || lhs.checked_rem(rhs).is_some() => {
+                        Expression::from(int_rem(lhs, rhs))
+                    }
+                    (lhs, rhs) => Expression::Mod(Box::new((lhs, rhs))),
+                }
+            }
+            Expression::Div(args) => {
+                let (lhs, rhs) = *args;
+                match (
+                    lhs.simplify_with_overflow(overflow),
+                    rhs.simplify_with_overflow(overflow),
+                ) {
+                    (
+                        Expression::Const(Val::Integer(lhs)),
+                        Expression::Const(Val::Integer(rhs)),
+                    ) if Overflow::Checked.div(lhs, rhs).is_ok() => {
+                        Expression::from(Overflow::Checked.div(lhs, rhs).expect("no overflow"))
+                    }
+                    (Expression::Const(lhs), Expression::Const(rhs))
+                        if matches!(lhs, Val::Float(_)) || matches!(rhs, Val::Float(_)) =>
+                    {
+                        fold_floats(&lhs, &rhs, |lhs, rhs| lhs / rhs)
+                    }
+                    // Dividing by integer one is the identity.
+                    (lhs, Expression::Const(Val::Integer(1))) => lhs,
+                    // Integer division by zero is defined to be zero.
+                    (lhs, Expression::Const(Val::Integer(0)))
+                        if matches!(lhs.r#type(), Ok(Type::Integer))
+                            && lhs.is_infallible(overflow) =>
+                    {
+                        Expression::from(0)
+                    }
+                    (lhs, rhs) => Expression::Div(Box::new((lhs, rhs))),
+                }
+            }
+            Expression::Pow(args) => {
+                let (lhs, rhs) = *args;
+                match (
+                    lhs.simplify_with_overflow(overflow),
+                    rhs.simplify_with_overflow(overflow),
+                ) {
+                    (
+                        Expression::Const(Val::Integer(lhs)),
+                        Expression::Const(Val::Integer(rhs)),
+                    ) if Overflow::Checked.pow(lhs, rhs).is_ok() => {
+                        Expression::from(Overflow::Checked.pow(lhs, rhs).expect("no overflow"))
+                    }
+                    (Expression::Const(lhs), Expression::Const(rhs))
+                        if matches!(lhs, Val::Float(_)) || matches!(rhs, Val::Float(_)) =>
+                    {
+                        fold_floats(&lhs, &rhs, Float::powf)
+                    }
+                    (lhs, rhs) => Expression::Pow(Box::new((lhs, rhs))),
+                }
+            }
+            Expression::Min(args) => simplify_extremum(args, false, overflow),
+            Expression::Max(args) => simplify_extremum(args, true, overflow),
+            Expression::Abs(expr) => match expr.simplify_with_overflow(overflow) {
+                Expression::Const(Val::Integer(i)) if i != Integer::MIN => {
+                    Expression::from(i.abs())
+                }
+                Expression::Const(Val::Float(f)) => Expression::from(f.into_inner().abs()),
+                Expression::Abs(expr) => Expression::Abs(expr),
+                expr => Expression::Abs(Box::new(expr)),
+            },
+            Expression::Floor(expr) => {
+                simplify_to_int(*expr, Expression::Floor, Float::floor, overflow)
+            }
+            Expression::Ceil(expr) => {
+                simplify_to_int(*expr, Expression::Ceil, Float::ceil, overflow)
+            }
+            Expression::Round(expr) => simplify_to_int(*expr, Expression::Round, round, overflow),
+            Expression::ToInt(expr) => {
+                simplify_to_int(*expr, Expression::ToInt, Float::trunc, overflow)
+            }
+            Expression::ToFloat(expr) => match expr.simplify_with_overflow(overflow) {
+                Expression::Const(Val::Integer(i)) => Expression::from(i as Float),
+                expr if matches!(expr.r#type(), Ok(Type::Float)) => expr,
+                expr => Expression::ToFloat(Box::new(expr)),
+            },
+            Expression::Equal(args) => {
+                simplify_comparison(*args, Expression::Equal, |o| o.is_eq(), overflow)
+            }
+            Expression::Greater(args) => {
+                simplify_comparison(*args, Expression::Greater, |o| o.is_gt(), overflow)
+            }
+            Expression::GreaterEq(args) => {
+                simplify_comparison(*args, Expression::GreaterEq, |o| o.is_ge(), overflow)
+            }
+            Expression::Less(args) => {
+                simplify_comparison(*args, Expression::Less, |o| o.is_lt(), overflow)
+            }
+            Expression::LessEq(args) => {
+                simplify_comparison(*args, Expression::LessEq, |o| o.is_le(), overflow)
+            }
+            Expression::Concat(args) => {
+                // Nested concatenations are flattened, adjacent constants are joined,
+                // and empty strings are dropped.
+                let mut simplified: Vec<Expression<V>> = Vec::with_capacity(args.len());
+                for arg in args
+                    .into_iter()
+                    .map(|arg| arg.simplify_with_overflow(overflow))
+                {
+                    let subs = match arg {
+                        Expression::Concat(subs) => subs,
+                        arg => vec![arg],
+                    };
+                    for sub in subs {
+                        match (simplified.last_mut(), sub) {
+                            (_, Expression::Const(Val::String(s))) if s.is_empty() => {}
+                            (
+                                Some(Expression::Const(Val::String(acc))),
+                                Expression::Const(Val::String(s)),
+                            ) => *acc = acc.concat(&s),
+                            (_, sub) => simplified.push(sub),
+                        }
+                    }
+                }
+                match simplified.len() {
+                    0 => Expression::Const(Val::String(Str::default())),
+                    1 => simplified.pop().expect("one argument"),
+                    _ => Expression::Concat(simplified),
+                }
+            }
+            Expression::Append(args) => {
+                let (list, elem) = *args;
+                match (
+                    list.simplify_with_overflow(overflow),
+                    elem.simplify_with_overflow(overflow),
+                ) {
+                    (Expression::Const(Val::List(t, mut vals)), Expression::Const(val))
+                        if val.r#type() == t =>
+                    {
+                        vals.push(val);
+                        Expression::Const(Val::List(t, vals))
+                    }
+                    (list, elem) => Expression::Append(Box::new((list, elem))),
+                }
+            }
+            Expression::Truncate(list) => match list.simplify_with_overflow(overflow) {
+                Expression::Const(Val::List(t, mut vals)) if !vals.is_empty() => {
+                    vals.pop();
+                    Expression::Const(Val::List(t, vals))
+                }
+                list => Expression::Truncate(Box::new(list)),
+            },
+            Expression::Len(list) => match list.simplify_with_overflow(overflow) {
+                Expression::Const(Val::List(_, vals) | Val::Array(_, vals)) => {
+                    Expression::from(vals.len() as Integer)
+                }
+                Expression::Array(args) if args.iter().all(|arg| arg.is_infallible(overflow)) => {
+                    Expression::from(args.len() as Integer)
+                }
+                // Arrays have fixed length.
+                Expression::Var(_, Type::Array(_, len)) => Expression::from(len as Integer),
+                list => Expression::Len(Box::new(list)),
+            },
+            Expression::Array(args) => {
+                let args: Vec<_> = args
+                    .into_iter()
+                    .map(|arg| arg.simplify_with_overflow(overflow))
+                    .collect();
+                if let Some(vals) = args
+                    .iter()
+                    .map(|arg| match arg {
+                        Expression::Const(val) => Some(val.clone()),
+                        _ => None,
+                    })
+                    .collect::<Option<Vec<_>>>()
+                {
+                    let t = vals.first().expect("non-empty array").r#type();
+                    Expression::Const(Val::Array(t, vals))
+                } else {
+                    Expression::Array(args)
+                }
+            }
+            Expression::Index(args) => {
+                let (list, idx) = *args;
+                match (
+                    list.simplify_with_overflow(overflow),
+                    idx.simplify_with_overflow(overflow),
+                ) {
+                    // Out-of-bounds indexes are not folded, as they fail at runtime.
+                    (
+                        Expression::Const(Val::List(_, mut vals) | Val::Array(_, mut vals)),
+                        Expression::Const(Val::Integer(i)),
+                    ) if index(i, vals.len()).is_ok() => {
+                        Expression::Const(vals.swap_remove(i as usize))
+                    }
+                    // Other elements can be discarded, if they cannot fail.
+                    (Expression::Array(mut args), Expression::Const(Val::Integer(i)))
+                        if index(i, args.len()).is_ok()
+                            && args.iter().all(|arg| arg.is_infallible(overflow)) =>
+                    {
+                        args.swap_remove(i as usize)
+                    }
+                    (list, idx) => Expression::Index(Box::new((list, idx))),
+                }
+            }
+            Expression::Update(args) => {
+                let (list, idx, elem) = *args;
+                match (
+                    list.simplify_with_overflow(overflow),
+                    idx.simplify_with_overflow(overflow),
+                    elem.simplify_with_overflow(overflow),
+                ) {
+                    (
+                        Expression::Const(Val::List(t, mut vals)),
+                        Expression::Const(Val::Integer(i)),
+                        Expression::Const(val),
+                    ) if index(i, vals.len()).is_ok() => {
+                        vals[i as usize] = val;
+                        Expression::Const(Val::List(t, vals))
+                    }
+                    (
+                        Expression::Const(Val::Array(t, mut vals)),
+                        Expression::Const(Val::Integer(i)),
+                        Expression::Const(val),
+                    ) if index(i, vals.len()).is_ok() => {
+                        vals[i as usize] = val;
+                        Expression::Const(Val::Array(t, vals))
+                    }
+                    (list, idx, elem) => Expression::Update(Box::new((list, idx, elem))),
+                }
+            }
+        }
+    }
+}
+
+impl<V> Expression<V>
+where
+    V: Clone,
+{
+    // Whether the evaluation of a (well-typed) expression can never fail.
+    fn is_infallible(&self, overflow: Overflow) -> bool {
+        let checked = overflow == Overflow::Checked;
+        let is_int = |expr: &Self| matches!(expr.r#type(), Ok(Type::Integer));
+        let is_float = |expr: &Self| matches!(expr.r#type(), Ok(Type::Float));
+        match self {
+            Expression::Const(_) | Expression::Var(_, _) => true,
+            Expression::Index(_) | Expression::Update(_) | Expression::Truncate(_) => false,
+            // Integer operations can overflow, if overflows are checked.
+            Expression::Sum(args) | Expression::Mult(args)
+                if checked && args.iter().any(is_int) =>
+            {
+                false
+            }
+            Expression::Opposite(expr) | Expression::Abs(expr) if checked && is_int(expr) => false,
+            Expression::Div(args) | Expression::Pow(args)
+                if checked && is_int(&args.0) && is_int(&args.1) =>
+            {
+                false
+            }
+            // Conversions of floats to integers can overflow, if overflows are checked.
+            Expression::Floor(expr)
+            | Expression::Ceil(expr)
+            | Expression::Round(expr)
+            | Expression::ToInt(expr)
+                if checked && is_float(expr) =>
+            {
+                false
+            }
+            Expression::Tuple(args)
+            | Expression::And(args)
+            | Expression::Or(args)
+            | Expression::Sum(args)
+            | Expression::Mult(args)
+            | Expression::Min(args)
+            | Expression::Max(args)
+            | Expression::Concat(args)
+            | Expression::Array(args) => args.iter().all(|arg| arg.is_infallible(overflow)),
+            Expression::Component(_, expr)
+            | Expression::Not(expr)
+            | Expression::Opposite(expr)
+            | Expression::Abs(expr)
+            | Expression::Floor(expr)
+            | Expression::Ceil(expr)
+            | Expression::Round(expr)
+            | Expression::ToInt(expr)
+            | Expression::ToFloat(expr)
+            | Expression::Len(expr) => expr.is_infallible(overflow),
+            Expression::Implies(args)
+            | Expression::Mod(args)
+            | Expression::Div(args)
+            | Expression::Pow(args)
+            | Expression::Equal(args)
+            | Expression::Greater(args)
+            | Expression::GreaterEq(args)
+            | Expression::Less(args)
+            | Expression::LessEq(args)
+            | Expression::Append(args) => {
+                args.0.is_infallible(overflow) && args.1.is_infallible(overflow)
+            }
+            Expression::Ite(args) => {
+                args.0.is_infallible(overflow)
+                    && args.1.is_infallible(overflow)
+                    && args.2.is_infallible(overflow)
+            }
+        }
+    }
+}
+
+// Simplifies conjunctions (if `and` is `true`) or disjunctions (otherwise),
+// which are dual of one another.
+fn simplify_junction<V: Clone + PartialEq>(
+    args: Vec<Expression<V>>,
+    and: bool,
+    overflow: Overflow,
+) -> Expression<V> {
+    // `and` is the neutral element of the operator, `!and` the absorbing one.
+    let mut simplified: Vec<Expression<V>> = Vec::with_capacity(args.len());
+    for arg in args {
+        let subs = match arg.simplify_with_overflow(overflow) {
+            Expression::Const(Val::Boolean(b)) if b == and => continue,
+            Expression::Const(Val::Boolean(_))
+                if simplified.iter().all(|arg| arg.is_infallible(overflow)) =>
+            {
+                return Expression::from(!and);
+            }
+            // Arguments following the absorbing element are never evaluated.
+            arg @ Expression::Const(Val::Boolean(_)) => {
+                simplified.push(arg);
+                break;
+            }
+            // Flatten nested operators.
+            Expression::And(subs) if and => subs,
+            Expression::Or(subs) if !and => subs,
+            arg => vec![arg],
+        };
+        for sub in subs {
+            // Idempotence: `a && a` is `a`.
+            if !simplified.contains(&sub) {
+                simplified.push(sub);
+            }
+        }
+    }
+    // The following laws can discard arguments, and change which ones are evaluated.
+    if !simplified.iter().all(|arg| arg.is_infallible(overflow)) {
+        return match simplified.len() {
+            1 => simplified.pop().expect("one argument"),
+            _ if and => Expression::And(simplified),
+            _ => Expression::Or(simplified),
+        };
+    }
+    // Complementation: `a && !a` is `false`.
+    if simplified
+        .iter()
+        .any(|arg| simplified.contains(&Expression::Not(Box::new(arg.clone()))))
+    {
+        return Expression::from(!and);
+    }
+    // Absorption: `a && (a || b)` is `a`.
+    let absorbed: Vec<bool> = simplified
+        .iter()
+        .map(|arg| match arg {
+            Expression::Or(subs) if and => subs.iter().any(|sub| simplified.contains(sub)),
+            Expression::And(subs) if !and => subs.iter().any(|sub| simplified.contains(sub)),
+            _ => false,
+        })
+        .collect();
+    let mut absorbed = absorbed.into_iter();
+    simplified.retain(|_| !absorbed.next().expect("same length"));
+    match simplified.len() {
+        0 => Expression::from(and),
+        1 => simplified.pop().expect("one argument"),
+        _ if and => Expression::And(simplified),
+        _ => Expression::Or(simplified),
+    }
+}
+
+// Folds two numerical constants as evaluation would, if it does not fail.
+fn fold_numbers(lhs: &Val, rhs: &Val, mult: bool, overflow: Overflow) -> Option<Val> {
+    match (lhs, rhs) {
+        (Val::Integer(lhs), Val::Integer(rhs)) if mult => {
+            overflow.mul(*lhs, *rhs).ok().map(Val::Integer)
+        }
+        (Val::Integer(lhs), Val::Integer(rhs)) => overflow.add(*lhs, *rhs).ok().map(Val::Integer),
+        _ => {
+            let lhs = as_float(lhs)?;
+            let rhs = as_float(rhs)?;
+            Some(Val::Float(if mult { lhs * rhs } else { lhs + rhs }))
+        }
+    }
+}
+
+fn as_float(val: &Val) -> Option<OrderedFloat<f64>> {
+    match val {
+        Val::Integer(i) => Some(OrderedFloat(*i as Float)),
+        Val::Float(f) => Some(*f),
+        _ => None,
+    }
+}
+
+// Folds two numerical constants, at least one of which is a float, with a float operation.
+fn fold_floats<V: Clone>(lhs: &Val, rhs: &Val, op: fn(Float, Float) -> Float) -> Expression<V> {
+    let lhs = as_float(lhs).expect("numerical constant");
+    let rhs = as_float(rhs).expect("numerical constant");
+    Expression::from(op(lhs.into_inner(), rhs.into_inner()))
+}
+
+// Simplifies minima (if `max` is `false`) or maxima (otherwise).
+fn simplify_extremum<V: Clone + PartialEq>(
+    args: Vec<Expression<V>>,
+    max: bool,
+    overflow: Overflow,
+) -> Expression<V> {
+    let mut simplified: Vec<Expression<V>> = Vec::with_capacity(args.len());
+    for arg in args {
+        let subs = match arg.simplify_with_overflow(overflow) {
+            // Flatten nested operators.
+            Expression::Min(subs) if !max => subs,
+            Expression::Max(subs) if max => subs,
+            arg => vec![arg],
+        };
+        for sub in subs {
+            if !simplified.contains(&sub) {
+                simplified.push(sub);
+            }
+        }
+    }
+    let vals = simplified
+        .iter()
+        .map(|arg| match arg {
+            Expression::Const(val) => Some(val.clone()),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>();
+    match vals {
+        Some(vals) if vals.iter().all(|val| matches!(val, Val::Integer(_))) => {
+            let ints = vals.into_iter().map(|val| match val {
+                Val::Integer(i) => i,
+                _ => unreachable!("integer constant"),
+            });
+            Expression::from(if max { ints.max() } else { ints.min() }.expect("non-empty"))
+        }
+        Some(vals) => {
+            let floats = vals
+                .iter()
+                .map(|val| as_float(val).expect("numerical constant").into_inner());
+            Expression::from(if max {
+                floats.fold(Float::NEG_INFINITY, Float::max)
+            } else {
+                floats.fold(Float::INFINITY, Float::min)
+            })
+        }
+        // The extremum of a single argument is the argument itself.
+        None if simplified.len() == 1 => simplified.pop().expect("one argument"),
+        None if max => Expression::Max(simplified),
+        None => Expression::Min(simplified),
+    }
+}
+
+// Simplifies a conversion from numbers to integers, applying `op` to floats.
+fn simplify_to_int<V: Clone + PartialEq>(
+    expr: Expression<V>,
+    conversion: fn(Box<Expression<V>>) -> Expression<V>,
+    op: fn(Float) -> Float,
+    overflow: Overflow,
+) -> Expression<V> {
+    match expr.simplify_with_overflow(overflow) {
+        // The conversion is the identity on integers.
+        expr if matches!(expr.r#type(), Ok(Type::Integer)) => expr,
+        // Out-of-range conversions are not folded, as they would saturate.
+        // `Integer::MAX as Float` rounds up to a power of two, which is out of range.
+        Expression::Const(Val::Float(f))
+            if (Integer::MIN as Float..Integer::MAX as Float).contains(&op(f.into_inner())) =>
+        {
+            Expression::from(op(f.into_inner()) as Integer)
+        }
+        expr => conversion(Box::new(expr)),
+    }
+}
+
+// Simplifies sums (if `mult` is `false`) or multiplications (otherwise).
+fn simplify_arithmetic<V: Clone + PartialEq>(
+    args: Vec<Expression<V>>,
+    mult: bool,
+    overflow: Overflow,
+) -> Expression<V> {
+    let args: Vec<_> = args
+        .into_iter()
+        .map(|arg| arg.simplify_with_overflow(overflow))
+        .collect();
+    // Wrapping integer arithmetic is associative and commutative,
+    // so operands can be freely rearranged.
+    // Otherwise, that could hide (or introduce) overflows, or change the rounding of floats,
+    // so operands are evaluated left to right as in the original expression.
+    let reorder = overflow == Overflow::Wrapping
+        && args
+            .iter()
+            .all(|arg| matches!(arg.r#type(), Ok(Type::Integer)));
+    let neutral = if mult { 1 } else { 0 };
+    let mut simplified: Vec<Expression<V>> = Vec::with_capacity(args.len());
+    // The constant that all (if reordering) or the leading operands fold to.
+    let mut constant: Option<Val> = None;
+    // Whether the operands so far have a float value.
+    let mut is_float = false;
+    for (idx, arg) in args.into_iter().enumerate() {
+        let subs = match arg {
+            // Flatten nested operators (only the leftmost one, unless reordering).
+            Expression::Sum(subs) if !mult && (reorder || idx == 0) => subs,
+            Expression::Mult(subs) if mult && (reorder || idx == 0) => subs,
+            arg => vec![arg],
+        };
+        for sub in subs {
+            match sub {
+                // An integer neutral element never changes the value of the result.
+                Expression::Const(Val::Integer(i)) if i == neutral => {}
+                // A float neutral element can be dropped only if the result is float already.
+                Expression::Const(Val::Float(f))
+                    if is_float && f == OrderedFloat(neutral as Float) => {}
+                Expression::Const(val) if reorder || simplified.is_empty() => {
+                    is_float |= matches!(val, Val::Float(_));
+                    match constant.take() {
+                        None => constant = Some(val),
+                        Some(acc) => match fold_numbers(&acc, &val, mult, overflow) {
+                            Some(folded) => constant = Some(folded),
+                            None => {
+                                // Folding would overflow.
+                                simplified.push(Expression::Const(acc));
+                                simplified.push(Expression::Const(val));
+                            }
+                        },
+                    }
+                }
+                sub => {
+                    is_float |= matches!(sub.r#type(), Ok(Type::Float));
+                    if !reorder {
+                        if let Some(acc) = constant.take() {
+                            simplified.push(Expression::Const(acc));
+                        }
+                    }
+                    simplified.push(sub);
+                }
+            }
+        }
+    }
+    match constant {
+        Some(Val::Integer(i)) if i == neutral => {}
+        // Integer products are absorbed by zero, unless other factors can fail.
+        Some(Val::Integer(0))
+            if mult && !is_float && simplified.iter().all(|arg| arg.is_infallible(overflow)) =>
+        {
+            return Expression::from(0)
+        }
+        Some(constant) => simplified.push(Expression::Const(constant)),
+        None => {}
+    }
+    match simplified.len() {
+        0 => Expression::from(neutral),
+        1 => simplified.pop().expect("one argument"),
+        _ if mult => Expression::Mult(simplified),
+        _ => Expression::Sum(simplified),
+    }
+}
+
+type BinaryOp<V> = fn(Box<(Expression<V>, Expression<V>)>) -> Expression<V>;
+
+fn simplify_comparison<V: Clone + PartialEq>(
+    (lhs, rhs): (Expression<V>, Expression<V>),
+    op: BinaryOp<V>,
+    holds: fn(std::cmp::Ordering) -> bool,
+    overflow: Overflow,
+) -> Expression<V> {
+    match (
+        lhs.simplify_with_overflow(overflow),
+        rhs.simplify_with_overflow(overflow),
+    ) {
+        (Expression::Const(lhs), Expression::Const(rhs)) => {
+            let ordering = match (&lhs, &rhs) {
+                (Val::Integer(lhs), Val::Integer(rhs)) => Some(lhs.cmp(rhs)),
+                (Val::Boolean(lhs), Val::Boolean(rhs)) => Some(lhs.cmp(rhs)),
+                (Val::String(lhs), Val::String(rhs)) => Some(lhs.as_str().cmp(rhs.as_str())),
+                // Compare floats as `f64` rather than as `OrderedFloat`, to respect NaN semantics.
+                (lhs, rhs) => as_float(lhs)
+                    .zip(as_float(rhs))
+                    .and_then(|(lhs, rhs)| lhs.into_inner().partial_cmp(&rhs.into_inner())),
+            };
+            match ordering {
+                Some(ordering) => Expression::from(holds(ordering)),
+                None if as_float(&lhs).is_some() && as_float(&rhs).is_some() => {
+                    // Comparisons with NaN are always false.
+                    Expression::from(false)
+                }
+                None => op(Box::new((Expression::Const(lhs), Expression::Const(rhs)))),
+            }
+        }
+        // Integer, Boolean and string expressions are equal to themselves.
+        (lhs, rhs)
+            if lhs == rhs
+                && lhs.is_infallible(overflow)
+                && matches!(
+                    lhs.r#type(),
+                    Ok(Type::Integer | Type::Boolean | Type::String)
+                ) =>
+        {
+            Expression::from(holds(std::cmp::Ordering::Equal))
+        }
+        (lhs, rhs) => op(Box::new((lhs, rhs))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grammar::{parse_expression, EvalError, FnExpression, Resolver};
+
+    fn types(name: &str) -> Option<Type> {
+        match name {
+            "x" | "y" => Some(Type::Integer),
+            "a" | "b" | "c" => Some(Type::Boolean),
+            "f" => Some(Type::Float),
+            "t" => Some(Type::Product(vec![Type::Integer, Type::Boolean])),
+            "l" => Some(Type::List(Box::new(Type::Integer))),
+            "v" => Some(Type::Array(Box::new(Type::Boolean), 2)),
+            "s" => Some(Type::String),
+            _ => None,
+        }
+    }
+
+    const NAMES: [&str; 10] = ["x", "y", "a", "b", "c", "f", "t", "l", "v", "s"];
+
+    fn check(input: &str, expected: &str) {
+        check_with_overflow(input, expected, Overflow::Checked);
+    }
+
+    // Checks that simplification gives the expected result,
+    // and that type and semantics are preserved on some sample assignments.
+    fn check_with_overflow(input: &str, expected: &str, overflow: Overflow) {
+        let mut resolver = Resolver::new();
+        for (idx, name) in NAMES.into_iter().enumerate() {
+            resolver.insert(name, idx, types(name).unwrap());
+        }
+        let expr = resolver.parse(input).expect("parsing");
+        let simplified = expr.clone().simplify_with_overflow(overflow);
+        assert_eq!(
+            simplified
+                .display(|idx| NAMES[*idx].to_string())
+                .to_string(),
+            expected
+        );
+        assert_eq!(expr.r#type(), simplified.r#type());
+        let expr = FnExpression::new(expr, overflow);
+        let simplified = FnExpression::new(simplified, overflow);
+        for (x, y, a, b, f) in [
+            (0, 1, false, true, 0.5),
+            (-3, 3, true, true, -1.5),
+            (7, 0, true, false, 0.0),
+            (2, 2, false, false, 2.0),
+            (Integer::MAX, Integer::MIN, true, false, 1e16),
+        ] {
+            let vals = [
+                Val::Integer(x),
+                Val::Integer(y),
+                Val::Boolean(a),
+                Val::Boolean(b),
+                Val::Boolean(a && b),
+                Val::from(f),
+                Val::Tuple(vec![Val::Integer(x), Val::Boolean(b)]),
+                Val::List(Type::Integer, vec![Val::Integer(x), Val::Integer(y)]),
+                Val::Array(Type::Boolean, vec![Val::Boolean(a), Val::Boolean(b)]),
+                Val::from(if a { "a" } else { "" }),
+            ];
+            assert_eq!(simplified.eval(&|v| &vals[v]), expr.eval(&|v| &vals[v]));
+        }
+    }
+
+    #[test]
+    fn arithmetic() {
+        check("1 + 2 + x", "3 + x");
+        check("x + 1 + 2", "x + 1 + 2");
+        check("1 + x * 1 + -1", "1 + x + -1");
+        check("(x + 1) + (y + 2)", "x + 1 + (y + 2)");
+        check("x * 0 * y", "x * 0 * y");
+        check("x * 2 * 3.0", "x * 2 * 3.0");
+        check("x + 0.0", "x + 0.0");
+        check("f + 0.0 + 0", "f");
+        check("-(-f)", "f");
+        check("7 % 3 + x", "1 + x");
+        check("7 % 0 + 7 / 0 + x", "7 + x");
+        check("-7 / 2 + x / 1", "-3 + x");
+        check("x / 2.0 + 1 / 2.0", "x / 2.0 + 0.5");
+        check("pow(2, 10) + pow(2, -1) + pow(x, 2)", "1024 + pow(x, 2)");
+    }
+
+    #[test]
+    fn wrapping() {
+        // Wrapping integer arithmetic can be freely reassociated.
+        let check = |input, expected| check_with_overflow(input, expected, Overflow::Wrapping);
+        check("x + 1 + 2", "x + 3");
+        check("1 + x * 1 + -1", "x");
+        check("(x + 1) + (y + 2)", "x + y + 3");
+        check("x * 0 * y", "0");
+        check("x * 2 * 3.0", "x * 2 * 3.0");
+    }
+
+    #[test]
+    fn overflows() {
+        // Reassociating `x + 1 + -1` into `x` would hide the overflow of `x + 1`.
+        let mut resolver = Resolver::new();
+        resolver.insert("x", 0usize, Type::Integer);
+        let expr = resolver.parse("x + 1 + -1").expect("parsing");
+        let max = Val::Integer(Integer::MAX);
+        for (overflow, result) in [
+            (Overflow::Checked, Err(EvalError::Overflow)),
+            (Overflow::Saturating, Ok(Val::Integer(Integer::MAX - 1))),
+            (Overflow::Wrapping, Ok(Val::Integer(Integer::MAX))),
+        ] {
+            let simplified = expr.clone().simplify_with_overflow(overflow);
+            assert_eq!(
+                FnExpression::new(simplified, overflow).eval(&|_| &max),
+                result
+            );
+        }
+        // Negating the minimum integer twice does not give it back.
+        let expr = Expression::Opposite(Box::new(Expression::Opposite(Box::new(Expression::Var(
+            0usize,
+            Type::Integer,
+        )))));
+        for overflow in [Overflow::Checked, Overflow::Saturating] {
+            assert_eq!(expr.clone().simplify_with_overflow(overflow), expr);
+        }
+    }
+
+    #[test]
+    fn functions() {
+        check("min(x, 1, min(y, x), 3)", "min(x, 1, y, 3)");
+        check("max(2, 1.5) + max(x)", "2.0 + x");
+        check("max(f)", "f");
+        check("abs(-3) + abs(abs(x))", "3 + abs(x)");
+        check(
+            "floor(-0.5) + ceil(0.5) + round(2.5) + int(-2.7) + floor(x)",
+            "1 + x",
+        );
+        check("float(2) + float(f)", "2.0 + f");
+    }
+
+    #[test]
+    fn strings() {
+        check(
+            r#"concat("a", "", concat("b", s), "c", "d")"#,
+            r#"concat("ab", s, "cd")"#,
+        );
+        check(r#"concat("", s, "")"#, "s");
+        check(r#"concat("x", "y") == "xy" && s == s"#, "true");
+        check(r#"concat(s, "x") == "x""#, r#"concat(s, "x") == "x""#);
+    }
+
+    #[test]
+    fn logic() {
+        check("a && true && (b && c)", "a && b && c");
+        check("a || false || !true", "a");
+        check("a && (a || b)", "a");
+        check("(a && b) || a", "a");
+        check("a && b && !a", "false");
+        check("a || !a", "true");
+        check("!!a && a", "a");
+        check("false => a", "true");
+        check("true => a", "a");
+        check("a => false", "!a");
+        check("a => (b => true)", "true");
+    }
+
+    #[test]
+    fn conditionals() {
+        check("(a || true) ? x : y", "x");
+        check("a ? x + 1 : x + 1", "x + 1");
+        check("a && b ? true : false", "a && b");
+        check("a ? false : true", "!a");
+        check("!a ? x : y / 0", "a ? 0 : x");
+        check(
+            "(a ? x : y) + 1 > 0 ? t : (x, false)",
+            "(a ? x : y) + 1 > 0 ? t : (x, false)",
+        );
+    }
+
+    #[test]
+    fn comparisons() {
+        check("1 + 2 == 3", "true");
+        check("x == x && y <= y", "true");
+        check("x < x || a", "a");
+        check("1 < 2.5", "true");
+        check("f < f", "f < f");
+        check("(x, a).1 || t.0 > 0", "a || t.0 > 0");
+    }
+
+    #[test]
+    fn lists() {
+        let types = |_: &str| None;
+        let expr = parse_expression("len(append(truncate([1, 2]), 3))", &types).unwrap();
+        assert_eq!(expr.simplify(), Expression::from(2));
+        check("[1, 2, 3][1] + array[x, y][1]", "2 + y");
+        check("len(v) + len(array[x]) + len(l)", "3 + len(l)");
+        check("update(array[1, 2], 0, 3)[0] + l[x]", "3 + l[x]");
+        check("update(v, 1, a)[x % 2]", "update(v, 1, a)[x % 2]");
+        check("array[a, b][1] || v[0]", "b || v[0]");
+    }
+
+    #[test]
+    fn semantics() {
+        // Reassociating would change the rounding.
+        check("f + 1.0 + 2.0", "f + 1.0 + 2.0");
+        check("f * 0.1 * 10.0", "f * 0.1 * 10.0");
+        // Sub-expressions that can fail are not discarded.
+        check_with_overflow("l[x] * 0", "l[x] * 0", Overflow::Wrapping);
+        check_with_overflow("(x + y) * 0", "0", Overflow::Wrapping);
+        check_with_overflow("x + 1 >= x + 1", "true", Overflow::Saturating);
+        check("(x + y) * 0", "(x + y) * 0");
+        check("x + 1 == x + 1", "x + 1 == x + 1");
+        check("(x + 1, a).1", "(x + 1, a).1");
+        check("x + 1 > 0 => true", "x + 1 > 0 => true");
+        check("false => x + 1 > 0", "true");
+        check("x + 1 > 0 || !(x + 1 > 0)", "x + 1 > 0 || !(x + 1 > 0)");
+        check("(x + 1 > 0 || a) && a", "(x + 1 > 0 || a) && a");
+        check("b && x + 1 > 0 && false && a", "b && x + 1 > 0 && false");
+        check("true || x + 1 > 0", "true");
+        check("l[x] == l[x]", "l[x] == l[x]");
+        check("l[x] / 0 + x / 0", "l[x] / 0");
+        check("l[x] > 0 ? y : y", "l[x] > 0 ? y : y");
+        check("(l[x], a).1", "(l[x], a).1");
+        check(
+            "array[l[x], y][1] + len(array[l[x]])",
+            "array[l[x], y][1] + len(array[l[x]])",
+        );
+        check("false => l[x] > 0", "true");
+    }
+
+    #[test]
+    fn runtime_errors() {
+        // Operations failing at runtime are not folded.
+        let types = |_: &str| None;
+        for input in [
+            "truncate(list<int>[])",
+            "[1, 2][2]",
+            "update(array[true], -1, false)",
+            "-9223372036854775808 / -1",
+            "pow(2, 63)",
+            "int(1e19)",
+            "int(9223372036854775808.0)",
+            "floor(9223372036854775808.0)",
+            "9223372036854775807 * 2",
+            "9223372036854775807 + 1 + 2",
+        ] {
+            let expr = parse_expression(input, &types).unwrap();
+            assert!(!matches!(expr.simplify(), Expression::Const(_)));
+        }
+    }
+}
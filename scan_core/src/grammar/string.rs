@@ -0,0 +1,164 @@
+//! Interned strings, used as the values of [`Type::String`](super::Type::String).
+//!
+//! All strings with the same content share a single allocation,
+//! so that cloning and comparing strings is as cheap as for integers.
+//! Strings that are no longer in use are periodically swept from the interner,
+//! so that memory stays proportional to the strings alive at any time.
+
+use std::{
+    collections::HashSet,
+    fmt,
+    hash::{Hash, Hasher},
+    ops::Deref,
+    sync::{Arc, Mutex, OnceLock},
+};
+
+// Minimum number of interned strings before unused ones are swept.
+const MIN_SWEEP: usize = 1024;
+
+#[derive(Default)]
+struct Interner {
+    strings: HashSet<Arc<str>>,
+    // Size of the interner after the last sweep.
+    swept: usize,
+}
+
+impl Interner {
+    fn intern(&mut self, s: &str) -> Arc<str> {
+        if let Some(interned) = self.strings.get(s) {
+            return interned.clone();
+        }
+        // Sweeping when the interner doubles in size keeps the cost amortized constant.
+        if self.strings.len() >= MIN_SWEEP.max(2 * self.swept) {
+            self.sweep();
+        }
+        let interned: Arc<str> = Arc::from(s);
+        self.strings.insert(interned.clone());
+        interned
+    }
+
+    // Removes the strings only referenced by the interner.
+    // Since new references can only be obtained through the (locked) interner,
+    // such strings can never be used again.
+    fn sweep(&mut self) {
+        self.strings.retain(|s| Arc::strong_count(s) > 1);
+        self.swept = self.strings.len();
+    }
+}
+
+fn interner() -> &'static Mutex<Interner> {
+    static INTERNER: OnceLock<Mutex<Interner>> = OnceLock::new();
+    INTERNER.get_or_init(Default::default)
+}
+
+/// An interned string.
+///
+/// ```
+/// # use scan_core::Str;
+/// let hello = Str::new("hello");
+/// assert_eq!(hello, Str::from(String::from("hel") + "lo"));
+/// assert_eq!(hello.concat(&Str::new(" world")).as_str(), "hello world");
+/// ```
+#[derive(Clone)]
+pub struct Str(Arc<str>);
+
+impl Str {
+    /// Interns the given string.
+    pub fn new(s: &str) -> Self {
+        Str(interner().lock().expect("lock interner").intern(s))
+    }
+
+    /// The content of the string.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Concatenates two strings, interning the result.
+    pub fn concat(&self, other: &Str) -> Self {
+        if other.is_empty() {
+            self.clone()
+        } else if self.is_empty() {
+            other.clone()
+        } else {
+            Str::new(&[self.as_str(), other.as_str()].concat())
+        }
+    }
+}
+
+impl Default for Str {
+    fn default() -> Self {
+        Str::new("")
+    }
+}
+
+// Since strings are interned, equal strings are the same allocation.
+impl PartialEq for Str {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Str {}
+
+impl Hash for Str {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Arc::as_ptr(&self.0).cast::<u8>().hash(state);
+    }
+}
+
+impl Deref for Str {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl From<&str> for Str {
+    fn from(value: &str) -> Self {
+        Str::new(value)
+    }
+}
+
+impl From<String> for Str {
+    fn from(value: String) -> Self {
+        Str::new(&value)
+    }
+}
+
+impl fmt::Debug for Str {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.as_str())
+    }
+}
+
+// Strings are (de)serialized by content, and interned again when deserialized.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Str {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Str {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Str::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unused_strings_are_freed() {
+        let hello = Str::new("hello");
+        for i in 0..100 * MIN_SWEEP {
+            let _ = Str::from(i.to_string());
+        }
+        // Other tests may intern strings concurrently, so the bound is loose.
+        assert!(interner().lock().unwrap().strings.len() < 10 * MIN_SWEEP);
+        assert_eq!(hello, Str::new("hello"));
+    }
+}
@@ -11,6 +11,8 @@ mod grammar;
 mod model;
 mod pmtl;
 pub mod program_graph;
+#[cfg(feature = "serde")]
+pub mod serde_seq;
 mod transition_system;
 
 pub use grammar::*;
@@ -1,7 +1,9 @@
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
-use crate::channel_system::{Channel, ChannelSystem, Event, EventType};
+use crate::channel_system::{
+    Channel, ChannelStats, ChannelSystem, ChannelSystemBuilder, CsError, Event, EventType,
+};
 use crate::transition_system::TransitionSystem;
 use crate::{Expression, FnExpression, Time, Val};
 
@@ -34,7 +36,7 @@ impl CsModelBuilder {
 
     pub fn add_predicate(&mut self, predicate: Expression<Channel>) -> usize {
         let predicate = FnExpression::<Channel>::from(predicate);
-        let _ = predicate.eval(&|port| self.vals.get(&port).unwrap().clone());
+        let _ = predicate.eval(&|port| self.vals.get(&port).unwrap());
         self.predicates.push(predicate);
         self.predicates.len() - 1
     }
@@ -45,6 +47,13 @@ impl CsModelBuilder {
     /// as it is not possible to add any further ones after the [`CsModel`] has been initialized.
     pub fn build(self) -> CsModel {
         CsModel {
+            stats: Arc::new(Mutex::new(
+                self.cs
+                    .channel_stats()
+                    .into_iter()
+                    .map(|(channel, _)| (channel, ChannelStats::default()))
+                    .collect(),
+            )),
             cs: self.cs,
             vals: self.vals,
             last_event: None,
@@ -53,6 +62,36 @@ impl CsModelBuilder {
     }
 }
 
+/// The definition of a [`CsModel`]:
+/// the [`ChannelSystemBuilder`] defining its CS,
+/// the ports with their initial values, and the predicates over the ports.
+///
+/// Unlike a [`CsModel`], it can be (de)serialized (with the `serde` feature),
+/// e.g., to cache a model on disk or to pass it to other tools.
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CsModelDef {
+    pub cs: ChannelSystemBuilder,
+    pub ports: Vec<(Channel, Val)>,
+    pub predicates: Vec<Expression<Channel>>,
+}
+
+impl CsModelDef {
+    /// Builds the [`CsModel`] defined by `self`.
+    ///
+    /// Predicates are indexed in the order they are listed.
+    pub fn build(self) -> CsModel {
+        let mut builder = CsModelBuilder::new(self.cs.build());
+        for (channel, init) in self.ports {
+            builder.add_port(channel, init);
+        }
+        for predicate in self.predicates {
+            builder.add_predicate(predicate);
+        }
+        builder.build()
+    }
+}
+
 /// Transition system model based on a [`ChannelSystem`].
 ///
 /// It is essentially a CS which keeps track of the [`Event`]s produced by the execution
@@ -63,6 +102,8 @@ pub struct CsModel {
     vals: HashMap<Channel, Val>,
     predicates: Arc<Vec<FnMdExpression>>,
     last_event: Option<Event>,
+    // Statistics on the channels accumulated over the runs concluded by the model and its clones.
+    stats: Arc<Mutex<Vec<(Channel, ChannelStats)>>>,
 }
 
 impl CsModel {
@@ -71,16 +112,31 @@ impl CsModel {
     pub fn channel_system(&self) -> &ChannelSystem {
         &self.cs
     }
+
+    /// Sets a limit to the number of messages that channels with infinite capacity can hold.
+    ///
+    /// See [`ChannelSystem::set_queue_limit`].
+    pub fn set_queue_limit(&mut self, limit: Option<usize>) {
+        self.cs.set_queue_limit(limit);
+    }
+
+    /// Returns the statistics on the occupancy of each channel,
+    /// accumulated over all runs concluded by the model and its clones.
+    pub fn channel_stats(&self) -> Vec<(Channel, ChannelStats)> {
+        self.stats.lock().expect("lock stats").clone()
+    }
 }
 
 impl TransitionSystem for CsModel {
     type Action = Event;
 
+    type Err = CsError;
+
     fn labels(&self) -> Vec<bool> {
         self.predicates
             .iter()
             .map(|prop| {
-                if let Val::Boolean(b) = prop.eval(&|port| self.vals.get(&port).unwrap().clone()) {
+                if let Ok(Val::Boolean(b)) = prop.eval(&|port| self.vals.get(&port).unwrap()) {
                     Some(b)
                 } else {
                     None
@@ -100,13 +156,24 @@ impl TransitionSystem for CsModel {
         &mut self,
         rng: &mut R,
         duration: Time,
-    ) -> Option<Self::Action> {
-        self.last_event = self.cs.montecarlo_execution(rng, duration);
+    ) -> Result<Option<Self::Action>, Self::Err> {
+        self.last_event = self.cs.montecarlo_execution(rng, duration)?;
         if let Some(event) = self.last_event.as_ref() {
-            if let EventType::Send(ref val) = event.event_type {
+            if let EventType::Send(ref val)
+            | EventType::Handshake(_, ref val)
+            | EventType::Broadcast(_, ref val)
+            | EventType::FaultySend(_, ref val) = event.event_type
+            {
                 self.vals.insert(event.channel, val.clone());
             }
         }
-        self.last_event.clone()
+        Ok(self.last_event.clone())
+    }
+
+    fn conclude_run(&mut self) {
+        let mut stats = self.stats.lock().expect("lock stats");
+        for ((_, stats), (_, run_stats)) in stats.iter_mut().zip(self.cs.channel_stats()) {
+            stats.merge(&run_stats);
+        }
     }
 }
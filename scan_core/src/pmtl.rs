@@ -8,6 +8,7 @@ use std::{hash::Hash, sync::Arc};
 type DenseTime = (Time, Time);
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Pmtl<V>
 where
     V: Clone,
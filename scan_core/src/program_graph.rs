@@ -71,6 +71,7 @@ use super::grammar::*;
 use crate::Time;
 pub use builder::*;
 use core::panic;
+use smallvec::SmallVec;
 use std::sync::Arc;
 use thiserror::Error;
 
@@ -79,6 +80,7 @@ use thiserror::Error;
 /// These cannot be directly created or manipulated,
 /// but have to be generated and/or provided by a [`ProgramGraphBuilder`] or [`ProgramGraph`].
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Location(u16);
 
 /// An indexing object for actions in a PG.
@@ -86,8 +88,15 @@ pub struct Location(u16);
 /// These cannot be directly created or manipulated,
 /// but have to be generated and/or provided by a [`ProgramGraphBuilder`] or [`ProgramGraph`].
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Action(u16);
 
+impl std::fmt::Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "a{}", self.0)
+    }
+}
+
 /// Epsilon action to enable autonomous transitions.
 /// It cannot have effects.
 const EPSILON: Action = Action(u16::MAX);
@@ -105,16 +114,62 @@ const TIME: Clock = Clock(0);
 ///
 /// These cannot be directly created or manipulated,
 /// but have to be generated and/or provided by a [`ProgramGraphBuilder`] or [`ProgramGraph`].
-#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Var(u16);
 
+impl std::fmt::Display for Var {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "v{}", self.0)
+    }
+}
+
 /// An indexing object for clocks in a PG.
 ///
 /// These cannot be directly created or manipulated,
 /// but have to be generated and/or provided by a [`ProgramGraphBuilder`] or [`ProgramGraph`].
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Clock(u16);
 
+impl Clock {
+    /// The expression evaluating to the time elapsed since the clock was last reset.
+    ///
+    /// It can be used to express time constraints within guards and invariants
+    /// that cannot be given as a [`TimeConstraint`].
+    pub fn elapsed(self) -> PgExpression {
+        self.elapsed_with(|var| var)
+    }
+
+    // Builds the elapsed-time expression, mapping the clock's underlying variables by `var`.
+    pub(crate) fn elapsed_with<V: Clone>(self, var: impl Fn(Var) -> V) -> Expression<V> {
+        Expression::Sum(vec![
+            Expression::Var(var(Var(TIME.0)), Type::Integer),
+            -Expression::Var(var(Var(self.0)), Type::Integer),
+        ])
+    }
+}
+
+/// How a location constrains the passing of time and the execution of the other PGs, as in UPPAAL.
+#[derive(Debug, Default, Clone, Copy, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Urgency {
+    /// Time can elapse in the location (as far as its invariant allows).
+    #[default]
+    Normal,
+    /// Time cannot elapse in the location.
+    Urgent,
+    /// Time cannot elapse in the location,
+    /// and, as long as the PG can transition, only PGs in atomic locations can
+    /// (unless some PG is in a committed location).
+    /// Moreover, once the PG transitions into an atomic location, only it can transition
+    /// until it leaves atomic locations, as long as it does not get stuck.
+    Atomic,
+    /// Time cannot elapse in the location,
+    /// and the next transition has to be executed by a PG in a committed location.
+    Committed,
+}
+
 /// A time constraint given by a clock and, optionally, a lower bound and/or an upper bound.
 pub type TimeConstraint = (Clock, Option<Time>, Option<Time>);
 
@@ -125,7 +180,7 @@ pub type PgExpression = Expression<Var>;
 #[derive(Debug, Clone, Error)]
 pub enum PgError {
     /// The expression is badly typed.
-    #[error("malformed expression {0:?}")]
+    #[error("malformed expression {0}")]
     BadExpression(PgExpression),
     /// There is no such action in the PG.
     #[error("action {0:?} does not belong to this program graph")]
@@ -170,12 +225,22 @@ pub enum PgError {
     /// Wait action should not be used directly.
     #[error("direct use of wait action")]
     Wait,
+    /// Time cannot elapse in an urgent (or atomic, or committed) location.
+    #[error("time cannot elapse in urgent location {0:?}")]
+    Urgent(Location),
     /// Cannot reset global timer.
     #[error("cannot reset global timer")]
     TimeClock,
     /// A type error
     #[error("type error")]
     Type(#[source] TypeError),
+    /// The evaluation of an expression failed.
+    #[error("evaluation error")]
+    Eval(#[source] EvalError),
+    /// The action failed to assign a value to a variable,
+    /// either because evaluating the effect failed or because the value is out of the variable's range.
+    #[error("action {0} failed to assign variable {1}")]
+    Effect(Action, Var, #[source] EvalError),
 }
 
 #[derive(Debug)]
@@ -183,6 +248,7 @@ enum FnEffect {
     Effects(Vec<(Var, FnExpression<Var>)>),
     Send(FnExpression<Var>),
     Receive(Var),
+    SelectiveReceive(Var, FnExpression<Var>),
 }
 
 type Transition = (Action, Location, Option<FnExpression<Var>>);
@@ -191,9 +257,35 @@ type Transition = (Action, Location, Option<FnExpression<Var>>);
 struct ProgramGraphDef {
     effects: Vec<FnEffect>,
     transitions: Vec<Vec<Transition>>,
+    // Indexed by locations.
+    urgencies: Vec<Urgency>,
+    // Types constraining the values of variables (if any), indexed by variables.
+    bounds: Vec<Option<Type>>,
 }
 
 impl ProgramGraphDef {
+    // Checks that the value can be assigned to the variable without violating its bounds (if any).
+    #[inline(always)]
+    fn check_bounds(&self, var: Var, val: &Val) -> Result<(), EvalError> {
+        self.bounds[var.0 as usize]
+            .as_ref()
+            .map_or(Ok(()), |bounds| bounds.check_bounds(val))
+    }
+
+    // Evaluates the effect on a variable against the given valuation of the variables,
+    // making sure the result does not violate the variable's bounds.
+    #[inline(always)]
+    fn eval_effect(
+        &self,
+        var: Var,
+        effect: &FnExpression<Var>,
+        vars: &[Val],
+    ) -> Result<Val, EvalError> {
+        let val = effect.eval(&|var| &vars[var.0 as usize])?;
+        self.check_bounds(var, &val)?;
+        Ok(val)
+    }
+
     // Returns transition's guard.
     // Panics if the pre- or post-state do not exist.
     // Returns error if the transition does not exist.
@@ -212,6 +304,16 @@ impl ProgramGraphDef {
     }
 }
 
+// Evaluates a guard against the given valuation of the variables.
+#[inline(always)]
+fn eval_guard(guard: &FnExpression<Var>, vars: &[Val]) -> Result<bool, EvalError> {
+    if let Val::Boolean(pass) = guard.eval(&|var| &vars[var.0 as usize])? {
+        Ok(pass)
+    } else {
+        panic!("guard is not a boolean");
+    }
+}
+
 /// Representation of a PG that can be executed transition-by-transition.
 ///
 /// The structure of the PG cannot be changed,
@@ -252,11 +354,20 @@ impl ProgramGraph {
         self.current_location
     }
 
+    /// Returns the urgency of the current location.
+    #[inline(always)]
+    pub fn urgency(&self) -> Urgency {
+        self.def.urgencies[self.current_location.0 as usize]
+    }
+
     /// Iterates over all transitions that can be admitted in the current state.
     ///
     /// An admittable transition is characterized by the required action and the post-state
     /// (the pre-state being necessarily the current state of the machine).
     /// The guard (if any) is guaranteed to be satisfied.
+    ///
+    /// Transitions whose guard, effects or post-location invariant fail to evaluate are admitted as well,
+    /// so that attempting them reports the error.
     pub fn possible_transitions(&self) -> impl Iterator<Item = (Action, Location)> + '_ {
         self.def.transitions[self.current_location.0 as usize]
             .iter()
@@ -264,129 +375,121 @@ impl ProgramGraph {
                 // WAIT should not be called directly!
                 if *action == WAIT {
                     None
-                } else if guard.as_ref().map_or(true, |guard| {
-                    if let Val::Boolean(pass) = guard.eval(&|var| self.vars[var.0 as usize].clone())
-                    {
-                        pass
-                    } else {
-                        panic!("guard is not a boolean");
-                    }
-                }) {
-                    if let Ok(Some(time_invariant)) = self.def.guard(*post_state, WAIT, *post_state)
-                    {
-                        // If action has effects
-                        if *action != EPSILON {
-                            if let FnEffect::Effects(ref effects) =
-                                self.def.effects[action.0 as usize]
-                            {
-                                if !effects.is_empty() {
-                                    // Avoid cloning variables unless it is absolutley necessary
-                                    let mut vars = self.vars.clone();
-                                    for (var, effect) in effects {
-                                        vars[var.0 as usize] =
-                                            effect.eval(&|var| vars[var.0 as usize].clone());
-                                    }
-                                    if let Val::Boolean(pass) = time_invariant
-                                        .eval(&move |var| vars[var.0 as usize].clone())
-                                    {
-                                        return pass.then_some((*action, *post_state));
-                                    } else {
-                                        panic!("guard is not a boolean");
-                                    }
-                                }
-                            }
-                        }
-                        // If action has no effects
-                        if let Val::Boolean(pass) =
-                            time_invariant.eval(&|var| self.vars[var.0 as usize].clone())
-                        {
-                            pass.then_some((*action, *post_state))
-                        } else {
-                            panic!("guard is not a boolean");
-                        }
-                    } else {
-                        Some((*action, *post_state))
-                    }
                 } else {
-                    None
+                    self.admits(*action, *post_state, guard.as_ref())
+                        .unwrap_or(true)
+                        .then_some((*action, *post_state))
                 }
             })
     }
 
+    fn admits(
+        &self,
+        action: Action,
+        post_state: Location,
+        guard: Option<&FnExpression<Var>>,
+    ) -> Result<bool, EvalError> {
+        if let Some(guard) = guard {
+            if !eval_guard(guard, &self.vars)? {
+                return Ok(false);
+            }
+        }
+        if let Ok(Some(time_invariant)) = self.def.guard(post_state, WAIT, post_state) {
+            // If action has effects
+            if action != EPSILON {
+                if let FnEffect::Effects(ref effects) = self.def.effects[action.0 as usize] {
+                    if !effects.is_empty() {
+                        // Avoid cloning variables unless it is absolutley necessary
+                        let mut vars = self.vars.clone();
+                        for (var, effect) in effects {
+                            vars[var.0 as usize] = self.def.eval_effect(*var, effect, &vars)?;
+                        }
+                        return eval_guard(time_invariant, &vars);
+                    }
+                }
+            }
+            // If action has no effects
+            eval_guard(time_invariant, &self.vars)
+        } else {
+            Ok(true)
+        }
+    }
+
     #[inline(always)]
     fn satisfies_guard(&self, action: Action, post_state: Location) -> Result<bool, PgError> {
         let transitions = &self.def.transitions[self.current_location.0 as usize];
-        transitions
+        let guard_idx = transitions
             .binary_search_by_key(&(action, post_state), |(a, p, _)| (*a, *p))
-            .map(|guard_idx| {
-                if let Some(ref guard) = transitions[guard_idx].2 {
-                    if let Val::Boolean(pass) = guard.eval(&|var| self.vars[var.0 as usize].clone())
-                    {
-                        pass
-                    } else {
-                        panic!("guard is not a boolean");
-                    }
-                } else {
-                    true
-                }
-            })
-            .map_err(|_| PgError::MissingTransition)
+            .map_err(|_| PgError::MissingTransition)?;
+        if let Some(ref guard) = transitions[guard_idx].2 {
+            eval_guard(guard, &self.vars).map_err(PgError::Eval)
+        } else {
+            Ok(true)
+        }
     }
 
     /// Executes a transition characterized by the argument action and post-state.
     ///
     /// Fails if the requested transition is not admissible,
     /// or if the post-location time invariants are violated.
+    /// If the evaluation of an effect fails, the transition is aborted
+    /// and the variables are left unchanged.
     pub fn transition(&mut self, action: Action, post_state: Location) -> Result<(), PgError> {
         if action == WAIT {
             return Err(PgError::Wait);
         } else if !self.satisfies_guard(action, post_state)? {
             return Err(PgError::UnsatisfiedGuard);
-        } else if let Ok(Some(time_invariant)) = self.def.guard(post_state, WAIT, post_state) {
-            let mut backup = Vec::with_capacity(self.vars.len());
-            // If action has effects
-            if action != EPSILON {
-                if let FnEffect::Effects(ref effects) = self.def.effects[action.0 as usize] {
-                    if !effects.is_empty() {
-                        // Clone only if necessary
-                        backup = self.vars.clone();
-                        for (var, effect) in effects {
-                            self.vars[var.0 as usize] =
-                                effect.eval(&|var| self.vars[var.0 as usize].clone());
-                        }
-                    }
-                } else {
-                    return Err(PgError::Communication(action));
-                }
-            }
+        }
+        let previous = if action != EPSILON {
+            self.apply_effects(action)?
+        } else {
+            SmallVec::new()
+        };
+        if let Ok(Some(time_invariant)) = self.def.guard(post_state, WAIT, post_state) {
             // Self::satisfies_guard should only be called after setting the post-location!
-            if let Val::Boolean(pass) =
-                time_invariant.eval(&|var| self.vars[var.0 as usize].clone())
-            {
-                if !pass {
-                    // Backup is unused if empty
-                    if action != EPSILON && !backup.is_empty() {
-                        self.vars = backup;
-                    }
-                    return Err(PgError::UnsatisfiedGuard);
-                }
-            } else {
-                panic!("guard is not a boolean");
-            }
-        } else if action != EPSILON {
-            if let FnEffect::Effects(ref effects) = self.def.effects[action.0 as usize] {
-                for (var, effect) in effects {
-                    self.vars[var.0 as usize] =
-                        effect.eval(&|var| self.vars[var.0 as usize].clone());
-                }
-            } else {
-                return Err(PgError::Communication(action));
+            let pass = eval_guard(time_invariant, &self.vars);
+            if !matches!(pass, Ok(true)) {
+                self.restore(previous);
+                return pass
+                    .map_err(PgError::Eval)
+                    .and(Err(PgError::UnsatisfiedGuard));
             }
         }
         self.current_location = post_state;
         Ok(())
     }
 
+    // Applies the effects of the action in order, returning the previous values of the assigned variables.
+    // If an effect fails to evaluate, the variables are restored to their previous values.
+    fn apply_effects(&mut self, action: Action) -> Result<SmallVec<[(Var, Val); 4]>, PgError> {
+        let FnEffect::Effects(ref effects) = self.def.effects[action.0 as usize] else {
+            return Err(PgError::Communication(action));
+        };
+        let mut previous: SmallVec<[(Var, Val); 4]> = SmallVec::with_capacity(effects.len());
+        for (var, effect) in effects {
+            match self.def.eval_effect(*var, effect, &self.vars) {
+                Ok(val) => {
+                    let prev = std::mem::replace(&mut self.vars[var.0 as usize], val);
+                    previous.push((*var, prev));
+                }
+                Err(err) => {
+                    for (var, val) in previous.into_iter().rev() {
+                        self.vars[var.0 as usize] = val;
+                    }
+                    return Err(PgError::Effect(action, *var, err));
+                }
+            }
+        }
+        Ok(previous)
+    }
+
+    // Restores the previous values of variables, as returned by `Self::apply_effects`.
+    fn restore(&mut self, previous: SmallVec<[(Var, Val); 4]>) {
+        for (var, val) in previous.into_iter().rev() {
+            self.vars[var.0 as usize] = val;
+        }
+    }
+
     /// Returns the current time of the Program Graph.
     #[inline(always)]
     pub fn time(&self) -> Time {
@@ -406,6 +509,9 @@ impl ProgramGraph {
     }
 
     pub fn wait(&mut self, delta: Time) -> Result<(), PgError> {
+        if delta > 0 && self.urgency() != Urgency::Normal {
+            return Err(PgError::Urgent(self.current_location));
+        }
         let prev_time;
         if let Val::Integer(ref mut time) = self.vars[TIME.0 as usize] {
             prev_time = *time;
@@ -414,38 +520,49 @@ impl ProgramGraph {
             panic!("Time must be an Integer variable");
         }
         let transitions = &self.def.transitions[self.current_location.0 as usize];
-        if transitions
+        let pass = transitions
             .binary_search_by_key(&(WAIT, self.current_location), |(a, p, _)| (*a, *p))
-            .map(|guard_idx| {
-                if let Some(ref guard) = transitions[guard_idx].2 {
-                    if let Val::Boolean(pass) = guard.eval(&|var| self.vars[var.0 as usize].clone())
-                    {
-                        pass
-                    } else {
-                        panic!("guard is not a boolean");
-                    }
-                } else {
-                    true
-                }
-            })
-            .unwrap_or(true)
-        {
+            .ok()
+            .and_then(|guard_idx| transitions[guard_idx].2.as_ref())
+            .map_or(Ok(true), |guard| eval_guard(guard, &self.vars));
+        if let Ok(true) = pass {
             Ok(())
         } else {
             // If the location's invariant is not satisfied,
             // reset time to original value.
             self.vars[TIME.0 as usize] = Val::Integer(prev_time);
-            Err(PgError::UnsatisfiedGuard)
+            pass.map_err(PgError::Eval)
+                .and(Err(PgError::UnsatisfiedGuard))
         }
     }
 
+    // Returns the current value of the variable.
+    #[inline(always)]
+    pub(crate) fn val(&self, var: Var) -> &Val {
+        &self.vars[var.0 as usize]
+    }
+
+    // Sets the value of the variable, which has to be of the same type.
+    // Only to be used by [`channel_system::ChannelSystem`] to share global variables among PGs.
+    #[inline(always)]
+    pub(crate) fn set_val(&mut self, var: Var, val: Val) {
+        self.vars[var.0 as usize] = val;
+    }
+
     pub(crate) fn send(&mut self, action: Action, post_state: Location) -> Result<Val, PgError> {
+        let val = self.message(action, post_state)?;
+        self.current_location = post_state;
+        Ok(val)
+    }
+
+    // Computes the message of a send transition, without executing it.
+    pub(crate) fn message(&self, action: Action, post_state: Location) -> Result<Val, PgError> {
         if !self.satisfies_guard(action, post_state)? {
             Err(PgError::UnsatisfiedGuard)
         } else if let FnEffect::Send(effect) = &self.def.effects[action.0 as usize] {
-            let val = effect.eval(&|var| self.vars[var.0 as usize].clone());
-            self.current_location = post_state;
-            Ok(val)
+            effect
+                .eval(&|var| &self.vars[var.0 as usize])
+                .map_err(PgError::Eval)
         } else {
             Err(PgError::NotSend(action))
         }
@@ -456,15 +573,65 @@ impl ProgramGraph {
         action: Action,
         post_state: Location,
         val: Val,
+    ) -> Result<(), PgError> {
+        self.check_receive(action, post_state, &val)?;
+        if let FnEffect::Receive(var) | FnEffect::SelectiveReceive(var, _) =
+            self.def.effects[action.0 as usize]
+        {
+            self.vars[var.0 as usize] = val;
+            self.current_location = post_state;
+        }
+        Ok(())
+    }
+
+    // Checks whether a receive transition would accept the given message,
+    // i.e., whether the message satisfies the receive's guard (if any).
+    pub(crate) fn accepts(&self, action: Action, val: &Val) -> Result<bool, PgError> {
+        match &self.def.effects[action.0 as usize] {
+            FnEffect::Receive(_) => Ok(true),
+            FnEffect::SelectiveReceive(var, guard) => {
+                // The guard is evaluated as if the message had already been received.
+                let pass = guard
+                    .eval(&|v| {
+                        if v == *var {
+                            val
+                        } else {
+                            &self.vars[v.0 as usize]
+                        }
+                    })
+                    .map_err(PgError::Eval)?;
+                if let Val::Boolean(pass) = pass {
+                    Ok(pass)
+                } else {
+                    panic!("guard is not a boolean");
+                }
+            }
+            _ => Err(PgError::NotReceive(action)),
+        }
+    }
+
+    // Checks that a receive transition can take place with the given value, without executing it.
+    pub(crate) fn check_receive(
+        &self,
+        action: Action,
+        post_state: Location,
+        val: &Val,
     ) -> Result<(), PgError> {
         if !self.satisfies_guard(action, post_state)? {
             Err(PgError::UnsatisfiedGuard)
-        } else if let FnEffect::Receive(var) = self.def.effects[action.0 as usize] {
-            let var_content = self.vars.get_mut(var.0 as usize).expect("variable exists");
+        } else if let FnEffect::Receive(var) | FnEffect::SelectiveReceive(var, _) =
+            self.def.effects[action.0 as usize]
+        {
+            let var_content = self.vars.get(var.0 as usize).expect("variable exists");
             if var_content.r#type() == val.r#type() {
-                *var_content = val;
-                self.current_location = post_state;
-                Ok(())
+                self.def
+                    .check_bounds(var, val)
+                    .map_err(|err| PgError::Effect(action, var, err))?;
+                if self.accepts(action, val)? {
+                    Ok(())
+                } else {
+                    Err(PgError::UnsatisfiedGuard)
+                }
             } else {
                 Err(PgError::TypeMismatch)
             }
@@ -506,6 +673,28 @@ mod tests {
         assert_eq!(pg.possible_transitions().count(), 0);
     }
 
+    #[test]
+    fn failing_effect() -> Result<(), PgError> {
+        let mut builder = ProgramGraphBuilder::new();
+        let initial = builder.initial_location();
+        let x = builder.new_var(PgExpression::from(0))?;
+        let y = builder.new_var_with_type(PgExpression::from(0), Type::Bounded(0, 1))?;
+        let action = builder.new_action();
+        builder.add_effect(action, x, PgExpression::from(1))?;
+        builder.add_effect(action, y, PgExpression::from(2))?;
+        let r#final = builder.new_location();
+        builder.add_transition(initial, action, r#final, None)?;
+        let mut pg = builder.build();
+        assert!(matches!(
+            pg.transition(action, r#final),
+            Err(PgError::Effect(_, var, EvalError::OutOfRange(2, 0, 1))) if var == y
+        ));
+        // No effect is applied if one of them fails.
+        assert_eq!(pg.val(x), &Val::Integer(0));
+        assert_eq!(pg.current_location(), initial);
+        Ok(())
+    }
+
     #[test]
     fn program_graph() -> Result<(), PgError> {
         // Create Program Graph
@@ -1,52 +1,73 @@
 use super::{
     Action, Clock, FnEffect, FnExpression, Location, PgError, PgExpression, ProgramGraph,
-    TimeConstraint, Var, EPSILON, TIME, WAIT,
+    TimeConstraint, Urgency, Var, EPSILON, TIME, WAIT,
 };
 use crate::{
-    grammar::{Type, Val},
+    grammar::{Expected, Overflow, Type, TypeError, TypeErrorKind, Val},
     program_graph::ProgramGraphDef,
     Integer,
 };
 // use ahash::{AHashMap as HashMap, AHashSet as HashSet};
 use hashbrown::HashMap;
-use log::info;
+use log::{info, warn};
 use std::sync::Arc;
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 enum Effect {
     Effects(Vec<(Var, PgExpression)>),
     Send(PgExpression),
     Receive(Var),
+    SelectiveReceive(Var, PgExpression),
 }
 
-impl From<Effect> for FnEffect {
-    fn from(value: Effect) -> Self {
-        match value {
+impl Effect {
+    fn compile(self, overflow: Overflow) -> FnEffect {
+        match self {
             Effect::Effects(effects) => {
                 let mut effects = effects
                     .into_iter()
                     .map(|(var, expr)| -> (Var, FnExpression<Var>) {
-                        (var, FnExpression::<Var>::from(expr))
+                        (
+                            var,
+                            FnExpression::new(expr.simplify_with_overflow(overflow), overflow),
+                        )
                     })
                     .collect::<Vec<_>>();
                 effects.shrink_to_fit();
                 FnEffect::Effects(effects)
             }
-            Effect::Send(msg) => FnEffect::Send(msg.into()),
+            Effect::Send(msg) => FnEffect::Send(FnExpression::new(
+                msg.simplify_with_overflow(overflow),
+                overflow,
+            )),
             Effect::Receive(var) => FnEffect::Receive(var),
+            Effect::SelectiveReceive(var, guard) => FnEffect::SelectiveReceive(
+                var,
+                FnExpression::new(guard.simplify_with_overflow(overflow), overflow),
+            ),
         }
     }
 }
 
 /// Defines and builds a PG.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ProgramGraphBuilder {
     // Effects are indexed by actions
     effects: Vec<Effect>,
     // Transitions are indexed by locations
     // We can assume there is at most one condition by logical disjunction
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_seq::nested"))]
     transitions: Vec<HashMap<(Action, Location), Option<PgExpression>>>,
     vars: Vec<Val>,
+    // Declared types of variables, if they constrain their values
+    // (i.e., if they contain bounded integers)
+    bounds: Vec<Option<Type>>,
+    // Urgency of locations, if not normal
+    #[cfg_attr(feature = "serde", serde(default, with = "crate::serde_seq"))]
+    urgencies: HashMap<Location, Urgency>,
+    overflow: Overflow,
 }
 
 impl Default for ProgramGraphBuilder {
@@ -65,7 +86,10 @@ impl ProgramGraphBuilder {
         let mut pgb = Self {
             effects: Vec::new(),
             vars: Vec::new(),
+            bounds: Vec::new(),
             transitions: Vec::new(),
+            urgencies: HashMap::new(),
+            overflow: Overflow::default(),
         };
         // Create an initial location and make sure it is equal to the constant `Self::INITIAL_LOCATION`
         // This is the simplest way to make sure the state of the builder is always consistent
@@ -78,6 +102,14 @@ impl ProgramGraphBuilder {
         pgb
     }
 
+    /// Sets how integer overflows behave in the expressions of the PG
+    /// (by default, overflows are [`Overflow::Checked`]).
+    ///
+    /// It also applies to the initial values of variables added afterwards.
+    pub fn set_overflow(&mut self, overflow: Overflow) {
+        self.overflow = overflow;
+    }
+
     /// Gets the initial location of the PG.
     /// This is created toghether with the [`ProgramGraphBuilder`] by default.
     pub fn initial_location(&self) -> Location {
@@ -108,19 +140,60 @@ impl ProgramGraphBuilder {
     ///     .expect_err("expression is badly-typed");
     /// ```
     pub fn new_var(&mut self, init: PgExpression) -> Result<Var, PgError> {
+        self.push_var(init, None)
+    }
+
+    /// Adds a new variable with the given initial value and declared type to the PG.
+    ///
+    /// If the type contains [`Type::Bounded`] integers,
+    /// assigning the variable a value out of range makes the transition fail.
+    ///
+    /// It fails if the expression giving the initial value of the variable is not well-typed,
+    /// if its type does not match the declared one, or if the initial value is out of range.
+    ///
+    /// ```
+    /// # use scan_core::{Type, program_graph::{PgExpression, ProgramGraphBuilder}};
+    /// # let mut pg_builder = ProgramGraphBuilder::new();
+    /// pg_builder
+    ///     .new_var_with_type(PgExpression::from(1), Type::Bounded(0, 255))
+    ///     .expect("initial value is in range");
+    /// pg_builder
+    ///     .new_var_with_type(PgExpression::from(-1), Type::Bounded(0, 255))
+    ///     .expect_err("initial value is out of range");
+    /// ```
+    pub fn new_var_with_type(&mut self, init: PgExpression, r#type: Type) -> Result<Var, PgError> {
+        let found = init.r#type().map_err(PgError::Type)?;
+        if found != r#type.unbounded() {
+            let kind = TypeErrorKind::Mismatch {
+                expected: Expected::Type(r#type.unbounded()),
+                found,
+            };
+            return Err(PgError::Type(kind.into()));
+        }
+        self.push_var(init, r#type.is_bounded().then_some(r#type))
+    }
+
+    fn push_var(&mut self, init: PgExpression, bounds: Option<Type>) -> Result<Var, PgError> {
         let idx = self.vars.len();
         // We check the type to make sure the expression is well-formed
         let _ = init.r#type().map_err(PgError::Type)?;
         init.context(&|var| self.vars.get(var.0 as usize).map(Val::r#type))
             .map_err(PgError::Type)?;
-        let val = FnExpression::from(init).eval(&|var| self.vars[var.0 as usize].clone());
+        let val = FnExpression::new(init, self.overflow)
+            .eval(&|var| &self.vars[var.0 as usize])
+            .map_err(PgError::Eval)?;
+        if let Some(ref bounds) = bounds {
+            bounds.check_bounds(&val).map_err(PgError::Eval)?;
+        }
         self.vars.push(val);
+        self.bounds.push(bounds);
         Ok(Var(idx as u16))
     }
 
     pub fn new_clock(&mut self) -> Clock {
         let idx = self.vars.len();
         self.vars.push(Val::Integer(0));
+        self.bounds.push(None);
         Clock(idx as u16)
     }
 
@@ -168,7 +241,8 @@ impl ProgramGraphBuilder {
             .get(var.0 as usize)
             .map(Val::r#type)
             .ok_or_else(|| PgError::MissingVar(var.to_owned()))?;
-        if var_type == effect.r#type().map_err(PgError::Type)? {
+        let found = effect.r#type().map_err(PgError::Type)?;
+        if var_type == found {
             match self
                 .effects
                 .get_mut(action.0 as usize)
@@ -179,10 +253,17 @@ impl ProgramGraphBuilder {
                     Ok(())
                 }
                 Effect::Send(_) => Err(PgError::EffectOnSend),
-                Effect::Receive(_) => Err(PgError::EffectOnReceive),
+                Effect::Receive(_) | Effect::SelectiveReceive(..) => Err(PgError::EffectOnReceive),
             }
         } else {
-            Err(PgError::TypeMismatch)
+            Err(PgError::Type(TypeError {
+                kind: TypeErrorKind::Mismatch {
+                    expected: Expected::Type(var_type),
+                    found,
+                },
+                path: Vec::new(),
+                var: Some(var.to_string()),
+            }))
         }
     }
 
@@ -221,6 +302,35 @@ impl ProgramGraphBuilder {
         }
     }
 
+    // The guard is evaluated on the message as if it had already been written onto `var`.
+    pub(crate) fn new_selective_receive(
+        &mut self,
+        var: Var,
+        guard: PgExpression,
+    ) -> Result<Action, PgError> {
+        if self.vars.len() as u16 <= var.0 {
+            Err(PgError::MissingVar(var.to_owned()))
+        } else {
+            match guard.r#type().map_err(PgError::Type)? {
+                Type::Boolean => {}
+                found => {
+                    let kind = TypeErrorKind::Mismatch {
+                        expected: Expected::Type(Type::Boolean),
+                        found,
+                    };
+                    return Err(PgError::Type(kind.into()));
+                }
+            }
+            guard
+                .context(&|var| self.vars.get(var.0 as usize).map(Val::r#type))
+                .map_err(PgError::Type)?;
+            // Actions are indexed progressively
+            let idx = self.effects.len();
+            self.effects.push(Effect::SelectiveReceive(var, guard));
+            Ok(Action(idx as u16))
+        }
+    }
+
     /// Adds a new location to the PG.
     pub fn new_location(&mut self) -> Location {
         // Locations are indexed progressively
@@ -229,6 +339,47 @@ impl ProgramGraphBuilder {
         Location(idx as u16)
     }
 
+    /// Adds a new location with the given [`Urgency`] to the PG.
+    ///
+    /// ```
+    /// # use scan_core::program_graph::{ProgramGraphBuilder, Urgency};
+    /// # let mut pg_builder = ProgramGraphBuilder::new();
+    /// let initial = pg_builder.initial_location();
+    /// let urgent = pg_builder.new_location_with_urgency(Urgency::Urgent);
+    /// let action = pg_builder.new_action();
+    /// pg_builder
+    ///     .add_transition(initial, action, urgent, None)
+    ///     .expect("this transition can be added");
+    /// let mut pg = pg_builder.build();
+    ///
+    /// // Time can elapse in the initial location, which is not urgent
+    /// pg.wait(1).expect("time can elapse");
+    /// pg.transition(action, urgent).expect("transition is possible");
+    /// pg.wait(1).expect_err("time cannot elapse in an urgent location");
+    /// ```
+    pub fn new_location_with_urgency(&mut self, urgency: Urgency) -> Location {
+        let loc = self.new_location();
+        self.set_urgency(loc, urgency)
+            .expect("location has just been created");
+        loc
+    }
+
+    /// Sets the [`Urgency`] of a location of the PG.
+    ///
+    /// Fails if the location does not belong to the PG.
+    pub fn set_urgency(&mut self, location: Location, urgency: Urgency) -> Result<(), PgError> {
+        if self.transitions.len() as u16 <= location.0 {
+            Err(PgError::MissingLocation(location))
+        } else {
+            if urgency == Urgency::Normal {
+                self.urgencies.remove(&location);
+            } else {
+                self.urgencies.insert(location, urgency);
+            }
+            Ok(())
+        }
+    }
+
     /// TODO
     pub fn new_timed_location(&mut self, invariants: &[TimeConstraint]) -> Location {
         // Locations are indexed progressively
@@ -240,6 +391,38 @@ impl ProgramGraphBuilder {
         loc
     }
 
+    /// Adds a new location to the PG, with an invariant given by an arbitrary boolean expression,
+    /// which is required to hold when entering the location and while time elapses in it.
+    ///
+    /// Fails if the invariant is not a boolean expression.
+    ///
+    /// ```
+    /// # use scan_core::program_graph::{PgExpression, ProgramGraphBuilder};
+    /// # let mut pg_builder = ProgramGraphBuilder::new();
+    /// let clock = pg_builder.new_clock();
+    /// let var = pg_builder.new_var(PgExpression::from(2)).expect("expression is well-typed");
+    ///
+    /// // Time can elapse at most for as long as the value of `var`
+    /// let invariant = PgExpression::LessEq(Box::new((clock.elapsed(), PgExpression::Var(var, scan_core::Type::Integer))));
+    /// pg_builder
+    ///     .new_location_with_invariant(invariant)
+    ///     .expect("the invariant is a boolean expression");
+    /// pg_builder
+    ///     .new_location_with_invariant(clock.elapsed())
+    ///     .expect_err("the invariant is not a boolean expression");
+    /// ```
+    pub fn new_location_with_invariant(
+        &mut self,
+        invariant: PgExpression,
+    ) -> Result<Location, PgError> {
+        let loc = self.new_location();
+        self.add_transition(loc, WAIT, loc, Some(invariant))
+            .inspect_err(|_| {
+                self.transitions.pop();
+            })?;
+        Ok(loc)
+    }
+
     /// Adds a transition to the PG.
     /// Requires specifying:
     ///
@@ -282,13 +465,18 @@ impl ProgramGraphBuilder {
         } else if action != EPSILON && action != WAIT && self.effects.len() as u16 <= action.0 {
             // Check 'action' exists
             Err(PgError::MissingAction(action))
-        } else if guard
-            .as_ref()
-            .is_some_and(|guard| !matches!(guard.r#type(), Ok(Type::Boolean)))
-        {
-            Err(PgError::TypeMismatch)
         } else {
             if let Some(guard) = guard.clone() {
+                match guard.r#type().map_err(PgError::Type)? {
+                    Type::Boolean => {}
+                    found => {
+                        let kind = TypeErrorKind::Mismatch {
+                            expected: Expected::Type(Type::Boolean),
+                            found,
+                        };
+                        return Err(PgError::Type(kind.into()));
+                    }
+                }
                 guard
                     .context(&|var| self.vars.get(var.0 as usize).map(Val::r#type))
                     .map_err(PgError::Type)?;
@@ -326,24 +514,12 @@ impl ProgramGraphBuilder {
                 let lower_bound = lower_bound.map(|lower_bound| {
                     PgExpression::LessEq(Box::new((
                         PgExpression::Const(Val::Integer(lower_bound as Integer)),
-                        PgExpression::Sum(vec![
-                            PgExpression::Var(Var(TIME.0), Type::Integer),
-                            PgExpression::Opposite(Box::new(PgExpression::Var(
-                                Var(clock.0),
-                                Type::Integer,
-                            ))),
-                        ]),
+                        clock.elapsed(),
                     )))
                 });
                 let upper_bound = upper_bound.map(|upper_bound| {
                     PgExpression::LessEq(Box::new((
-                        PgExpression::Sum(vec![
-                            PgExpression::Var(Var(TIME.0), Type::Integer),
-                            PgExpression::Opposite(Box::new(PgExpression::Var(
-                                Var(clock.0),
-                                Type::Integer,
-                            ))),
-                        ]),
+                        clock.elapsed(),
                         PgExpression::Const(Val::Integer(upper_bound as Integer)),
                     )))
                 });
@@ -397,11 +573,48 @@ impl ProgramGraphBuilder {
         self.add_timed_transition(pre, EPSILON, post, guard, constraints)
     }
 
+    /// Returns the transitions (as triples of pre-location, action and post-location)
+    /// whose guard simplifies to `false`, meaning they can never be taken.
+    ///
+    /// ```
+    /// # use scan_core::program_graph::{PgExpression, ProgramGraphBuilder};
+    /// # let mut pg_builder = ProgramGraphBuilder::new();
+    /// let initial_loc = pg_builder.initial_location();
+    /// let action = pg_builder.new_action();
+    /// let guard = PgExpression::and(vec![PgExpression::from(true), PgExpression::from(false)]);
+    /// pg_builder.add_transition(initial_loc, action, initial_loc, Some(guard)).unwrap();
+    /// assert_eq!(pg_builder.dead_transitions(), vec![(initial_loc, action, initial_loc)]);
+    /// ```
+    pub fn dead_transitions(&self) -> Vec<(Location, Action, Location)> {
+        let mut dead = Vec::new();
+        for (pre, transitions) in self.transitions.iter().enumerate() {
+            for ((action, post), guard) in transitions {
+                if guard.as_ref().is_some_and(|guard| {
+                    matches!(
+                        guard.clone().simplify_with_overflow(self.overflow),
+                        PgExpression::Const(Val::Boolean(false))
+                    )
+                }) {
+                    dead.push((Location(pre as u16), *action, *post));
+                }
+            }
+        }
+        dead.sort_unstable_by_key(|(pre, action, post)| (*pre, *action, *post));
+        dead
+    }
+
     /// Produces a [`ProgramGraph`] defined by the [`ProgramGraphBuilder`]'s data and consuming it.
     ///
+    /// Guards and effects are simplified before being compiled,
+    /// and transitions whose guard simplifies to `false` are reported as warnings
+    /// (see also [`Self::dead_transitions`]).
+    ///
     /// Since the construction of the builder is already checked ad every step,
     /// this method cannot fail.
     pub fn build(mut self) -> ProgramGraph {
+        for (pre, action, post) in self.dead_transitions() {
+            warn!("transition from {pre:?} to {post:?} with {action:?} can never be taken, as its guard is always false");
+        }
         // Since vectors of effects and transitions will become unmutable,
         // they should be shrunk to take as little space as possible
         self.effects.shrink_to_fit();
@@ -410,10 +623,13 @@ impl ProgramGraphBuilder {
             .transitions
             .into_iter()
             .map(|v| {
-                let mut trans = Vec::from_iter(
-                    v.into_iter()
-                        .map(|((a, p), g)| (a, p, g.map(FnExpression::from))),
-                );
+                let mut trans = Vec::from_iter(v.into_iter().map(|((a, p), g)| {
+                    // A guard that is always true is as good as no guard at all.
+                    let g = g
+                        .map(|g| g.simplify_with_overflow(self.overflow))
+                        .filter(|g| !matches!(g, PgExpression::Const(Val::Boolean(true))));
+                    (a, p, g.map(|g| FnExpression::new(g, self.overflow)))
+                }));
                 trans.sort_unstable_by_key(|(a, p, _)| (*a, *p));
                 trans.shrink_to_fit();
                 trans
@@ -422,6 +638,7 @@ impl ProgramGraphBuilder {
         // Vars are not going to be unmutable,
         // but their number will be constant anyway
         self.vars.shrink_to_fit();
+        self.bounds.shrink_to_fit();
         // Build program graph
         info!(
             "create Program Graph with:\n{} locations\n{} actions\n{} vars",
@@ -429,9 +646,19 @@ impl ProgramGraphBuilder {
             self.effects.len(),
             self.vars.len()
         );
+        let mut urgencies = vec![Urgency::Normal; transitions.len()];
+        for (location, urgency) in self.urgencies {
+            urgencies[location.0 as usize] = urgency;
+        }
         let def = ProgramGraphDef {
-            effects: self.effects.into_iter().map(FnEffect::from).collect(),
+            effects: self
+                .effects
+                .into_iter()
+                .map(|effect| effect.compile(self.overflow))
+                .collect(),
             transitions,
+            urgencies,
+            bounds: self.bounds,
         };
         ProgramGraph {
             current_location: Self::INITIAL,
@@ -0,0 +1,77 @@
+//! (De)serialization of maps as sequences of key-value pairs,
+//! since formats such as JSON only admit strings as keys of maps.
+//! Pairs are sorted by key, so that equal maps are always serialized the same way.
+//!
+//! To be used through `#[serde(with = "scan_core::serde_seq")]`,
+//! or `#[serde(with = "scan_core::serde_seq::nested")]` for vectors of maps.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Serializes a map as a sequence of key-value pairs, sorted by key.
+pub fn serialize<'a, M, K, V, S>(map: &'a M, serializer: S) -> Result<S::Ok, S::Error>
+where
+    &'a M: IntoIterator<Item = (&'a K, &'a V)>,
+    K: Serialize + Ord + 'a,
+    V: Serialize + 'a,
+    S: Serializer,
+{
+    let mut pairs = map.into_iter().collect::<Vec<_>>();
+    pairs.sort_unstable_by_key(|(key, _)| *key);
+    serializer.collect_seq(pairs)
+}
+
+/// Deserializes a map from a sequence of key-value pairs.
+pub fn deserialize<'de, M, K, V, D>(deserializer: D) -> Result<M, D::Error>
+where
+    M: FromIterator<(K, V)>,
+    K: Deserialize<'de>,
+    V: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    Vec::<(K, V)>::deserialize(deserializer).map(|pairs| pairs.into_iter().collect())
+}
+
+/// (De)serialization of vectors of maps as sequences of sequences of key-value pairs.
+pub mod nested {
+    use super::*;
+
+    // Serializes a map as a sequence.
+    struct Seq<'a, M>(&'a M);
+
+    impl<'a, M, K, V> Serialize for Seq<'a, M>
+    where
+        &'a M: IntoIterator<Item = (&'a K, &'a V)>,
+        K: Serialize + Ord + 'a,
+        V: Serialize + 'a,
+    {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            super::serialize(self.0, serializer)
+        }
+    }
+
+    /// Serializes a map as a sequence of key-value pairs, sorted by key.
+    pub fn serialize<'a, M, K, V, S>(maps: &'a [M], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        &'a M: IntoIterator<Item = (&'a K, &'a V)>,
+        K: Serialize + Ord + 'a,
+        V: Serialize + 'a,
+        S: Serializer,
+    {
+        serializer.collect_seq(maps.iter().map(Seq))
+    }
+
+    /// Deserializes each map from a sequence of key-value pairs.
+    pub fn deserialize<'de, M, K, V, D>(deserializer: D) -> Result<Vec<M>, D::Error>
+    where
+        M: FromIterator<(K, V)>,
+        K: Deserialize<'de>,
+        V: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        Vec::<Vec<(K, V)>>::deserialize(deserializer).map(|maps| {
+            maps.into_iter()
+                .map(|pairs| pairs.into_iter().collect())
+                .collect()
+        })
+    }
+}
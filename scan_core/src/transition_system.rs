@@ -1,5 +1,5 @@
 use crate::{Pmtl, PmtlOracle, Time};
-use log::{info, trace};
+use log::{error, info, trace};
 use rand::prelude::*;
 use rayon::prelude::*;
 use std::fmt::Debug;
@@ -7,6 +7,7 @@ use std::hash::Hash;
 use std::sync::{Arc, Mutex};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Atom<A: Clone + PartialEq + Eq> {
     Predicate(usize),
     Event(A),
@@ -31,6 +32,9 @@ pub trait TransitionSystem: Clone + Send + Sync {
     /// The type of the actions that trigger transitions between states in the TS.
     type Action: Debug + Clone + Eq + Send + Sync + Hash;
 
+    /// The type of the errors that can occur while executing the TS.
+    type Err: std::error::Error + Send + Sync + 'static;
+
     /// The label function of the TS valuates the propositions of its set of propositions for the current state.
     // TODO FIXME: bitset instead of Vec<bool>?
     fn labels(&self) -> Vec<bool>;
@@ -38,16 +42,24 @@ pub trait TransitionSystem: Clone + Send + Sync {
     // The transition relation relates [`Self::Action`]s and post-states that constitutes possible transitions from the current state.
     // fn transitions(self) -> Vec<(Self::Action, Self)>;
 
+    /// Executes a random transition, if any is possible before `max_time`.
+    ///
+    /// Fails if the execution of the transition fails, e.g., because evaluating an expression failed.
     fn montecarlo_transition<R: Rng>(
         &mut self,
         rng: &mut R,
         max_time: Time,
-    ) -> Option<Self::Action>;
+    ) -> Result<Option<Self::Action>, Self::Err>;
 
     fn time(&self) -> Time {
         0
     }
 
+    /// Concludes a run of the TS, e.g., by collecting statistics about it.
+    ///
+    /// It is called at the end of every [`Self::experiment`], whatever its outcome.
+    fn conclude_run(&mut self) {}
+
     fn experiment<P>(
         mut self,
         mut oracle: PmtlOracle<Self::Action>,
@@ -55,7 +67,7 @@ pub trait TransitionSystem: Clone + Send + Sync {
         length: usize,
         duration: Time,
         run_state: Arc<Mutex<(u32, u32, bool)>>,
-    ) -> Option<bool>
+    ) -> Result<Option<bool>, Self::Err>
     where
         P: Publisher<Self::Action>,
     {
@@ -68,7 +80,18 @@ pub trait TransitionSystem: Clone + Send + Sync {
             publisher.init();
         }
         trace!("new run starting");
-        while let Some(action) = self.montecarlo_transition(rng, duration) {
+        let result = loop {
+            let action = match self.montecarlo_transition(rng, duration) {
+                Ok(Some(action)) => action,
+                Ok(None) => {
+                    trace!("run succeeds");
+                    break Ok(Some(true));
+                }
+                Err(err) => {
+                    trace!("run errors");
+                    break Err(err);
+                }
+            };
             current_len += 1;
             let state = self.labels();
             let time = self.time();
@@ -80,41 +103,30 @@ pub trait TransitionSystem: Clone + Send + Sync {
                 Some(true) => {
                     if current_len >= length {
                         trace!("run exceeds maximum lenght");
-                        if let Some(publisher) = publisher {
-                            publisher.finalize(None);
-                        }
-                        return None;
+                        break Ok(None);
                     }
                 }
                 Some(false) => {
                     trace!("run fails");
-                    if let Some(publisher) = publisher {
-                        publisher.finalize(Some(false));
-                    }
-                    return Some(false);
+                    break Ok(Some(false));
                 }
                 None => {
                     trace!("run undetermined");
-                    if let Some(publisher) = publisher {
-                        publisher.finalize(None);
-                    }
-                    return None;
+                    break Ok(None);
                 }
             }
             if !run_state.lock().expect("lock state").2 {
-                if let Some(publisher) = publisher {
-                    publisher.finalize(None);
-                }
-                return None;
+                break Ok(None);
             }
-        }
-        trace!("run succeeds");
+        };
+        self.conclude_run();
         if let Some(publisher) = publisher {
-            publisher.finalize(Some(true));
+            publisher.finalize(result.as_ref().ok().copied().flatten());
         }
-        Some(true)
+        result
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn par_adaptive<P>(
         &self,
         guarantees: &[Pmtl<Atom<Self::Action>>],
@@ -125,11 +137,14 @@ pub trait TransitionSystem: Clone + Send + Sync {
         duration: Time,
         publisher: Option<P>,
         state: Arc<Mutex<(u32, u32, bool)>>,
-    ) where
+    ) -> Result<(), Self::Err>
+    where
         P: Publisher<Self::Action> + Clone + Send + Sync,
     {
         info!("verification starting");
         let oracle = PmtlOracle::new(assumes, guarantees);
+        // The first error occurred in a run, which stops the verification.
+        let failure = Mutex::new(None);
         // WARN FIXME TODO: Implement algorithm for 2.4 Distributed sample generation in Budde et al.
         (0..usize::MAX)
             .into_par_iter()
@@ -144,6 +159,15 @@ pub trait TransitionSystem: Clone + Send + Sync {
                 );
                 let (s, f, running) = &mut *state.lock().expect("lock state");
                 if *running {
+                    let result = match result {
+                        Ok(result) => result,
+                        Err(err) => {
+                            error!("run failed: {err}");
+                            *running = false;
+                            failure.lock().expect("lock failure").get_or_insert(err);
+                            return false;
+                        }
+                    };
                     if let Some(result) = result {
                         if result {
                             *s += 1;
@@ -168,6 +192,10 @@ pub trait TransitionSystem: Clone + Send + Sync {
             })
             .count();
         info!("verification terminating");
+        failure
+            .into_inner()
+            .expect("lock failure")
+            .map_or(Ok(()), Err)
     }
 }
 
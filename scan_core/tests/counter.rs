@@ -31,3 +31,76 @@ fn counter_pg() -> Result<(), PgError> {
     }
     Ok(())
 }
+
+#[test]
+fn counter_pg_parsed() -> Result<(), PgError> {
+    let mut pg = ProgramGraphBuilder::new();
+    let initial = pg.initial_location();
+    let action = pg.new_action();
+    let var = pg.new_var(Expression::from(0))?;
+    let mut resolver = Resolver::new();
+    resolver.insert("counter", var, Type::Integer);
+    pg.add_effect(action, var, resolver.parse("counter + 1").unwrap())?;
+    pg.add_transition(
+        initial,
+        action,
+        initial,
+        Some(resolver.parse("counter < 10").unwrap()),
+    )?;
+    let mut pg = pg.build();
+    let mut steps = 0;
+    while let Some((act, post)) = pg.possible_transitions().last() {
+        pg.transition(act, post)?;
+        steps += 1;
+    }
+    assert_eq!(steps, 10);
+    Ok(())
+}
+
+#[test]
+fn counter_overflow() -> Result<(), PgError> {
+    for overflow in [Overflow::Checked, Overflow::Saturating, Overflow::Wrapping] {
+        let mut pg = ProgramGraphBuilder::new();
+        pg.set_overflow(overflow);
+        let initial = pg.initial_location();
+        let action = pg.new_action();
+        let var = pg.new_var(Expression::from(Integer::MAX - 1))?;
+        let mut resolver = Resolver::new();
+        resolver.insert("counter", var, Type::Integer);
+        pg.add_effect(action, var, resolver.parse("counter + 1").unwrap())?;
+        pg.add_transition(initial, action, initial, None)?;
+        let mut pg = pg.build();
+        pg.transition(action, initial)?;
+        let result = pg.transition(action, initial);
+        if overflow == Overflow::Checked {
+            assert!(matches!(
+                result,
+                Err(PgError::Effect(act, failed, EvalError::Overflow)) if act == action && failed == var
+            ));
+        } else {
+            result?;
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn counter_bounded() -> Result<(), PgError> {
+    let mut pg = ProgramGraphBuilder::new();
+    let initial = pg.initial_location();
+    let action = pg.new_action();
+    let var = pg.new_var_with_type(Expression::from(0), Type::Bounded(0, 9))?;
+    let mut resolver = Resolver::new();
+    resolver.insert("counter", var, Type::Bounded(0, 9));
+    pg.add_effect(action, var, resolver.parse("counter + 1").unwrap())?;
+    pg.add_transition(initial, action, initial, None)?;
+    let mut pg = pg.build();
+    for _ in 0..9 {
+        pg.transition(action, initial)?;
+    }
+    assert!(matches!(
+        pg.transition(action, initial),
+        Err(PgError::Effect(act, failed, EvalError::OutOfRange(10, 0, 9))) if act == action && failed == var
+    ));
+    Ok(())
+}
@@ -0,0 +1,88 @@
+#![cfg(feature = "serde")]
+
+use rand::{rngs::SmallRng, SeedableRng};
+use scan_core::{channel_system::*, *};
+
+// A producer sending increasing counters (and a greeting) to a consumer.
+fn model() -> Result<CsModelDef, CsError> {
+    let mut cs = ChannelSystemBuilder::new();
+    let producer = cs.new_program_graph();
+    let consumer = cs.new_program_graph();
+    let counters = cs.new_channel(Type::Integer, Some(2));
+    let greetings = cs.new_channel(Type::String, None);
+
+    let initial = cs.initial_location(producer)?;
+    let counter = cs.new_var_with_type(producer, Expression::from(0), Type::Bounded(0, 100))?;
+    let increment = cs.new_action(producer)?;
+    cs.add_effect(
+        producer,
+        increment,
+        counter,
+        Expression::Sum(vec![
+            Expression::Var(counter, Type::Integer),
+            Expression::from(1),
+        ]),
+    )?;
+    let send = cs.new_send(producer, counters, Expression::Var(counter, Type::Integer))?;
+    let greet = cs.new_send(
+        producer,
+        greetings,
+        Expression::Concat(vec![Expression::from("hello "), Expression::from("world")]),
+    )?;
+    let sent = cs.new_location(producer)?;
+    cs.add_transition(producer, initial, send, sent, None)?;
+    cs.add_transition(producer, sent, greet, sent, None)?;
+    cs.add_transition(
+        producer,
+        sent,
+        increment,
+        initial,
+        Some(Expression::Less(Box::new((
+            Expression::Var(counter, Type::Integer),
+            Expression::from(100),
+        )))),
+    )?;
+
+    let initial = cs.initial_location(consumer)?;
+    let received = cs.new_var(consumer, Expression::from(0))?;
+    let receive = cs.new_receive(consumer, counters, received)?;
+    cs.add_transition(consumer, initial, receive, initial, None)?;
+
+    Ok(CsModelDef {
+        cs,
+        ports: vec![(counters, Val::Integer(0))],
+        predicates: vec![Expression::Equal(Box::new((
+            Expression::Mod(Box::new((
+                Expression::Var(counters, Type::Integer),
+                Expression::from(2),
+            ))),
+            Expression::from(0),
+        )))],
+    })
+}
+
+#[test]
+fn roundtrip() -> Result<(), CsError> {
+    let def = model()?;
+    let json = serde_json::to_string(&def).expect("serialize");
+    let reloaded: CsModelDef = serde_json::from_str(&json).expect("deserialize");
+    assert_eq!(def.predicates, reloaded.predicates);
+    assert_eq!(def.ports, reloaded.ports);
+
+    let mut original = def.build();
+    let mut reloaded = reloaded.build();
+    let mut rng = SmallRng::seed_from_u64(0);
+    let mut rng_reloaded = SmallRng::seed_from_u64(0);
+    for _ in 0..1000 {
+        let event = original.montecarlo_transition(&mut rng, Time::MAX)?;
+        assert_eq!(
+            event,
+            reloaded.montecarlo_transition(&mut rng_reloaded, Time::MAX)?
+        );
+        assert_eq!(original.labels(), reloaded.labels());
+        if event.is_none() {
+            break;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,1140 @@
+//! Model builder for JANI models.
+//!
+//! The whole automata network is encoded into a single program graph,
+//! so that synchronisations and global variables are handled atomically:
+//!
+//! - the current location of each automaton is stored in an integer variable;
+//! - every combination of edges that can be taken together
+//!   (i.e., every silent edge, and every combination of edges matching a synchronisation vector)
+//!   becomes a guarded action of the program graph,
+//!   replicated according to the probabilities of its destinations;
+//! - after each step, the verdicts of the properties are published on a channel,
+//!   so that predicates can observe them.
+
+use crate::spec::{Assignment, Destination, Edge, Expr, Model, VariableDecl};
+use anyhow::{anyhow, bail, Context};
+use log::{info, warn};
+use scan_core::{channel_system::*, program_graph::Var as PgVar, *};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// The model types that can be built.
+const SUPPORTED_TYPES: [&str; 5] = ["lts", "dtmc", "mdp", "ta", "pta"];
+
+/// The largest denominator used to represent the probabilities of the destinations of an edge.
+const MAX_DENOMINATOR: u32 = 100;
+
+// Verdicts of a property along a run.
+const PENDING: Integer = 0;
+const SATISFIED: Integer = 1;
+const VIOLATED: Integer = 2;
+
+/// A model loaded from a JANI file,
+/// together with the names used in the specification.
+#[derive(Debug, Clone)]
+pub struct JaniModel {
+    pub model: CsModel,
+    /// The names of the properties that have been translated into guarantees, in the same order.
+    pub properties: Vec<String>,
+    pub guarantees: Vec<Pmtl<Atom<Event>>>,
+    pub assumes: Vec<Pmtl<Atom<Event>>>,
+    // Associates each PG with the name of the model it encodes.
+    pub pg_names: HashMap<PgId, String>,
+    // Associates the variables of each PG with their names.
+    pub vars: HashMap<(PgId, PgVar), String>,
+    /// The largest time bound among the properties, if any.
+    ///
+    /// Runs have to last longer than this for the verdicts to be decided.
+    pub time_bound: Option<Time>,
+}
+
+// A time bound, and whether it is exclusive.
+type Bound = Option<(Time, bool)>;
+
+// What an identifier refers to.
+#[derive(Debug, Clone)]
+enum Entry {
+    Var(Var, Type),
+    Clock(Clock),
+    Const(Val),
+    // Transient variables are not supported, and their assignments are dropped.
+    Transient,
+}
+
+enum JaniType {
+    Basic(Type),
+    Clock,
+}
+
+// An instance of an automaton in the composition.
+struct Instance {
+    location: Var,
+    locations: HashMap<String, Integer>,
+    scope: HashMap<String, Entry>,
+}
+
+// A property translated into conditions over the current state.
+struct Objective {
+    status: Var,
+    satisfied: CsExpression,
+    violated: CsExpression,
+    // The verdict when the network deadlocks.
+    deadlock: Integer,
+}
+
+pub(crate) struct ModelBuilder<'a> {
+    spec: &'a Model,
+    cs: ChannelSystemBuilder,
+    pg_id: PgId,
+    overflow: Overflow,
+    globals: HashMap<String, Entry>,
+    instances: Vec<Instance>,
+    vars: HashMap<(PgId, PgVar), String>,
+    // Variables holding intermediate values of simultaneous assignments, by type.
+    temps: HashMap<Type, Vec<Var>>,
+    timed: bool,
+    // A clock that is never reset, measuring the time elapsed since the beginning of the run.
+    time: Option<Clock>,
+}
+
+impl<'a> ModelBuilder<'a> {
+    pub(crate) fn build(spec: &'a Model, overflow: Overflow) -> anyhow::Result<JaniModel> {
+        if spec.jani_version != 1 {
+            bail!("unsupported JANI version {}", spec.jani_version);
+        }
+        if !SUPPORTED_TYPES.contains(&spec.model_type.as_str()) {
+            bail!(
+                "unsupported model type '{}' (supported types are {})",
+                spec.model_type,
+                SUPPORTED_TYPES.join(", ")
+            );
+        }
+        check_restrict_initial(spec.restrict_initial.as_ref())?;
+        let mut cs = ChannelSystemBuilder::new();
+        cs.set_overflow(overflow);
+        let pg_id = cs.new_program_graph();
+        let mut builder = ModelBuilder {
+            spec,
+            cs,
+            pg_id,
+            overflow,
+            globals: HashMap::new(),
+            instances: Vec::new(),
+            vars: HashMap::new(),
+            temps: HashMap::new(),
+            timed: false,
+            time: None,
+        };
+
+        info!("Building constants");
+        for (idx, constant) in spec.constants.iter().enumerate() {
+            builder
+                .build_constant(constant)
+                .with_context(|| format!("in constants[{idx}] '{}'", constant.name))?;
+        }
+
+        info!("Building variables");
+        for (idx, var) in spec.variables.iter().enumerate() {
+            let entry = builder
+                .build_variable(var, &var.name, None)
+                .with_context(|| format!("in variables[{idx}] '{}'", var.name))?;
+            if builder.globals.insert(var.name.to_owned(), entry).is_some() {
+                bail!("duplicate identifier '{}'", var.name);
+            }
+        }
+
+        info!("Building automata");
+        for (idx, element) in spec.system.elements.iter().enumerate() {
+            builder
+                .build_instance(&element.automaton)
+                .with_context(|| format!("in system element {idx} '{}'", element.automaton))?;
+        }
+
+        info!("Building properties");
+        let mut properties = Vec::new();
+        let mut objectives = Vec::new();
+        let mut time_bound = None;
+        for property in spec.properties.iter() {
+            match builder.build_objective(&property.expression) {
+                Ok((objective, bound)) => {
+                    properties.push(property.name.to_owned());
+                    objectives.push(objective);
+                    time_bound = time_bound.max(bound);
+                }
+                Err(err) => warn!("skipping property '{}': {err:#}", property.name),
+            }
+        }
+
+        info!("Building transitions");
+        let channel = builder.build_transitions(&objectives)?;
+
+        // Verdicts are observed through the channel they are published on.
+        let verdict_type = Type::Product(vec![Type::Integer; objectives.len()]);
+        let predicates = (0..objectives.len())
+            .map(|idx| {
+                !Expression::Equal(Box::new((
+                    Expression::Var(channel, verdict_type.clone()).component(idx),
+                    Expression::from(VIOLATED),
+                )))
+            })
+            .collect();
+        let guarantees = (0..objectives.len())
+            .map(|idx| Pmtl::Atom(Atom::Predicate(idx)))
+            .collect();
+        let model = CsModelDef {
+            cs: builder.cs,
+            ports: vec![(channel, verdict_type.default_value())],
+            predicates,
+        }
+        .build();
+        Ok(JaniModel {
+            model,
+            properties,
+            guarantees,
+            assumes: Vec::new(),
+            pg_names: HashMap::from([(pg_id, spec.name.to_owned())]),
+            vars: builder.vars,
+            time_bound,
+        })
+    }
+
+    fn build_constant(&mut self, constant: &crate::spec::ConstantDecl) -> anyhow::Result<()> {
+        let r#type = match self
+            .r#type(&constant.r#type)
+            .context("in attribute 'type'")?
+        {
+            JaniType::Basic(r#type) => r#type,
+            JaniType::Clock => bail!("constants cannot be clocks"),
+        };
+        let value = constant
+            .value
+            .as_ref()
+            .ok_or_else(|| anyhow!("constant has no value (model parameters are not supported)"))?;
+        let expr = coerce(self.expression(value, None)?, &r#type)?;
+        let val = self.constant(&expr).context("in attribute 'value'")?;
+        r#type.check_bounds(&val)?;
+        if self
+            .globals
+            .insert(constant.name.to_owned(), Entry::Const(val))
+            .is_some()
+        {
+            bail!("duplicate identifier");
+        }
+        Ok(())
+    }
+
+    fn build_variable(
+        &mut self,
+        var: &VariableDecl,
+        name: &str,
+        scope: Option<&HashMap<String, Entry>>,
+    ) -> anyhow::Result<Entry> {
+        if var.transient {
+            info!("dropping transient variable '{name}'");
+            return Ok(Entry::Transient);
+        }
+        match self.r#type(&var.r#type).context("in attribute 'type'")? {
+            JaniType::Clock => {
+                if let Some(init) = var.initial_value.as_ref() {
+                    let init = self.expression(init, scope)?;
+                    if self.constant(&init)? != Val::Integer(0) {
+                        bail!("clocks can only be initialized to zero");
+                    }
+                }
+                self.timed = true;
+                Ok(Entry::Clock(self.cs.new_clock(self.pg_id)?))
+            }
+            JaniType::Basic(r#type) => {
+                let init = var
+                    .initial_value
+                    .as_ref()
+                    .map(|init| {
+                        self.expression(init, scope)
+                            .and_then(|init| coerce(init, &r#type))
+                    })
+                    .transpose()
+                    .context("in attribute 'initial-value'")?
+                    .unwrap_or_else(|| Expression::Const(r#type.default_value()));
+                let new_var = self
+                    .cs
+                    .new_var_with_type(self.pg_id, init, r#type.clone())
+                    .context("in attribute 'initial-value'")?;
+                self.vars.insert(new_var.into(), name.to_owned());
+                Ok(Entry::Var(new_var, r#type.unbounded()))
+            }
+        }
+    }
+
+    fn build_instance(&mut self, name: &str) -> anyhow::Result<()> {
+        let automaton = self
+            .spec
+            .automata
+            .iter()
+            .find(|automaton| automaton.name == name)
+            .ok_or_else(|| anyhow!("unknown automaton"))?;
+        check_restrict_initial(automaton.restrict_initial.as_ref())?;
+        let mut locations = HashMap::new();
+        for (idx, location) in automaton.locations.iter().enumerate() {
+            if locations
+                .insert(location.name.to_owned(), idx as Integer)
+                .is_some()
+            {
+                bail!("duplicate location name '{}'", location.name);
+            }
+        }
+        let [initial] = automaton.initial_locations.as_slice() else {
+            bail!("automata must have exactly one initial location");
+        };
+        let initial = *locations
+            .get(initial)
+            .ok_or_else(|| anyhow!("unknown initial location '{initial}'"))?;
+        let location = self.cs.new_var_with_type(
+            self.pg_id,
+            Expression::from(initial),
+            Type::Bounded(0, locations.len() as Integer - 1),
+        )?;
+        self.vars
+            .insert(location.into(), format!("{name}.location"));
+
+        let mut scope = self.globals.clone();
+        for (idx, var) in automaton.variables.iter().enumerate() {
+            let entry = self
+                .build_variable(var, &format!("{name}.{}", var.name), Some(&scope))
+                .with_context(|| format!("in variables[{idx}] '{}'", var.name))?;
+            scope.insert(var.name.to_owned(), entry);
+        }
+        self.instances.push(Instance {
+            location,
+            locations,
+            scope,
+        });
+        Ok(())
+    }
+
+    // Translates a property into the conditions determining its verdict,
+    // together with its time bound (if any).
+    fn build_objective(&mut self, property: &Value) -> anyhow::Result<(Objective, Option<Time>)> {
+        let mut property = property;
+        if op(property) == Some("filter") {
+            let fun = field(property, "fun")?;
+            if !matches!(fun.as_str(), Some("max" | "min" | "avg" | "values")) {
+                bail!("unsupported filter function {fun}");
+            }
+            let states = field(property, "states")?;
+            if op(states) != Some("initial") {
+                bail!("filters are only supported over initial states");
+            }
+            property = field(property, "values")?;
+        }
+        if !matches!(op(property), Some("P" | "Pmin" | "Pmax")) {
+            bail!("only probability properties are supported");
+        }
+        let path = field(property, "exp")?;
+        if path.get("step-bounds").is_some() || path.get("reward-bounds").is_some() {
+            bail!("only time bounds are supported");
+        }
+        let (lower, upper) = self.time_bounds(path.get("time-bounds"))?;
+        if self.timed && upper.is_none() {
+            bail!("properties of timed models require an upper time bound");
+        }
+        // Only properties with time bounds need to measure time.
+        let time = if lower.is_some() || upper.is_some() {
+            self.time_clock()?.elapsed()
+        } else {
+            Expression::from(0)
+        };
+        let in_bounds = Expression::and(
+            lower
+                .map(|(lower, exclusive)| {
+                    let lower = Expression::from(lower as Integer);
+                    if exclusive {
+                        Expression::Greater(Box::new((time.clone(), lower)))
+                    } else {
+                        Expression::GreaterEq(Box::new((time.clone(), lower)))
+                    }
+                })
+                .into_iter()
+                .chain(upper.map(|(upper, exclusive)| {
+                    let upper = Expression::from(upper as Integer);
+                    if exclusive {
+                        Expression::Less(Box::new((time.clone(), upper)))
+                    } else {
+                        Expression::LessEq(Box::new((time.clone(), upper)))
+                    }
+                }))
+                .collect(),
+        );
+        let expired = if let Some((upper, exclusive)) = upper {
+            let upper = Expression::from(upper as Integer);
+            if exclusive {
+                Expression::GreaterEq(Box::new((time, upper)))
+            } else {
+                Expression::Greater(Box::new((time, upper)))
+            }
+        } else {
+            Expression::from(false)
+        };
+        let state_formula = |builder: &mut Self, name: &str| {
+            let expr = builder.expression(field(path, name)?, None)?;
+            check_type(&expr, Type::Boolean)?;
+            Ok::<_, anyhow::Error>(expr)
+        };
+        let (satisfied, violated, deadlock) = match op(path) {
+            Some("U") | Some("F") => {
+                let (left, right) = if op(path) == Some("U") {
+                    (state_formula(self, "left")?, state_formula(self, "right")?)
+                } else {
+                    (Expression::from(true), state_formula(self, "exp")?)
+                };
+                let satisfied = Expression::and(vec![right, in_bounds]);
+                let violated = Expression::and(vec![
+                    !satisfied.clone(),
+                    Expression::or(vec![!left, expired]),
+                ]);
+                (satisfied, violated, VIOLATED)
+            }
+            Some("G") => {
+                let exp = state_formula(self, "exp")?;
+                let violated = Expression::and(vec![!exp, in_bounds]);
+                let satisfied = Expression::and(vec![!violated.clone(), expired]);
+                (satisfied, violated, SATISFIED)
+            }
+            _ => bail!("only until, eventually and globally path formulae are supported"),
+        };
+        let status = self.cs.new_var_with_type(
+            self.pg_id,
+            Expression::from(PENDING),
+            Type::Bounded(0, 2),
+        )?;
+        self.vars.insert(status.into(), "verdict".to_owned());
+        let objective = Objective {
+            status,
+            satisfied,
+            violated,
+            deadlock,
+        };
+        Ok((objective, upper.map(|(upper, _)| upper)))
+    }
+
+    // Returns the lower and upper time bounds, if any.
+    fn time_bounds(&mut self, bounds: Option<&Value>) -> anyhow::Result<(Bound, Bound)> {
+        let Some(bounds) = bounds else {
+            return Ok((None, None));
+        };
+        let bound = |name: &str| {
+            bounds
+                .get(name)
+                .map(|bound| {
+                    let expr = self.expression(bound, None)?;
+                    let val = self.constant(&expr)?;
+                    let time = match val {
+                        Val::Integer(time) => Time::try_from(time).ok(),
+                        _ => None,
+                    }
+                    .ok_or_else(|| anyhow!("time bounds must be non-negative integers"))?;
+                    let exclusive = bounds
+                        .get(format!("{name}-exclusive"))
+                        .is_some_and(|exclusive| exclusive == &Value::Bool(true));
+                    Ok::<_, anyhow::Error>((time, exclusive))
+                })
+                .transpose()
+        };
+        Ok((bound("lower")?, bound("upper")?))
+    }
+
+    fn time_clock(&mut self) -> anyhow::Result<Clock> {
+        if let Some(clock) = self.time {
+            Ok(clock)
+        } else {
+            let clock = self.cs.new_clock(self.pg_id)?;
+            self.time = Some(clock);
+            Ok(clock)
+        }
+    }
+
+    // Builds the transitions of the network,
+    // and returns the channel where the verdicts of the objectives are published.
+    fn build_transitions(&mut self, objectives: &[Objective]) -> anyhow::Result<Channel> {
+        let pg_id = self.pg_id;
+
+        // Location invariants hold whenever the automata are in the respective locations.
+        let mut invariants = Vec::new();
+        for (idx, element) in self.spec.system.elements.iter().enumerate() {
+            let automaton = self.automaton(&element.automaton);
+            let instance = &self.instances[idx];
+            for location in automaton.locations.iter() {
+                if let Some(Expr { exp }) = location.time_progress.as_ref() {
+                    let invariant = self
+                        .expression(exp, Some(&instance.scope))
+                        .and_then(|invariant| {
+                            check_type(&invariant, Type::Boolean)?;
+                            Ok(invariant)
+                        })
+                        .context("in attribute 'time-progress'")
+                        .with_context(|| {
+                            format!(
+                                "in location '{}' of automaton '{}'",
+                                location.name, automaton.name
+                            )
+                        })?;
+                    invariants.push(Expression::Implies(Box::new((
+                        at(instance, &location.name),
+                        invariant,
+                    ))));
+                }
+            }
+        }
+        let start = self.cs.initial_location(pg_id)?;
+        let (main, publish) = if invariants.is_empty() {
+            (self.cs.new_location(pg_id)?, self.cs.new_location(pg_id)?)
+        } else {
+            let invariant = Expression::and(invariants);
+            (
+                self.cs
+                    .new_location_with_invariant(pg_id, invariant.clone())?,
+                self.cs.new_location_with_invariant(pg_id, invariant)?,
+            )
+        };
+        let ack = self.cs.new_location(pg_id)?;
+
+        // Verdicts are updated after each step and whenever time makes them change.
+        let pending = Expression::or(
+            objectives
+                .iter()
+                .map(|objective| is(objective.status, PENDING))
+                .collect(),
+        );
+        let needs_update = Expression::or(
+            objectives
+                .iter()
+                .map(|objective| {
+                    Expression::and(vec![
+                        is(objective.status, PENDING),
+                        Expression::or(vec![
+                            objective.satisfied.clone(),
+                            objective.violated.clone(),
+                        ]),
+                    ])
+                })
+                .collect(),
+        );
+        let update = self.cs.new_action(pg_id)?;
+        self.update_verdicts(update, objectives)?;
+        self.cs
+            .add_transition(pg_id, start, update, publish, None)?;
+        if !objectives.is_empty() {
+            self.cs
+                .add_transition(pg_id, main, update, publish, Some(needs_update.clone()))?;
+        }
+        // Steps can be taken once verdicts are up to date, as long as some verdict is pending.
+        let step_guard = if objectives.is_empty() {
+            Expression::from(true)
+        } else {
+            Expression::and(vec![pending.clone(), !needs_update.clone()])
+        };
+
+        let mut enabled = Vec::new();
+        for participants in self.combinations()? {
+            // Steps that cannot change the state (e.g., self-loops of absorbing states) are dropped,
+            // so that absorbing states are treated as deadlocks.
+            if participants
+                .iter()
+                .all(|&(idx, edge_idx)| self.is_idle(idx, edge_idx))
+            {
+                continue;
+            }
+            let guard =
+                self.build_step(&participants, step_guard.clone(), objectives, main, publish)?;
+            enabled.push(guard);
+        }
+
+        // In untimed models, deadlocks are final.
+        if !self.timed && !objectives.is_empty() {
+            let deadlock = self.cs.new_action(pg_id)?;
+            for objective in objectives {
+                let status = Expression::Var(objective.status, Type::Integer);
+                self.cs.add_effect(
+                    pg_id,
+                    deadlock,
+                    objective.status,
+                    Expression::Ite(Box::new((
+                        is(objective.status, PENDING),
+                        Expression::from(objective.deadlock),
+                        status,
+                    ))),
+                )?;
+            }
+            let guard = Expression::and(vec![step_guard, !Expression::or(enabled)]);
+            self.cs
+                .add_transition(pg_id, main, deadlock, publish, Some(guard))?;
+        }
+
+        let verdicts = Expression::Tuple(
+            objectives
+                .iter()
+                .map(|objective| Expression::Var(objective.status, Type::Integer))
+                .collect(),
+        );
+        let verdict_type = Type::Product(vec![Type::Integer; objectives.len()]);
+        let channel = self.cs.new_channel(verdict_type.clone(), Some(1));
+        let send = self.cs.new_send(pg_id, channel, verdicts)?;
+        self.cs.add_transition(pg_id, publish, send, ack, None)?;
+        let sink = self
+            .cs
+            .new_var(pg_id, Expression::Const(verdict_type.default_value()))?;
+        let receive = self.cs.new_receive(pg_id, channel, sink)?;
+        self.cs.add_transition(pg_id, ack, receive, main, None)?;
+        Ok(channel)
+    }
+
+    // Whether the edge leaves the automaton in the same location without assigning anything.
+    fn is_idle(&self, idx: usize, edge_idx: usize) -> bool {
+        let edge = &self
+            .automaton(&self.spec.system.elements[idx].automaton)
+            .edges[edge_idx];
+        edge.destinations.iter().all(|destination| {
+            destination.location == edge.location && destination.assignments.is_empty()
+        })
+    }
+
+    // Enumerates the combinations of edges (by automaton instance and edge index) that can be taken together.
+    fn combinations(&self) -> anyhow::Result<Vec<Vec<(usize, usize)>>> {
+        let elements = &self.spec.system.elements;
+        let mut combinations = Vec::new();
+        for (idx, element) in elements.iter().enumerate() {
+            let automaton = self.automaton(&element.automaton);
+            for (edge_idx, edge) in automaton.edges.iter().enumerate() {
+                if edge.rate.is_some() {
+                    bail!(
+                        "in edges[{edge_idx}] of automaton '{}': rates are not supported",
+                        automaton.name
+                    );
+                }
+                if edge.action.is_none() {
+                    combinations.push(vec![(idx, edge_idx)]);
+                }
+            }
+        }
+        for (sync_idx, sync) in self.spec.system.syncs.iter().enumerate() {
+            if sync.synchronise.len() != elements.len() {
+                bail!("in syncs[{sync_idx}]: the synchronisation vector does not match the system elements");
+            }
+            let mut partial = vec![Vec::new()];
+            for (idx, action) in sync.synchronise.iter().enumerate() {
+                let Some(action) = action else {
+                    continue;
+                };
+                let automaton = self.automaton(&elements[idx].automaton);
+                let edges = automaton
+                    .edges
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, edge)| edge.action.as_ref() == Some(action))
+                    .map(|(edge_idx, _)| (idx, edge_idx))
+                    .collect::<Vec<_>>();
+                partial = partial
+                    .into_iter()
+                    .flat_map(|combination: Vec<(usize, usize)>| {
+                        edges.iter().map(move |edge| {
+                            let mut combination = combination.clone();
+                            combination.push(*edge);
+                            combination
+                        })
+                    })
+                    .collect();
+            }
+            combinations.extend(partial.into_iter().filter(|c| !c.is_empty()));
+        }
+        Ok(combinations)
+    }
+
+    // Builds the actions for a combination of edges, and returns the guard enabling it.
+    fn build_step(
+        &mut self,
+        participants: &[(usize, usize)],
+        step_guard: CsExpression,
+        objectives: &[Objective],
+        main: Location,
+        publish: Location,
+    ) -> anyhow::Result<CsExpression> {
+        let pg_id = self.pg_id;
+        let elements = &self.spec.system.elements;
+        let mut guards = Vec::new();
+        let mut destinations: Vec<Vec<(usize, &Destination, u32)>> = vec![Vec::new()];
+        let mut denominator = 1;
+        for &(idx, edge_idx) in participants {
+            let automaton = self.automaton(&elements[idx].automaton);
+            let edge = &automaton.edges[edge_idx];
+            let context = || format!("in edges[{edge_idx}] of automaton '{}'", automaton.name);
+            let instance = &self.instances[idx];
+            if !instance.locations.contains_key(&edge.location) {
+                return Err(anyhow!("unknown location '{}'", edge.location)).with_context(context);
+            }
+            guards.push(at(instance, &edge.location));
+            if let Some(Expr { exp }) = edge.guard.as_ref() {
+                let guard = self
+                    .expression(exp, Some(&instance.scope))
+                    .and_then(|guard| {
+                        check_type(&guard, Type::Boolean)?;
+                        Ok(guard)
+                    })
+                    .context("in attribute 'guard'")
+                    .with_context(context)?;
+                guards.push(guard);
+            }
+            let (weights, edge_denominator) = self.weights(edge, idx).with_context(context)?;
+            denominator *= edge_denominator;
+            if denominator > MAX_DENOMINATOR.pow(2) {
+                return Err(anyhow!("too many probabilistic outcomes")).with_context(context);
+            }
+            destinations = destinations
+                .into_iter()
+                .flat_map(|combination| {
+                    edge.destinations
+                        .iter()
+                        .zip(weights.iter())
+                        .filter(|(_, weight)| **weight > 0)
+                        .map(move |(destination, weight)| {
+                            let mut combination = combination.clone();
+                            combination.push((idx, destination, *weight));
+                            combination
+                        })
+                })
+                .collect();
+        }
+        let guard = Expression::and(guards);
+        let full_guard = Expression::and(vec![step_guard, guard.clone()]);
+        // Steps with multiple outcomes are taken by a single transition to a location of their own,
+        // where the outcome is chosen, so that all steps are equally likely to be scheduled.
+        let (pre, outcome_guard) = if destinations.len() > 1 {
+            let resolve = self.cs.new_location(pg_id)?;
+            let choose = self.cs.new_action(pg_id)?;
+            self.cs
+                .add_transition(pg_id, main, choose, resolve, Some(full_guard))?;
+            (resolve, None)
+        } else {
+            (main, Some(full_guard))
+        };
+        for destination in destinations {
+            let replicas = destination
+                .iter()
+                .map(|(_, _, weight)| weight)
+                .product::<u32>();
+            // Replicas make the outcome as likely as its probability.
+            for _ in 0..replicas {
+                let action = self.cs.new_action(pg_id)?;
+                self.build_effects(action, &destination)?;
+                self.update_verdicts(action, objectives)?;
+                self.cs
+                    .add_transition(pg_id, pre, action, publish, outcome_guard.clone())?;
+            }
+        }
+        Ok(guard)
+    }
+
+    // Computes the integer weights of the destinations of an edge, and their sum.
+    fn weights(&self, edge: &Edge, idx: usize) -> anyhow::Result<(Vec<u32>, u32)> {
+        let scope = &self.instances[idx].scope;
+        let probabilities = edge
+            .destinations
+            .iter()
+            .enumerate()
+            .map(|(dest_idx, destination)| {
+                destination
+                    .probability
+                    .as_ref()
+                    .map_or(Ok(1.), |Expr { exp }| {
+                        let expr = self.expression(exp, Some(scope))?;
+                        match self.constant(&expr)? {
+                            Val::Integer(p) => Ok(p as Float),
+                            Val::Float(p) => Ok(*p),
+                            val => Err(anyhow!("probability {val:?} is not a number")),
+                        }
+                    })
+                    .context("in attribute 'probability'")
+                    .with_context(|| format!("in destinations[{dest_idx}]"))
+            })
+            .collect::<anyhow::Result<Vec<Float>>>()?;
+        if probabilities.iter().any(|p| *p < 0.) {
+            bail!("the probabilities of the destinations must not be negative");
+        }
+        if (probabilities.iter().sum::<Float>() - 1.).abs() > 1e-9 {
+            bail!("the probabilities of the destinations do not sum to one");
+        }
+        // Find the closest approximation, preferring smaller denominators (i.e., fewer replicas).
+        let mut best = (Vec::new(), 0, Float::INFINITY);
+        for denominator in 1..=MAX_DENOMINATOR {
+            let weights = apportion(&probabilities, denominator);
+            let error = probabilities
+                .iter()
+                .zip(&weights)
+                .map(|(p, weight)| (p - *weight as Float / denominator as Float).abs())
+                .fold(0., Float::max);
+            if error < best.2 - 1e-9 {
+                best = (weights, denominator, error);
+            }
+        }
+        let (weights, denominator, error) = best;
+        if error > 1e-9 {
+            warn!(
+                "probabilities {probabilities:?} approximated as {weights:?} out of {denominator}"
+            );
+        }
+        Ok((weights, denominator))
+    }
+
+    fn build_effects(
+        &mut self,
+        action: Action,
+        destination: &[(usize, &Destination, u32)],
+    ) -> anyhow::Result<()> {
+        let pg_id = self.pg_id;
+        let mut assignments: Vec<(&Assignment, usize)> = Vec::new();
+        for &(idx, dest, _) in destination {
+            let instance = &self.instances[idx];
+            let location = *instance
+                .locations
+                .get(&dest.location)
+                .ok_or_else(|| anyhow!("unknown location '{}'", dest.location))?;
+            self.cs
+                .add_effect(pg_id, action, instance.location, Expression::from(location))?;
+            assignments.extend(dest.assignments.iter().map(|assignment| (assignment, idx)));
+        }
+        assignments.sort_by_key(|(assignment, _)| assignment.index);
+        for group in assignments.chunk_by(|(a, _), (b, _)| a.index == b.index) {
+            let mut updates = Vec::new();
+            for (assignment, idx) in group {
+                let scope = &self.instances[*idx].scope;
+                let target = assignment
+                    .target
+                    .as_str()
+                    .ok_or_else(|| anyhow!("only variables can be assigned to"))?;
+                match scope.get(target) {
+                    Some(Entry::Var(var, r#type)) => {
+                        let expr = self
+                            .expression(&assignment.value, Some(scope))
+                            .and_then(|expr| coerce(expr, r#type))
+                            .with_context(|| format!("in assignment to '{target}'"))?;
+                        updates.push((*var, expr));
+                    }
+                    Some(Entry::Clock(clock)) => {
+                        let value = self.expression(&assignment.value, Some(scope))?;
+                        if self.constant(&value)? != Val::Integer(0) {
+                            bail!("clock '{target}' can only be reset to zero");
+                        }
+                        self.cs.reset_clock(pg_id, action, *clock)?;
+                    }
+                    Some(Entry::Transient) => {}
+                    Some(Entry::Const(_)) => bail!("cannot assign to constant '{target}'"),
+                    None => bail!("unknown variable '{target}'"),
+                }
+            }
+            if let [(var, expr)] = updates.as_slice() {
+                self.cs.add_effect(pg_id, action, *var, expr.clone())?;
+            } else {
+                // Assignments with the same index are simultaneous,
+                // so their values are computed before any variable is updated.
+                let mut temps: HashMap<Type, usize> = HashMap::new();
+                let mut assigned = Vec::new();
+                for (var, expr) in updates {
+                    let r#type = expr.r#type()?;
+                    let count = temps.entry(r#type.clone()).or_default();
+                    let temp = self.temp(&r#type, *count)?;
+                    *count += 1;
+                    self.cs.add_effect(pg_id, action, temp, expr)?;
+                    assigned.push((var, Expression::Var(temp, r#type)));
+                }
+                for (var, temp) in assigned {
+                    self.cs.add_effect(pg_id, action, var, temp)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn temp(&mut self, r#type: &Type, idx: usize) -> anyhow::Result<Var> {
+        let pool = self.temps.entry(r#type.clone()).or_default();
+        while pool.len() <= idx {
+            let temp = self
+                .cs
+                .new_var(self.pg_id, Expression::Const(r#type.default_value()))?;
+            self.vars.insert(temp.into(), "temp".to_owned());
+            pool.push(temp);
+        }
+        Ok(pool[idx])
+    }
+
+    fn update_verdicts(&mut self, action: Action, objectives: &[Objective]) -> anyhow::Result<()> {
+        for objective in objectives {
+            let status = Expression::Var(objective.status, Type::Integer);
+            let verdict = Expression::Ite(Box::new((
+                objective.satisfied.clone(),
+                Expression::from(SATISFIED),
+                Expression::Ite(Box::new((
+                    objective.violated.clone(),
+                    Expression::from(VIOLATED),
+                    Expression::from(PENDING),
+                ))),
+            )));
+            self.cs.add_effect(
+                self.pg_id,
+                action,
+                objective.status,
+                Expression::Ite(Box::new((is(objective.status, PENDING), verdict, status))),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn automaton(&self, name: &str) -> &'a crate::spec::Automaton {
+        self.spec
+            .automata
+            .iter()
+            .find(|automaton| automaton.name == name)
+            .expect("automaton exists")
+    }
+
+    fn r#type(&mut self, value: &Value) -> anyhow::Result<JaniType> {
+        match value {
+            Value::String(name) => match name.as_str() {
+                "bool" => Ok(JaniType::Basic(Type::Boolean)),
+                "int" => Ok(JaniType::Basic(Type::Integer)),
+                "real" => Ok(JaniType::Basic(Type::Float)),
+                "clock" => Ok(JaniType::Clock),
+                _ => bail!("unsupported type '{name}'"),
+            },
+            Value::Object(_) if value.get("kind").and_then(Value::as_str) == Some("bounded") => {
+                match field(value, "base")?.as_str() {
+                    Some("int") => {
+                        let bound = |name: &str| {
+                            value
+                                .get(name)
+                                .map(|bound| {
+                                    let expr = self.expression(bound, None)?;
+                                    match self.constant(&expr)? {
+                                        Val::Integer(bound) => Ok(bound),
+                                        val => Err(anyhow!("bound {val:?} is not an integer")),
+                                    }
+                                })
+                                .transpose()
+                        };
+                        let lower = bound("lower-bound")?.unwrap_or(Integer::MIN);
+                        let upper = bound("upper-bound")?.unwrap_or(Integer::MAX);
+                        Ok(JaniType::Basic(Type::Bounded(lower, upper)))
+                    }
+                    Some("real") => Ok(JaniType::Basic(Type::Float)),
+                    _ => bail!("unsupported bounded type {value}"),
+                }
+            }
+            _ => bail!("unsupported type {value}"),
+        }
+    }
+
+    // Evaluates an expression that has to be constant.
+    fn constant(&self, expr: &CsExpression) -> anyhow::Result<Val> {
+        expr.eval_constant(self.overflow)
+            .ok_or_else(|| anyhow!("expected a constant expression"))?
+            .map_err(anyhow::Error::from)
+    }
+
+    // Translates an expression, resolving identifiers in the given scope (or among globals).
+    fn expression(
+        &self,
+        value: &Value,
+        scope: Option<&HashMap<String, Entry>>,
+    ) -> anyhow::Result<CsExpression> {
+        let expr = match value {
+            Value::Bool(b) => Expression::from(*b),
+            Value::Number(n) => {
+                if let Some(i) = n.as_i64() {
+                    Expression::from(i)
+                } else {
+                    Expression::from(n.as_f64().ok_or_else(|| anyhow!("invalid number {n}"))?)
+                }
+            }
+            Value::String(name) => match scope.unwrap_or(&self.globals).get(name) {
+                Some(Entry::Var(var, r#type)) => Expression::Var(*var, r#type.clone()),
+                Some(Entry::Clock(clock)) => clock.elapsed(),
+                Some(Entry::Const(val)) => Expression::Const(val.clone()),
+                Some(Entry::Transient) => bail!("transient variable '{name}' is not supported"),
+                None => bail!("unknown identifier '{name}'"),
+            },
+            Value::Object(_) => {
+                let arg = |name: &str| self.expression(field(value, name)?, scope);
+                let binary = |name: &str| {
+                    arg("left")
+                        .and_then(|left| Ok((left, arg("right")?)))
+                        .with_context(|| format!("in operator '{name}'"))
+                };
+                match op(value).ok_or_else(|| anyhow!("unsupported expression {value}"))? {
+                    "ite" => {
+                        let (then, r#else) = numeric_pair(arg("then")?, arg("else")?)?;
+                        Expression::Ite(Box::new((arg("if")?, then, r#else)))
+                    }
+                    "¬" => !arg("exp")?,
+                    "∧" => {
+                        let (lhs, rhs) = binary("∧")?;
+                        Expression::and(vec![lhs, rhs])
+                    }
+                    "∨" => {
+                        let (lhs, rhs) = binary("∨")?;
+                        Expression::or(vec![lhs, rhs])
+                    }
+                    "⇒" => Expression::Implies(Box::new(binary("⇒")?)),
+                    "=" => equal(binary("=")?)?,
+                    "≠" => !equal(binary("≠")?)?,
+                    "<" => Expression::Less(Box::new(binary("<")?)),
+                    ">" => Expression::Greater(Box::new(binary(">")?)),
+                    "≤" => {
+                        let (lhs, rhs) = binary("≤")?;
+                        if is_integer(&lhs)? && is_integer(&rhs)? {
+                            Expression::LessEq(Box::new((lhs, rhs)))
+                        } else {
+                            !Expression::Greater(Box::new((lhs, rhs)))
+                        }
+                    }
+                    "≥" => {
+                        let (lhs, rhs) = binary("≥")?;
+                        if is_integer(&lhs)? && is_integer(&rhs)? {
+                            Expression::GreaterEq(Box::new((lhs, rhs)))
+                        } else {
+                            !Expression::Less(Box::new((lhs, rhs)))
+                        }
+                    }
+                    "+" => {
+                        let (lhs, rhs) = binary("+")?;
+                        Expression::Sum(vec![lhs, rhs])
+                    }
+                    "-" => {
+                        let (lhs, rhs) = binary("-")?;
+                        Expression::Sum(vec![lhs, -rhs])
+                    }
+                    "*" => {
+                        let (lhs, rhs) = binary("*")?;
+                        Expression::Mult(vec![lhs, rhs])
+                    }
+                    // Division is always real-valued.
+                    "/" => {
+                        let (lhs, rhs) = binary("/")?;
+                        Expression::Div(Box::new((Expression::ToFloat(Box::new(lhs)), rhs)))
+                    }
+                    "%" => Expression::Mod(Box::new(binary("%")?)),
+                    "pow" => Expression::Pow(Box::new(binary("pow")?)),
+                    "min" => {
+                        let (lhs, rhs) = binary("min")?;
+                        Expression::Min(vec![lhs, rhs])
+                    }
+                    "max" => {
+                        let (lhs, rhs) = binary("max")?;
+                        Expression::Max(vec![lhs, rhs])
+                    }
+                    "abs" => Expression::Abs(Box::new(arg("exp")?)),
+                    "floor" => Expression::Floor(Box::new(arg("exp")?)),
+                    "ceil" => Expression::Ceil(Box::new(arg("exp")?)),
+                    op => bail!("unsupported operator '{op}'"),
+                }
+            }
+            _ => bail!("unsupported expression {value}"),
+        };
+        // Reject badly typed expressions where they appear.
+        expr.r#type()
+            .with_context(|| format!("in expression {value}"))?;
+        Ok(expr)
+    }
+}
+
+fn op(value: &Value) -> Option<&str> {
+    value.get("op").and_then(Value::as_str)
+}
+
+fn field<'v>(value: &'v Value, name: &str) -> anyhow::Result<&'v Value> {
+    value
+        .get(name)
+        .ok_or_else(|| anyhow!("missing field '{name}' in {value}"))
+}
+
+fn check_restrict_initial(restrict: Option<&Expr>) -> anyhow::Result<()> {
+    if restrict.is_some_and(|Expr { exp }| exp != &Value::Bool(true)) {
+        bail!("restricting initial states is not supported");
+    }
+    Ok(())
+}
+
+// Splits `denominator` into integer weights proportional to the given probabilities (summing to one),
+// by rounding down and giving the remainder to the largest fractional parts.
+fn apportion(probabilities: &[Float], denominator: u32) -> Vec<u32> {
+    let scaled = probabilities
+        .iter()
+        .map(|p| p * denominator as Float)
+        .collect::<Vec<_>>();
+    let mut weights = scaled.iter().map(|w| w.floor() as u32).collect::<Vec<_>>();
+    let remainder = denominator.saturating_sub(weights.iter().sum());
+    let mut order = (0..scaled.len()).collect::<Vec<_>>();
+    order.sort_by(|&a, &b| {
+        (scaled[b] - scaled[b].floor()).total_cmp(&(scaled[a] - scaled[a].floor()))
+    });
+    for &idx in order.iter().take(remainder as usize) {
+        weights[idx] += 1;
+    }
+    weights
+}
+
+// Whether the automaton instance is in the given location.
+fn at(instance: &Instance, location: &str) -> CsExpression {
+    Expression::Equal(Box::new((
+        Expression::Var(instance.location, Type::Integer),
+        Expression::from(instance.locations[location]),
+    )))
+}
+
+fn is(var: Var, val: Integer) -> CsExpression {
+    Expression::Equal(Box::new((
+        Expression::Var(var, Type::Integer),
+        Expression::from(val),
+    )))
+}
+
+fn is_integer(expr: &CsExpression) -> anyhow::Result<bool> {
+    Ok(expr.r#type()? == Type::Integer)
+}
+
+fn check_type(expr: &CsExpression, expected: Type) -> anyhow::Result<()> {
+    let found = expr.r#type()?;
+    if found != expected {
+        bail!("expected expression of type {expected}, found {found}");
+    }
+    Ok(())
+}
+
+// Integers are implicitly converted to reals where required.
+fn coerce(expr: CsExpression, r#type: &Type) -> anyhow::Result<CsExpression> {
+    if *r#type == Type::Float && is_integer(&expr)? {
+        Ok(Expression::ToFloat(Box::new(expr)))
+    } else {
+        Ok(expr)
+    }
+}
+
+fn numeric_pair(
+    lhs: CsExpression,
+    rhs: CsExpression,
+) -> anyhow::Result<(CsExpression, CsExpression)> {
+    match (lhs.r#type()?, rhs.r#type()?) {
+        (Type::Float, Type::Integer) => Ok((lhs, Expression::ToFloat(Box::new(rhs)))),
+        (Type::Integer, Type::Float) => Ok((Expression::ToFloat(Box::new(lhs)), rhs)),
+        _ => Ok((lhs, rhs)),
+    }
+}
+
+fn equal((lhs, rhs): (CsExpression, CsExpression)) -> anyhow::Result<CsExpression> {
+    let (lhs, rhs) = numeric_pair(lhs, rhs)?;
+    if lhs.r#type()? == Type::Float {
+        // Reals cannot be compared for equality directly.
+        Ok(Expression::and(vec![
+            !Expression::Less(Box::new((lhs.clone(), rhs.clone()))),
+            !Expression::Greater(Box::new((lhs, rhs))),
+        ]))
+    } else {
+        Ok(Expression::Equal(Box::new((lhs, rhs))))
+    }
+}
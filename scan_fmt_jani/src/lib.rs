@@ -0,0 +1,96 @@
+//! Parser and model builder for [JANI](https://jani-spec.org/) models.
+//!
+//! The following subset of the JANI specification is supported:
+//!
+//! - model types `lts`, `dtmc`, `mdp`, `ta` and `pta`;
+//! - constants with a value (model parameters are not supported);
+//! - global and local variables of type `bool`, `int`, bounded `int`, `real` and `clock`;
+//! - automata with guarded edges, probabilistic destinations and (indexed) assignments,
+//!   and locations with `time-progress` invariants;
+//! - parallel composition with synchronisation vectors;
+//! - properties of the form `P`, `Pmin` or `Pmax` of
+//!   until (`U`), eventually (`F`) and globally (`G`) path formulae with optional `time-bounds`,
+//!   possibly under a `filter` over the initial states.
+//!
+//! Since SCAN is a statistical model checker,
+//! nondeterminism is resolved uniformly at random
+//! (so `Pmin` and `Pmax` properties are estimated under a uniform scheduler),
+//! and the probabilities of destinations, which have to be constant,
+//! are approximated by the closest fractions with denominator at most 100
+//! (so probabilities below 1/200 may be approximated by zero).
+//! Time is discrete, and time bounds have to be integers.
+//! In timed models, properties have to be time-bounded, as time can always diverge.
+//! Properties that are not supported (e.g., expected rewards) are skipped with a warning.
+//!
+//! Every supported property is translated into a guarantee,
+//! in the order given by [`JaniModel::properties`].
+//! States without outgoing edges (including absorbing states whose edges only loop with no effect)
+//! are deadlocks: runs reaching them violate pending reachability properties
+//! and satisfy pending safety properties.
+//!
+//! ```
+//! let model = scan_fmt_jani::parse(r#"{
+//!     "jani-version": 1,
+//!     "name": "coin",
+//!     "type": "dtmc",
+//!     "variables": [{ "name": "heads", "type": "bool", "initial-value": false }],
+//!     "properties": [{
+//!         "name": "heads",
+//!         "expression": {
+//!             "op": "filter", "fun": "max", "states": { "op": "initial" },
+//!             "values": { "op": "Pmax", "exp": { "op": "F", "exp": "heads" } }
+//!         }
+//!     }],
+//!     "automata": [{
+//!         "name": "coin",
+//!         "locations": [{ "name": "flip" }, { "name": "done" }],
+//!         "initial-locations": ["flip"],
+//!         "edges": [{
+//!             "location": "flip",
+//!             "destinations": [
+//!                 { "location": "done", "probability": { "exp": 0.5 },
+//!                   "assignments": [{ "ref": "heads", "value": true }] },
+//!                 { "location": "done", "probability": { "exp": 0.5 } }
+//!             ]
+//!         }]
+//!     }],
+//!     "system": { "elements": [{ "automaton": "coin" }] }
+//! }"#).expect("valid model");
+//! assert_eq!(model.properties, vec!["heads"]);
+//! assert_eq!(model.guarantees.len(), 1);
+//! ```
+
+mod builder;
+pub mod spec;
+
+pub use builder::JaniModel;
+pub use scan_core;
+
+use anyhow::Context;
+use scan_core::Overflow;
+use std::path::Path;
+
+/// Loads the JANI model at `path`,
+/// with integer overflows checked.
+pub fn load(path: &Path) -> anyhow::Result<JaniModel> {
+    load_with_overflow(path, Overflow::default())
+}
+
+/// Loads the JANI model at `path`, with integer overflows behaving according to `overflow`.
+pub fn load_with_overflow(path: &Path, overflow: Overflow) -> anyhow::Result<JaniModel> {
+    let text =
+        std::fs::read_to_string(path).with_context(|| format!("cannot read {}", path.display()))?;
+    parse_with_overflow(&text, overflow).with_context(|| format!("in {}", path.display()))
+}
+
+/// Parses a JANI model from text,
+/// with integer overflows checked.
+pub fn parse(text: &str) -> anyhow::Result<JaniModel> {
+    parse_with_overflow(text, Overflow::default())
+}
+
+/// Parses a JANI model from text, with integer overflows behaving according to `overflow`.
+pub fn parse_with_overflow(text: &str, overflow: Overflow) -> anyhow::Result<JaniModel> {
+    let model: spec::Model = serde_json::from_str(text)?;
+    builder::ModelBuilder::build(&model, overflow)
+}
@@ -0,0 +1,143 @@
+//! The (deserializable) structure of the subset of JANI models supported by SCAN.
+//!
+//! Types, expressions and properties are kept as raw JSON values:
+//! they are interpreted when the model is built.
+//! Fields that do not affect the semantics (e.g., comments and metadata) are ignored.
+
+use serde::Deserialize;
+use serde_json::Value;
+
+/// A JANI model: a network of automata, together with its properties.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Model {
+    #[serde(rename = "jani-version")]
+    pub jani_version: u32,
+    pub name: String,
+    /// The kind of model, e.g., `"dtmc"` or `"ta"`.
+    #[serde(rename = "type")]
+    pub model_type: String,
+    #[serde(default)]
+    pub actions: Vec<ActionDecl>,
+    #[serde(default)]
+    pub constants: Vec<ConstantDecl>,
+    #[serde(default)]
+    pub variables: Vec<VariableDecl>,
+    #[serde(default, rename = "restrict-initial")]
+    pub restrict_initial: Option<Expr>,
+    #[serde(default)]
+    pub properties: Vec<Property>,
+    pub automata: Vec<Automaton>,
+    pub system: Composition,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ActionDecl {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ConstantDecl {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub r#type: Value,
+    /// Constants without value are parameters of the model.
+    #[serde(default)]
+    pub value: Option<Value>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct VariableDecl {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub r#type: Value,
+    #[serde(default, rename = "initial-value")]
+    pub initial_value: Option<Value>,
+    #[serde(default)]
+    pub transient: bool,
+}
+
+/// An expression wrapped in an object, as used for guards, invariants and probabilities.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Expr {
+    pub exp: Value,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Automaton {
+    pub name: String,
+    #[serde(default)]
+    pub variables: Vec<VariableDecl>,
+    #[serde(default, rename = "restrict-initial")]
+    pub restrict_initial: Option<Expr>,
+    pub locations: Vec<LocationDecl>,
+    #[serde(rename = "initial-locations")]
+    pub initial_locations: Vec<String>,
+    #[serde(default)]
+    pub edges: Vec<Edge>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LocationDecl {
+    pub name: String,
+    /// The invariant of the location.
+    #[serde(default, rename = "time-progress")]
+    pub time_progress: Option<Expr>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Edge {
+    pub location: String,
+    /// Edges without action are silent, and never synchronise.
+    #[serde(default)]
+    pub action: Option<String>,
+    #[serde(default)]
+    pub rate: Option<Expr>,
+    #[serde(default)]
+    pub guard: Option<Expr>,
+    pub destinations: Vec<Destination>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Destination {
+    pub location: String,
+    /// Defaults to probability one.
+    #[serde(default)]
+    pub probability: Option<Expr>,
+    #[serde(default)]
+    pub assignments: Vec<Assignment>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Assignment {
+    #[serde(rename = "ref")]
+    pub target: Value,
+    pub value: Value,
+    /// Assignments are executed by increasing index,
+    /// and simultaneously within the same index.
+    #[serde(default)]
+    pub index: i64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Composition {
+    pub elements: Vec<Element>,
+    #[serde(default)]
+    pub syncs: Vec<Sync>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Element {
+    pub automaton: String,
+}
+
+/// A synchronisation vector, with one (optional) action per element of the composition.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Sync {
+    pub synchronise: Vec<Option<String>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Property {
+    pub name: String,
+    pub expression: Value,
+}
@@ -0,0 +1,101 @@
+use scan_fmt_jani::{
+    scan_core::{channel_system::Event, *},
+    JaniModel,
+};
+use std::{
+    path::PathBuf,
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+#[derive(Clone)]
+struct NoPublisher;
+
+impl Publisher<Event> for NoPublisher {
+    fn init(&mut self) {}
+
+    fn publish(&mut self, _action: &Event, _time: Time, _state: &[bool]) {}
+
+    fn finalize(self, _success: Option<bool>) {}
+}
+
+fn load(name: &str) -> anyhow::Result<JaniModel> {
+    scan_fmt_jani::load(&PathBuf::from_str(&format!("./tests/assets/{name}"))?)
+}
+
+// Estimates the probability that the given guarantee is satisfied.
+fn estimate(model: &JaniModel, property: &str) -> anyhow::Result<f64> {
+    let idx = model
+        .properties
+        .iter()
+        .position(|name| name == property)
+        .expect("property exists");
+    let state = Arc::new(Mutex::new((0, 0, true)));
+    model.model.par_adaptive(
+        &model.guarantees[idx..=idx],
+        &model.assumes,
+        0.95,
+        0.02,
+        10000,
+        100,
+        None::<NoPublisher>,
+        state.clone(),
+    )?;
+    let (s, f, _) = *state.lock().expect("lock state");
+    Ok(s as f64 / (s + f) as f64)
+}
+
+#[test]
+fn die() -> anyhow::Result<()> {
+    let model = load("die.jani")?;
+    // Expected-value properties are not supported, and are skipped.
+    assert_eq!(model.properties, vec!["six"]);
+    let rate = estimate(&model, "six")?;
+    assert!((rate - 1. / 6.).abs() < 0.05, "rate {rate}");
+    Ok(())
+}
+
+#[test]
+fn swap() -> anyhow::Result<()> {
+    let model = load("swap.jani")?;
+    // Synchronised assignments are simultaneous.
+    assert_eq!(estimate(&model, "swapped")?, 1.);
+    Ok(())
+}
+
+#[test]
+fn timer() -> anyhow::Result<()> {
+    let model = load("timer.jani")?;
+    assert_eq!(model.time_bound, Some(5));
+    assert_eq!(estimate(&model, "in time")?, 1.);
+    assert_eq!(estimate(&model, "too early")?, 0.);
+    Ok(())
+}
+
+#[test]
+fn type_error() -> anyhow::Result<()> {
+    let err = load("type_error.jani").expect_err("integer variable used as guard");
+    let err = format!("{err:#}");
+    assert!(err.contains("type_error.jani"));
+    assert!(err.contains("in edges[0] of automaton 'a'"));
+    assert!(err.contains("in attribute 'guard'"));
+    Ok(())
+}
+
+#[test]
+fn coin() -> anyhow::Result<()> {
+    // Probabilities that are not multiples of 1/n for small n are approximated.
+    let model = load("coin.jani")?;
+    let rate = estimate(&model, "heads")?;
+    assert!((rate - 0.637).abs() < 0.05, "rate {rate}");
+    Ok(())
+}
+
+#[test]
+fn scheduler() -> anyhow::Result<()> {
+    // Nondeterministic edges are equally likely, whatever the probabilities of their destinations.
+    let model = load("scheduler.jani")?;
+    let rate = estimate(&model, "direct")?;
+    assert!((rate - 0.5).abs() < 0.05, "rate {rate}");
+    Ok(())
+}
@@ -0,0 +1,497 @@
+//! Model builder for SCAN's JSON format.
+
+use crate::spec::{ActionSpec, ConstraintSpec, FormulaSpec, ProcessSpec, Spec, TransitionSpec};
+use anyhow::{anyhow, bail, Context};
+use log::info;
+use scan_core::{
+    channel_system::*,
+    program_graph::{PgError, Var as PgVar},
+    *,
+};
+use std::collections::HashMap;
+
+/// A model loaded from SCAN's JSON format,
+/// together with the names used in the specification.
+#[derive(Debug, Clone)]
+pub struct JsonModel {
+    pub model: CsModel,
+    pub predicates: Vec<String>,
+    pub guarantees: Vec<Pmtl<Atom<Event>>>,
+    pub assumes: Vec<Pmtl<Atom<Event>>>,
+    // Associates each PG with the name of the process it represents.
+    pub pg_names: HashMap<PgId, String>,
+    // Associates the variables of each PG with their names.
+    pub vars: HashMap<(PgId, PgVar), String>,
+    pub channel_names: HashMap<Channel, String>,
+}
+
+pub(crate) struct ModelBuilder {
+    cs: ChannelSystemBuilder,
+    channels: HashMap<String, (Channel, Type)>,
+    pg_names: HashMap<PgId, String>,
+    vars: HashMap<(PgId, PgVar), String>,
+}
+
+impl ModelBuilder {
+    pub(crate) fn build(spec: &Spec, overflow: Overflow) -> anyhow::Result<JsonModel> {
+        let mut builder = ModelBuilder {
+            cs: ChannelSystemBuilder::new(),
+            channels: HashMap::new(),
+            pg_names: HashMap::new(),
+            vars: HashMap::new(),
+        };
+        builder.cs.set_overflow(overflow);
+
+        info!("Building channels");
+        let mut ports = Vec::new();
+        for (idx, channel) in spec.channels.iter().enumerate() {
+            let context = || format!("in channels[{idx}] '{}'", channel.name);
+            let r#type = parse_type(&channel.r#type)
+                .map_err(|err| located(err, &channel.r#type))
+                .context("in attribute 'type'")
+                .with_context(context)?;
+            let init = channel
+                .init
+                .as_ref()
+                .map(|init| constant(init, &r#type, overflow).context("in attribute 'init'"))
+                .transpose()
+                .with_context(context)?
+                .unwrap_or_else(|| r#type.default_value());
+            let chn = if channel.broadcast {
+                builder
+                    .cs
+                    .new_broadcast_channel(r#type.clone(), channel.capacity)
+            } else {
+                builder.cs.new_channel(r#type.clone(), channel.capacity)
+            };
+            if builder
+                .channels
+                .insert(channel.name.to_owned(), (chn, r#type.unbounded()))
+                .is_some()
+            {
+                return Err(anyhow!("duplicate channel name")).with_context(context);
+            }
+            ports.push((chn, init));
+        }
+
+        info!("Building processes");
+        for (idx, process) in spec.processes.iter().enumerate() {
+            builder
+                .build_process(process)
+                .with_context(|| format!("in processes[{idx}] '{}'", process.name))?;
+        }
+
+        info!("Building predicates");
+        let mut resolver = Resolver::new();
+        for (name, (chn, r#type)) in builder.channels.iter() {
+            resolver.insert(name, *chn, r#type.clone());
+        }
+        let mut predicates = Vec::new();
+        let mut pred_indexes = HashMap::new();
+        for (idx, predicate) in spec.predicates.iter().enumerate() {
+            let context = || format!("in predicates[{idx}] '{}'", predicate.name);
+            let expr = parse(&resolver, &predicate.expr)
+                .and_then(|expr| {
+                    check_type(&expr, &resolver, Type::Boolean)?;
+                    Ok(expr)
+                })
+                .context("in attribute 'expr'")
+                .with_context(context)?;
+            if pred_indexes
+                .insert(predicate.name.to_owned(), predicates.len())
+                .is_some()
+            {
+                return Err(anyhow!("duplicate predicate name")).with_context(context);
+            }
+            predicates.push((predicate.name.to_owned(), expr));
+        }
+        let properties = |kind: &str, properties: &[crate::spec::PropertySpec]| {
+            properties
+                .iter()
+                .enumerate()
+                .map(|(idx, property)| {
+                    build_formula(&property.formula, &pred_indexes)
+                        .with_context(|| format!("in {kind}[{idx}] '{}'", property.name))
+                })
+                .collect::<anyhow::Result<Vec<_>>>()
+        };
+        let guarantees = properties("guarantees", &spec.guarantees)?;
+        let assumes = properties("assumes", &spec.assumes)?;
+
+        let channel_names = builder
+            .channels
+            .iter()
+            .map(|(name, (chn, _))| (*chn, name.to_owned()))
+            .collect();
+        let (predicate_names, predicates) = predicates.into_iter().unzip();
+        let model = CsModelDef {
+            cs: builder.cs,
+            ports,
+            predicates,
+        }
+        .build();
+        Ok(JsonModel {
+            model,
+            predicates: predicate_names,
+            guarantees,
+            assumes,
+            pg_names: builder.pg_names,
+            vars: builder.vars,
+            channel_names,
+        })
+    }
+
+    fn build_process(&mut self, process: &ProcessSpec) -> anyhow::Result<()> {
+        let pg_id = self.cs.new_program_graph();
+        self.pg_names.insert(pg_id, process.name.to_owned());
+
+        let mut resolver = Resolver::new();
+        for (idx, var) in process.variables.iter().enumerate() {
+            let context = || format!("in variables[{idx}] '{}'", var.name);
+            let r#type = parse_type(&var.r#type)
+                .map_err(|err| located(err, &var.r#type))
+                .context("in attribute 'type'")
+                .with_context(context)?;
+            let init = var
+                .init
+                .as_ref()
+                .map(|init| parse(&resolver, init))
+                .transpose()
+                .context("in attribute 'init'")
+                .with_context(context)?
+                .unwrap_or_else(|| Expression::Const(r#type.default_value()));
+            let new_var = self
+                .cs
+                .new_var_with_type(pg_id, init.clone(), r#type.clone())
+                .map_err(|err| explain(&resolver, &init, err))
+                .context("in attribute 'init'")
+                .with_context(context)?;
+            if resolver
+                .insert(&var.name, new_var, r#type.unbounded())
+                .is_some()
+            {
+                return Err(anyhow!("duplicate variable name")).with_context(context);
+            }
+            self.vars.insert(new_var.into(), var.name.to_owned());
+        }
+
+        let mut clocks = HashMap::new();
+        for name in process.clocks.iter() {
+            let clock = self.cs.new_clock(pg_id).expect("program graph exists");
+            if clocks.insert(name.as_str(), clock).is_some() {
+                bail!("duplicate clock name '{name}'");
+            }
+        }
+
+        let mut locations = HashMap::new();
+        for (idx, location) in process.locations.iter().enumerate() {
+            let context = || format!("in locations[{idx}] '{}'", location.name);
+            let loc = if idx == 0 {
+                if !location.invariants.is_empty() {
+                    return Err(anyhow!("the initial location cannot have invariants"))
+                        .with_context(context);
+                }
+                self.cs.initial_location(pg_id)?
+            } else if location.invariants.is_empty() {
+                self.cs.new_location(pg_id)?
+            } else {
+                let invariants = constraints(&location.invariants, &clocks)
+                    .context("in attribute 'invariants'")
+                    .with_context(context)?;
+                self.cs.new_timed_location(pg_id, &invariants)?
+            };
+            if locations.insert(location.name.as_str(), loc).is_some() {
+                return Err(anyhow!("duplicate location name")).with_context(context);
+            }
+        }
+        if locations.is_empty() {
+            bail!("a process needs at least one (initial) location");
+        }
+
+        let mut actions = HashMap::new();
+        for (idx, action) in process.actions.iter().enumerate() {
+            let context = || format!("in actions[{idx}] '{}'", action.name);
+            let new_action = self
+                .build_action(pg_id, action, &resolver, &clocks)
+                .with_context(context)?;
+            if actions.insert(action.name.as_str(), new_action).is_some() {
+                return Err(anyhow!("duplicate action name")).with_context(context);
+            }
+        }
+
+        for (idx, transition) in process.transitions.iter().enumerate() {
+            self.build_transition(pg_id, transition, &resolver, &clocks, &locations, &actions)
+                .with_context(|| {
+                    format!(
+                        "in transitions[{idx}] from '{}' to '{}'",
+                        transition.from, transition.to
+                    )
+                })?;
+        }
+        Ok(())
+    }
+
+    fn build_action(
+        &mut self,
+        pg_id: PgId,
+        action: &ActionSpec,
+        resolver: &Resolver<Var>,
+        clocks: &HashMap<&str, Clock>,
+    ) -> anyhow::Result<Action> {
+        let communications = [
+            action.send.is_some(),
+            action.receive.is_some(),
+            action.probe_empty.is_some(),
+            action.probe_full.is_some(),
+        ];
+        let communications = communications.into_iter().filter(|c| *c).count();
+        if communications > 1 {
+            bail!("an action can perform at most one communication");
+        } else if communications == 1 && !(action.effects.is_empty() && action.resets.is_empty()) {
+            bail!("communication actions cannot have effects or reset clocks");
+        }
+        if let Some(ref send) = action.send {
+            let (channel, _) = self.channel(&send.channel).context("in attribute 'send'")?;
+            let msg = parse(resolver, &send.expr).context("in attribute 'send'")?;
+            self.cs
+                .new_send(pg_id, channel, msg.clone())
+                .map_err(|err| explain(resolver, &msg, err))
+                .context("in attribute 'send'")
+        } else if let Some(ref receive) = action.receive {
+            let (channel, _) = self
+                .channel(&receive.channel)
+                .context("in attribute 'receive'")?;
+            let (var, _) = var(resolver, &receive.var).context("in attribute 'receive'")?;
+            self.cs
+                .new_receive(pg_id, channel, var)
+                .context("in attribute 'receive'")
+        } else if let Some(ref name) = action.probe_empty {
+            let (channel, _) = self.channel(name).context("in attribute 'probe_empty'")?;
+            self.cs
+                .new_probe_empty_queue(pg_id, channel)
+                .context("in attribute 'probe_empty'")
+        } else if let Some(ref name) = action.probe_full {
+            let (channel, _) = self.channel(name).context("in attribute 'probe_full'")?;
+            self.cs
+                .new_probe_full_queue(pg_id, channel)
+                .context("in attribute 'probe_full'")
+        } else {
+            let new_action = self.cs.new_action(pg_id)?;
+            for (idx, effect) in action.effects.iter().enumerate() {
+                let context = || format!("in effects[{idx}] assigning '{}'", effect.var);
+                let (var, _) = var(resolver, &effect.var).with_context(context)?;
+                let expr = parse(resolver, &effect.expr)
+                    .context("in attribute 'expr'")
+                    .with_context(context)?;
+                self.cs
+                    .add_effect(pg_id, new_action, var, expr.clone())
+                    .map_err(|err| explain(resolver, &expr, err))
+                    .context("in attribute 'expr'")
+                    .with_context(context)?;
+            }
+            for name in action.resets.iter() {
+                let clock = clocks
+                    .get(name.as_str())
+                    .ok_or_else(|| anyhow!("unknown clock '{name}'"))
+                    .context("in attribute 'resets'")?;
+                self.cs.reset_clock(pg_id, new_action, *clock)?;
+            }
+            Ok(new_action)
+        }
+    }
+
+    fn build_transition(
+        &mut self,
+        pg_id: PgId,
+        transition: &TransitionSpec,
+        resolver: &Resolver<Var>,
+        clocks: &HashMap<&str, Clock>,
+        locations: &HashMap<&str, Location>,
+        actions: &HashMap<&str, Action>,
+    ) -> anyhow::Result<()> {
+        let location = |name: &str| {
+            locations
+                .get(name)
+                .copied()
+                .ok_or_else(|| anyhow!("unknown location '{name}'"))
+        };
+        let pre = location(&transition.from).context("in attribute 'from'")?;
+        let post = location(&transition.to).context("in attribute 'to'")?;
+        let action = transition
+            .action
+            .as_ref()
+            .map(|name| {
+                actions
+                    .get(name.as_str())
+                    .copied()
+                    .ok_or_else(|| anyhow!("unknown action '{name}'"))
+            })
+            .transpose()
+            .context("in attribute 'action'")?;
+        let guard = transition
+            .guard
+            .as_ref()
+            .map(|guard| parse(resolver, guard))
+            .transpose()
+            .context("in attribute 'guard'")?;
+        let constraints =
+            constraints(&transition.constraints, clocks).context("in attribute 'constraints'")?;
+        match action {
+            Some(action) if constraints.is_empty() => {
+                self.cs
+                    .add_transition(pg_id, pre, action, post, guard.clone())
+            }
+            Some(action) => {
+                self.cs
+                    .add_timed_transition(pg_id, pre, action, post, guard.clone(), &constraints)
+            }
+            None if constraints.is_empty() => {
+                self.cs
+                    .add_autonomous_transition(pg_id, pre, post, guard.clone())
+            }
+            None => self.cs.add_autonomous_timed_transition(
+                pg_id,
+                pre,
+                post,
+                guard.clone(),
+                &constraints,
+            ),
+        }
+        .map_err(|err| match guard {
+            Some(ref guard) => explain(resolver, guard, err),
+            None => err.into(),
+        })
+        .context("in attribute 'guard'")
+    }
+
+    fn channel(&self, name: &str) -> anyhow::Result<(Channel, Type)> {
+        self.channels
+            .get(name)
+            .cloned()
+            .ok_or_else(|| anyhow!("unknown channel '{name}'"))
+    }
+}
+
+fn var(resolver: &Resolver<Var>, name: &str) -> anyhow::Result<(Var, Type)> {
+    resolver
+        .get(name)
+        .cloned()
+        .ok_or_else(|| anyhow!("unknown variable '{name}'"))
+}
+
+fn constraints(
+    constraints: &[ConstraintSpec],
+    clocks: &HashMap<&str, Clock>,
+) -> anyhow::Result<Vec<TimeConstraint>> {
+    constraints
+        .iter()
+        .map(|constraint| {
+            clocks
+                .get(constraint.clock.as_str())
+                .map(|clock| (*clock, constraint.lower, constraint.upper))
+                .ok_or_else(|| anyhow!("unknown clock '{}'", constraint.clock))
+        })
+        .collect()
+}
+
+// Parses an expression, locating errors within its text.
+fn parse<V: Clone>(resolver: &Resolver<V>, text: &str) -> anyhow::Result<Expression<V>> {
+    resolver.parse(text).map_err(|err| located(err, text))
+}
+
+fn located(err: ParseError, text: &str) -> anyhow::Error {
+    let column = text[..err.span().start].chars().count() + 1;
+    anyhow::Error::new(err).context(format!("at column {column} of \"{text}\""))
+}
+
+// Parses and evaluates a constant expression of the given type.
+fn constant(text: &str, r#type: &Type, overflow: Overflow) -> anyhow::Result<Val> {
+    let resolver = Resolver::<Channel>::new();
+    let expr = parse(&resolver, text)?;
+    check_type(&expr, &resolver, r#type.unbounded())?;
+    let val = expr
+        .eval_constant(overflow)
+        .expect("no variables can be resolved")?;
+    r#type.check_bounds(&val)?;
+    Ok(val)
+}
+
+fn check_type<V>(expr: &Expression<V>, resolver: &Resolver<V>, expected: Type) -> anyhow::Result<()>
+where
+    V: Clone + PartialEq,
+{
+    let found = expr
+        .r#type()
+        .map_err(|err| explain_type(resolver, expr, err))?;
+    if found != expected {
+        bail!("expected expression of type {expected}, found {found}");
+    }
+    Ok(())
+}
+
+// Points type errors in `expr` to the offending sub-expression, printed with the names of the variables.
+fn explain(resolver: &Resolver<Var>, expr: &CsExpression, err: CsError) -> anyhow::Error {
+    match err {
+        CsError::Type(type_err) | CsError::ProgramGraph(_, PgError::Type(type_err)) => {
+            explain_type(resolver, expr, type_err)
+        }
+        err => err.into(),
+    }
+}
+
+fn explain_type<V>(resolver: &Resolver<V>, expr: &Expression<V>, err: TypeError) -> anyhow::Error
+where
+    V: Clone + PartialEq,
+{
+    match expr.subexpression(&err.path) {
+        Some(sub_expr) => {
+            let sub_expr = sub_expr
+                .display(|var| resolver.name(var).unwrap_or("?").to_string())
+                .to_string();
+            anyhow::Error::new(err).context(format!("in sub-expression `{sub_expr}`"))
+        }
+        None => err.into(),
+    }
+}
+
+fn build_formula(
+    formula: &FormulaSpec,
+    predicates: &HashMap<String, usize>,
+) -> anyhow::Result<Pmtl<Atom<Event>>> {
+    let build = |formula: &FormulaSpec| build_formula(formula, predicates);
+    Ok(match formula {
+        FormulaSpec::True => Pmtl::True,
+        FormulaSpec::False => Pmtl::False,
+        FormulaSpec::Predicate(name) => Pmtl::Atom(Atom::Predicate(
+            *predicates
+                .get(name)
+                .ok_or_else(|| anyhow!("unknown predicate '{name}'"))?,
+        )),
+        FormulaSpec::And(formulae) => {
+            Pmtl::And(formulae.iter().map(build).collect::<Result<_, _>>()?)
+        }
+        FormulaSpec::Or(formulae) => {
+            Pmtl::Or(formulae.iter().map(build).collect::<Result<_, _>>()?)
+        }
+        FormulaSpec::Not(formula) => Pmtl::Not(Box::new(build(formula)?)),
+        FormulaSpec::Implies(formulae) => {
+            Pmtl::Implies(Box::new((build(&formulae.0)?, build(&formulae.1)?)))
+        }
+        FormulaSpec::Historically {
+            formula,
+            lower,
+            upper,
+        } => Pmtl::Historically(Box::new(build(formula)?), *lower, *upper),
+        FormulaSpec::Previously {
+            formula,
+            lower,
+            upper,
+        } => Pmtl::Previously(Box::new(build(formula)?), *lower, *upper),
+        FormulaSpec::Since {
+            lhs,
+            rhs,
+            lower,
+            upper,
+        } => Pmtl::Since(Box::new((build(lhs)?, build(rhs)?)), *lower, *upper),
+    })
+}
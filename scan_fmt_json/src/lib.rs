@@ -0,0 +1,126 @@
+//! Parser and model builder for SCAN's JSON intermediate format.
+//!
+//! The format describes a channel system, together with predicates and PMTL properties over it,
+//! and is meant to be produced by other tools (e.g., model generators) targeting SCAN directly.
+//!
+//! # Format (version 1)
+//!
+//! A model is a JSON object with the following fields:
+//!
+//! - `version`: the version of the format, currently [`VERSION`];
+//! - `channels` (optional): the channels, each with
+//!   - `name`,
+//!   - `type`,
+//!   - `capacity` (optional, unbounded if missing, `0` for handshake channels),
+//!   - `broadcast` (optional, `false` if missing): whether each message is delivered to all processes receiving from the channel,
+//!   - `init` (optional): the value of the channel seen by predicates before anything is sent through it,
+//!     defaulting to the default value of its type;
+//! - `processes`: the program graphs, each with
+//!   - `name`,
+//!   - `variables` (optional): each with `name`, `type` and `init` (optional, defaults to the default value of the type),
+//!     where the initial value can use previously declared variables,
+//!   - `clocks` (optional): the names of the clocks,
+//!   - `locations`: each with `name` and `invariants` (optional, a list of time constraints),
+//!     where the first location is the initial one and cannot have invariants,
+//!   - `actions` (optional): each with `name` and either
+//!     `effects` (a list of assignments, each with `var` and `expr`) and `resets` (a list of clocks),
+//!     or a single communication among
+//!     `send` (with `channel` and `expr`), `receive` (with `channel` and `var`),
+//!     `probe_empty` (a channel) and `probe_full` (a channel),
+//!   - `transitions` (optional): each with
+//!     `from` and `to` locations,
+//!     `action` (optional, the transition is autonomous if missing),
+//!     `guard` (optional, a Boolean expression) and
+//!     `constraints` (optional, a list of time constraints);
+//! - `predicates` (optional): each with `name` and `expr`,
+//!   a Boolean expression over the channels, which evaluate to the last value sent through them;
+//! - `guarantees` and `assumes` (optional): PMTL properties, each with `name` and `formula`.
+//!
+//! Time constraints have a `clock` and optional `lower` and `upper` bounds.
+//! Types and expressions are given as strings,
+//! in the syntax of [`parse_type`](scan_core::parse_type) and [`parse_expression`](scan_core::parse_expression).
+//!
+//! Formulae are either `"true"`, `"false"` or objects with a single field among
+//! `predicate` (the name of a predicate),
+//! `and`, `or` (lists of formulae),
+//! `not` (a formula),
+//! `implies` (a pair of formulae),
+//! `historically` and `previously` (with `formula`, and optional `lower` and `upper` time bounds),
+//! and `since` (with `lhs`, `rhs`, and optional `lower` and `upper` time bounds).
+//!
+//! Unknown fields are rejected.
+//! Syntax errors are reported with their line and column in the file,
+//! while errors in the model are reported with the path of the item that causes them
+//! (and the column within expressions).
+//!
+//! ```
+//! let model = scan_fmt_json::parse(r#"{
+//!     "version": 1,
+//!     "channels": [{ "name": "c", "type": "int", "capacity": 1 }],
+//!     "processes": [{
+//!         "name": "sender",
+//!         "variables": [{ "name": "x", "type": "int<0, 10>" }],
+//!         "locations": [{ "name": "l" }],
+//!         "actions": [
+//!             { "name": "inc", "effects": [{ "var": "x", "expr": "x + 1" }] },
+//!             { "name": "send", "send": { "channel": "c", "expr": "x" } }
+//!         ],
+//!         "transitions": [
+//!             { "from": "l", "to": "l", "action": "inc", "guard": "x < 10" },
+//!             { "from": "l", "to": "l", "action": "send" }
+//!         ]
+//!     }],
+//!     "predicates": [{ "name": "small", "expr": "c < 10" }],
+//!     "guarantees": [{ "name": "always small", "formula": { "historically": { "formula": { "predicate": "small" } } } }]
+//! }"#).expect("well-formed model");
+//! assert_eq!(model.predicates, vec!["small"]);
+//! assert_eq!(model.guarantees.len(), 1);
+//! ```
+
+mod builder;
+pub mod spec;
+
+use anyhow::{bail, Context};
+use std::path::Path;
+
+pub use builder::JsonModel;
+pub use scan_core;
+
+use scan_core::Overflow;
+
+/// The version of the format supported by this crate.
+pub const VERSION: u32 = 1;
+
+/// Loads the model in the JSON file at `path`,
+/// with integer overflows checked.
+pub fn load(path: &Path) -> anyhow::Result<JsonModel> {
+    load_with_overflow(path, Overflow::default())
+}
+
+/// Loads the model in the JSON file at `path`, with integer overflows behaving according to `overflow`.
+pub fn load_with_overflow(path: &Path, overflow: Overflow) -> anyhow::Result<JsonModel> {
+    let text =
+        std::fs::read_to_string(path).with_context(|| format!("cannot read {}", path.display()))?;
+    parse_with_overflow(&text, overflow).with_context(|| format!("in {}", path.display()))
+}
+
+/// Parses a model from JSON text,
+/// with integer overflows checked.
+pub fn parse(text: &str) -> anyhow::Result<JsonModel> {
+    parse_with_overflow(text, Overflow::default())
+}
+
+/// Parses a model from JSON text, with integer overflows behaving according to `overflow`.
+pub fn parse_with_overflow(text: &str, overflow: Overflow) -> anyhow::Result<JsonModel> {
+    // The version is checked before the rest of the model,
+    // so that models in other versions are not reported as malformed.
+    let version: spec::Version = serde_json::from_str(text)?;
+    if version.version != VERSION {
+        bail!(
+            "unsupported format version {} (supported version is {VERSION})",
+            version.version
+        );
+    }
+    let spec: spec::Spec = serde_json::from_str(text)?;
+    builder::ModelBuilder::build(&spec, overflow)
+}
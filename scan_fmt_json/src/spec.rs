@@ -0,0 +1,202 @@
+//! The (de)serializable structure of SCAN's JSON format.
+//!
+//! Names, types and expressions are kept as text:
+//! they are resolved and parsed when the model is built.
+
+use scan_core::Time;
+use serde::{Deserialize, Serialize};
+
+/// Only the version of a model, ignoring any other field,
+/// since these may change between versions.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Version {
+    pub version: u32,
+}
+
+/// A whole model: a channel system, together with predicates and properties over it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Spec {
+    /// The version of the format.
+    pub version: u32,
+    #[serde(default)]
+    pub channels: Vec<ChannelSpec>,
+    pub processes: Vec<ProcessSpec>,
+    #[serde(default)]
+    pub predicates: Vec<PredicateSpec>,
+    #[serde(default)]
+    pub guarantees: Vec<PropertySpec>,
+    #[serde(default)]
+    pub assumes: Vec<PropertySpec>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChannelSpec {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub r#type: String,
+    /// No capacity means unbounded; zero capacity means handshake.
+    #[serde(default)]
+    pub capacity: Option<usize>,
+    /// Broadcast channels deliver each message to all receiving processes.
+    #[serde(default)]
+    pub broadcast: bool,
+    /// The value of the channel seen by predicates before anything is sent through it.
+    #[serde(default)]
+    pub init: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProcessSpec {
+    pub name: String,
+    #[serde(default)]
+    pub variables: Vec<VariableSpec>,
+    #[serde(default)]
+    pub clocks: Vec<String>,
+    /// The first location is the initial one.
+    pub locations: Vec<LocationSpec>,
+    #[serde(default)]
+    pub actions: Vec<ActionSpec>,
+    #[serde(default)]
+    pub transitions: Vec<TransitionSpec>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VariableSpec {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub r#type: String,
+    /// Defaults to the default value of the type.
+    #[serde(default)]
+    pub init: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LocationSpec {
+    pub name: String,
+    #[serde(default)]
+    pub invariants: Vec<ConstraintSpec>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConstraintSpec {
+    pub clock: String,
+    #[serde(default)]
+    pub lower: Option<Time>,
+    #[serde(default)]
+    pub upper: Option<Time>,
+}
+
+/// An action is either a communication (`send`, `receive`, `probe_empty` or `probe_full`),
+/// or performs `effects` and `resets` clocks.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ActionSpec {
+    pub name: String,
+    #[serde(default)]
+    pub effects: Vec<EffectSpec>,
+    #[serde(default)]
+    pub resets: Vec<String>,
+    #[serde(default)]
+    pub send: Option<SendSpec>,
+    #[serde(default)]
+    pub receive: Option<ReceiveSpec>,
+    #[serde(default)]
+    pub probe_empty: Option<String>,
+    #[serde(default)]
+    pub probe_full: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EffectSpec {
+    pub var: String,
+    pub expr: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SendSpec {
+    pub channel: String,
+    pub expr: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReceiveSpec {
+    pub channel: String,
+    pub var: String,
+}
+
+/// A transition without action is autonomous.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TransitionSpec {
+    pub from: String,
+    pub to: String,
+    #[serde(default)]
+    pub action: Option<String>,
+    #[serde(default)]
+    pub guard: Option<String>,
+    #[serde(default)]
+    pub constraints: Vec<ConstraintSpec>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PredicateSpec {
+    pub name: String,
+    pub expr: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PropertySpec {
+    pub name: String,
+    pub formula: FormulaSpec,
+}
+
+/// A PMTL formula over predicates.
+/// Time bounds default to `0` (lower) and unbounded (upper).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum FormulaSpec {
+    True,
+    False,
+    Predicate(String),
+    And(Vec<FormulaSpec>),
+    Or(Vec<FormulaSpec>),
+    Not(Box<FormulaSpec>),
+    Implies(Box<(FormulaSpec, FormulaSpec)>),
+    Historically {
+        formula: Box<FormulaSpec>,
+        #[serde(default)]
+        lower: Time,
+        #[serde(default = "unbounded")]
+        upper: Time,
+    },
+    Previously {
+        formula: Box<FormulaSpec>,
+        #[serde(default)]
+        lower: Time,
+        #[serde(default = "unbounded")]
+        upper: Time,
+    },
+    Since {
+        lhs: Box<FormulaSpec>,
+        rhs: Box<FormulaSpec>,
+        #[serde(default)]
+        lower: Time,
+        #[serde(default = "unbounded")]
+        upper: Time,
+    },
+}
+
+fn unbounded() -> Time {
+    Time::MAX
+}
@@ -0,0 +1,73 @@
+use anyhow::anyhow;
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+const MAXSTEP: usize = 1000;
+
+#[test]
+fn pingpong() -> anyhow::Result<()> {
+    test(&PathBuf::from_str("./tests/assets/pingpong.json")?)
+}
+
+#[test]
+fn syntax_error() -> anyhow::Result<()> {
+    let err = test(&PathBuf::from_str("./tests/assets/syntax_error.json")?)
+        .expect_err("misspelled field");
+    let err = format!("{err:#}");
+    assert!(err.contains("syntax_error.json"));
+    assert!(err.contains("unknown field `guards`"));
+    assert!(err.contains("line 7 column"));
+    Ok(())
+}
+
+#[test]
+fn type_error() -> anyhow::Result<()> {
+    let err = test(&PathBuf::from_str("./tests/assets/type_error.json")?)
+        .expect_err("integer variable used as guard");
+    let err = format!("{err:#}");
+    assert!(err.contains("in processes[0] 'p'"));
+    assert!(err.contains("in transitions[0] from 'l' to 'l'"));
+    assert!(err.contains("in attribute 'guard'"));
+    assert!(err.contains("in sub-expression `c`"));
+    assert!(err.contains("expected bool, found int"));
+    Ok(())
+}
+
+#[test]
+fn unknown_var() -> anyhow::Result<()> {
+    let err = test(&PathBuf::from_str("./tests/assets/unknown_var.json")?)
+        .expect_err("undeclared variable");
+    let err = format!("{err:#}");
+    assert!(err.contains("in actions[0] 'a'"));
+    assert!(err.contains("at column 5 of \"x + y\""));
+    Ok(())
+}
+
+#[test]
+fn version() -> anyhow::Result<()> {
+    let err =
+        test(&PathBuf::from_str("./tests/assets/version.json")?).expect_err("unsupported version");
+    assert!(format!("{err:#}").contains("version 2"));
+    Ok(())
+}
+
+fn test(path: &Path) -> anyhow::Result<()> {
+    let mut model = scan_fmt_json::load(path)?.model.channel_system().to_owned();
+    let mut steps = 0;
+    assert!(model.possible_transitions().count() > 0);
+    while let Some((pg_id, act, loc)) = model
+        .possible_transitions()
+        .take(1)
+        .collect::<Vec<_>>()
+        .pop()
+    {
+        model.transition(pg_id, act, loc)?;
+        steps += 1;
+        if steps >= MAXSTEP {
+            return Err(anyhow!("step limit reached"));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,122 @@
+//! The abstract syntax of the subset of Promela supported by SCAN.
+
+use scan_core::Integer;
+use std::fmt;
+
+/// A position in the source text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Pos {
+    pub(crate) line: usize,
+    pub(crate) column: usize,
+}
+
+impl fmt::Display for Pos {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+/// A Promela specification.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Spec {
+    /// The symbolic constants declared by `mtype`, in order.
+    pub(crate) mtypes: Vec<(String, Pos)>,
+    pub(crate) channels: Vec<ChanDecl>,
+    pub(crate) processes: Vec<Proctype>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TypeName {
+    Bit,
+    Bool,
+    Byte,
+    Short,
+    Int,
+    Mtype,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct ChanDecl {
+    pub(crate) name: String,
+    pub(crate) capacity: Expr,
+    /// The types of the fields of the messages.
+    pub(crate) fields: Vec<TypeName>,
+    pub(crate) pos: Pos,
+}
+
+/// An active process type, or the `init` process.
+#[derive(Debug, Clone)]
+pub(crate) struct Proctype {
+    pub(crate) name: String,
+    /// The number of instances that are created.
+    pub(crate) instances: Expr,
+    pub(crate) body: Vec<Stmt>,
+    pub(crate) pos: Pos,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Stmt {
+    pub(crate) kind: StmtKind,
+    pub(crate) pos: Pos,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum StmtKind {
+    /// Declaration of local variables, with optional initial values.
+    Decl(TypeName, Vec<(String, Option<Expr>)>),
+    /// A condition, which blocks until it holds.
+    Cond(Expr),
+    Skip,
+    Assign(String, Expr),
+    Send(String, Vec<Expr>),
+    Receive(String, Vec<String>),
+    /// Blocks until the channel is empty.
+    Empty(String),
+    /// Blocks until the channel is full.
+    Full(String),
+    Assert(Expr),
+    /// Statements without effect on the model, such as `printf`.
+    Print,
+    If(Vec<Vec<Stmt>>),
+    Do(Vec<Vec<Stmt>>),
+    Else,
+    Break,
+    Goto(String),
+    Label(String, Box<Stmt>),
+    /// A sequence of statements, executed atomically if the flag is set.
+    Block(Vec<Stmt>, bool),
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum Expr {
+    Int(Integer),
+    Bool(bool),
+    Name(String, Pos),
+    Unary(UnOp, Box<Expr>),
+    Binary(BinOp, Box<(Expr, Expr)>),
+    /// The conditional expression `(c -> a : b)`.
+    Cond(Box<(Expr, Expr, Expr)>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum UnOp {
+    Not,
+    Neg,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BinOp {
+    Or,
+    And,
+    Eq,
+    Neq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+}
@@ -0,0 +1,736 @@
+//! Model builder for Promela specifications.
+//!
+//! Every instance of an active process becomes a program graph,
+//! whose locations are the control points of the process
+//! and whose transitions are its basic statements:
+//!
+//! - conditions become guarded autonomous transitions;
+//! - assignments become actions with effects;
+//! - sends, receives and `empty`/`full` checks become communication actions;
+//! - the options of `if` and `do` statements start from the same location,
+//!   so that one among the executable ones is chosen nondeterministically.
+
+use crate::ast::*;
+use anyhow::{anyhow, bail, Context};
+use log::{info, warn};
+use scan_core::{
+    channel_system::*,
+    program_graph::{Urgency, Var as PgVar},
+    *,
+};
+use std::collections::{HashMap, HashSet};
+
+/// A model loaded from a Promela specification,
+/// together with the names used in the specification.
+#[derive(Debug, Clone)]
+pub struct PromelaModel {
+    pub model: CsModel,
+    /// The guarantee that no assertion is ever violated, if the specification has any assertions.
+    pub guarantees: Vec<Pmtl<Atom<Event>>>,
+    pub assumes: Vec<Pmtl<Atom<Event>>>,
+    // Associates each PG with the name of the process instance it represents.
+    pub pg_names: HashMap<PgId, String>,
+    // Associates the variables of each PG with their names.
+    pub vars: HashMap<(PgId, PgVar), String>,
+    pub channel_names: HashMap<Channel, String>,
+}
+
+pub(crate) struct ModelBuilder {
+    cs: ChannelSystemBuilder,
+    overflow: Overflow,
+    mtypes: HashMap<String, Integer>,
+    // Channels, with the types of the fields of their messages.
+    channels: HashMap<String, (Channel, Vec<Type>)>,
+    // The channel where assertion violations are reported, if any assertion has been built.
+    assertions: Option<Channel>,
+    pg_names: HashMap<PgId, String>,
+    vars: HashMap<(PgId, PgVar), String>,
+}
+
+impl ModelBuilder {
+    pub(crate) fn build(spec: &Spec, overflow: Overflow) -> anyhow::Result<PromelaModel> {
+        let mut builder = ModelBuilder {
+            cs: ChannelSystemBuilder::new(),
+            overflow,
+            mtypes: HashMap::new(),
+            channels: HashMap::new(),
+            assertions: None,
+            pg_names: HashMap::new(),
+            vars: HashMap::new(),
+        };
+        builder.cs.set_overflow(overflow);
+
+        // Symbolic constants are numbered from 1, as 0 is the default value of variables.
+        for (idx, (name, pos)) in spec.mtypes.iter().enumerate() {
+            if builder
+                .mtypes
+                .insert(name.to_owned(), idx as Integer + 1)
+                .is_some()
+            {
+                bail!("duplicate mtype '{name}' at {pos}");
+            }
+        }
+
+        info!("Building channels");
+        for chan in spec.channels.iter() {
+            builder
+                .build_channel(chan)
+                .with_context(|| format!("in channel '{}' at {}", chan.name, chan.pos))?;
+        }
+
+        info!("Building processes");
+        let mut pid = 0;
+        for proctype in spec.processes.iter() {
+            let context = || format!("in proctype '{}' at {}", proctype.name, proctype.pos);
+            let instances = builder
+                .constant(&proctype.instances)
+                .context("in number of instances")
+                .with_context(context)?;
+            if instances == 0 {
+                warn!(
+                    "proctype '{}' is not active, and is never instantiated",
+                    proctype.name
+                );
+            }
+            for idx in 0..instances {
+                let name = if instances == 1 {
+                    proctype.name.to_owned()
+                } else {
+                    format!("{}[{idx}]", proctype.name)
+                };
+                builder
+                    .build_process(name, pid, &proctype.body)
+                    .with_context(context)?;
+                pid += 1;
+            }
+        }
+
+        let mut ports = Vec::new();
+        let mut predicates = Vec::new();
+        let mut guarantees = Vec::new();
+        let mut channel_names: HashMap<Channel, String> = builder
+            .channels
+            .iter()
+            .map(|(name, (chn, _))| (*chn, name.to_owned()))
+            .collect();
+        if let Some(assertions) = builder.assertions {
+            // The channel only ever receives `false`, when an assertion is violated.
+            ports.push((assertions, Val::Boolean(true)));
+            predicates.push(Expression::Var(assertions, Type::Boolean));
+            guarantees.push(Pmtl::Atom(Atom::Predicate(0)));
+            channel_names.insert(assertions, "assertions".to_owned());
+        }
+        let model = CsModelDef {
+            cs: builder.cs,
+            ports,
+            predicates,
+        }
+        .build();
+        Ok(PromelaModel {
+            model,
+            guarantees,
+            assumes: Vec::new(),
+            pg_names: builder.pg_names,
+            vars: builder.vars,
+            channel_names,
+        })
+    }
+
+    fn build_channel(&mut self, chan: &ChanDecl) -> anyhow::Result<()> {
+        let capacity = self.constant(&chan.capacity).context("in capacity")?;
+        let fields: Vec<Type> = chan.fields.iter().map(|field| field_type(*field)).collect();
+        let r#type = if let [r#type] = fields.as_slice() {
+            r#type.to_owned()
+        } else {
+            Type::Product(fields.clone())
+        };
+        let channel = self.cs.new_channel(r#type, Some(capacity as usize));
+        if self.mtypes.contains_key(&chan.name)
+            || self
+                .channels
+                .insert(chan.name.to_owned(), (channel, fields))
+                .is_some()
+        {
+            bail!("name is already in use");
+        }
+        Ok(())
+    }
+
+    fn build_process(&mut self, name: String, pid: Integer, body: &[Stmt]) -> anyhow::Result<()> {
+        let pg_id = self.cs.new_program_graph();
+        self.pg_names.insert(pg_id, name);
+        let initial = self.cs.initial_location(pg_id)?;
+        let end = self.cs.new_location(pg_id)?;
+        let mut process = ProcessBuilder {
+            model: self,
+            pg_id,
+            pid,
+            locals: HashMap::new(),
+            temps: HashMap::new(),
+            labels: HashMap::new(),
+            defined: HashSet::new(),
+            breaks: Vec::new(),
+            atomic: false,
+        };
+        process.sequence(body, initial, end, false)?;
+        if let Some(label) = process
+            .labels
+            .keys()
+            .find(|label| !process.defined.contains(*label))
+        {
+            bail!("undefined label '{label}'");
+        }
+        Ok(())
+    }
+
+    // Evaluates a constant, non-negative integer expression.
+    fn constant(&self, expr: &Expr) -> anyhow::Result<Integer> {
+        let expr = expression(expr, &|name, pos| {
+            self.mtypes
+                .get(name)
+                .map(|val| Expression::from(*val))
+                .ok_or_else(|| anyhow!("'{name}' at {pos} is not a constant"))
+        })
+        .and_then(to_int)?;
+        match expr.eval_constant(self.overflow) {
+            Some(Ok(Val::Integer(val))) if val >= 0 => Ok(val),
+            Some(Err(err)) => Err(err.into()),
+            _ => bail!("expected a non-negative integer constant"),
+        }
+    }
+
+    // The channel reporting assertion violations, created on first use.
+    fn assertions(&mut self) -> Channel {
+        *self
+            .assertions
+            .get_or_insert_with(|| self.cs.new_channel(Type::Boolean, None))
+    }
+}
+
+struct ProcessBuilder<'a> {
+    model: &'a mut ModelBuilder,
+    pg_id: PgId,
+    pid: Integer,
+    locals: HashMap<String, (Var, Type)>,
+    // Variables receiving the messages with multiple fields from each channel.
+    temps: HashMap<Channel, Var>,
+    // Locations of labels, including those that are yet to be defined.
+    labels: HashMap<String, Location>,
+    defined: HashSet<String>,
+    // Where `break` jumps to, for the enclosing `do` loops.
+    breaks: Vec<Location>,
+    // Whether the statements being built are in an `atomic` or `d_step` sequence.
+    atomic: bool,
+}
+
+impl ProcessBuilder<'_> {
+    // Builds a sequence of statements between the given locations.
+    //
+    // A location is `shared` if other options of an `if` or `do` statement also start from it,
+    // so that the sequence has to start with a transition representing its first statement.
+    fn sequence(
+        &mut self,
+        stmts: &[Stmt],
+        pre: Location,
+        post: Location,
+        shared: bool,
+    ) -> anyhow::Result<()> {
+        // Local variables are all initialized when the process starts, as in Spin.
+        let mut body = Vec::new();
+        for stmt in stmts {
+            if let StmtKind::Decl(r#type, vars) = &stmt.kind {
+                self.declare(*r#type, vars)
+                    .with_context(|| format!("in declaration at {}", stmt.pos))?;
+            } else {
+                body.push(stmt);
+            }
+        }
+        if body.is_empty() {
+            return self.jump(pre, post, None);
+        }
+        let mut current = pre;
+        for (idx, stmt) in body.iter().enumerate() {
+            let next = if idx + 1 == body.len() {
+                post
+            } else {
+                self.location()?
+            };
+            self.statement(stmt, current, next, shared && idx == 0)
+                .with_context(|| format!("in statement at {}", stmt.pos))?;
+            current = next;
+        }
+        Ok(())
+    }
+
+    fn declare(&mut self, r#type: TypeName, vars: &[(String, Option<Expr>)]) -> anyhow::Result<()> {
+        for (name, init) in vars {
+            let var_type = var_type(r#type);
+            let init = match init {
+                Some(init) => coerce(self.expression(init)?, &var_type.unbounded())?,
+                None => Expression::Const(var_type.default_value()),
+            };
+            let var = self
+                .model
+                .cs
+                .new_var_with_type(self.pg_id, init, var_type.clone())
+                .with_context(|| format!("in initial value of '{name}'"))?;
+            if name == "_pid"
+                || self.model.mtypes.contains_key(name)
+                || self.model.channels.contains_key(name)
+                || self
+                    .locals
+                    .insert(name.to_owned(), (var, var_type.unbounded()))
+                    .is_some()
+            {
+                bail!("name '{name}' is already in use");
+            }
+            self.model.vars.insert(var.into(), name.to_owned());
+        }
+        Ok(())
+    }
+
+    fn statement(
+        &mut self,
+        stmt: &Stmt,
+        pre: Location,
+        post: Location,
+        shared: bool,
+    ) -> anyhow::Result<()> {
+        let pg_id = self.pg_id;
+        match &stmt.kind {
+            StmtKind::Decl(..) => unreachable!("declarations are handled by sequences"),
+            StmtKind::Cond(expr) => {
+                let guard = self.expression(expr).and_then(to_bool)?;
+                self.jump(pre, post, Some(guard))
+            }
+            StmtKind::Skip | StmtKind::Print => self.jump(pre, post, None),
+            StmtKind::Assign(name, expr) => {
+                let (var, r#type) = self.var(name)?;
+                let expr = coerce(self.expression(expr)?, &r#type)?;
+                let action = self.model.cs.new_action(pg_id)?;
+                self.model.cs.add_effect(pg_id, action, var, expr)?;
+                self.model
+                    .cs
+                    .add_transition(pg_id, pre, action, post, None)?;
+                Ok(())
+            }
+            StmtKind::Send(name, args) => {
+                let (channel, fields) = self.channel(name)?;
+                if args.len() != fields.len() {
+                    bail!(
+                        "channel '{name}' has {} fields, but {} values are sent",
+                        fields.len(),
+                        args.len()
+                    );
+                }
+                let mut values = args
+                    .iter()
+                    .zip(fields.iter())
+                    .map(|(arg, r#type)| coerce(self.expression(arg)?, r#type))
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                let msg = if values.len() == 1 {
+                    values.pop().expect("one value")
+                } else {
+                    Expression::Tuple(values)
+                };
+                let action = self.model.cs.new_send(pg_id, channel, msg)?;
+                self.model
+                    .cs
+                    .add_transition(pg_id, pre, action, post, None)?;
+                Ok(())
+            }
+            StmtKind::Receive(name, args) => self.receive(name, args, pre, post),
+            StmtKind::Empty(name) => {
+                let (channel, _) = self.channel(name)?;
+                let action = self.model.cs.new_probe_empty_queue(pg_id, channel)?;
+                self.model
+                    .cs
+                    .add_transition(pg_id, pre, action, post, None)?;
+                Ok(())
+            }
+            StmtKind::Full(name) => {
+                let (channel, _) = self.channel(name)?;
+                let action = self.model.cs.new_probe_full_queue(pg_id, channel)?;
+                self.model
+                    .cs
+                    .add_transition(pg_id, pre, action, post, None)?;
+                Ok(())
+            }
+            StmtKind::Assert(expr) => {
+                let holds = self.expression(expr).and_then(to_bool)?;
+                self.jump(pre, post, Some(holds.clone()))?;
+                // A violation is reported, and then the process stops.
+                let assertions = self.model.assertions();
+                let report = self
+                    .model
+                    .cs
+                    .new_send(pg_id, assertions, Expression::from(false))?;
+                let failed = self.location()?;
+                self.model
+                    .cs
+                    .add_transition(pg_id, pre, report, failed, Some(!holds))?;
+                Ok(())
+            }
+            StmtKind::If(options) => self.options(options, pre, post),
+            StmtKind::Do(options) => {
+                let head = self.entry(stmt, pre, shared)?;
+                self.breaks.push(post);
+                let result = self.options(options, head, head);
+                self.breaks.pop();
+                result
+            }
+            StmtKind::Else => bail!("'else' can only be the first statement of an option"),
+            StmtKind::Break => {
+                let target = *self
+                    .breaks
+                    .last()
+                    .ok_or_else(|| anyhow!("'break' outside of a loop"))?;
+                self.jump(pre, target, None)
+            }
+            StmtKind::Goto(label) => {
+                let target = self.label(label)?;
+                self.jump(pre, target, None)
+            }
+            StmtKind::Label(label, inner) => {
+                if !self.defined.insert(label.to_owned()) {
+                    bail!("duplicate label '{label}'");
+                }
+                let target = self.label(label)?;
+                let entry = self.entry(inner, pre, shared)?;
+                self.jump(entry, target, None)?;
+                self.statement(inner, target, post, false)
+            }
+            StmtKind::Block(stmts, atomic) => {
+                let outer = self.atomic;
+                self.atomic |= *atomic;
+                let result = self.sequence(stmts, pre, post, shared);
+                self.atomic = outer;
+                result
+            }
+        }
+    }
+
+    // The location where a compound statement starts.
+    //
+    // If the given location is shared with other options,
+    // a new location is entered through a transition guarded by the executability of the statement.
+    fn entry(&mut self, stmt: &Stmt, pre: Location, shared: bool) -> anyhow::Result<Location> {
+        if shared {
+            let guard = self.executable(stmt)?;
+            let entry = self.location()?;
+            self.jump(pre, entry, Some(guard))?;
+            Ok(entry)
+        } else {
+            // No other transition leaves the location, so the statement can start from there.
+            Ok(pre)
+        }
+    }
+
+    fn options(
+        &mut self,
+        options: &[Vec<Stmt>],
+        pre: Location,
+        post: Location,
+    ) -> anyhow::Result<()> {
+        let else_options = options
+            .iter()
+            .filter(|option| matches!(option[0].kind, StmtKind::Else))
+            .count();
+        if else_options > 1 {
+            bail!("at most one option can start with 'else'");
+        }
+        for option in options {
+            if matches!(option[0].kind, StmtKind::Else) {
+                // The `else` option is executable when no other option is.
+                let others = options
+                    .iter()
+                    .filter(|other| !matches!(other[0].kind, StmtKind::Else))
+                    .map(|other| self.executable(&other[0]))
+                    .collect::<anyhow::Result<Vec<_>>>()
+                    .with_context(|| format!("in 'else' at {}", option[0].pos))?;
+                let guard = !Expression::or(others);
+                if option.len() == 1 {
+                    self.jump(pre, post, Some(guard))?;
+                } else {
+                    let next = self.location()?;
+                    self.jump(pre, next, Some(guard))?;
+                    self.sequence(&option[1..], next, post, false)?;
+                }
+            } else {
+                self.sequence(option, pre, post, true)?;
+            }
+        }
+        Ok(())
+    }
+
+    // The condition under which a statement is executable,
+    // which can only be computed for statements that do not start with a communication.
+    fn executable(&self, stmt: &Stmt) -> anyhow::Result<CsExpression> {
+        match &stmt.kind {
+            StmtKind::Cond(expr) => self.expression(expr).and_then(to_bool),
+            StmtKind::Decl(..)
+            | StmtKind::Skip
+            | StmtKind::Assign(..)
+            | StmtKind::Assert(_)
+            | StmtKind::Print
+            | StmtKind::Break
+            | StmtKind::Goto(_) => Ok(Expression::from(true)),
+            StmtKind::Label(_, stmt) => self.executable(stmt),
+            StmtKind::Block(stmts, _) => stmts
+                .iter()
+                .find(|stmt| !matches!(stmt.kind, StmtKind::Decl(..)))
+                .map_or(Ok(Expression::from(true)), |stmt| self.executable(stmt)),
+            StmtKind::If(options) | StmtKind::Do(options) => options
+                .iter()
+                .map(|option| self.executable(&option[0]))
+                .collect::<anyhow::Result<Vec<_>>>()
+                .map(Expression::or),
+            StmtKind::Else => bail!("nested 'else' at {}", stmt.pos),
+            StmtKind::Send(..) | StmtKind::Receive(..) | StmtKind::Empty(_) | StmtKind::Full(_) => {
+                bail!(
+                    "the executability of the communication at {} cannot be determined in advance",
+                    stmt.pos
+                )
+            }
+        }
+    }
+
+    fn receive(
+        &mut self,
+        name: &str,
+        args: &[String],
+        pre: Location,
+        post: Location,
+    ) -> anyhow::Result<()> {
+        let pg_id = self.pg_id;
+        let (channel, fields) = self.channel(name)?;
+        if args.len() != fields.len() {
+            bail!(
+                "channel '{name}' has {} fields, but {} variables receive them",
+                fields.len(),
+                args.len()
+            );
+        }
+        let vars = args
+            .iter()
+            .zip(fields.iter())
+            .map(|(arg, field)| {
+                let (var, r#type) = self.var(arg)?;
+                if r#type != *field {
+                    bail!("variable '{arg}' of type {type} cannot receive values of type {field}");
+                }
+                Ok(var)
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        if let [var] = vars.as_slice() {
+            let action = self.model.cs.new_receive(pg_id, channel, *var)?;
+            self.model
+                .cs
+                .add_transition(pg_id, pre, action, post, None)?;
+        } else {
+            // The message is received as a whole, and then unpacked into the variables.
+            let r#type = Type::Product(fields.clone());
+            let temp = match self.temps.get(&channel) {
+                Some(temp) => *temp,
+                None => {
+                    let temp = self.model.cs.new_var_with_type(
+                        pg_id,
+                        Expression::Const(r#type.default_value()),
+                        r#type.clone(),
+                    )?;
+                    self.temps.insert(channel, temp);
+                    temp
+                }
+            };
+            let action = self.model.cs.new_receive(pg_id, channel, temp)?;
+            let received = self.location()?;
+            self.model
+                .cs
+                .add_transition(pg_id, pre, action, received, None)?;
+            let unpack = self.model.cs.new_action(pg_id)?;
+            for (idx, var) in vars.into_iter().enumerate() {
+                let field =
+                    Expression::Component(idx, Box::new(Expression::Var(temp, r#type.clone())));
+                self.model.cs.add_effect(pg_id, unpack, var, field)?;
+            }
+            self.model
+                .cs
+                .add_transition(pg_id, received, unpack, post, None)?;
+        }
+        Ok(())
+    }
+
+    fn jump(
+        &mut self,
+        pre: Location,
+        post: Location,
+        guard: Option<CsExpression>,
+    ) -> anyhow::Result<()> {
+        self.model
+            .cs
+            .add_autonomous_transition(self.pg_id, pre, post, guard)?;
+        Ok(())
+    }
+
+    // A new location, which is atomic within `atomic` and `d_step` sequences,
+    // so that once the sequence starts no other process can interleave with it (unless it blocks).
+    fn location(&mut self) -> anyhow::Result<Location> {
+        let location = if self.atomic {
+            self.model
+                .cs
+                .new_location_with_urgency(self.pg_id, Urgency::Atomic)?
+        } else {
+            self.model.cs.new_location(self.pg_id)?
+        };
+        Ok(location)
+    }
+
+    fn label(&mut self, label: &str) -> anyhow::Result<Location> {
+        if let Some(location) = self.labels.get(label) {
+            Ok(*location)
+        } else {
+            let location = self.location()?;
+            self.labels.insert(label.to_owned(), location);
+            Ok(location)
+        }
+    }
+
+    fn var(&self, name: &str) -> anyhow::Result<(Var, Type)> {
+        self.locals
+            .get(name)
+            .cloned()
+            .ok_or_else(|| anyhow!("unknown variable '{name}'"))
+    }
+
+    fn channel(&self, name: &str) -> anyhow::Result<(Channel, Vec<Type>)> {
+        self.model
+            .channels
+            .get(name)
+            .cloned()
+            .ok_or_else(|| anyhow!("unknown channel '{name}'"))
+    }
+
+    fn expression(&self, expr: &Expr) -> anyhow::Result<CsExpression> {
+        expression(expr, &|name, pos| {
+            if let Some((var, r#type)) = self.locals.get(name) {
+                Ok(Expression::Var(*var, r#type.clone()))
+            } else if let Some(val) = self.model.mtypes.get(name) {
+                Ok(Expression::from(*val))
+            } else if name == "_pid" {
+                Ok(Expression::from(self.pid))
+            } else if self.model.channels.contains_key(name) {
+                bail!("channel '{name}' at {pos} cannot be used as a value")
+            } else {
+                bail!("unknown name '{name}' at {pos}")
+            }
+        })
+    }
+}
+
+// Translates an expression, resolving names through the given function.
+fn expression(
+    expr: &Expr,
+    resolve: &dyn Fn(&str, Pos) -> anyhow::Result<CsExpression>,
+) -> anyhow::Result<CsExpression> {
+    match expr {
+        Expr::Int(int) => Ok(Expression::from(*int)),
+        Expr::Bool(bool) => Ok(Expression::from(*bool)),
+        Expr::Name(name, pos) => resolve(name, *pos),
+        Expr::Unary(UnOp::Not, arg) => Ok(!to_bool(expression(arg, resolve)?)?),
+        Expr::Unary(UnOp::Neg, arg) => Ok(-to_int(expression(arg, resolve)?)?),
+        Expr::Binary(op, args) => {
+            let lhs = expression(&args.0, resolve)?;
+            let rhs = expression(&args.1, resolve)?;
+            let ints = || Ok::<_, anyhow::Error>((to_int(lhs.clone())?, to_int(rhs.clone())?));
+            match op {
+                BinOp::Or => Ok(Expression::or(vec![to_bool(lhs)?, to_bool(rhs)?])),
+                BinOp::And => Ok(Expression::and(vec![to_bool(lhs)?, to_bool(rhs)?])),
+                BinOp::Eq | BinOp::Neq => {
+                    let equal = if lhs.r#type()? == Type::Boolean && rhs.r#type()? == Type::Boolean
+                    {
+                        Expression::Equal(Box::new((lhs, rhs)))
+                    } else {
+                        Expression::Equal(Box::new(ints()?))
+                    };
+                    Ok(if *op == BinOp::Eq { equal } else { !equal })
+                }
+                BinOp::Lt => Ok(Expression::Less(Box::new(ints()?))),
+                BinOp::Le => Ok(Expression::LessEq(Box::new(ints()?))),
+                BinOp::Gt => Ok(Expression::Greater(Box::new(ints()?))),
+                BinOp::Ge => Ok(Expression::GreaterEq(Box::new(ints()?))),
+                BinOp::Add => {
+                    let (lhs, rhs) = ints()?;
+                    Ok(Expression::Sum(vec![lhs, rhs]))
+                }
+                BinOp::Sub => {
+                    let (lhs, rhs) = ints()?;
+                    Ok(Expression::Sum(vec![lhs, -rhs]))
+                }
+                BinOp::Mul => {
+                    let (lhs, rhs) = ints()?;
+                    Ok(Expression::Mult(vec![lhs, rhs]))
+                }
+                BinOp::Div => Ok(Expression::Div(Box::new(ints()?))),
+                BinOp::Mod => Ok(Expression::Mod(Box::new(ints()?))),
+            }
+        }
+        Expr::Cond(args) => {
+            let cond = to_bool(expression(&args.0, resolve)?)?;
+            let then = expression(&args.1, resolve)?;
+            let otherwise = expression(&args.2, resolve)?;
+            let (then, otherwise) =
+                if then.r#type()? == Type::Boolean && otherwise.r#type()? == Type::Boolean {
+                    (then, otherwise)
+                } else {
+                    (to_int(then)?, to_int(otherwise)?)
+                };
+            Ok(Expression::Ite(Box::new((cond, then, otherwise))))
+        }
+    }
+}
+
+// Integers are used as conditions by comparing them with zero, as in C.
+fn to_bool(expr: CsExpression) -> anyhow::Result<CsExpression> {
+    match expr.r#type()? {
+        Type::Boolean => Ok(expr),
+        Type::Integer => Ok(!Expression::Equal(Box::new((expr, Expression::from(0))))),
+        r#type => bail!("expected a condition, found type {type}"),
+    }
+}
+
+// Booleans are used as integers by converting them to 0 or 1.
+fn to_int(expr: CsExpression) -> anyhow::Result<CsExpression> {
+    match expr.r#type()? {
+        Type::Integer => Ok(expr),
+        Type::Boolean => Ok(Expression::Ite(Box::new((
+            expr,
+            Expression::from(1),
+            Expression::from(0),
+        )))),
+        r#type => bail!("expected an integer, found type {type}"),
+    }
+}
+
+fn coerce(expr: CsExpression, r#type: &Type) -> anyhow::Result<CsExpression> {
+    if *r#type == Type::Boolean {
+        to_bool(expr)
+    } else {
+        to_int(expr)
+    }
+}
+
+// The type of the variables declared with the given type name, with the ranges used by Spin.
+fn var_type(name: TypeName) -> Type {
+    match name {
+        TypeName::Bit => Type::Bounded(0, 1),
+        TypeName::Bool => Type::Boolean,
+        TypeName::Byte | TypeName::Mtype => Type::Bounded(0, 255),
+        TypeName::Short => Type::Bounded(i16::MIN as Integer, i16::MAX as Integer),
+        TypeName::Int => Type::Bounded(i32::MIN as Integer, i32::MAX as Integer),
+    }
+}
+
+// The type of the message fields declared with the given type name.
+fn field_type(name: TypeName) -> Type {
+    var_type(name).unbounded()
+}
@@ -0,0 +1,86 @@
+//! Parser and model builder for [Promela](https://spinroot.com/spin/Man/Manual.html) specifications.
+//!
+//! The following subset of Promela is supported:
+//!
+//! - `mtype` declarations, whose constants are numbered from 1 in order of declaration;
+//! - global `chan` declarations with a constant capacity,
+//!   where capacity 0 gives a rendezvous (handshake) channel;
+//! - `active` proctypes (possibly with multiple instances, as in `active [2] proctype`)
+//!   and the `init` process, without parameters;
+//! - local variables of type `bit`, `bool`, `byte`, `short`, `int` and `mtype`;
+//! - conditions, assignments (including `++` and `--`), `skip`, `printf` (which is ignored),
+//!   sends (`c ! e1, e2`), receives into variables (`c ? x, y`), `empty(c)` and `full(c)`;
+//! - `if` and `do` with guarded options and `else`, `break`, labels and `goto`;
+//! - `atomic` and `d_step` sequences;
+//! - `assert`;
+//! - object-like macros (`#define NAME text`).
+//!
+//! Processes can only communicate through channels, so global variables are not supported,
+//! and neither are arrays, dynamic process creation (`run`), `timeout` and temporal claims (`ltl` and `never`),
+//! which are skipped with a warning.
+//!
+//! The resulting model has a single guarantee (if the specification has any assertions),
+//! stating that no assertion is ever violated.
+//! A process violating an assertion stops, and the run fails.
+//!
+//! Nondeterministic choices are resolved uniformly at random.
+//! Since processes do not share variables, SCAN only interleaves them when they communicate.
+//! Once started, `atomic` and `d_step` sequences are executed without interruptions, unless they block.
+//! Unlike Spin, assigning an out-of-range value to a variable is an error, rather than truncating it.
+//!
+//! ```
+//! let model = scan_fmt_promela::parse(r#"
+//!     chan c = [1] of { byte };
+//!
+//!     active proctype sender() {
+//!         byte x;
+//!         do
+//!         :: x < 3 -> c ! x; x++
+//!         :: else -> break
+//!         od
+//!     }
+//!
+//!     active proctype receiver() {
+//!         byte y;
+//!         do :: c ? y -> assert(y < 3) od
+//!     }
+//! "#).expect("valid specification");
+//! assert_eq!(model.pg_names.len(), 2);
+//! assert_eq!(model.guarantees.len(), 1);
+//! ```
+
+mod ast;
+mod builder;
+mod parser;
+
+pub use builder::PromelaModel;
+pub use scan_core;
+
+use anyhow::Context;
+use scan_core::Overflow;
+use std::path::Path;
+
+/// Loads the Promela specification at `path`,
+/// with integer overflows checked.
+pub fn load(path: &Path) -> anyhow::Result<PromelaModel> {
+    load_with_overflow(path, Overflow::default())
+}
+
+/// Loads the Promela specification at `path`, with integer overflows behaving according to `overflow`.
+pub fn load_with_overflow(path: &Path, overflow: Overflow) -> anyhow::Result<PromelaModel> {
+    let text =
+        std::fs::read_to_string(path).with_context(|| format!("cannot read {}", path.display()))?;
+    parse_with_overflow(&text, overflow).with_context(|| format!("in {}", path.display()))
+}
+
+/// Parses a Promela specification from text,
+/// with integer overflows checked.
+pub fn parse(text: &str) -> anyhow::Result<PromelaModel> {
+    parse_with_overflow(text, Overflow::default())
+}
+
+/// Parses a Promela specification from text, with integer overflows behaving according to `overflow`.
+pub fn parse_with_overflow(text: &str, overflow: Overflow) -> anyhow::Result<PromelaModel> {
+    let spec = parser::parse(text)?;
+    builder::ModelBuilder::build(&spec, overflow)
+}
@@ -0,0 +1,724 @@
+//! Lexer and recursive-descent parser for the subset of Promela supported by SCAN.
+//!
+//! Object-like macros (`#define NAME text`) are expanded by the lexer,
+//! while other preprocessor directives are rejected.
+
+use crate::ast::*;
+use anyhow::{anyhow, bail, Context};
+use log::warn;
+use scan_core::Integer;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    Ident(String),
+    Int(Integer),
+    Str,
+    Sym(&'static str),
+}
+
+impl std::fmt::Display for Tok {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Tok::Ident(name) => write!(f, "'{name}'"),
+            Tok::Int(int) => write!(f, "'{int}'"),
+            Tok::Str => write!(f, "string"),
+            Tok::Sym(sym) => write!(f, "'{sym}'"),
+        }
+    }
+}
+
+// Macros, with the tokens they expand to.
+type Macros = HashMap<String, Vec<Tok>>;
+
+// Longer symbols come first, so that they take precedence over their prefixes.
+const SYMBOLS: [&str; 35] = [
+    "::", "->", "==", "!=", "<=", ">=", "&&", "||", "++", "--", "<<", ">>", "!!", "??", "{", "}",
+    "(", ")", "[", "]", ";", ",", ":", "=", "<", ">", "+", "-", "*", "/", "%", "!", "?", "&", "|",
+];
+
+// Characters that are not valid tokens on their own, but are reported as such.
+const UNSUPPORTED_SYMBOLS: [char; 3] = ['^', '~', '.'];
+
+struct Lexer<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    line: usize,
+    column: usize,
+    // Whether only whitespace has been read since the start of the line.
+    line_start: bool,
+    macros: Macros,
+    tokens: Vec<(Tok, Pos)>,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(text: &'a str, line: usize, macros: Macros) -> Self {
+        Lexer {
+            chars: text.chars().peekable(),
+            line,
+            column: 1,
+            line_start: true,
+            macros,
+            tokens: Vec::new(),
+        }
+    }
+
+    fn pos(&self) -> Pos {
+        Pos {
+            line: self.line,
+            column: self.column,
+        }
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let ch = self.chars.next()?;
+        if ch == '\n' {
+            self.line += 1;
+            self.column = 1;
+            self.line_start = true;
+        } else {
+            self.column += 1;
+            if !ch.is_whitespace() {
+                self.line_start = false;
+            }
+        }
+        Some(ch)
+    }
+
+    fn take_while(&mut self, cond: impl Fn(char) -> bool) -> String {
+        let mut taken = String::new();
+        while let Some(&ch) = self.chars.peek() {
+            if !cond(ch) {
+                break;
+            }
+            taken.push(ch);
+            self.bump();
+        }
+        taken
+    }
+
+    fn run(mut self) -> anyhow::Result<(Vec<(Tok, Pos)>, Macros)> {
+        while let Some(&ch) = self.chars.peek() {
+            let pos = self.pos();
+            if ch.is_whitespace() {
+                self.bump();
+            } else if ch == '#' && self.line_start {
+                self.directive()
+                    .with_context(|| format!("in preprocessor directive at {pos}"))?;
+            } else if ch == '/' && self.comment()? {
+                // Comments are skipped.
+            } else if ch.is_ascii_digit() {
+                let digits = self.take_while(|ch| ch.is_ascii_alphanumeric());
+                let int = digits
+                    .parse()
+                    .map_err(|_| anyhow!("invalid number '{digits}' at {pos}"))?;
+                self.tokens.push((Tok::Int(int), pos));
+            } else if ch.is_ascii_alphabetic() || ch == '_' {
+                let name = self.take_while(|ch| ch.is_ascii_alphanumeric() || ch == '_');
+                if let Some(expansion) = self.macros.get(&name) {
+                    self.tokens
+                        .extend(expansion.iter().map(|tok| (tok.clone(), pos)));
+                } else {
+                    self.tokens.push((Tok::Ident(name), pos));
+                }
+            } else if ch == '"' {
+                self.bump();
+                loop {
+                    match self.bump() {
+                        Some('"') => break,
+                        Some('\\') => {
+                            self.bump();
+                        }
+                        Some(_) => {}
+                        None => bail!("unterminated string at {pos}"),
+                    }
+                }
+                self.tokens.push((Tok::Str, pos));
+            } else if UNSUPPORTED_SYMBOLS.contains(&ch) {
+                bail!("unsupported operator '{ch}' at {pos}");
+            } else {
+                let rest = self.chars.clone().take(2).collect::<String>();
+                let sym = SYMBOLS
+                    .iter()
+                    .find(|sym| rest.starts_with(**sym))
+                    .ok_or_else(|| anyhow!("unexpected character '{ch}' at {pos}"))?;
+                for _ in 0..sym.len() {
+                    self.bump();
+                }
+                self.tokens.push((Tok::Sym(sym), pos));
+            }
+        }
+        Ok((self.tokens, self.macros))
+    }
+
+    // Skips a comment, if one starts here.
+    fn comment(&mut self) -> anyhow::Result<bool> {
+        let pos = self.pos();
+        let rest = self.chars.clone().take(2).collect::<String>();
+        if rest == "//" {
+            self.take_while(|ch| ch != '\n');
+            Ok(true)
+        } else if rest == "/*" {
+            self.bump();
+            self.bump();
+            let mut star = false;
+            loop {
+                match self.bump() {
+                    Some('/') if star => return Ok(true),
+                    Some(ch) => star = ch == '*',
+                    None => bail!("unterminated comment at {pos}"),
+                }
+            }
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn directive(&mut self) -> anyhow::Result<()> {
+        self.bump();
+        let directive = self.take_while(|ch| ch.is_ascii_alphabetic());
+        if directive != "define" {
+            bail!("directive '#{directive}' is not supported");
+        }
+        self.take_while(|ch| ch == ' ' || ch == '\t');
+        let name = self.take_while(|ch| ch.is_ascii_alphanumeric() || ch == '_');
+        if name.is_empty() {
+            bail!("missing macro name");
+        } else if self.chars.peek() == Some(&'(') {
+            bail!("macros with parameters are not supported");
+        }
+        let line = self.line;
+        let body = self.take_while(|ch| ch != '\n');
+        let (body, macros) = Lexer::new(&body, line, std::mem::take(&mut self.macros)).run()?;
+        self.macros = macros;
+        self.macros
+            .insert(name, body.into_iter().map(|(tok, _)| tok).collect());
+        Ok(())
+    }
+}
+
+/// Parses the text of a Promela specification.
+pub(crate) fn parse(text: &str) -> anyhow::Result<Spec> {
+    let (tokens, _) = Lexer::new(text, 1, HashMap::new()).run()?;
+    let mut parser = Parser { tokens, idx: 0 };
+    parser.spec()
+}
+
+struct Parser {
+    tokens: Vec<(Tok, Pos)>,
+    idx: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Tok> {
+        self.tokens.get(self.idx).map(|(tok, _)| tok)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<&Tok> {
+        self.tokens.get(self.idx + offset).map(|(tok, _)| tok)
+    }
+
+    // The position of the next token, or of the last one if the input is over.
+    fn pos(&self) -> Pos {
+        self.tokens
+            .get(self.idx)
+            .or(self.tokens.last())
+            .map(|(_, pos)| *pos)
+            .unwrap_or(Pos { line: 1, column: 1 })
+    }
+
+    fn is_sym(&self, sym: &str) -> bool {
+        matches!(self.peek(), Some(Tok::Sym(s)) if *s == sym)
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Tok::Ident(name)) if name == keyword)
+    }
+
+    fn eat_sym(&mut self, sym: &str) -> bool {
+        let found = self.is_sym(sym);
+        if found {
+            self.idx += 1;
+        }
+        found
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = self.is_keyword(keyword);
+        if found {
+            self.idx += 1;
+        }
+        found
+    }
+
+    fn unexpected(&self, expected: &str) -> anyhow::Error {
+        match self.peek() {
+            Some(tok) => anyhow!("unexpected {tok} at {}, expected {expected}", self.pos()),
+            None => anyhow!("unexpected end of input, expected {expected}"),
+        }
+    }
+
+    fn expect_sym(&mut self, sym: &str) -> anyhow::Result<()> {
+        if self.eat_sym(sym) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("'{sym}'")))
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> anyhow::Result<()> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("'{keyword}'")))
+        }
+    }
+
+    fn ident(&mut self) -> anyhow::Result<String> {
+        if let Some(Tok::Ident(name)) = self.peek() {
+            let name = name.to_owned();
+            self.idx += 1;
+            Ok(name)
+        } else {
+            Err(self.unexpected("a name"))
+        }
+    }
+
+    fn type_name(&self) -> Option<TypeName> {
+        match self.peek() {
+            Some(Tok::Ident(name)) => match name.as_str() {
+                "bit" => Some(TypeName::Bit),
+                "bool" => Some(TypeName::Bool),
+                "byte" => Some(TypeName::Byte),
+                "short" => Some(TypeName::Short),
+                "int" => Some(TypeName::Int),
+                "mtype" => Some(TypeName::Mtype),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn spec(&mut self) -> anyhow::Result<Spec> {
+        let mut spec = Spec::default();
+        while let Some(tok) = self.peek() {
+            let pos = self.pos();
+            let Tok::Ident(keyword) = tok else {
+                if self.eat_sym(";") {
+                    continue;
+                }
+                return Err(self.unexpected("a declaration"));
+            };
+            match keyword.as_str() {
+                "mtype" if !matches!(self.peek_at(1), Some(Tok::Ident(_))) => {
+                    self.idx += 1;
+                    self.eat_sym("=");
+                    self.expect_sym("{")?;
+                    loop {
+                        let pos = self.pos();
+                        spec.mtypes.push((self.ident()?, pos));
+                        if !self.eat_sym(",") {
+                            break;
+                        }
+                    }
+                    self.expect_sym("}")?;
+                }
+                "chan" => {
+                    self.idx += 1;
+                    loop {
+                        spec.channels.push(self.chan_decl()?);
+                        if !self.eat_sym(",") {
+                            break;
+                        }
+                    }
+                }
+                "active" | "proctype" => spec.processes.push(self.proctype()?),
+                "init" => {
+                    self.idx += 1;
+                    let body = self.block().context("in init process")?;
+                    spec.processes.push(Proctype {
+                        name: "init".to_owned(),
+                        instances: Expr::Int(1),
+                        body,
+                        pos,
+                    });
+                }
+                "ltl" | "never" => {
+                    let kind = keyword.to_owned();
+                    self.idx += 1;
+                    while !self.is_sym("{") {
+                        if self.peek().is_none() {
+                            return Err(self.unexpected("'{'"));
+                        }
+                        self.idx += 1;
+                    }
+                    self.skip_braces()?;
+                    warn!("skipping {kind} claim at {pos}: temporal claims are not supported");
+                }
+                _ if self.type_name().is_some() => {
+                    bail!("global variables are not supported at {pos}: processes can only communicate through channels");
+                }
+                _ => {
+                    return Err(self.unexpected("a declaration"));
+                }
+            }
+        }
+        Ok(spec)
+    }
+
+    // Skips a block enclosed in (possibly nested) braces.
+    fn skip_braces(&mut self) -> anyhow::Result<()> {
+        self.expect_sym("{")?;
+        let mut depth = 1;
+        while depth > 0 {
+            match self.peek() {
+                Some(Tok::Sym("{")) => depth += 1,
+                Some(Tok::Sym("}")) => depth -= 1,
+                Some(_) => {}
+                None => return Err(self.unexpected("'}'")),
+            }
+            self.idx += 1;
+        }
+        Ok(())
+    }
+
+    fn chan_decl(&mut self) -> anyhow::Result<ChanDecl> {
+        let pos = self.pos();
+        let name = self.ident()?;
+        let context = || format!("in declaration of channel '{name}' at {pos}");
+        if !self.is_sym("=") {
+            return Err(anyhow!("channels must be initialized")).with_context(context);
+        }
+        self.idx += 1;
+        self.expect_sym("[").with_context(context)?;
+        let capacity = self.expr().with_context(context)?;
+        self.expect_sym("]").with_context(context)?;
+        self.expect_keyword("of").with_context(context)?;
+        self.expect_sym("{").with_context(context)?;
+        let mut fields = Vec::new();
+        loop {
+            let field = self
+                .type_name()
+                .ok_or_else(|| self.unexpected("a type"))
+                .with_context(context)?;
+            self.idx += 1;
+            fields.push(field);
+            if !self.eat_sym(",") {
+                break;
+            }
+        }
+        self.expect_sym("}").with_context(context)?;
+        Ok(ChanDecl {
+            name,
+            capacity,
+            fields,
+            pos,
+        })
+    }
+
+    fn proctype(&mut self) -> anyhow::Result<Proctype> {
+        let pos = self.pos();
+        let instances = if self.eat_keyword("active") {
+            if self.eat_sym("[") {
+                let instances = self.expr()?;
+                self.expect_sym("]")?;
+                instances
+            } else {
+                Expr::Int(1)
+            }
+        } else {
+            Expr::Int(0)
+        };
+        self.expect_keyword("proctype")?;
+        let name = self.ident()?;
+        let context = || format!("in proctype '{name}' at {pos}");
+        self.expect_sym("(").with_context(context)?;
+        if !self.eat_sym(")") {
+            return Err(anyhow!("process parameters are not supported")).with_context(context);
+        }
+        let body = self.block().with_context(context)?;
+        Ok(Proctype {
+            name,
+            instances,
+            body,
+            pos,
+        })
+    }
+
+    // Parses a sequence of statements enclosed in braces.
+    fn block(&mut self) -> anyhow::Result<Vec<Stmt>> {
+        self.expect_sym("{")?;
+        let stmts = self.sequence()?;
+        self.expect_sym("}")?;
+        Ok(stmts)
+    }
+
+    // Parses a sequence of statements, up to the first token that cannot continue it.
+    fn sequence(&mut self) -> anyhow::Result<Vec<Stmt>> {
+        let mut stmts = Vec::new();
+        loop {
+            while self.eat_sym(";") || self.eat_sym("->") {}
+            if self.peek().is_none()
+                || self.is_sym("}")
+                || self.is_sym("::")
+                || self.is_keyword("fi")
+                || self.is_keyword("od")
+            {
+                return Ok(stmts);
+            }
+            stmts.push(self.stmt()?);
+            if !(self.is_sym(";") || self.is_sym("->")) {
+                return Ok(stmts);
+            }
+        }
+    }
+
+    fn options(&mut self, end: &str) -> anyhow::Result<Vec<Vec<Stmt>>> {
+        let mut options = Vec::new();
+        while self.eat_sym("::") {
+            let option = self.sequence()?;
+            if option.is_empty() {
+                bail!("empty option at {}", self.pos());
+            }
+            options.push(option);
+        }
+        if options.is_empty() {
+            return Err(self.unexpected("'::'"));
+        }
+        self.expect_keyword(end)?;
+        Ok(options)
+    }
+
+    fn stmt(&mut self) -> anyhow::Result<Stmt> {
+        let pos = self.pos();
+        let kind = self.stmt_kind()?;
+        Ok(Stmt { kind, pos })
+    }
+
+    fn stmt_kind(&mut self) -> anyhow::Result<StmtKind> {
+        let pos = self.pos();
+        if let Some(r#type) = self.type_name() {
+            self.idx += 1;
+            let mut vars = Vec::new();
+            loop {
+                let name = self.ident()?;
+                if self.is_sym("[") {
+                    bail!("arrays are not supported at {}", self.pos());
+                }
+                let init = self.eat_sym("=").then(|| self.expr()).transpose()?;
+                vars.push((name, init));
+                if !self.eat_sym(",") {
+                    break;
+                }
+            }
+            return Ok(StmtKind::Decl(r#type, vars));
+        }
+        if self.is_sym("{") {
+            return Ok(StmtKind::Block(self.block()?, false));
+        }
+        let Some(Tok::Ident(keyword)) = self.peek() else {
+            return Ok(StmtKind::Cond(self.expr()?));
+        };
+        let keyword = keyword.to_owned();
+        match keyword.as_str() {
+            "if" => {
+                self.idx += 1;
+                Ok(StmtKind::If(self.options("fi")?))
+            }
+            "do" => {
+                self.idx += 1;
+                Ok(StmtKind::Do(self.options("od")?))
+            }
+            "atomic" | "d_step" => {
+                self.idx += 1;
+                Ok(StmtKind::Block(self.block()?, true))
+            }
+            "else" => {
+                self.idx += 1;
+                Ok(StmtKind::Else)
+            }
+            "break" => {
+                self.idx += 1;
+                Ok(StmtKind::Break)
+            }
+            "skip" => {
+                self.idx += 1;
+                Ok(StmtKind::Skip)
+            }
+            "goto" => {
+                self.idx += 1;
+                Ok(StmtKind::Goto(self.ident()?))
+            }
+            "assert" => {
+                self.idx += 1;
+                self.expect_sym("(")?;
+                let expr = self.expr()?;
+                self.expect_sym(")")?;
+                Ok(StmtKind::Assert(expr))
+            }
+            "printf" | "printm" => {
+                self.idx += 1;
+                self.expect_sym("(")?;
+                let mut depth = 1;
+                while depth > 0 {
+                    match self.peek() {
+                        Some(Tok::Sym("(")) => depth += 1,
+                        Some(Tok::Sym(")")) => depth -= 1,
+                        Some(_) => {}
+                        None => return Err(self.unexpected("')'")),
+                    }
+                    self.idx += 1;
+                }
+                Ok(StmtKind::Print)
+            }
+            "empty" | "full" => {
+                self.idx += 1;
+                self.expect_sym("(")?;
+                let channel = self.ident()?;
+                self.expect_sym(")")?;
+                if keyword == "empty" {
+                    Ok(StmtKind::Empty(channel))
+                } else {
+                    Ok(StmtKind::Full(channel))
+                }
+            }
+            "nempty" | "nfull" | "len" | "timeout" | "run" | "unless" | "select" | "for"
+            | "chan" => {
+                bail!("'{keyword}' is not supported at {pos}")
+            }
+            _ => match self.peek_at(1) {
+                Some(Tok::Sym(":")) => {
+                    self.idx += 2;
+                    Ok(StmtKind::Label(keyword, Box::new(self.stmt()?)))
+                }
+                Some(Tok::Sym("!")) => {
+                    self.idx += 2;
+                    let mut args = vec![self.expr()?];
+                    while self.eat_sym(",") {
+                        args.push(self.expr()?);
+                    }
+                    Ok(StmtKind::Send(keyword, args))
+                }
+                Some(Tok::Sym("?")) => {
+                    self.idx += 2;
+                    let mut args = Vec::new();
+                    loop {
+                        if !matches!(self.peek(), Some(Tok::Ident(_))) {
+                            bail!(
+                                "only variables can be used as arguments of receive at {}",
+                                self.pos()
+                            );
+                        }
+                        args.push(self.ident()?);
+                        if !self.eat_sym(",") {
+                            break;
+                        }
+                    }
+                    Ok(StmtKind::Receive(keyword, args))
+                }
+                Some(Tok::Sym(sym @ ("!!" | "??"))) => {
+                    bail!("'{sym}' is not supported at {}", self.pos())
+                }
+                Some(Tok::Sym("=")) => {
+                    self.idx += 2;
+                    Ok(StmtKind::Assign(keyword, self.expr()?))
+                }
+                Some(Tok::Sym(sym @ ("++" | "--"))) => {
+                    let op = if *sym == "++" { BinOp::Add } else { BinOp::Sub };
+                    let var = Expr::Name(keyword.clone(), pos);
+                    self.idx += 2;
+                    Ok(StmtKind::Assign(
+                        keyword,
+                        Expr::Binary(op, Box::new((var, Expr::Int(1)))),
+                    ))
+                }
+                _ => Ok(StmtKind::Cond(self.expr()?)),
+            },
+        }
+    }
+
+    fn expr(&mut self) -> anyhow::Result<Expr> {
+        self.binary(0)
+    }
+
+    // Parses binary operators by precedence climbing, from the given level up.
+    fn binary(&mut self, level: usize) -> anyhow::Result<Expr> {
+        const LEVELS: [&[(&str, BinOp)]; 6] = [
+            &[("||", BinOp::Or)],
+            &[("&&", BinOp::And)],
+            &[("==", BinOp::Eq), ("!=", BinOp::Neq)],
+            &[
+                ("<", BinOp::Lt),
+                ("<=", BinOp::Le),
+                (">", BinOp::Gt),
+                (">=", BinOp::Ge),
+            ],
+            &[("+", BinOp::Add), ("-", BinOp::Sub)],
+            &[("*", BinOp::Mul), ("/", BinOp::Div), ("%", BinOp::Mod)],
+        ];
+        let Some(ops) = LEVELS.get(level) else {
+            return self.unary();
+        };
+        let mut lhs = self.binary(level + 1)?;
+        loop {
+            if let Some(Tok::Sym(sym @ ("&" | "|" | "<<" | ">>"))) = self.peek() {
+                bail!("unsupported operator '{sym}' at {}", self.pos());
+            }
+            let Some(op) = ops
+                .iter()
+                .find_map(|(sym, op)| self.is_sym(sym).then_some(*op))
+            else {
+                return Ok(lhs);
+            };
+            self.idx += 1;
+            let rhs = self.binary(level + 1)?;
+            lhs = Expr::Binary(op, Box::new((lhs, rhs)));
+        }
+    }
+
+    fn unary(&mut self) -> anyhow::Result<Expr> {
+        let pos = self.pos();
+        match self.peek() {
+            Some(Tok::Sym("!")) => {
+                self.idx += 1;
+                Ok(Expr::Unary(UnOp::Not, Box::new(self.unary()?)))
+            }
+            Some(Tok::Sym("-")) => {
+                self.idx += 1;
+                Ok(Expr::Unary(UnOp::Neg, Box::new(self.unary()?)))
+            }
+            Some(Tok::Sym("(")) => {
+                self.idx += 1;
+                let expr = self.expr()?;
+                let expr = if self.eat_sym("->") {
+                    let then = self.expr()?;
+                    self.expect_sym(":")?;
+                    let otherwise = self.expr()?;
+                    Expr::Cond(Box::new((expr, then, otherwise)))
+                } else {
+                    expr
+                };
+                self.expect_sym(")")?;
+                Ok(expr)
+            }
+            Some(Tok::Int(int)) => {
+                let int = *int;
+                self.idx += 1;
+                Ok(Expr::Int(int))
+            }
+            Some(Tok::Ident(name)) => {
+                let name = name.to_owned();
+                self.idx += 1;
+                match name.as_str() {
+                    "true" => Ok(Expr::Bool(true)),
+                    "false" => Ok(Expr::Bool(false)),
+                    "len" | "empty" | "nempty" | "full" | "nfull" | "timeout" | "run" => {
+                        bail!("'{name}' is not supported within expressions at {pos}")
+                    }
+                    _ if self.is_sym("[") => bail!("arrays are not supported at {pos}"),
+                    _ => Ok(Expr::Name(name, pos)),
+                }
+            }
+            _ => Err(self.unexpected("an expression")),
+        }
+    }
+}
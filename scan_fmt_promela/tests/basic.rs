@@ -0,0 +1,98 @@
+use scan_fmt_promela::{
+    scan_core::{channel_system::Event, *},
+    PromelaModel,
+};
+use std::{
+    path::PathBuf,
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+#[derive(Clone)]
+struct NoPublisher;
+
+impl Publisher<Event> for NoPublisher {
+    fn init(&mut self) {}
+
+    fn publish(&mut self, _action: &Event, _time: Time, _state: &[bool]) {}
+
+    fn finalize(self, _success: Option<bool>) {}
+}
+
+fn load(name: &str) -> anyhow::Result<PromelaModel> {
+    scan_fmt_promela::load(&PathBuf::from_str(&format!("./tests/assets/{name}"))?)
+}
+
+// Estimates the probability that no assertion is violated.
+fn estimate(model: &PromelaModel) -> anyhow::Result<f64> {
+    let state = Arc::new(Mutex::new((0, 0, true)));
+    model.model.par_adaptive(
+        &model.guarantees,
+        &model.assumes,
+        0.95,
+        0.02,
+        10000,
+        100,
+        None::<NoPublisher>,
+        state.clone(),
+    )?;
+    let (s, f, _) = *state.lock().expect("lock state");
+    Ok(s as f64 / (s + f) as f64)
+}
+
+#[test]
+fn pingpong() -> anyhow::Result<()> {
+    let model = load("pingpong.pml")?;
+    assert_eq!(model.pg_names.len(), 2);
+    assert_eq!(model.guarantees.len(), 1);
+    assert_eq!(estimate(&model)?, 1.);
+    Ok(())
+}
+
+#[test]
+fn coin() -> anyhow::Result<()> {
+    let model = load("coin.pml")?;
+    let rate = estimate(&model)?;
+    assert!((rate - 0.5).abs() < 0.05, "rate {rate}");
+    Ok(())
+}
+
+#[test]
+fn syntax_error() -> anyhow::Result<()> {
+    let err = load("syntax_error.pml").expect_err("missing operand");
+    let err = format!("{err:#}");
+    assert!(err.contains("syntax_error.pml"));
+    assert!(err.contains("unexpected ';' at line 3, column 13"));
+    Ok(())
+}
+
+#[test]
+fn unknown_var() -> anyhow::Result<()> {
+    let err = load("unknown_var.pml").expect_err("undeclared variable");
+    let err = format!("{err:#}");
+    assert!(err.contains("in proctype 'p' at line 3, column 1"));
+    assert!(err.contains("in statement at line 5, column 5"));
+    assert!(err.contains("unknown name 'y' at line 5, column 13"));
+    Ok(())
+}
+
+#[test]
+fn global_var() -> anyhow::Result<()> {
+    let err = load("global_var.pml").expect_err("global variables are not supported");
+    assert!(format!("{err:#}").contains("global variables are not supported at line 1, column 1"));
+    Ok(())
+}
+
+#[test]
+fn rendezvous() -> anyhow::Result<()> {
+    let model = load("rendezvous.pml")?;
+    assert_eq!(estimate(&model)?, 0.);
+    Ok(())
+}
+
+#[test]
+fn atomic() -> anyhow::Result<()> {
+    let model = load("atomic.pml")?;
+    assert_eq!(estimate(&model)?, 1.);
+    Ok(())
+}
@@ -1,12 +1,16 @@
 //! Model builder for SCAN's XML specification format.
 
 use crate::parser::{Executable, Fsm, If, OmgType, OmgTypes, Param, Parser, Scxml, Send, Target};
-use anyhow::anyhow;
+use anyhow::{anyhow, Context};
 use boa_interner::ToInternedString;
 use log::{info, trace};
-use scan_core::{channel_system::*, *};
+use scan_core::{
+    channel_system::*,
+    program_graph::{PgError, Urgency, Var as PgVar},
+    *,
+};
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeSet, HashMap, HashSet},
     ops::Not,
 };
 
@@ -14,21 +18,54 @@ use std::{
 //
 // -[ ] WARN FIXME System is fragile if name/id/path do not coincide
 
+/// A model built from the XML specification,
+/// together with the names needed to backtrack SCXML's ids.
+///
+/// The model is either a [`CsModel`], ready to be verified,
+/// or its [`CsModelDef`], e.g., to be serialized (with the `serde` feature) together with the names.
 #[derive(Debug, Clone)]
-pub struct ScxmlModel {
-    pub model: CsModel,
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScxmlModel<M = CsModel> {
+    pub model: M,
     pub predicates: Vec<String>,
     pub guarantees: Vec<Pmtl<Atom<Event>>>,
     pub assumes: Vec<Pmtl<Atom<Event>>>,
+    #[cfg_attr(feature = "serde", serde(with = "scan_core::serde_seq"))]
     pub fsm_names: HashMap<PgId, String>,
+    #[cfg_attr(feature = "serde", serde(with = "scan_core::serde_seq"))]
     pub fsm_indexes: HashMap<usize, String>,
+    #[cfg_attr(feature = "serde", serde(with = "scan_core::serde_seq"))]
     pub parameters: HashMap<Channel, (PgId, PgId, usize, String)>,
-    pub int_queues: HashSet<Channel>,
+    pub int_queues: BTreeSet<Channel>,
+    #[cfg_attr(feature = "serde", serde(with = "scan_core::serde_seq"))]
     pub ext_queues: HashMap<Channel, PgId>,
+    #[cfg_attr(feature = "serde", serde(with = "scan_core::serde_seq"))]
     pub events: HashMap<usize, String>,
+    // Associates the variables of each PG with the id of the datamodel data they represent.
+    #[cfg_attr(feature = "serde", serde(with = "scan_core::serde_seq"))]
+    pub vars: HashMap<(PgId, PgVar), String>,
     // TODO: ...other stuff needed to backtrack scxml's ids
 }
 
+impl ScxmlModel<CsModelDef> {
+    /// Builds the [`CsModel`] out of its definition, keeping the names of the model.
+    pub fn build(self) -> ScxmlModel {
+        ScxmlModel {
+            model: self.model.build(),
+            predicates: self.predicates,
+            guarantees: self.guarantees,
+            assumes: self.assumes,
+            fsm_names: self.fsm_names,
+            fsm_indexes: self.fsm_indexes,
+            parameters: self.parameters,
+            int_queues: self.int_queues,
+            ext_queues: self.ext_queues,
+            events: self.events,
+            vars: self.vars,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 struct FsmBuilder {
     pg_id: PgId,
@@ -84,14 +121,21 @@ pub struct ModelBuilder {
     // - paramName
     // that is needed
     parameters: HashMap<(PgId, PgId, usize, String), Channel>,
+    // Unreliable links between (origin, target) pairs of State Charts have dedicated channels,
+    // used in place of the target's external queue.
+    links: HashMap<(PgId, PgId), Channel>,
+    // Associates the id of each blackboard data with its global variable and OMG type.
+    globals: HashMap<String, (GlobalVar, String)>,
     // Properties
     guarantees: HashMap<String, Pmtl<String>>,
     assumes: HashMap<String, Pmtl<String>>,
     predicates: HashMap<String, Expression<Channel>>,
-    atoms: HashMap<String, Atom<Event>>,
+    // Ports observing events, as formulae since an event may be sent in different ways.
+    atoms: HashMap<String, Pmtl<Atom<Event>>>,
     ports: HashMap<String, (Channel, Val)>,
     // extra data
-    int_queues: HashSet<Channel>,
+    int_queues: BTreeSet<Channel>,
+    var_names: HashMap<(PgId, PgVar), String>,
 }
 
 impl ModelBuilder {
@@ -100,7 +144,9 @@ impl ModelBuilder {
     /// Can fail if the model specification contains semantic errors
     /// (particularly type mismatches)
     /// or references to non-existing items.
-    pub fn build(mut parser: Parser) -> anyhow::Result<ScxmlModel> {
+    ///
+    /// Integer overflows in the model's expressions behave according to `overflow`.
+    pub fn build(mut parser: Parser, overflow: Overflow) -> anyhow::Result<ScxmlModel<CsModelDef>> {
         let mut model_builder = ModelBuilder {
             cs: ChannelSystemBuilder::new(),
             types: HashMap::new(),
@@ -111,22 +157,35 @@ impl ModelBuilder {
             events: Vec::new(),
             event_indexes: HashMap::new(),
             parameters: HashMap::new(),
+            links: HashMap::new(),
+            globals: HashMap::new(),
             guarantees: HashMap::new(),
             assumes: HashMap::new(),
             predicates: HashMap::new(),
             ports: HashMap::new(),
             atoms: HashMap::new(),
-            int_queues: HashSet::new(),
+            int_queues: BTreeSet::new(),
+            var_names: HashMap::new(),
         };
 
+        model_builder.cs.set_overflow(overflow);
+
         info!("Building types");
         model_builder.build_types(&parser.types)?;
 
+        info!("Building blackboard");
+        model_builder.build_blackboard(&parser, overflow)?;
+
         model_builder.prebuild_processes(&mut parser)?;
 
+        info!("Building links");
+        model_builder.build_links(&parser)?;
+
         info!("Visit process list");
-        for (_id, fsm) in parser.process_list.iter() {
-            model_builder.build_fsm(fsm)?;
+        for (id, fsm) in parser.process_list.iter() {
+            model_builder.build_fsm(fsm).with_context(|| {
+                format!("failed to build fsm '{id}' from {}", fsm.path.display())
+            })?;
         }
 
         model_builder.build_predicates(&parser)?;
@@ -140,8 +199,11 @@ impl ModelBuilder {
         for (name, omg_type) in omg_types.types.iter() {
             let scan_type = match omg_type {
                 OmgType::Boolean => Type::Boolean,
-                OmgType::Int32 => Type::Integer,
+                OmgType::Int(Integer::MIN, Integer::MAX) => Type::Integer,
+                OmgType::Int(min, max) => Type::Bounded(*min, *max),
                 OmgType::F64 => Type::Float,
+                OmgType::String => Type::String,
+                // Processes are addressed by the index of their PG.
                 OmgType::Uri => Type::Integer,
                 OmgType::Structure(fields) => {
                     let mut fields_type: Vec<Type> = Vec::new();
@@ -149,12 +211,14 @@ impl ModelBuilder {
                         self.structs
                             .insert((name.to_owned(), field_id.to_owned()), index);
                         // NOTE: fields must have an already known type, to aviod recursion.
-                        let (_, field_type) = self.types.get(field_type).ok_or(anyhow!(
-                            "unknown type {} of field {} in struct {}",
-                            field_type,
-                            field_id,
-                            name
-                        ))?;
+                        let (_, field_type) = self.resolve_type(field_type).map_err(|_| {
+                            anyhow!(
+                                "unknown type {} of field {} in struct {}",
+                                field_type,
+                                field_id,
+                                name
+                            )
+                        })?;
                         // NOTE: fields have to be inserted in this order or they will not correspond to their index.
                         fields_type.push(field_type.clone());
                     }
@@ -172,6 +236,7 @@ impl ModelBuilder {
                     }
                     Type::Integer
                 }
+                OmgType::Array(_, _) | OmgType::Sequence(_) => self.compound_type(omg_type)?,
             };
             self.types
                 .insert(name.to_owned(), (omg_type.to_owned(), scan_type));
@@ -179,6 +244,47 @@ impl ModelBuilder {
         Ok(())
     }
 
+    // Returns the type with the given name,
+    // registering it first if it is a compound type (array or sequence) of known types.
+    fn resolve_type(&mut self, name: &str) -> anyhow::Result<&(OmgType, Type)> {
+        if !self.types.contains_key(name) {
+            let omg_type = OmgType::compound(name).ok_or(anyhow!("unknown type {name}"))?;
+            let scan_type = self.compound_type(&omg_type)?;
+            self.types.insert(name.to_owned(), (omg_type, scan_type));
+        }
+        Ok(self.types.get(name).expect("type registered"))
+    }
+
+    fn compound_type(&mut self, omg_type: &OmgType) -> anyhow::Result<Type> {
+        match omg_type {
+            OmgType::Array(elem, len) => {
+                let (_, elem) = self.resolve_type(elem)?;
+                Ok(Type::Array(Box::new(elem.to_owned()), *len))
+            }
+            OmgType::Sequence(elem) => {
+                let (_, elem) = self.resolve_type(elem)?;
+                Ok(Type::List(Box::new(elem.to_owned())))
+            }
+            _ => Err(anyhow!("{omg_type:?} is not a compound type")),
+        }
+    }
+
+    // Whether the type with the given name is a float type.
+    fn is_float(&self, type_name: &str) -> bool {
+        matches!(self.types.get(type_name), Some((_, Type::Float)))
+    }
+
+    // Returns the name of the element type of an array or sequence type.
+    fn element_type(&self, type_name: &str) -> anyhow::Result<String> {
+        match self.types.get(type_name) {
+            Some((OmgType::Array(elem, _), _)) | Some((OmgType::Sequence(elem), _)) => {
+                Ok(elem.to_owned())
+            }
+            Some(_) => Err(anyhow!("type {type_name} cannot be indexed")),
+            None => Err(anyhow!("unknown type {type_name}")),
+        }
+    }
+
     fn event_index(&mut self, id: &str) -> usize {
         self.event_indexes.get(id).cloned().unwrap_or_else(|| {
             let index = self.events.len();
@@ -214,14 +320,99 @@ impl ModelBuilder {
         Ok(())
     }
 
+    fn build_links(&mut self, parser: &Parser) -> anyhow::Result<()> {
+        for ((origin, target), (faults, latency)) in parser.links.iter() {
+            let context = || format!("in link from '{origin}' to '{target}'");
+            let origin_id = self
+                .fsm_builders
+                .get(origin)
+                .ok_or(anyhow!("missing origin fsm {origin}"))
+                .with_context(context)?
+                .pg_id;
+            let target_id = self
+                .fsm_builders
+                .get(target)
+                .ok_or(anyhow!("missing target fsm {target}"))
+                .with_context(context)?
+                .pg_id;
+            // Parameters are passed through channels of their own,
+            // which could not be kept consistent with a faulty delivery of the events.
+            if let Some(event_builder) = self.events.iter().find(|eb| {
+                *faults != Faults::default()
+                    && !eb.params.is_empty()
+                    && eb.senders.contains(&origin_id)
+                    && eb.receivers.contains(&target_id)
+            }) {
+                let event = self
+                    .event_indexes
+                    .iter()
+                    .find_map(|(event, idx)| (*idx == event_builder.index).then_some(event))
+                    .expect("event index exists");
+                return Err(anyhow!(
+                    "unreliable link could carry event '{event}' with parameters"
+                ))
+                .with_context(context);
+            }
+            let link = self
+                .cs
+                .new_channel(Type::Product(vec![Type::Integer, Type::Integer]), None);
+            self.cs.set_faults(link, *faults).with_context(context)?;
+            self.cs.set_latency(link, *latency).with_context(context)?;
+            self.links.insert((origin_id, target_id), link);
+        }
+        Ok(())
+    }
+
+    fn build_blackboard(&mut self, parser: &Parser, overflow: Overflow) -> anyhow::Result<()> {
+        for data in parser.blackboard.iter() {
+            let context = || format!("in blackboard <data> '{}'", data.id);
+            let scan_type = self
+                .resolve_type(&data.omg_type)
+                .with_context(context)?
+                .1
+                .to_owned();
+            let init = if let Some(ref expr) = data.expression {
+                let expr = self
+                    .typed_expression(
+                        expr,
+                        &scan_type,
+                        &parser.interner,
+                        &HashMap::new(),
+                        None,
+                        &HashMap::new(),
+                    )
+                    .with_context(context)?;
+                expr.eval_constant(overflow)
+                    .ok_or(anyhow!("initial value is not a constant expression"))
+                    .with_context(context)?
+                    .with_context(context)?
+            } else {
+                scan_type.default_value()
+            };
+            let global = self
+                .cs
+                .new_global_var(init, scan_type)
+                .with_context(context)?;
+            self.globals
+                .insert(data.id.to_owned(), (global, data.omg_type.to_owned()));
+        }
+        Ok(())
+    }
+
     fn prebuild_fsms(
         &mut self,
         pg_id: PgId,
         fmt: &mut Scxml,
         interner: &boa_interner::Interner,
     ) -> anyhow::Result<()> {
-        let mut types = HashMap::new();
+        // Blackboard data is visible to all processes, unless shadowed by their own datamodel.
+        let mut types = HashMap::from_iter(
+            self.globals
+                .iter()
+                .map(|(id, (_, omg_type))| (id.to_owned(), omg_type.to_owned())),
+        );
         for data in &fmt.datamodel {
+            self.resolve_type(&data.omg_type)?;
             types.insert(data.id.to_owned(), data.omg_type.as_str().to_owned());
         }
         for (_, state) in fmt.states.iter_mut() {
@@ -273,6 +464,7 @@ impl ModelBuilder {
                     let param_type = param_type
                         .or_else(|| self.infer_type(&param.expr, types, interner).ok())
                         .ok_or(anyhow!("missing type annotation for param {}", param.name))?;
+                    self.resolve_type(&param_type)?;
                     // Update omg_type value so that it contains its type for sure
                     param.omg_type = Some(param_type.to_owned());
                     let builder = self.events.get_mut(event_index).expect("index must exist");
@@ -313,6 +505,7 @@ impl ModelBuilder {
         types: &HashMap<String, String>,
         interner: &boa_interner::Interner,
     ) -> anyhow::Result<String> {
+        use boa_ast::expression::access::{PropertyAccess, PropertyAccessField};
         match expr {
             boa_ast::Expression::This => todo!(),
             boa_ast::Expression::Identifier(ident) => {
@@ -332,8 +525,8 @@ impl ModelBuilder {
             boa_ast::Expression::Literal(lit) => {
                 use boa_ast::expression::literal::Literal;
                 match lit {
-                    Literal::String(_) => todo!(),
-                    Literal::Num(_) => Ok(String::from("f64")),
+                    Literal::String(_) => Ok(String::from("string")),
+                    Literal::Num(_) => Ok(String::from("float64")),
                     Literal::Int(_) => Ok(String::from("int32")),
                     Literal::BigInt(_) => todo!(),
                     Literal::Bool(_) => Ok(String::from("bool")),
@@ -342,7 +535,15 @@ impl ModelBuilder {
                 }
             }
             boa_ast::Expression::RegExpLiteral(_) => todo!(),
-            boa_ast::Expression::ArrayLiteral(_) => todo!(),
+            boa_ast::Expression::ArrayLiteral(arr) => {
+                let elem = arr
+                    .as_ref()
+                    .first()
+                    .and_then(Option::as_ref)
+                    .ok_or(anyhow!("cannot infer type of empty array"))?;
+                let elem = self.infer_type(elem, types, interner)?;
+                Ok(format!("{elem}[{}]", arr.as_ref().len()))
+            }
             boa_ast::Expression::ObjectLiteral(_) => todo!(),
             boa_ast::Expression::Spread(_) => todo!(),
             boa_ast::Expression::Function(_) => todo!(),
@@ -353,9 +554,46 @@ impl ModelBuilder {
             boa_ast::Expression::AsyncGenerator(_) => todo!(),
             boa_ast::Expression::Class(_) => todo!(),
             boa_ast::Expression::TemplateLiteral(_) => todo!(),
+            boa_ast::Expression::PropertyAccess(PropertyAccess::Simple(prop_acc)) => {
+                match prop_acc.field() {
+                    PropertyAccessField::Expr(_) => {
+                        let target = self.infer_type(prop_acc.target(), types, interner)?;
+                        self.element_type(&target)
+                    }
+                    PropertyAccessField::Const(sym)
+                        if interner.resolve(*sym).and_then(|sym| sym.utf8()) == Some("length") =>
+                    {
+                        Ok(String::from("int32"))
+                    }
+                    PropertyAccessField::Const(_) => Err(anyhow!("cannot infer type of property")),
+                }
+            }
             boa_ast::Expression::PropertyAccess(_) => todo!(),
             boa_ast::Expression::New(_) => todo!(),
-            boa_ast::Expression::Call(_) => todo!(),
+            boa_ast::Expression::Call(call) => {
+                match call.function().to_interned_string(interner).as_str() {
+                    "Math.floor" | "Math.ceil" | "Math.round" | "Math.trunc" => {
+                        Ok(String::from("int32"))
+                    }
+                    "Math.sqrt" | "Number" => Ok(String::from("float64")),
+                    // The result is a float if any argument is.
+                    "Math.min" | "Math.max" | "Math.abs" | "Math.pow" => {
+                        let mut arg_types = call
+                            .args()
+                            .iter()
+                            .map(|arg| self.infer_type(arg, types, interner))
+                            .collect::<anyhow::Result<Vec<_>>>()?;
+                        if arg_types.iter().any(|t| self.is_float(t)) {
+                            Ok(String::from("float64"))
+                        } else if arg_types.is_empty() {
+                            Err(anyhow!("missing arguments"))
+                        } else {
+                            Ok(arg_types.swap_remove(0))
+                        }
+                    }
+                    function => Err(anyhow!("unknown function {function}")),
+                }
+            }
             boa_ast::Expression::SuperCall(_) => todo!(),
             boa_ast::Expression::ImportCall(_) => todo!(),
             boa_ast::Expression::Optional(_) => todo!(),
@@ -385,16 +623,22 @@ impl ModelBuilder {
                     .1
                     .clone();
                 let rhs = self
-                    .infer_type(bin.lhs(), types, interner)
+                    .infer_type(bin.rhs(), types, interner)
                     .and_then(|t| self.types.get(&t).ok_or(anyhow!("unknown type {t}")))?
                     .1
                     .clone();
                 match bin.op() {
+                    // As in ECMAScript, division always gives a float.
+                    boa_ast::expression::operator::binary::BinaryOp::Arithmetic(
+                        boa_ast::expression::operator::binary::ArithmeticOp::Div,
+                    ) => Ok(String::from("float64")),
                     boa_ast::expression::operator::binary::BinaryOp::Arithmetic(_) => {
                         if lhs == rhs {
                             Ok(type_name)
+                        } else if lhs == Type::Float || rhs == Type::Float {
+                            Ok(String::from("float64"))
                         } else {
-                            todo!()
+                            Err(anyhow!("cannot infer type of arithmetic operation"))
                         }
                     }
                     boa_ast::expression::operator::binary::BinaryOp::Bitwise(_) => todo!(),
@@ -406,7 +650,9 @@ impl ModelBuilder {
                 }
             }
             boa_ast::Expression::BinaryInPrivate(_) => todo!(),
-            boa_ast::Expression::Conditional(_) => todo!(),
+            boa_ast::Expression::Conditional(cond) => {
+                self.infer_type(cond.if_true(), types, interner)
+            }
             boa_ast::Expression::Await(_) => todo!(),
             boa_ast::Expression::Yield(_) => todo!(),
             boa_ast::Expression::Parenthesized(_) => todo!(),
@@ -429,10 +675,24 @@ impl ModelBuilder {
             .cs
             .initial_location(pg_id)
             .expect("program graph must exist");
+        self.cs
+            .set_urgency(initial_loc, Urgency::Atomic)
+            .expect("program graph must exist");
         let mut initialize = None;
         // Initialize variables from datamodel
         // NOTE vars cannot be initialized using previously defined vars because datamodel is an HashMap
         let mut vars = HashMap::new();
+        // Blackboard data is accessed through variables of the process' own,
+        // unless shadowed by its datamodel.
+        for (id, (global, omg_type)) in self
+            .globals
+            .iter()
+            .filter(|(id, _)| scxml.datamodel.iter().all(|data| data.id != **id))
+        {
+            let var = self.cs.global_var(pg_id, *global)?;
+            vars.insert(id.to_owned(), (var, omg_type.to_owned()));
+            self.var_names.insert(var.into(), id.to_owned());
+        }
         for data in scxml.datamodel.iter() {
             let scan_type = self
                 .types
@@ -442,19 +702,37 @@ impl ModelBuilder {
                 .to_owned();
             let var = self
                 .cs
-                .new_var(pg_id, CsExpression::Const(scan_type.default_value()))
+                .new_var_with_type(
+                    pg_id,
+                    CsExpression::Const(scan_type.default_value()),
+                    scan_type.to_owned(),
+                )
                 .expect("program graph exists!");
             vars.insert(data.id.to_owned(), (var, data.omg_type.to_owned()));
+            self.var_names.insert(var.into(), data.id.to_owned());
             // Initialize variable with `expr`, if any, by adding it as effect of `initialize` action.
             if let Some(ref expr) = data.expression {
-                let expr = self.expression(expr, &fsm.interner, &vars, None, &HashMap::new())?;
+                let context = || format!("in <data> '{}' attribute 'expr'", data.id);
+                let expr = self
+                    .typed_expression(
+                        expr,
+                        &scan_type,
+                        &fsm.interner,
+                        &vars,
+                        None,
+                        &HashMap::new(),
+                    )
+                    .with_context(context)?;
                 // Initialization has at least an effect, so we need to perform it.
                 // Create action if there was none.
                 let initialize = *initialize.get_or_insert_with(|| {
                     self.cs.new_action(pg_id).expect("program graph must exist")
                 });
                 // This might fail if `expr` does not typecheck.
-                self.cs.add_effect(pg_id, initialize, var, expr)?;
+                self.cs
+                    .add_effect(pg_id, initialize, var, expr.clone())
+                    .map_err(|err| self.explain(&expr, err))
+                    .with_context(context)?;
             }
         }
         // Make vars immutable
@@ -463,7 +741,8 @@ impl ModelBuilder {
         // After initializing datamodel, transition to location representing point-of-entry of initial state of State Chart.
         let initial_state;
         if let Some(initialize) = initialize {
-            initial_state = self.cs.new_location(pg_id).expect("program graph exists!");
+            initial_state =
+                new_transient_location(&mut self.cs, pg_id).expect("program graph exists!");
             self.cs
                 .add_transition(pg_id, initial_loc, initialize, initial_state, None)
                 .expect("hand-coded args");
@@ -517,6 +796,21 @@ impl ModelBuilder {
             .cs
             .new_receive(pg_id, ext_queue, current_event_and_origin_var)
             .expect("hand-coded args");
+        // Events coming through unreliable links are dequeued together with the external ones.
+        let links = Vec::from_iter(
+            self.links
+                .iter()
+                .filter(|((_, target), _)| *target == pg_id)
+                .map(|(_, link)| *link),
+        );
+        let dequeue_links = links
+            .into_iter()
+            .map(|link| {
+                self.cs
+                    .new_receive(pg_id, link, current_event_and_origin_var)
+                    .expect("hand-coded args")
+            })
+            .collect::<Vec<_>>();
         // Process external event to assign event and origin values to respective vars
         let process_ext_event = self.cs.new_action(pg_id)?;
         self.cs
@@ -601,24 +895,26 @@ impl ModelBuilder {
             trace!("build state {}", state_id);
             // Each state is modeled by multiple locations connected by transitions
             // A starting location is used as a point-of-entry to the execution of the state.
-            let start_loc = *states
-                .entry(state_id.to_owned())
-                .or_insert_with(|| self.cs.new_location(pg_id).expect("program graph exists!"));
+            let start_loc = *states.entry(state_id.to_owned()).or_insert_with(|| {
+                new_transient_location(&mut self.cs, pg_id).expect("program graph exists!")
+            });
             // Execute the state's `onentry` executable content
             let mut onentry_loc = start_loc;
             for executable in state.on_entry.iter() {
                 // Each executable content attaches suitable transitions to the point-of-entry location
                 // and returns the target of such transitions as updated point-of-entry location.
-                onentry_loc = self.add_executable(
-                    executable,
-                    pg_id,
-                    int_queue,
-                    onentry_loc,
-                    &vars,
-                    None,
-                    &HashMap::new(),
-                    &fsm.interner,
-                )?;
+                onentry_loc = self
+                    .add_executable(
+                        executable,
+                        pg_id,
+                        int_queue,
+                        onentry_loc,
+                        &vars,
+                        None,
+                        &HashMap::new(),
+                        &fsm.interner,
+                    )
+                    .with_context(|| format!("in <onentry> of state '{state_id}'"))?;
             }
             // Make immutable
             let onentry_loc = onentry_loc;
@@ -626,15 +922,19 @@ impl ModelBuilder {
             // Location where autonomous/eventless/NULL transitions activate
             let mut null_trans = onentry_loc;
             // Location where internal events are dequeued
-            let int_queue_loc = self.cs.new_location(pg_id).expect("program graph exists!");
+            let int_queue_loc =
+                new_transient_location(&mut self.cs, pg_id).expect("program graph exists!");
             // Location where external events are dequeued
+            // This is where the FSM waits for new events, so time can elapse.
             let ext_queue_loc = self.cs.new_location(pg_id).expect("program graph exists!");
             // Location where eventful transitions activate
-            let mut eventful_trans = self.cs.new_location(pg_id).expect("program graph exists!");
+            let mut eventful_trans =
+                new_transient_location(&mut self.cs, pg_id).expect("program graph exists!");
             // int_origin_loc will not be needed outside of this scope
             {
                 // Location where the origin of internal events is set as own.
-                let int_origin_loc = self.cs.new_location(pg_id).expect("program graph exists!");
+                let int_origin_loc =
+                    new_transient_location(&mut self.cs, pg_id).expect("program graph exists!");
                 // Transition dequeueing a new internal event and searching for first active eventful transition
                 self.cs
                     .add_transition(pg_id, int_queue_loc, dequeue_int, int_origin_loc, None)
@@ -659,13 +959,13 @@ impl ModelBuilder {
             }
             // Location where parameters of events are read into suitable variables.
             let ext_event_processing_param =
-                self.cs.new_location(pg_id).expect("program graph exists!");
+                new_transient_location(&mut self.cs, pg_id).expect("program graph exists!");
             // Process external events by reading the (event, origin) pair and writing the components to the designated variables.
             // ext_event_processing_loc will not be needed outside of this scope.
             {
                 // Location where the index/origin of external events are dequeued
                 let ext_event_processing_loc =
-                    self.cs.new_location(pg_id).expect("program graph exists!");
+                    new_transient_location(&mut self.cs, pg_id).expect("program graph exists!");
                 // Dequeue a new external event and search for first active named transition.
                 self.cs
                     .add_transition(
@@ -676,6 +976,17 @@ impl ModelBuilder {
                         None,
                     )
                     .expect("hand-coded args");
+                for &dequeue_link in dequeue_links.iter() {
+                    self.cs
+                        .add_transition(
+                            pg_id,
+                            ext_queue_loc,
+                            dequeue_link,
+                            ext_event_processing_loc,
+                            None,
+                        )
+                        .expect("hand-coded args");
+                }
                 self.cs
                     .add_transition(
                         pg_id,
@@ -719,7 +1030,8 @@ impl ModelBuilder {
                         let read_param = *param_actions
                             .get(&(sender_id, event_index, param_name.to_owned()))
                             .expect("has to be there");
-                        let next_loc = self.cs.new_location(pg_id).expect("program graph exists!");
+                        let next_loc = new_transient_location(&mut self.cs, pg_id)
+                            .expect("program graph exists!");
                         self.cs
                             .add_transition(
                                 pg_id,
@@ -771,7 +1083,9 @@ impl ModelBuilder {
                 // Get or create the location corresponding to the target state.
                 let target_loc = *states
                     .entry(transition.target.to_owned())
-                    .or_insert_with(|| self.cs.new_location(pg_id).expect("pg_id should exist"));
+                    .or_insert_with(|| {
+                        new_transient_location(&mut self.cs, pg_id).expect("pg_id should exist")
+                    });
 
                 // Set up origin and parameters for conditional/executable content.
                 let exec_origin;
@@ -794,19 +1108,23 @@ impl ModelBuilder {
                 // Condition activating the transition.
                 // It has to be parsed/built as a Boolean expression.
                 // Could fail if `expr` is invalid.
+                let cond_context =
+                    || format!("in <transition> attribute 'cond' of state '{state_id}'");
                 let cond: Option<CsExpression> = transition
                     .cond
                     .as_ref()
                     .map(|cond| {
                         self.expression(cond, &fsm.interner, &vars, exec_origin, &exec_params)
                     })
-                    .transpose()?;
+                    .transpose()
+                    .with_context(cond_context)?;
 
                 // Location corresponding to checking if the transition is active.
                 // Has to be defined depending on the type of transition.
                 let check_trans_loc;
                 // Location corresponding to verifying the transition is not active and moving to next one.
-                let next_trans_loc = self.cs.new_location(pg_id).expect("{pg_id:?} exists");
+                let next_trans_loc =
+                    new_transient_location(&mut self.cs, pg_id).expect("{pg_id:?} exists");
 
                 // Guard for transition.
                 // Has to be defined depending on the type of transition, etc...
@@ -842,26 +1160,48 @@ impl ModelBuilder {
 
                 // If transition is active, execute the relevant executable content and then the transition to the target.
                 // Could fail if 'cond' expression was not acceptable as guard.
-                let mut exec_trans_loc = self.cs.new_location(pg_id)?;
-                self.cs.add_autonomous_transition(
-                    pg_id,
-                    check_trans_loc,
-                    exec_trans_loc,
-                    guard.to_owned(),
-                )?;
-                // First execute the executable content of the state's `on_exit` tag,
-                // then that of the `transition` tag, following the specs.
-                for exec in state.on_exit.iter().chain(transition.effects.iter()) {
-                    exec_trans_loc = self.add_executable(
-                        exec,
+                let mut exec_trans_loc = new_transient_location(&mut self.cs, pg_id)?;
+                self.cs
+                    .add_autonomous_transition(
                         pg_id,
-                        int_queue,
+                        check_trans_loc,
                         exec_trans_loc,
-                        &vars,
-                        exec_origin,
-                        &exec_params,
-                        &fsm.interner,
-                    )?;
+                        guard.to_owned(),
+                    )
+                    .map_err(|err| match guard {
+                        Some(ref guard) => self.explain(guard, err),
+                        None => err.into(),
+                    })
+                    .with_context(cond_context)?;
+                // First execute the executable content of the state's `on_exit` tag,
+                // then that of the `transition` tag, following the specs.
+                for (idx, exec) in state
+                    .on_exit
+                    .iter()
+                    .chain(transition.effects.iter())
+                    .enumerate()
+                {
+                    exec_trans_loc = self
+                        .add_executable(
+                            exec,
+                            pg_id,
+                            int_queue,
+                            exec_trans_loc,
+                            &vars,
+                            exec_origin,
+                            &exec_params,
+                            &fsm.interner,
+                        )
+                        .with_context(|| {
+                            if idx < state.on_exit.len() {
+                                format!("in <onexit> of state '{state_id}'")
+                            } else {
+                                format!(
+                                    "in <transition> to '{}' of state '{state_id}'",
+                                    transition.target
+                                )
+                            }
+                        })?;
                 }
                 // Transitioning to the target state/location.
                 // At this point, the transition cannot be stopped so there can be no guard.
@@ -896,6 +1236,7 @@ impl ModelBuilder {
     }
 
     // WARN: vars and params have the same type so they could be easily swapped by mistake when calling the function.
+    #[allow(clippy::too_many_arguments)]
     fn add_executable(
         &mut self,
         executable: &Executable,
@@ -914,7 +1255,7 @@ impl ModelBuilder {
                 let raise =
                     self.cs
                         .new_send(pg_id, int_queue, CsExpression::from(event_idx as Integer))?;
-                let next_loc = self.cs.new_location(pg_id)?;
+                let next_loc = new_transient_location(&mut self.cs, pg_id)?;
                 // queue the internal event
                 self.cs.add_transition(pg_id, loc, raise, next_loc, None)?;
                 Ok(next_loc)
@@ -945,7 +1286,7 @@ impl ModelBuilder {
                         .add_transition(pg_id, loc, reset, next_loc, None)
                         .expect("params are right");
                     loc = next_loc;
-                    let next_loc = self.cs.new_location(pg_id).expect("PG exists");
+                    let next_loc = new_transient_location(&mut self.cs, pg_id).expect("PG exists");
                     self.cs
                         .add_autonomous_timed_transition(
                             pg_id,
@@ -958,7 +1299,7 @@ impl ModelBuilder {
                     loc = next_loc;
                 }
                 if let Some(target) = target {
-                    let done_loc = self.cs.new_location(pg_id)?;
+                    let done_loc = new_transient_location(&mut self.cs, pg_id)?;
                     let targets;
                     let target_expr;
                     match target {
@@ -973,8 +1314,10 @@ impl ModelBuilder {
                             ));
                         }
                         Target::Expr(targetexpr) => {
-                            target_expr =
-                                Some(self.expression(targetexpr, interner, vars, origin, params)?);
+                            target_expr = Some(
+                                self.expression(targetexpr, interner, vars, origin, params)
+                                    .context("in <send> attribute 'targetexpr'")?,
+                            );
                             targets = self.events[event_idx].receivers.iter().cloned().collect();
                         }
                     }
@@ -982,7 +1325,11 @@ impl ModelBuilder {
                         let target_name = self.fsm_names.get(&target_id).unwrap();
                         let target_builder =
                             self.fsm_builders.get(target_name).expect("it must exist");
-                        let target_ext_queue = target_builder.ext_queue;
+                        let target_ext_queue = self
+                            .links
+                            .get(&(pg_id, target_id))
+                            .copied()
+                            .unwrap_or(target_builder.ext_queue);
                         let send_event = self
                             .cs
                             .new_send(
@@ -996,7 +1343,8 @@ impl ModelBuilder {
                             .expect("params are hard-coded");
 
                         // Send event and event origin before moving on to next location.
-                        let mut next_loc = self.cs.new_location(pg_id).expect("PG exists");
+                        let mut next_loc =
+                            new_transient_location(&mut self.cs, pg_id).expect("PG exists");
                         self.cs
                             .add_transition(
                                 pg_id,
@@ -1015,10 +1363,14 @@ impl ModelBuilder {
                         // Pass parameters. This could fail due to param content.
                         for param in send_params {
                             // Updates next location.
-                            next_loc = self.send_param(
-                                pg_id, target_id, param, event_idx, next_loc, vars, origin, params,
-                                interner,
-                            )?;
+                            next_loc = self
+                                .send_param(
+                                    pg_id, target_id, param, event_idx, next_loc, vars, origin,
+                                    params, interner,
+                                )
+                                .with_context(|| {
+                                    format!("in <param> '{}' attribute 'expr'", param.name)
+                                })?;
                         }
                         // Once sending event and args done, get to exit-point
                         self.cs
@@ -1030,6 +1382,11 @@ impl ModelBuilder {
                 } else {
                     // WARN: This behavior is non-compliant with the SCXML specification
                     // An event sent without specifiying the target is sent to all FSMs that can process it
+                    // NOTE: Broadcast channels are not used here:
+                    // each FSM has a single FIFO external queue shared by all senders,
+                    // while a broadcast channel would be a separate queue for each of its subscribers,
+                    // breaking the order of events with those sent directly to the FSM.
+                    // Parameters are also passed through channels specific to each target.
                     let targets = self.events[event_idx]
                         .receivers
                         .iter()
@@ -1059,27 +1416,49 @@ impl ModelBuilder {
             }
             Executable::Assign { location, expr } => {
                 // Add a transition that perform the assignment via the effect of the `assign` action.
-                let expr = self.expression(expr, interner, vars, origin, params)?;
-                let (var, _scan_type) = vars.get(location).ok_or(anyhow!("undefined variable"))?;
+                let context = || format!("in <assign> to '{location}'");
+                let (var, _, expr) = self
+                    .assign(location, expr, interner, vars, origin, params)
+                    .with_context(context)?;
                 let assign = self.cs.new_action(pg_id).expect("PG exists");
-                self.cs.add_effect(pg_id, assign, *var, expr)?;
-                let next_loc = self.cs.new_location(pg_id).unwrap();
+                self.cs
+                    .add_effect(pg_id, assign, var, expr.clone())
+                    .map_err(|err| self.explain(&expr, err))
+                    .with_context(context)?;
+                let next_loc = new_transient_location(&mut self.cs, pg_id).unwrap();
                 self.cs.add_transition(pg_id, loc, assign, next_loc, None)?;
                 Ok(next_loc)
             }
-            Executable::If(If { r#elif, r#else, .. }) => {
+            Executable::If(r#if) => {
+                // Blocks made only of assignments are performed by a single action.
+                if let Some(effects) = self
+                    .assign_only_if(r#if, vars, origin, params, interner)
+                    .context("in <if>")?
+                {
+                    let assign = self.cs.new_action(pg_id)?;
+                    for (var, expr) in effects {
+                        self.cs
+                            .add_effect(pg_id, assign, var, expr.clone())
+                            .map_err(|err| self.explain(&expr, err))
+                            .context("in <if>")?;
+                    }
+                    let next_loc = new_transient_location(&mut self.cs, pg_id)?;
+                    self.cs.add_transition(pg_id, loc, assign, next_loc, None)?;
+                    return Ok(next_loc);
+                }
+                let If { r#elif, r#else, .. } = r#if;
                 // We go to this location after the if/elif/else block
-                let end_loc = self.cs.new_location(pg_id).unwrap();
+                let end_loc = new_transient_location(&mut self.cs, pg_id).unwrap();
                 let mut curr_loc = loc;
                 for (cond, execs) in r#elif {
-                    let mut next_loc = self.cs.new_location(pg_id).unwrap();
-                    let cond = self.expression(cond, interner, vars, origin, params)?;
-                    self.cs.add_autonomous_transition(
-                        pg_id,
-                        curr_loc,
-                        next_loc,
-                        Some(cond.to_owned()),
-                    )?;
+                    let mut next_loc = new_transient_location(&mut self.cs, pg_id).unwrap();
+                    let cond = self
+                        .expression(cond, interner, vars, origin, params)
+                        .context("in <if> attribute 'cond'")?;
+                    self.cs
+                        .add_autonomous_transition(pg_id, curr_loc, next_loc, Some(cond.to_owned()))
+                        .map_err(|err| self.explain(&cond, err))
+                        .context("in <if> attribute 'cond'")?;
                     for exec in execs {
                         next_loc = self.add_executable(
                             exec, pg_id, int_queue, next_loc, vars, origin, params, interner,
@@ -1090,7 +1469,7 @@ impl ModelBuilder {
                         .add_autonomous_transition(pg_id, next_loc, end_loc, None)?;
                     // `elif/else` branch
                     let old_loc = curr_loc;
-                    curr_loc = self.cs.new_location(pg_id).unwrap();
+                    curr_loc = new_transient_location(&mut self.cs, pg_id).unwrap();
                     self.cs
                         .add_autonomous_transition(
                             pg_id,
@@ -1113,7 +1492,208 @@ impl ModelBuilder {
         }
     }
 
+    // Compiles an `<if>` block made only of assignments into the effects of a single action,
+    // one for each assigned variable, selecting the assigned value through conditional expressions.
+    // Returns `None` if the block contains other executables,
+    // or if conditions or assigned expressions read any of the assigned variables,
+    // as then assignments cannot be performed independently of one another.
+    fn assign_only_if(
+        &mut self,
+        r#if: &If,
+        vars: &HashMap<String, (Var, String)>,
+        origin: Option<Var>,
+        params: &HashMap<String, (Var, String)>,
+        interner: &boa_interner::Interner,
+    ) -> anyhow::Result<Option<Vec<(Var, CsExpression)>>> {
+        let mut branches = Vec::new();
+        let mut assigned: Vec<(Var, Type)> = Vec::new();
+        let branch_execs = r#if
+            .r#elif
+            .iter()
+            .map(|(cond, execs)| (Some(cond), execs))
+            .chain(std::iter::once((None, &r#if.r#else)));
+        for (cond, execs) in branch_execs {
+            let cond = cond
+                .map(|cond| self.expression(cond, interner, vars, origin, params))
+                .transpose()?;
+            let mut assigns = Vec::new();
+            for exec in execs {
+                if let Executable::Assign { location, expr } = exec {
+                    let (var, t, expr) =
+                        self.assign(location, expr, interner, vars, origin, params)?;
+                    if !assigned.iter().any(|(assigned, _)| *assigned == var) {
+                        assigned.push((var, t));
+                    }
+                    assigns.push((var, expr));
+                } else {
+                    return Ok(None);
+                }
+            }
+            branches.push((cond, assigns));
+        }
+        // `context` fails on variables of unknown type,
+        // so it detects expressions reading the assigned variables.
+        let var_types: HashMap<Var, Type> = vars
+            .values()
+            .chain(params.values())
+            .filter_map(|(var, type_name)| {
+                self.types.get(type_name).map(|(_, t)| (*var, t.to_owned()))
+            })
+            .chain(origin.map(|origin| (origin, Type::Integer)))
+            .collect();
+        let not_assigned = |var: Var| {
+            if assigned.iter().any(|(assigned, _)| *assigned == var) {
+                None
+            } else {
+                var_types.get(&var).cloned()
+            }
+        };
+        if branches.iter().any(|(cond, assigns)| {
+            cond.iter()
+                .chain(assigns.iter().map(|(_, expr)| expr))
+                .any(|expr| expr.context(&not_assigned).is_err())
+        }) {
+            return Ok(None);
+        }
+        let effects = assigned
+            .into_iter()
+            .map(|(var, t)| {
+                // Build the conditional expression starting from the last (`else`) branch.
+                let expr = branches.iter().rev().fold(
+                    CsExpression::Var(var, t.to_owned()),
+                    |r#else, (cond, assigns)| {
+                        // The last assignment of the branch is the one that counts.
+                        let value = assigns
+                            .iter()
+                            .rev()
+                            .find(|(assigned, _)| *assigned == var)
+                            .map(|(_, expr)| expr.to_owned())
+                            .unwrap_or(CsExpression::Var(var, t.to_owned()));
+                        if let Some(cond) = cond {
+                            CsExpression::Ite(Box::new((cond.to_owned(), value, r#else)))
+                        } else {
+                            value
+                        }
+                    },
+                );
+                (var, expr)
+            })
+            .collect();
+        Ok(Some(effects))
+    }
+
+    // Resolves the location of an `<assign>` into the assigned variable, its type and its new value.
+    // Locations can index into arrays and sequences, as in `arr[i][j]`,
+    // in which case the new value of the variable is obtained by writing `expr` at the given indexes.
+    fn assign(
+        &mut self,
+        location: &str,
+        expr: &boa_ast::Expression,
+        interner: &boa_interner::Interner,
+        vars: &HashMap<String, (Var, String)>,
+        origin: Option<Var>,
+        params: &HashMap<String, (Var, String)>,
+    ) -> anyhow::Result<(Var, Type, CsExpression)> {
+        use boa_ast::expression::access::{PropertyAccess, PropertyAccessField};
+        // Locations are parsed with their own interner, as the one of the fsm cannot be modified.
+        let mut loc_interner = boa_interner::Interner::new();
+        let statement = boa_parser::Parser::new(boa_parser::Source::from_bytes(location))
+            .parse_script(&mut loc_interner)
+            .map_err(|err| anyhow!("cannot parse location {location}: {err}"))?
+            .statements()
+            .first()
+            .cloned();
+        let Some(boa_ast::StatementListItem::Statement(boa_ast::Statement::Expression(mut target))) =
+            statement
+        else {
+            return Err(anyhow!("location {location} is not an expression"));
+        };
+        // Indexes are collected from the last one to the first one.
+        let mut indexes = Vec::new();
+        let ident = loop {
+            match target {
+                boa_ast::Expression::Identifier(ident) => {
+                    break ident.to_interned_string(&loc_interner);
+                }
+                boa_ast::Expression::PropertyAccess(PropertyAccess::Simple(ref prop_acc)) => {
+                    let PropertyAccessField::Expr(idx) = prop_acc.field() else {
+                        return Err(anyhow!("unsupported location {location}"));
+                    };
+                    indexes.push(idx.as_ref().to_owned());
+                    target = prop_acc.target().to_owned();
+                }
+                _ => return Err(anyhow!("unsupported location {location}")),
+            }
+        };
+        let (var, type_name) = vars.get(&ident).ok_or(anyhow!("undefined variable"))?;
+        let var_type = self
+            .types
+            .get(type_name)
+            .ok_or(anyhow!("unknown type {type_name}"))?
+            .1
+            .to_owned();
+        // Read the nested arrays being written into, from the outermost to the innermost.
+        let mut containers = Vec::new();
+        let mut container = CsExpression::Var(*var, var_type.to_owned());
+        let mut elem_type = type_name.to_owned();
+        for idx in indexes.iter().rev() {
+            let idx = self.expression(idx, &loc_interner, vars, origin, params)?;
+            containers.push((container.to_owned(), idx.to_owned()));
+            container = CsExpression::Index(Box::new((container, idx)));
+            elem_type = self.element_type(&elem_type)?;
+        }
+        let (_, elem_type) = self
+            .types
+            .get(&elem_type)
+            .ok_or(anyhow!("unknown type {elem_type}"))?
+            .to_owned();
+        let value = self.typed_expression(expr, &elem_type, interner, vars, origin, params)?;
+        let expr = containers
+            .into_iter()
+            .rev()
+            .fold(value, |value, (container, idx)| {
+                CsExpression::Update(Box::new((container, idx, value)))
+            });
+        Ok((*var, var_type, expr))
+    }
+
+    // Builds an expression to be assigned to a location of the given type.
+    // ECMAScript does not distinguish arrays from sequences,
+    // so array literals assigned to a sequence are built as lists (and can then be empty).
+    fn typed_expression(
+        &mut self,
+        expr: &boa_ast::Expression,
+        scan_type: &Type,
+        interner: &boa_interner::Interner,
+        vars: &HashMap<String, (Var, String)>,
+        origin: Option<Var>,
+        params: &HashMap<String, (Var, String)>,
+    ) -> anyhow::Result<CsExpression> {
+        match (expr, scan_type) {
+            (boa_ast::Expression::ArrayLiteral(arr), Type::List(t) | Type::Array(t, _)) => {
+                let elements = arr
+                    .as_ref()
+                    .iter()
+                    .map(|elem| {
+                        let elem = elem.as_ref().ok_or(anyhow!("array literal with holes"))?;
+                        self.typed_expression(elem, t, interner, vars, origin, params)
+                    })
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                if let Type::List(_) = scan_type {
+                    Ok(elements.into_iter().fold(
+                        CsExpression::Const(scan_type.default_value()),
+                        |list, elem| CsExpression::Append(Box::new((list, elem))),
+                    ))
+                } else {
+                    Ok(CsExpression::Array(elements))
+                }
+            }
+            _ => self.expression(expr, interner, vars, origin, params),
+        }
+    }
+
     // WARN: vars and params have the same type so they could be easily swapped by mistake when calling the function.
+    #[allow(clippy::too_many_arguments)]
     fn send_param(
         &mut self,
         pg_id: PgId,
@@ -1134,21 +1714,50 @@ impl ModelBuilder {
             .ok_or(anyhow!("undefined type"))?
             .1;
         // Build expression from ECMAScript expression.
-        let expr = self.expression(&param.expr, interner, vars, origin, params)?;
+        let expr =
+            self.typed_expression(&param.expr, &scan_type, interner, vars, origin, params)?;
         // Retreive or create channel for parameter passing.
         let param_chn = *self
             .parameters
             .entry((pg_id, target_id, event_idx, param.name.to_owned()))
             .or_insert(self.cs.new_channel(scan_type, None));
         // Can return error if expr is badly typed
-        let pass_param = self.cs.new_send(pg_id, param_chn, expr)?;
-        let next_loc = self.cs.new_location(pg_id).expect("PG exists");
+        let pass_param = self
+            .cs
+            .new_send(pg_id, param_chn, expr.clone())
+            .map_err(|err| self.explain(&expr, err))?;
+        let next_loc = new_transient_location(&mut self.cs, pg_id).expect("PG exists");
         self.cs
             .add_transition(pg_id, param_loc, pass_param, next_loc, None)
             .expect("hand-made params are correct");
         Ok(next_loc)
     }
 
+    // Points type errors in `expr` to the offending sub-expression,
+    // printed using the names of the datamodel variables.
+    fn explain(&self, expr: &CsExpression, err: CsError) -> anyhow::Error {
+        let sub_expr = match err {
+            CsError::Type(ref type_err) | CsError::ProgramGraph(_, PgError::Type(ref type_err)) => {
+                expr.subexpression(&type_err.path)
+            }
+            _ => None,
+        };
+        match sub_expr {
+            Some(sub_expr) => {
+                let sub_expr = sub_expr
+                    .display(|var| {
+                        self.var_names
+                            .get(&(*var).into())
+                            .cloned()
+                            .unwrap_or_else(|| var.to_string())
+                    })
+                    .to_string();
+                anyhow::Error::new(err).context(format!("in sub-expression `{sub_expr}`"))
+            }
+            None => err.into(),
+        }
+    }
+
     // WARN: vars and params have the same type so they could be easily swapped by mistake when calling the function.
     fn expression(
         &mut self,
@@ -1178,22 +1787,31 @@ impl ModelBuilder {
             boa_ast::Expression::Literal(lit) => {
                 use boa_ast::expression::literal::Literal;
                 match lit {
-                    Literal::String(_) => todo!(),
+                    Literal::String(sym) => interner
+                        .resolve(*sym)
+                        .map(|s| CsExpression::from(s.to_string().as_str()))
+                        .ok_or(anyhow!("unknown symbol {:?}", sym))?,
                     Literal::Num(f) => CsExpression::from(*f),
-                    Literal::Int(i) => CsExpression::from(*i),
+                    Literal::Int(i) => CsExpression::from(Integer::from(*i)),
                     Literal::BigInt(_) => todo!(),
                     Literal::Bool(b) => CsExpression::from(*b),
                     Literal::Null => todo!(),
                     Literal::Undefined => todo!(),
                 }
             }
-            boa_ast::Expression::ArrayLiteral(_arr) => {
-                todo!()
-                // arr
-                //             .to_pattern(true)
-                //             .ok_or(anyhow!("array syntax error"))?
-                //             .into_iter()
-                //             .map(|element| match element {})
+            boa_ast::Expression::ArrayLiteral(arr) => {
+                let elements = arr
+                    .as_ref()
+                    .iter()
+                    .map(|elem| {
+                        let elem = elem.as_ref().ok_or(anyhow!("array literal with holes"))?;
+                        self.expression(elem, interner, vars, origin, params)
+                    })
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                if elements.is_empty() {
+                    return Err(anyhow!("cannot infer type of empty array"));
+                }
+                CsExpression::Array(elements)
             }
             boa_ast::Expression::PropertyAccess(prop_acc) => {
                 let expr = &boa_ast::Expression::PropertyAccess(prop_acc.to_owned());
@@ -1223,11 +1841,21 @@ impl ModelBuilder {
                 let rhs = self.expression(bin.rhs(), interner, vars, origin, params)?;
                 match bin.op() {
                     BinaryOp::Arithmetic(ar_bin) => match ar_bin {
+                        // In ECMAScript, `+` also concatenates strings.
+                        ArithmeticOp::Add
+                            if matches!(lhs.r#type(), Ok(Type::String))
+                                || matches!(rhs.r#type(), Ok(Type::String)) =>
+                        {
+                            CsExpression::Concat(vec![lhs, rhs])
+                        }
                         ArithmeticOp::Add => lhs + rhs,
                         ArithmeticOp::Sub => lhs + (-rhs),
-                        ArithmeticOp::Div => todo!(),
+                        // As in ECMAScript, division is between floats even for integer operands.
+                        ArithmeticOp::Div => {
+                            CsExpression::Div(Box::new((CsExpression::ToFloat(Box::new(lhs)), rhs)))
+                        }
                         ArithmeticOp::Mul => lhs * rhs,
-                        ArithmeticOp::Exp => todo!(),
+                        ArithmeticOp::Exp => CsExpression::Pow(Box::new((lhs, rhs))),
                         ArithmeticOp::Mod => CsExpression::Mod(Box::new((lhs, rhs))),
                     },
                     BinaryOp::Relational(rel_bin) => match rel_bin {
@@ -1250,7 +1878,20 @@ impl ModelBuilder {
                     _ => return Err(anyhow!("unimplemented operator")),
                 }
             }
-            boa_ast::Expression::Conditional(_) => todo!(),
+            boa_ast::Expression::Call(call) => {
+                let args = call
+                    .args()
+                    .iter()
+                    .map(|arg| self.expression(arg, interner, vars, origin, params))
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                let function = call.function().to_interned_string(interner);
+                math_function(&function, args)?
+            }
+            boa_ast::Expression::Conditional(cond) => CsExpression::Ite(Box::new((
+                self.expression(cond.condition(), interner, vars, origin, params)?,
+                self.expression(cond.if_true(), interner, vars, origin, params)?,
+                self.expression(cond.if_false(), interner, vars, origin, params)?,
+            ))),
             boa_ast::Expression::Parenthesized(par) => {
                 self.expression(par.expression(), interner, vars, origin, params)?
             }
@@ -1277,7 +1918,7 @@ impl ModelBuilder {
                 use boa_ast::expression::literal::Literal;
                 match lit {
                     Literal::Num(f) => Val::from(*f),
-                    Literal::Int(i) => Val::Integer(*i),
+                    Literal::Int(i) => Val::Integer(Integer::from(*i)),
                     Literal::Bool(b) => Val::Boolean(*b),
                     _ => return Err(anyhow!("unsupported type")),
                 }
@@ -1331,7 +1972,7 @@ impl ModelBuilder {
                         match ar_bin {
                             ArithmeticOp::Add => Val::Integer(lhs + rhs),
                             ArithmeticOp::Sub => Val::Integer(lhs - rhs),
-                            ArithmeticOp::Div if rhs != 0 => Val::Integer(lhs / rhs),
+                            ArithmeticOp::Div => Val::from(lhs as Float / rhs as Float),
                             ArithmeticOp::Mul => Val::Integer(lhs * rhs),
                             ArithmeticOp::Exp if !rhs.is_negative() => {
                                 Val::Integer(lhs.pow(rhs as u32))
@@ -1458,9 +2099,13 @@ impl ModelBuilder {
                                             .0
                                         {
                                             OmgType::Boolean => todo!(),
-                                            OmgType::Int32 => todo!(),
+                                            OmgType::Int(_, _) => Err(anyhow!(
+                                                "integer type {type_name} has no field {ident}"
+                                            )),
                                             OmgType::F64 => todo!(),
-                                            OmgType::Uri => todo!(),
+                                            OmgType::String | OmgType::Uri => Err(anyhow!(
+                                                "string type {type_name} has no field {ident}"
+                                            )),
                                             OmgType::Structure(fields) => {
                                                 let index = *self
                                                     .structs
@@ -1475,6 +2120,17 @@ impl ModelBuilder {
                                                 ))
                                             }
                                             OmgType::Enumeration(_) => todo!(),
+                                            OmgType::Array(_, _) | OmgType::Sequence(_)
+                                                if ident == "length" =>
+                                            {
+                                                Ok(EcmaObj::PrimitiveData(
+                                                    CsExpression::Len(Box::new(expr)),
+                                                    String::from("int32"),
+                                                ))
+                                            }
+                                            OmgType::Array(_, _) | OmgType::Sequence(_) => {
+                                                Err(anyhow!("property {} not found", ident))
+                                            }
                                         }
                                     }
                                     EcmaObj::Properties(fields) => fields
@@ -1483,7 +2139,18 @@ impl ModelBuilder {
                                         .cloned(),
                                 }
                             }
-                            PropertyAccessField::Expr(_) => todo!(),
+                            PropertyAccessField::Expr(idx) => match prop_target {
+                                EcmaObj::PrimitiveData(expr, type_name) => {
+                                    let elem_type = self.element_type(&type_name)?;
+                                    let idx =
+                                        self.expression(idx, interner, vars, origin, params)?;
+                                    Ok(EcmaObj::PrimitiveData(
+                                        CsExpression::Index(Box::new((expr, idx))),
+                                        elem_type,
+                                    ))
+                                }
+                                EcmaObj::Properties(_) => Err(anyhow!("cannot index properties")),
+                            },
                         }
                     }
                     PropertyAccess::Private(_) => todo!(),
@@ -1518,18 +2185,33 @@ impl ModelBuilder {
                     .ok_or(anyhow!("param {param} not found"))?;
                 self.ports.insert(port_id.to_owned(), (channel, init));
             } else {
-                let channel = target_builder.ext_queue;
-                self.atoms.insert(
-                    port_id.to_owned(),
-                    Atom::Event(Event {
+                let link = self.links.get(&(origin, target)).copied();
+                let channel = link.unwrap_or(target_builder.ext_queue);
+                let val = Val::Tuple(vec![
+                    Val::Integer(event_id as Integer),
+                    Val::Integer(u16::from(origin) as Integer),
+                ]);
+                let send = |event_type| {
+                    Pmtl::Atom(Atom::Event(Event {
                         pg_id: origin,
                         channel,
-                        event_type: EventType::Send(Val::Tuple(vec![
-                            Val::Integer(event_id as Integer),
-                            Val::Integer(u16::from(origin) as Integer),
-                        ])),
-                    }),
-                );
+                        event_type,
+                    }))
+                };
+                let atom = if link.is_some() {
+                    // Events sent through a link may be affected by faults.
+                    Pmtl::Or(
+                        [Fault::Loss, Fault::Duplication, Fault::Reordering]
+                            .into_iter()
+                            .map(|fault| EventType::FaultySend(fault, val.clone()))
+                            .chain(std::iter::once(EventType::Send(val.clone())))
+                            .map(send)
+                            .collect(),
+                    )
+                } else {
+                    send(EventType::Send(val))
+                };
+                self.atoms.insert(port_id.to_owned(), atom);
             }
         }
         for (predicate_id, predicate) in parser.properties.predicates.iter() {
@@ -1563,6 +2245,11 @@ impl ModelBuilder {
                 .ok_or(anyhow!("missing port {port}")),
             Expression::Tuple(_) => todo!(),
             Expression::Component(_, _) => todo!(),
+            Expression::Ite(exprs) => Ok(Expression::Ite(Box::new((
+                self.build_predicate(&exprs.0)?,
+                self.build_predicate(&exprs.1)?,
+                self.build_predicate(&exprs.2)?,
+            )))),
             Expression::And(exprs) => exprs
                 .iter()
                 .map(|expr| self.build_predicate(expr))
@@ -1614,30 +2301,84 @@ impl ModelBuilder {
             Expression::Len(expr) => Ok(Expression::Len(Box::new(
                 self.build_predicate(expr.as_ref())?,
             ))),
+            Expression::Array(exprs) => exprs
+                .iter()
+                .map(|expr| self.build_predicate(expr))
+                .collect::<Result<_, _>>()
+                .map(Expression::Array),
+            Expression::Index(exprs) => Ok(Expression::Index(Box::new((
+                self.build_predicate(&exprs.0)?,
+                self.build_predicate(&exprs.1)?,
+            )))),
+            Expression::Update(exprs) => Ok(Expression::Update(Box::new((
+                self.build_predicate(&exprs.0)?,
+                self.build_predicate(&exprs.1)?,
+                self.build_predicate(&exprs.2)?,
+            )))),
             Expression::Mod(exprs) => Ok(Expression::Mod(Box::new((
                 self.build_predicate(&exprs.0)?,
                 self.build_predicate(&exprs.1)?,
             )))),
+            Expression::Div(exprs) => Ok(Expression::Div(Box::new((
+                self.build_predicate(&exprs.0)?,
+                self.build_predicate(&exprs.1)?,
+            )))),
+            Expression::Pow(exprs) => Ok(Expression::Pow(Box::new((
+                self.build_predicate(&exprs.0)?,
+                self.build_predicate(&exprs.1)?,
+            )))),
+            Expression::Min(exprs) => exprs
+                .iter()
+                .map(|expr| self.build_predicate(expr))
+                .collect::<Result<_, _>>()
+                .map(Expression::Min),
+            Expression::Max(exprs) => exprs
+                .iter()
+                .map(|expr| self.build_predicate(expr))
+                .collect::<Result<_, _>>()
+                .map(Expression::Max),
+            Expression::Concat(exprs) => exprs
+                .iter()
+                .map(|expr| self.build_predicate(expr))
+                .collect::<Result<_, _>>()
+                .map(Expression::Concat),
+            Expression::Abs(expr) => Ok(Expression::Abs(Box::new(
+                self.build_predicate(expr.as_ref())?,
+            ))),
+            Expression::Floor(expr) => Ok(Expression::Floor(Box::new(
+                self.build_predicate(expr.as_ref())?,
+            ))),
+            Expression::Ceil(expr) => Ok(Expression::Ceil(Box::new(
+                self.build_predicate(expr.as_ref())?,
+            ))),
+            Expression::Round(expr) => Ok(Expression::Round(Box::new(
+                self.build_predicate(expr.as_ref())?,
+            ))),
+            Expression::ToFloat(expr) => Ok(Expression::ToFloat(Box::new(
+                self.build_predicate(expr.as_ref())?,
+            ))),
+            Expression::ToInt(expr) => Ok(Expression::ToInt(Box::new(
+                self.build_predicate(expr.as_ref())?,
+            ))),
         }
     }
 
-    fn build_model(self) -> ScxmlModel {
-        let mut model = CsModelBuilder::new(self.cs.build());
+    fn build_model(self) -> ScxmlModel<CsModelDef> {
         let mut pred_names: HashMap<String, usize> = HashMap::new();
         let mut predicates = Vec::new();
-        for (_port_name, (channel, init)) in self.ports {
-            // TODO FIXME handle error.
-            model.add_port(channel, init);
-        }
+        let mut pred_exprs = Vec::new();
+        // Predicates are indexed in the order they are listed in the definition.
         for (pred_name, pred_expr) in self.predicates {
-            // TODO FIXME handle error.
-            let id = model.add_predicate(pred_expr);
-            pred_names.insert(pred_name.to_owned(), id);
-            assert_eq!(id, predicates.len());
+            pred_names.insert(pred_name.to_owned(), predicates.len());
             predicates.push(pred_name);
+            pred_exprs.push(pred_expr);
         }
         ScxmlModel {
-            model: model.build(),
+            model: CsModelDef {
+                cs: self.cs,
+                ports: self.ports.into_values().collect(),
+                predicates: pred_exprs,
+            },
             guarantees: self
                 .guarantees
                 .values()
@@ -1655,6 +2396,7 @@ impl ModelBuilder {
                 })
                 .collect(),
             fsm_names: self.fsm_names,
+            vars: self.var_names,
             parameters: self
                 .parameters
                 .into_iter()
@@ -1664,6 +2406,11 @@ impl ModelBuilder {
                 .fsm_builders
                 .values()
                 .map(|b| (b.ext_queue, b.pg_id))
+                .chain(
+                    self.links
+                        .iter()
+                        .map(|((_, target), link)| (*link, *target)),
+                )
                 .collect(),
             int_queues: self.int_queues,
             events: self
@@ -1681,7 +2428,7 @@ impl ModelBuilder {
     }
 
     fn build_pmtl_property(
-        atoms: &HashMap<String, Atom<Event>>,
+        atoms: &HashMap<String, Pmtl<Atom<Event>>>,
         property: &Pmtl<String>,
         predicates: &HashMap<String, usize>,
     ) -> anyhow::Result<Pmtl<Atom<Event>>> {
@@ -1691,7 +2438,7 @@ impl ModelBuilder {
             // FIXME TODO handle error
             Pmtl::Atom(pred) => {
                 if let Some(atom) = atoms.get(pred.as_str()) {
-                    Ok(Pmtl::Atom(atom.to_owned()))
+                    Ok(atom.to_owned())
                 } else {
                     Ok(Pmtl::Atom(Atom::Predicate(
                         *predicates.get(pred.as_str()).unwrap(),
@@ -1750,3 +2497,42 @@ impl ModelBuilder {
         }
     }
 }
+
+// Translates calls to the ECMAScript `Math` library (and the `Number` conversion function).
+fn math_function(function: &str, args: Vec<CsExpression>) -> anyhow::Result<CsExpression> {
+    let unary = |args: Vec<CsExpression>| -> anyhow::Result<Box<CsExpression>> {
+        let [arg] = <[CsExpression; 1]>::try_from(args)
+            .map_err(|_| anyhow!("function {function} takes one argument"))?;
+        Ok(Box::new(arg))
+    };
+    match function {
+        "Math.abs" => unary(args).map(CsExpression::Abs),
+        "Math.floor" => unary(args).map(CsExpression::Floor),
+        "Math.ceil" => unary(args).map(CsExpression::Ceil),
+        "Math.round" => unary(args).map(CsExpression::Round),
+        "Math.trunc" => unary(args).map(CsExpression::ToInt),
+        "Number" => unary(args).map(CsExpression::ToFloat),
+        "Math.sqrt" => {
+            unary(args).map(|arg| CsExpression::Pow(Box::new((*arg, CsExpression::from(0.5)))))
+        }
+        "Math.pow" => {
+            let [base, exp] = <[CsExpression; 2]>::try_from(args)
+                .map_err(|_| anyhow!("function {function} takes two arguments"))?;
+            Ok(CsExpression::Pow(Box::new((base, exp))))
+        }
+        "Math.min" | "Math.max" if args.is_empty() => {
+            Err(anyhow!("function {function} requires some arguments"))
+        }
+        "Math.min" => Ok(CsExpression::Min(args)),
+        "Math.max" => Ok(CsExpression::Max(args)),
+        _ => Err(anyhow!("unknown function {function}")),
+    }
+}
+
+// FSMs react to events immediately, so time cannot elapse in the locations they only go through
+// (i.e., all but those where they wait for new events or for the delay of a send).
+// Moreover, FSMs process events run-to-completion, so that other FSMs cannot observe the intermediate states
+// of a macro-step, i.e., of the transitions between two such waiting locations.
+fn new_transient_location(cs: &mut ChannelSystemBuilder, pg_id: PgId) -> Result<Location, CsError> {
+    cs.new_location_with_urgency(pg_id, Urgency::Atomic)
+}
@@ -8,11 +8,37 @@ use std::path::Path;
 pub use builder::ScxmlModel;
 pub use scan_core;
 
+use scan_core::{CsModelDef, Overflow};
+
+/// Loads the model at `path`, which is either its main file or the folder containing it,
+/// with integer overflows checked.
 pub fn load(path: &Path) -> anyhow::Result<ScxmlModel> {
+    load_with_overflow(path, Overflow::default())
+}
+
+/// Loads the model at `path`, with integer overflows behaving according to `overflow`.
+pub fn load_with_overflow(path: &Path, overflow: Overflow) -> anyhow::Result<ScxmlModel> {
+    load_def_with_overflow(path, overflow).map(ScxmlModel::build)
+}
+
+/// Loads the definition of the model at `path`, with integer overflows checked.
+///
+/// Unlike the built model, the definition can be (de)serialized
+/// (with `scan_core`'s `serde` feature).
+pub fn load_def(path: &Path) -> anyhow::Result<ScxmlModel<CsModelDef>> {
+    load_def_with_overflow(path, Overflow::default())
+}
+
+/// Loads the definition of the model at `path`,
+/// with integer overflows behaving according to `overflow`.
+pub fn load_def_with_overflow(
+    path: &Path,
+    overflow: Overflow,
+) -> anyhow::Result<ScxmlModel<CsModelDef>> {
     let parser = if path.is_file() {
         parser::Parser::parse(path)
     } else {
         parser::Parser::parse_folder(path)
     }?;
-    builder::ModelBuilder::build(parser)
+    builder::ModelBuilder::build(parser, overflow)
 }
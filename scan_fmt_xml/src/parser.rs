@@ -21,6 +21,7 @@ pub use self::omg_types::*;
 pub use self::property::*;
 pub use self::vocabulary::*;
 use scan_core::channel_system::*;
+use scan_core::Time;
 
 #[derive(Error, Debug)]
 pub enum ParserError {
@@ -59,6 +60,8 @@ enum ConvinceTag {
     Specification,
     Model,
     ProcessList,
+    LinkList,
+    Blackboard,
     DataTypeList,
     Enumeration(String),
     Structure(String),
@@ -70,6 +73,8 @@ impl From<ConvinceTag> for &'static str {
             ConvinceTag::Specification => TAG_SPECIFICATION,
             ConvinceTag::Model => TAG_MODEL,
             ConvinceTag::ProcessList => TAG_PROCESS_LIST,
+            ConvinceTag::LinkList => TAG_LINK_LIST,
+            ConvinceTag::Blackboard => TAG_BLACKBOARD,
             ConvinceTag::DataTypeList => TAG_DATA_TYPE_LIST,
             ConvinceTag::Enumeration(_) => TAG_ENUMERATION,
             ConvinceTag::Structure(_) => TAG_STRUCT,
@@ -82,6 +87,11 @@ impl From<ConvinceTag> for &'static str {
 pub struct Parser {
     root_folder: PathBuf,
     pub(crate) process_list: HashMap<String, Fsm>,
+    // Associates the (origin, target) pair of processes of a link with its faults and latency.
+    pub(crate) links: HashMap<(String, String), (Faults, Latency)>,
+    // Global data shared by all processes, with the interner of its expressions.
+    pub(crate) blackboard: Vec<Data>,
+    pub(crate) interner: boa_interner::Interner,
     pub(crate) types: OmgTypes,
     pub(crate) properties: Properties,
 }
@@ -101,8 +111,9 @@ impl Parser {
                     .is_some_and(|ext| ext.to_str().unwrap() == "scxml")
                 {
                     info!("creating reader from file {0}", path.display());
-                    let mut reader = Reader::from_file(path)?;
-                    let fsm = Fsm::parse(&mut reader)?;
+                    let mut reader = Reader::from_file(&path)?;
+                    let mut fsm = Fsm::parse(&mut reader)?;
+                    fsm.path = path;
                     process_list.insert(fsm.scxml.id.to_owned(), fsm);
                 }
             }
@@ -124,6 +135,9 @@ impl Parser {
         Ok(Parser {
             root_folder: path.to_path_buf(),
             process_list,
+            links: HashMap::new(),
+            blackboard: Vec::new(),
+            interner: boa_interner::Interner::new(),
             types: OmgTypes::new(),
             properties,
         })
@@ -138,6 +152,9 @@ impl Parser {
         let mut spec = Parser {
             root_folder,
             process_list: HashMap::new(),
+            links: HashMap::new(),
+            blackboard: Vec::new(),
+            interner: boa_interner::Interner::new(),
             types: OmgTypes::new(),
             properties: Properties::new(),
         };
@@ -171,6 +188,16 @@ impl Parser {
                         {
                             stack.push(ConvinceTag::ProcessList);
                         }
+                        TAG_LINK_LIST
+                            if stack.last().is_some_and(|tag| *tag == ConvinceTag::Model) =>
+                        {
+                            stack.push(ConvinceTag::LinkList);
+                        }
+                        TAG_BLACKBOARD
+                            if stack.last().is_some_and(|tag| *tag == ConvinceTag::Model) =>
+                        {
+                            stack.push(ConvinceTag::Blackboard);
+                        }
                         // Unknown tag: skip till maching end tag
                         _ => {
                             error!(target: "parsing", "unknown or unexpected tag {tag_name}, skipping");
@@ -210,6 +237,29 @@ impl Parser {
                                 format!("parser position {}", reader.buffer_position())
                             })?;
                         }
+                        TAG_LINK
+                            if stack
+                                .last()
+                                .is_some_and(|tag| *tag == ConvinceTag::LinkList) =>
+                        {
+                            spec.parse_link(tag).with_context(|| {
+                                format!("parser position {}", reader.buffer_position())
+                            })?;
+                        }
+                        TAG_DATA
+                            if stack
+                                .last()
+                                .is_some_and(|tag| *tag == ConvinceTag::Blackboard) =>
+                        {
+                            let data =
+                                Data::parse(tag, None, &mut spec.interner).with_context(|| {
+                                    format!("parser position {}", reader.buffer_position())
+                                })?;
+                            if spec.blackboard.iter().any(|other| other.id == data.id) {
+                                return Err(anyhow!(ParserError::AlreadyDeclared(data.id)));
+                            }
+                            spec.blackboard.push(data);
+                        }
                         TAG_TYPES
                             if stack
                                 .last()
@@ -303,8 +353,10 @@ impl Parser {
         let fsm = match moc.as_str() {
             "fsm" => {
                 info!("creating reader from file {0}", root_path.display());
-                let mut reader = Reader::from_file(root_path)?;
-                Fsm::parse(&mut reader)?
+                let mut reader = Reader::from_file(&root_path)?;
+                let mut fsm = Fsm::parse(&mut reader)?;
+                fsm.path = root_path;
+                fsm
             }
             moc => {
                 return Err(anyhow!(ParserError::UnknownMoC(moc.to_string())));
@@ -322,6 +374,87 @@ impl Parser {
         }
     }
 
+    fn parse_link(&mut self, tag: quick_xml::events::BytesStart<'_>) -> anyhow::Result<()> {
+        let mut origin: Option<String> = None;
+        let mut target: Option<String> = None;
+        let mut faults = Faults::default();
+        let mut min_delay: Option<Time> = None;
+        let mut max_delay: Option<Time> = None;
+        let mut mean_delay: Option<f64> = None;
+        for attr in tag
+            .attributes()
+            .collect::<Result<Vec<Attribute>, AttrError>>()?
+        {
+            match std::str::from_utf8(attr.key.as_ref())? {
+                ATTR_ORIGIN => {
+                    origin = Some(String::from_utf8(attr.value.into_owned())?);
+                }
+                ATTR_TARGET => {
+                    target = Some(String::from_utf8(attr.value.into_owned())?);
+                }
+                key @ (ATTR_LOSS | ATTR_DUPLICATION | ATTR_REORDERING) => {
+                    let probability = std::str::from_utf8(&attr.value)?
+                        .parse::<f64>()
+                        .with_context(|| format!("in attribute '{key}'"))?;
+                    match key {
+                        ATTR_LOSS => faults.loss = probability,
+                        ATTR_DUPLICATION => faults.duplication = probability,
+                        _ => faults.reordering = probability,
+                    }
+                }
+                key @ (ATTR_MIN_DELAY | ATTR_MAX_DELAY) => {
+                    let delay = std::str::from_utf8(&attr.value)?
+                        .parse::<Time>()
+                        .with_context(|| format!("in attribute '{key}'"))?;
+                    if key == ATTR_MIN_DELAY {
+                        min_delay = Some(delay);
+                    } else {
+                        max_delay = Some(delay);
+                    }
+                }
+                ATTR_MEAN_DELAY => {
+                    mean_delay = Some(
+                        std::str::from_utf8(&attr.value)?
+                            .parse::<f64>()
+                            .with_context(|| format!("in attribute '{ATTR_MEAN_DELAY}'"))?,
+                    );
+                }
+                key => {
+                    error!("found unknown attribute {key}");
+                    return Err(anyhow::Error::new(ParserError::UnknownKey(key.to_owned())));
+                }
+            }
+        }
+        let latency = match (min_delay, max_delay, mean_delay) {
+            (None, None, Some(mean)) => Latency::Exponential(mean),
+            (_, _, Some(_)) => {
+                return Err(anyhow!(
+                    "attribute '{ATTR_MEAN_DELAY}' excludes '{ATTR_MIN_DELAY}' and '{ATTR_MAX_DELAY}'"
+                ));
+            }
+            (min, max, None) => {
+                let min = min.unwrap_or(0);
+                match max {
+                    Some(max) if max != min => Latency::Interval(min, max),
+                    _ => Latency::Fixed(min),
+                }
+            }
+        };
+        let origin = origin.ok_or(anyhow!(ParserError::MissingAttr(ATTR_ORIGIN.to_string())))?;
+        let target = target.ok_or(anyhow!(ParserError::MissingAttr(ATTR_TARGET.to_string())))?;
+        if self
+            .links
+            .insert((origin.to_owned(), target.to_owned()), (faults, latency))
+            .is_none()
+        {
+            Ok(())
+        } else {
+            Err(anyhow!(ParserError::AlreadyDeclared(format!(
+                "link from {origin} to {target}"
+            ))))
+        }
+    }
+
     fn parse_types(&mut self, tag: quick_xml::events::BytesStart<'_>) -> anyhow::Result<()> {
         let mut path: Option<String> = None;
         for attr in tag
@@ -9,6 +9,7 @@ use scan_core::Time;
 use std::collections::HashMap;
 use std::fmt::Debug;
 use std::io::{BufRead, Read};
+use std::path::PathBuf;
 use std::str;
 
 #[derive(Debug)]
@@ -55,7 +56,7 @@ pub struct Data {
 }
 
 impl Data {
-    fn parse(
+    pub(super) fn parse(
         tag: events::BytesStart<'_>,
         // ident: Option<String>,
         omg_type: Option<String>,
@@ -588,6 +589,8 @@ impl Scxml {
 pub struct Fsm {
     pub(crate) interner: boa_interner::Interner,
     pub(crate) scxml: Scxml,
+    // The file the fsm has been parsed from, to report errors.
+    pub(crate) path: PathBuf,
 }
 
 impl Fsm {
@@ -692,6 +695,7 @@ impl Fsm {
                                     return Ok(Fsm {
                                         interner,
                                         scxml: fsm,
+                                        path: PathBuf::new(),
                                     });
                                 }
                                 ScxmlTag::Datamodel(datamodel)
@@ -10,6 +10,7 @@ use quick_xml::{
     },
     Reader,
 };
+use scan_core::Integer;
 use std::str;
 
 use crate::parser::{ConvinceTag, ParserError, ATTR_TYPE, TAG_FIELD};
@@ -18,11 +19,41 @@ use crate::parser::{ATTR_ID, TAG_DATA_TYPE_LIST, TAG_ENUMERATION, TAG_LABEL, TAG
 #[derive(Debug, Clone)]
 pub enum OmgType {
     Boolean,
-    Int32,
+    /// Integer type with the given (inclusive) range.
+    Int(Integer, Integer),
     F64,
+    String,
+    /// Address of a process, as given by `_event.origin` and used by `targetexpr`.
     Uri,
     Structure(HashMap<String, String>),
     Enumeration(Vec<String>),
+    /// Array `T[N]` of fixed length.
+    Array(String, usize),
+    /// Sequence `T[]`, or `sequence<T>`, of variable length.
+    Sequence(String),
+}
+
+impl OmgType {
+    /// Parses the name of a compound type, i.e., an array or a sequence,
+    /// returning `None` if the name does not denote one.
+    ///
+    /// Element types are returned by name, and can be compound types themselves.
+    pub fn compound(name: &str) -> Option<Self> {
+        if let Some(elem) = name
+            .strip_prefix("sequence<")
+            .and_then(|name| name.strip_suffix('>'))
+        {
+            return Some(OmgType::Sequence(elem.trim().to_owned()));
+        }
+        let (elem, len) = name.strip_suffix(']')?.rsplit_once('[')?;
+        let elem = elem.trim().to_owned();
+        let len = len.trim();
+        if len.is_empty() {
+            Some(OmgType::Sequence(elem))
+        } else {
+            len.parse().ok().map(|len| OmgType::Array(elem, len))
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -31,15 +62,27 @@ pub struct OmgTypes {
 }
 
 impl OmgTypes {
-    pub const BASE_TYPES: [(&'static str, OmgType); 9] = [
+    pub const BASE_TYPES: [(&'static str, OmgType); 15] = [
         ("boolean", OmgType::Boolean),
         ("bool", OmgType::Boolean),
-        ("int8", OmgType::Int32),
-        ("int16", OmgType::Int32),
-        ("int32", OmgType::Int32),
-        ("int64", OmgType::Int32),
+        ("int8", OmgType::Int(i8::MIN as Integer, i8::MAX as Integer)),
+        ("uint8", OmgType::Int(0, u8::MAX as Integer)),
+        (
+            "int16",
+            OmgType::Int(i16::MIN as Integer, i16::MAX as Integer),
+        ),
+        ("uint16", OmgType::Int(0, u16::MAX as Integer)),
+        (
+            "int32",
+            OmgType::Int(i32::MIN as Integer, i32::MAX as Integer),
+        ),
+        ("uint32", OmgType::Int(0, u32::MAX as Integer)),
+        ("int64", OmgType::Int(Integer::MIN, Integer::MAX)),
+        ("uint64", OmgType::Int(0, Integer::MAX)),
         ("float32", OmgType::F64),
         ("float64", OmgType::F64),
+        ("string", OmgType::String),
+        ("wstring", OmgType::String),
         ("URI", OmgType::Uri),
     ];
 
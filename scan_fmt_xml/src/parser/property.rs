@@ -10,7 +10,7 @@ use quick_xml::{
     },
     Reader,
 };
-use scan_core::{Expression, Float, Pmtl, Time, Val};
+use scan_core::{Expression, Float, Integer, Pmtl, Time, Val};
 use std::{collections::HashMap, io::BufRead, str};
 
 const TAG_PORTS: &str = "ports";
@@ -834,9 +834,11 @@ impl Properties {
         let val = val.ok_or(anyhow!("missing expression"))?;
 
         match r#type.ok_or(anyhow!("missing type"))?.as_str() {
-            "int32" => Ok(val.parse::<i32>().map(Val::Integer)?),
+            "int32" => Ok(val.parse::<i32>().map(|i| Val::Integer(Integer::from(i)))?),
+            "int64" => Ok(val.parse::<Integer>().map(Val::Integer)?),
             "float64" => Ok(val.parse::<Float>().map(Val::from)?),
             "boolean" => Ok(val.parse::<bool>().map(Val::Boolean)?),
+            "string" => Ok(Val::from(val.as_str())),
             unknown => Err(anyhow!("unwnown type {unknown}")),
         }
     }
@@ -12,6 +12,9 @@ pub const TAG_VAR: &str = "var";
 pub const TAG_CONST: &str = "const";
 pub const TAG_PROCESS: &str = "process";
 pub const TAG_PROCESS_LIST: &str = "processList";
+pub const TAG_LINK: &str = "link";
+pub const TAG_LINK_LIST: &str = "linkList";
+pub const TAG_BLACKBOARD: &str = "blackboard";
 pub const TAG_STATE: &str = "state";
 pub const TAG_SCXML: &str = "scxml";
 pub const ATTR_INITIAL: &str = "initial";
@@ -22,6 +25,13 @@ pub const ATTR_TYPE: &str = "type";
 pub const ATTR_PARAM: &str = "param_name";
 pub const ATTR_MOC: &str = "moc";
 pub const ATTR_PATH: &str = "path";
+pub const ATTR_ORIGIN: &str = "origin";
+pub const ATTR_LOSS: &str = "loss";
+pub const ATTR_DUPLICATION: &str = "duplication";
+pub const ATTR_REORDERING: &str = "reordering";
+pub const ATTR_MIN_DELAY: &str = "min_delay";
+pub const ATTR_MAX_DELAY: &str = "max_delay";
+pub const ATTR_MEAN_DELAY: &str = "mean_delay";
 // pub const TAG_VERSION: &str = "version";
 // pub const TAG_NAME: &str = "name";
 // pub const TAG_XMLNS: &str = "xmlns";
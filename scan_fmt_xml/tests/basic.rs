@@ -1,7 +1,12 @@
 use anyhow::anyhow;
+use scan_fmt_xml::{
+    scan_core::{channel_system::Event, Publisher, Time, TransitionSystem},
+    ScxmlModel,
+};
 use std::{
     path::{Path, PathBuf},
     str::FromStr,
+    sync::{Arc, Mutex},
 };
 
 const MAXSTEP: usize = 1000;
@@ -75,6 +80,50 @@ fn param_tennis() -> anyhow::Result<()> {
     )?)
 }
 
+#[test]
+fn link() -> anyhow::Result<()> {
+    test(&PathBuf::from_str("./tests/assets/test_link/model.xml")?)
+}
+
+#[test]
+fn link_fault() -> anyhow::Result<()> {
+    // Sending the message is observed even when the link loses it.
+    let model = scan_fmt_xml::load(&PathBuf::from_str(
+        "./tests/assets/test_link_fault/model.xml",
+    )?)?;
+    assert_eq!(estimate(&model)?, 0.);
+    Ok(())
+}
+
+#[test]
+fn link_param() -> anyhow::Result<()> {
+    let err = test(&PathBuf::from_str(
+        "./tests/assets/test_link_param/model.xml",
+    )?)
+    .expect_err("events with parameters cannot go through unreliable links");
+    let err = format!("{err:#}");
+    assert!(err.contains("in link from 'fsm_1' to 'fsm_2'"));
+    assert!(err.contains("with parameters"));
+    Ok(())
+}
+
+#[test]
+fn blackboard() -> anyhow::Result<()> {
+    test(&PathBuf::from_str(
+        "./tests/assets/test_blackboard/model.xml",
+    )?)
+}
+
+#[test]
+fn macrostep() -> anyhow::Result<()> {
+    // The observer never sees the flag set during the macro-step of the writer.
+    let model = scan_fmt_xml::load(&PathBuf::from_str(
+        "./tests/assets/test_macrostep/model.xml",
+    )?)?;
+    assert_eq!(estimate(&model)?, 1.);
+    Ok(())
+}
+
 #[test]
 fn conditional() -> anyhow::Result<()> {
     test(&PathBuf::from_str("./tests/assets/test_if/model.xml")?)
@@ -85,6 +134,87 @@ fn elif() -> anyhow::Result<()> {
     test(&PathBuf::from_str("./tests/assets/test_elif/model.xml")?)
 }
 
+#[test]
+fn ite() -> anyhow::Result<()> {
+    test(&PathBuf::from_str("./tests/assets/test_ite/model.xml")?)
+}
+
+#[test]
+fn math() -> anyhow::Result<()> {
+    test(&PathBuf::from_str("./tests/assets/test_math/model.xml")?)
+}
+
+#[test]
+fn array() -> anyhow::Result<()> {
+    test(&PathBuf::from_str("./tests/assets/test_array/model.xml")?)
+}
+
+#[test]
+fn string() -> anyhow::Result<()> {
+    test(&PathBuf::from_str("./tests/assets/test_string/model.xml")?)
+}
+
+#[test]
+fn array_bounds() -> anyhow::Result<()> {
+    assert!(test(&PathBuf::from_str(
+        "./tests/assets/test_array_bounds/model.xml",
+    )?)
+    .is_err_and(|err| err.to_string() != "step limit reached"));
+    Ok(())
+}
+
+#[test]
+fn int_range() -> anyhow::Result<()> {
+    let err = test(&PathBuf::from_str(
+        "./tests/assets/test_int_range/model.xml",
+    )?)
+    .expect_err("uint8 counter goes out of range");
+    assert!(format!("{err:#}").contains("value 256 is out of range [0, 255]"));
+    Ok(())
+}
+
+#[test]
+fn type_error() -> anyhow::Result<()> {
+    let err = test(&PathBuf::from_str(
+        "./tests/assets/test_type_error/model.xml",
+    )?)
+    .expect_err("integer variable used as condition");
+    let err = format!("{err:#}");
+    assert!(err.contains("fsm.scxml"));
+    assert!(err.contains("in <transition> attribute 'cond' of state 'idle'"));
+    assert!(err.contains("in sub-expression `c`"));
+    assert!(err.contains("expected bool, found int"));
+    Ok(())
+}
+
+#[derive(Clone)]
+struct NoPublisher;
+
+impl Publisher<Event> for NoPublisher {
+    fn init(&mut self) {}
+
+    fn publish(&mut self, _action: &Event, _time: Time, _state: &[bool]) {}
+
+    fn finalize(self, _success: Option<bool>) {}
+}
+
+// Estimates the probability that the guarantees of the model are satisfied.
+fn estimate(model: &ScxmlModel) -> anyhow::Result<f64> {
+    let state = Arc::new(Mutex::new((0, 0, true)));
+    model.model.par_adaptive(
+        &model.guarantees,
+        &model.assumes,
+        0.95,
+        0.02,
+        MAXSTEP,
+        100,
+        None::<NoPublisher>,
+        state.clone(),
+    )?;
+    let (s, f, _) = *state.lock().expect("lock state");
+    Ok(s as f64 / (s + f) as f64)
+}
+
 fn test(path: &Path) -> anyhow::Result<()> {
     let mut model = scan_fmt_xml::load(path)?.model.channel_system().to_owned();
     let mut steps = 0;
@@ -0,0 +1,25 @@
+use rand::{rngs::SmallRng, SeedableRng};
+use scan_fmt_xml::{
+    scan_core::{CsModelDef, TransitionSystem},
+    ScxmlModel,
+};
+use std::{path::PathBuf, str::FromStr};
+
+// The definition of a model built from XML, together with its names and properties, survives a round-trip through JSON.
+#[test]
+fn roundtrip() -> anyhow::Result<()> {
+    let def = scan_fmt_xml::load_def(&PathBuf::from_str(
+        "./tests/assets/test_param_tennis/model.xml",
+    )?)?;
+    let json = serde_json::to_string(&def)?;
+    let reloaded: ScxmlModel<CsModelDef> = serde_json::from_str(&json)?;
+    assert_eq!(json, serde_json::to_string(&reloaded)?);
+
+    let model = reloaded.build().model;
+    for seed in 0..100 {
+        let mut rng = SmallRng::seed_from_u64(seed);
+        let mut model = model.clone();
+        while model.montecarlo_transition(&mut rng, 100)?.is_some() {}
+    }
+    Ok(())
+}
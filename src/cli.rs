@@ -1,18 +1,57 @@
 use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufReader, BufWriter},
     path::PathBuf,
     sync::{Arc, Mutex},
 };
 
 use crate::PrintTrace;
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use indicatif::{ProgressBar, ProgressStyle};
-use scan_fmt_xml::scan_core::*;
+use scan_fmt_xml::{
+    scan_core::{
+        channel_system::{Channel, CsError, Event, PgId},
+        program_graph::{PgError, Var as PgVar},
+        *,
+    },
+    ScxmlModel,
+};
+
+/// Behaviour of integer arithmetic on overflow
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum OverflowMode {
+    /// Runs fail with an error
+    Checked,
+    /// Results are clamped to the bounds of 64-bit integers
+    Saturating,
+    /// Results wrap around
+    Wrapping,
+}
+
+impl From<OverflowMode> for Overflow {
+    fn from(value: OverflowMode) -> Self {
+        match value {
+            OverflowMode::Checked => Overflow::Checked,
+            OverflowMode::Saturating => Overflow::Saturating,
+            OverflowMode::Wrapping => Overflow::Wrapping,
+        }
+    }
+}
+
+// How PGs are called in the model, their names, the names of their variables and of the channels.
+type ModelNames<'a> = (
+    &'a str,
+    &'a HashMap<PgId, String>,
+    &'a HashMap<(PgId, PgVar), String>,
+    &'a HashMap<Channel, String>,
+);
 
 /// A statistical model checker for large concurrent systems
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 pub struct Cli {
-    /// Path of model's main XML file
+    /// Path of model's main XML file, of a model in SCAN's JSON format (.json), of a JANI model (.jani), or of a Promela model (.pml)
     #[arg(value_hint = clap::ValueHint::DirPath, default_value = ".")]
     model: PathBuf,
     /// Confidence
@@ -30,11 +69,161 @@ pub struct Cli {
     /// Saves execution traces in gz-compressed csv format
     #[arg(long = "save-traces", default_value = "false")]
     trace: bool,
+    /// Behaviour of integer arithmetic on overflow
+    #[arg(long, value_enum, default_value = "checked")]
+    overflow: OverflowMode,
+    /// Max number of messages queued in channels of unbounded capacity (runs exceeding it fail with an error)
+    #[arg(long = "queue-limit")]
+    queue_limit: Option<usize>,
+    /// Reports statistics on the occupancy of channels
+    #[arg(long = "channel-stats", default_value = "false")]
+    channel_stats: bool,
+    /// Saves the definition of the built model to the given path in JSON format
+    #[arg(long = "save-model", value_hint = clap::ValueHint::FilePath)]
+    save_model: Option<PathBuf>,
+    /// Loads the model from a definition saved with --save-model (integer overflows behave as when it was saved)
+    #[arg(long = "load-model", default_value = "false")]
+    load_model: bool,
 }
 
 impl Cli {
     pub fn run(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let scxml_model = scan_fmt_xml::load(&self.model)?;
+        let overflow = self.overflow.into();
+        if self.load_model {
+            let reader = BufReader::new(File::open(&self.model)?);
+            let scxml_model: ScxmlModel<CsModelDef> = serde_json::from_reader(reader)?;
+            self.verify_scxml(scxml_model)
+        } else if self.model.extension().is_some_and(|ext| ext == "json") {
+            if self.trace {
+                return Err("saving traces is only supported for SCXML models".into());
+            }
+            if self.save_model.is_some() {
+                return Err("saving models is only supported for SCXML models".into());
+            }
+            let json_model = scan_fmt_json::load_with_overflow(&self.model, overflow)?;
+            self.verify(
+                &json_model.model,
+                &json_model.guarantees,
+                &json_model.assumes,
+                None::<PrintTrace>,
+                (
+                    "process",
+                    &json_model.pg_names,
+                    &json_model.vars,
+                    &json_model.channel_names,
+                ),
+            )
+        } else if self.model.extension().is_some_and(|ext| ext == "jani") {
+            if self.trace {
+                return Err("saving traces is only supported for SCXML models".into());
+            }
+            if self.save_model.is_some() {
+                return Err("saving models is only supported for SCXML models".into());
+            }
+            let jani_model = scan_fmt_jani::load_with_overflow(&self.model, overflow)?;
+            if let Some(bound) = jani_model
+                .time_bound
+                .filter(|bound| *bound >= self.duration)
+            {
+                return Err(format!(
+                    "max duration must exceed the time bounds of the properties ({bound})"
+                )
+                .into());
+            }
+            self.verify(
+                &jani_model.model,
+                &jani_model.guarantees,
+                &jani_model.assumes,
+                None::<PrintTrace>,
+                (
+                    "model",
+                    &jani_model.pg_names,
+                    &jani_model.vars,
+                    &HashMap::new(),
+                ),
+            )
+        } else if self.model.extension().is_some_and(|ext| ext == "pml") {
+            if self.trace {
+                return Err("saving traces is only supported for SCXML models".into());
+            }
+            if self.save_model.is_some() {
+                return Err("saving models is only supported for SCXML models".into());
+            }
+            let promela_model = scan_fmt_promela::load_with_overflow(&self.model, overflow)?;
+            self.verify(
+                &promela_model.model,
+                &promela_model.guarantees,
+                &promela_model.assumes,
+                None::<PrintTrace>,
+                (
+                    "process",
+                    &promela_model.pg_names,
+                    &promela_model.vars,
+                    &promela_model.channel_names,
+                ),
+            )
+        } else {
+            let scxml_model = scan_fmt_xml::load_def_with_overflow(&self.model, overflow)?;
+            self.verify_scxml(scxml_model)
+        }
+    }
+
+    fn verify_scxml(
+        &self,
+        scxml_model: ScxmlModel<CsModelDef>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(path) = &self.save_model {
+            let writer = BufWriter::new(File::create(path)?);
+            serde_json::to_writer(writer, &scxml_model)?;
+        }
+        let scxml_model = scxml_model.build();
+        let fsm_name = |pg_id| scxml_model.fsm_names.get(pg_id).map_or("?", String::as_str);
+        let channel_names = scxml_model
+            .ext_queues
+            .iter()
+            .map(|(channel, pg_id)| (*channel, format!("external queue of '{}'", fsm_name(pg_id))))
+            .chain(
+                scxml_model
+                    .parameters
+                    .iter()
+                    .map(|(channel, (src, trg, event, param))| {
+                        let event = scxml_model.events.get(event).map_or("?", String::as_str);
+                        (
+                            *channel,
+                            format!(
+                                "param '{param}' of event '{event}' from '{}' to '{}'",
+                                fsm_name(src),
+                                fsm_name(trg)
+                            ),
+                        )
+                    }),
+            )
+            .collect();
+        self.verify(
+            &scxml_model.model,
+            &scxml_model.guarantees,
+            &scxml_model.assumes,
+            self.trace.then(|| PrintTrace::new(&scxml_model)),
+            (
+                "fsm",
+                &scxml_model.fsm_names,
+                &scxml_model.vars,
+                &channel_names,
+            ),
+        )
+    }
+
+    fn verify<P>(
+        &self,
+        model: &CsModel,
+        guarantees: &[Pmtl<Atom<Event>>],
+        assumes: &[Pmtl<Atom<Event>>],
+        publisher: Option<P>,
+        names: ModelNames,
+    ) -> Result<(), Box<dyn std::error::Error>>
+    where
+        P: Publisher<Event> + Clone + Send + Sync,
+    {
         let model_name = self
             .model
             .file_stem()
@@ -54,17 +243,44 @@ impl Cli {
             std::fs::create_dir("./traces/failure").expect("create failure dir");
             std::fs::create_dir("./traces/undetermined").expect("create undetermined dir");
         }
-        scxml_model.model.par_adaptive(
-            &scxml_model.guarantees,
-            &scxml_model.assumes,
+        let mut model = model.clone();
+        model.set_queue_limit(self.queue_limit);
+        let result = model.par_adaptive(
+            guarantees,
+            assumes,
             confidence,
             precision,
             self.length,
             self.duration,
-            self.trace.then_some(PrintTrace::new(&scxml_model)),
+            publisher,
             run_state.clone(),
         );
         bar.join().expect("terminate bar process");
+        let (kind, pg_names, vars, channel_names) = names;
+        if self.channel_stats {
+            print_channel_stats(&model, channel_names);
+        }
+        if let Err(CsError::ProgramGraph(pg_id, PgError::Effect(action, var, err))) = &result {
+            // Report failing effects by the names used in the model.
+            let pg = pg_names.get(pg_id).map_or("?", String::as_str);
+            let var = vars.get(&(*pg_id, *var)).map_or("?", String::as_str);
+            return Err(format!(
+                "run failed in {kind} '{pg}' on action {action} assigning to '{var}': {err}"
+            )
+            .into());
+        }
+        if let Err(CsError::QueueLimit(channel, limit)) = &result {
+            // Report the channel by the name used in the model.
+            let name = channel_names
+                .get(channel)
+                .cloned()
+                .unwrap_or_else(|| channel.to_string());
+            return Err(format!(
+                "run failed: channel '{name}' reached the limit of {limit} queued messages"
+            )
+            .into());
+        }
+        result?;
         let (s, f, running) = *run_state.lock().expect("lock state");
         assert!(!running);
         // println!("Completed {} runs with {s} successes, {f} failures", s + f);
@@ -79,6 +295,28 @@ impl Cli {
     }
 }
 
+// Prints the statistics of the channels that have been used, by the names used in the model.
+fn print_channel_stats(model: &CsModel, channel_names: &HashMap<Channel, String>) {
+    println!("Channel statistics:");
+    for (channel, stats) in model
+        .channel_stats()
+        .into_iter()
+        .filter(|(_, stats)| stats.max_occupancy > 0 || stats.blocked_sends > 0)
+    {
+        let name = channel_names
+            .get(&channel)
+            .cloned()
+            .unwrap_or_else(|| channel.to_string());
+        println!(
+            "  {name}: max occupancy {}, avg occupancy {:.2}, {} blocked sends, full {:.2}% of time",
+            stats.max_occupancy,
+            stats.avg_occupancy(),
+            stats.blocked_sends,
+            100f64 * stats.full_ratio(),
+        );
+    }
+}
+
 fn print_progress_bar(confidence: f64, precision: f64, bar_state: Arc<Mutex<(u32, u32, bool)>>) {
     const FINE_BAR: &str = "█▉▊▋▌▍▎▏  ";
     let bound = okamoto_bound(confidence, precision);
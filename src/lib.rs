@@ -11,8 +11,10 @@
 //! At the moment the following languages are planned or implemented:
 //!
 //! - [x] [State Chart XML (SCXML)](https://www.w3.org/TR/scxml/).
-//! - [ ] [Promela](https://spinroot.com/spin/Man/Manual.html)
-//! - [ ] [JANI](https://jani-spec.org/)
+//! - [x] [Promela](https://spinroot.com/spin/Man/Manual.html)
+//! - [x] [JANI](https://jani-spec.org/)
+//!
+//! Models can also be given directly in SCAN's own JSON format (see the `scan_fmt_json` crate).
 //!
 //! [^1]: Baier, C., & Katoen, J. (2008). *Principles of model checking*. MIT Press.
 
@@ -7,5 +7,10 @@ fn main() {
     let result = cli.run();
     if let Err(err) = result {
         println!("ERROR: {err}");
+        let mut source = err.source();
+        while let Some(err) = source {
+            println!("caused by: {err}");
+            source = err.source();
+        }
     }
 }
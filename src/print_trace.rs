@@ -1,5 +1,5 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeSet, HashMap},
     fs::File,
     path::PathBuf,
     str::FromStr,
@@ -25,7 +25,7 @@ pub struct PrintTrace {
     fsm_names: Arc<HashMap<PgId, String>>,
     fsm_indexes: Arc<HashMap<usize, String>>,
     parameters: Arc<HashMap<Channel, (PgId, PgId, usize, String)>>,
-    int_queues: Arc<HashSet<Channel>>,
+    int_queues: Arc<BTreeSet<Channel>>,
     ext_queues: Arc<HashMap<Channel, PgId>>,
     events: Arc<HashMap<usize, String>>,
 }
@@ -121,14 +121,23 @@ impl Publisher<Event> for PrintTrace {
                     action = "R".to_string();
                     param_value = format!("{val:?}");
                 }
-                channel_system::EventType::ProbeEmptyQueue
+                // Parameters are passed through reliable, point-to-point channels with a queue.
+                channel_system::EventType::Handshake(..)
+                | channel_system::EventType::Broadcast(..)
+                | channel_system::EventType::FaultySend(..)
+                | channel_system::EventType::Peek(_)
+                | channel_system::EventType::ProbeEmptyQueue
                 | channel_system::EventType::ProbeFullQueue => return,
             }
         } else if let Some(trg) = self.ext_queues.get(&event.channel) {
             target_name = self.fsm_names.get(trg).unwrap().to_owned();
             match event.event_type {
-                channel_system::EventType::Send(ref val) => {
-                    action = "S".to_string();
+                channel_system::EventType::Send(ref val)
+                | channel_system::EventType::FaultySend(_, ref val) => {
+                    action = match event.event_type {
+                        channel_system::EventType::FaultySend(fault, _) => format!("S({fault:?})"),
+                        _ => "S".to_string(),
+                    };
                     if let Val::Tuple(e) = val {
                         if let (Val::Integer(sent_event), Val::Integer(origin)) = (&e[0], &e[1]) {
                             origin_name = self
@@ -163,7 +172,10 @@ impl Publisher<Event> for PrintTrace {
                         panic!("events should be pairs");
                     }
                 }
-                channel_system::EventType::ProbeEmptyQueue
+                channel_system::EventType::Handshake(..)
+                | channel_system::EventType::Broadcast(..)
+                | channel_system::EventType::Peek(_)
+                | channel_system::EventType::ProbeEmptyQueue
                 | channel_system::EventType::ProbeFullQueue => return,
             }
         } else if self.int_queues.contains(&event.channel) {
@@ -186,7 +198,11 @@ impl Publisher<Event> for PrintTrace {
                         panic!("events should be indexed by integer");
                     }
                 }
-                channel_system::EventType::ProbeEmptyQueue
+                channel_system::EventType::Handshake(..)
+                | channel_system::EventType::Broadcast(..)
+                | channel_system::EventType::FaultySend(..)
+                | channel_system::EventType::Peek(_)
+                | channel_system::EventType::ProbeEmptyQueue
                 | channel_system::EventType::ProbeFullQueue => return,
             }
         } else {
@@ -203,6 +219,32 @@ impl Publisher<Event> for PrintTrace {
                     target_name = self.fsm_names.get(&event.pg_id).unwrap().to_owned();
                     param_value = format!("{val:?}");
                 }
+                channel_system::EventType::Peek(ref val) => {
+                    action = "P".to_string();
+                    origin_name = format!("{:?}", event.channel);
+                    target_name = self.fsm_names.get(&event.pg_id).unwrap().to_owned();
+                    param_value = format!("{val:?}");
+                }
+                channel_system::EventType::Handshake(receiver, ref val) => {
+                    origin_name = self.fsm_names.get(&event.pg_id).unwrap().to_owned();
+                    target_name = self.fsm_names.get(&receiver).unwrap().to_owned();
+                    param_value = format!("{val:?}");
+                }
+                channel_system::EventType::FaultySend(fault, ref val) => {
+                    action = format!("S({fault:?})");
+                    origin_name = self.fsm_names.get(&event.pg_id).unwrap().to_owned();
+                    target_name = format!("{:?}", event.channel);
+                    param_value = format!("{val:?}");
+                }
+                channel_system::EventType::Broadcast(ref recipients, ref val) => {
+                    origin_name = self.fsm_names.get(&event.pg_id).unwrap().to_owned();
+                    target_name = recipients
+                        .iter()
+                        .map(|pg_id| self.fsm_names.get(pg_id).unwrap().as_str())
+                        .collect::<Vec<_>>()
+                        .join("|");
+                    param_value = format!("{val:?}");
+                }
                 channel_system::EventType::ProbeEmptyQueue
                 | channel_system::EventType::ProbeFullQueue => return,
             }
@@ -0,0 +1,33 @@
+use clap::Parser;
+use scan::Cli;
+use std::path::PathBuf;
+
+// A model saved with `--save-model` can be loaded back with `--load-model` and verified,
+// and saving it again yields the same definition.
+#[test]
+fn save_load() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = std::env::temp_dir().join(format!("scan_save_load_{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    let saved = dir.join("saved.json");
+    let resaved = dir.join("resaved.json");
+    let model = PathBuf::from("./scan_fmt_xml/tests/assets/test_param_tennis/model.xml");
+    let args = ["scan", "-c", "0.9", "-p", "0.1", "-d", "100"];
+
+    Cli::parse_from(args.iter().copied().chain([
+        model.to_str().unwrap(),
+        "--save-model",
+        saved.to_str().unwrap(),
+    ]))
+    .run()?;
+    Cli::parse_from(args.iter().copied().chain([
+        saved.to_str().unwrap(),
+        "--load-model",
+        "--save-model",
+        resaved.to_str().unwrap(),
+    ]))
+    .run()?;
+    assert_eq!(std::fs::read(&saved)?, std::fs::read(&resaved)?);
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}